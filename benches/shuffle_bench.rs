@@ -0,0 +1,37 @@
+#![feature(test)]
+
+extern crate test;
+
+use doppio::elgamal::{Ciphertext, PublicKey, SecretKey};
+use doppio::shuffle::*;
+use doppio::*;
+use rand_chacha::ChaChaRng;
+use rand_core::SeedableRng;
+use test::Bencher;
+
+const N: usize = 1000;
+
+fn setup() -> (ChaChaRng, CommitmentKey, PublicKey, Vec<Ciphertext>) {
+    let mut rng = ChaChaRng::from_seed([7u8; 32]);
+    let pk = PublicKey::from(&SecretKey::new(&mut rng));
+    let inputs = (0..N)
+        .map(|i| pk.encrypt(&Fr::from(i as u64), &mut rng))
+        .collect();
+
+    (rng, CommitmentKey::new(N), pk, inputs)
+}
+
+#[bench]
+fn bench_shuffle_prove_1000(bencher: &mut Bencher) {
+    let (mut rng, ck, pk, inputs) = setup();
+    let shuffle = Shuffle::new(&pk, &inputs, &mut rng);
+    bencher.iter(|| shuffle.prove(&ck, &mut rng));
+}
+
+#[bench]
+fn bench_shuffle_verify_1000(bencher: &mut Bencher) {
+    let (mut rng, ck, pk, inputs) = setup();
+    let shuffle = Shuffle::new(&pk, &inputs, &mut rng);
+    let proof = shuffle.prove(&ck, &mut rng).unwrap();
+    bencher.iter(|| proof.verify(&ck, &pk, &inputs, shuffle.outputs()));
+}
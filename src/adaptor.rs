@@ -0,0 +1,241 @@
+//! This module implements adaptor signatures for the Schnorr signatures
+//! of the `schnorr` module, as used for scriptless atomic swaps.
+//!
+//! A pre-signature on `msg` under the public key `PK = x * G` and the
+//! adaptor point `T = t * G` is `(R, s')` with `R = k * G` and
+//! `s' = k + c * x`, where the challenge
+//!
+//! ```text
+//! c = Fr::from_bytes_wide(SHA-512("DOPPIO-SCHNORR-SHA512-v1" || "chal" || R + T || PK || msg))
+//! ```
+//!
+//! is the Schnorr challenge for the nonce commitment `R + T`, bound
+//! through its encoding. Anyone can check a pre-signature against `T`,
+//! but only someone who knows `t` can adapt it into the ordinary
+//! signature `(R + T, s' + t)`, and once that signature is published,
+//! the pre-signer recovers `t = s - s'`.
+//!
+//! As in the `schnorr` module, every check is multiplied by the cofactor.
+//! Adaptor points outside the prime order subgroup are rejected: for an
+//! adaptor `T' = t * G + T` with a torsion component `T`, adapting with
+//! `t` would give the nonce commitment `R + t * G` rather than the
+//! `R + T'` that the pre-signature commits to.
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::schnorr::{challenge, nonce_generate, Signature, SigningKey, VerifyingKey};
+use crate::{AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The context string used to derive nonces.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-SCHNORR-ADAPTOR-SHA512-v1";
+
+/// A pre-signature `(R, s')`, which becomes a Schnorr signature once
+/// adapted with the discrete logarithm of the adaptor point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PreSignature {
+    r: AffinePoint,
+    s: Fr,
+}
+
+/// Returns the nonce commitment `R + T` of the adapted signature.
+fn adapted_nonce(r: &AffinePoint, adaptor: &AffinePoint) -> AffinePoint {
+    AffinePoint::from(ExtendedPoint::from(*r) + ExtendedPoint::from(*adaptor))
+}
+
+/// Pre-signs `msg` with `sk` under the adaptor point `adaptor`. This
+/// returns `None` if the adaptor point is not in the prime order
+/// subgroup or is the identity.
+pub fn pre_sign<R: RngCore + CryptoRng>(
+    sk: &SigningKey,
+    msg: &[u8],
+    adaptor: &AffinePoint,
+    rng: &mut R,
+) -> Option<PreSignature> {
+    if !bool::from(adaptor.is_prime_order()) {
+        return None;
+    }
+
+    let mut k = nonce_generate(CONTEXT_STRING, sk.scalar(), rng);
+    let r = AffinePoint::from(ExtendedPoint::from(GENERATOR) * k);
+    let c = challenge(
+        &adapted_nonce(&r, adaptor),
+        &sk.verifying_key().to_point(),
+        msg,
+    );
+    let s = k + c * sk.scalar();
+    k.zeroize();
+
+    Some(PreSignature { r, s })
+}
+
+/// Checks that `pre_signature` is a valid pre-signature on `msg` under
+/// `vk` and the adaptor point `adaptor`, so that adapting it with the
+/// discrete logarithm of `adaptor` yields a valid signature. This
+/// rejects adaptor points that are not in the prime order subgroup or
+/// are the identity.
+pub fn pre_verify(
+    vk: &VerifyingKey,
+    msg: &[u8],
+    adaptor: &AffinePoint,
+    pre_signature: &PreSignature,
+) -> bool {
+    if !bool::from(adaptor.is_prime_order()) {
+        return false;
+    }
+
+    let pk = vk.to_point();
+    let c = challenge(&adapted_nonce(&pre_signature.r, adaptor), &pk, msg);
+    let lhs = ExtendedPoint::from(GENERATOR) * pre_signature.s;
+    let rhs = ExtendedPoint::from(pre_signature.r) + ExtendedPoint::from(pk) * c;
+
+    bool::from((lhs - rhs).mul_by_cofactor().is_identity())
+}
+
+impl PreSignature {
+    /// Returns the nonce commitment `R` of this pre-signature.
+    pub fn r(&self) -> AffinePoint {
+        self.r
+    }
+
+    /// Adapts this pre-signature with the secret `t` of the adaptor point
+    /// `T = t * G`, producing the signature `(R + T, s' + t)`.
+    pub fn adapt(&self, t: &Fr) -> Signature {
+        let adaptor = AffinePoint::from(ExtendedPoint::from(GENERATOR) * t);
+
+        Signature::from_parts(adapted_nonce(&self.r, &adaptor), self.s + t)
+    }
+
+    /// Extracts the secret `t` of the adaptor point `adaptor` from
+    /// `signature`, the result of adapting this pre-signature. This
+    /// returns `None` if `signature` was not obtained by adapting this
+    /// pre-signature under `adaptor`.
+    pub fn extract(&self, signature: &Signature, adaptor: &AffinePoint) -> Option<Fr> {
+        if signature.r() != adapted_nonce(&self.r, adaptor) {
+            return None;
+        }
+
+        let t = signature.z() - self.s;
+        let check = ExtendedPoint::from(GENERATOR) * t - ExtendedPoint::from(*adaptor);
+        if bool::from(check.mul_by_cofactor().is_identity()) {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    /// Attempts to interpret the 64-byte representation `R || s'` of a
+    /// pre-signature, failing if either component is not canonically
+    /// encoded.
+    pub fn from_bytes(bytes: [u8; 64]) -> CtOption<PreSignature> {
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        s.copy_from_slice(&bytes[32..]);
+
+        let r = AffinePoint::from_bytes(r);
+        let s = Fr::from_bytes(s);
+        let is_some = r.is_some() & s.is_some();
+
+        CtOption::new(
+            PreSignature {
+                r: r.unwrap_or(AffinePoint::identity()),
+                s: s.unwrap_or(Fr::zero()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this pre-signature into its 64-byte representation
+    /// `R || s'`.
+    pub fn into_bytes(&self) -> [u8; 64] {
+        let mut res = [0u8; 64];
+        res[..32].copy_from_slice(&self.r.into_bytes());
+        res[32..].copy_from_slice(&self.s.into_bytes());
+
+        res
+    }
+}
+
+#[test]
+fn test_swap() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let vk = sk.verifying_key();
+    let t = Fr::random(&mut rng);
+    let adaptor = AffinePoint::from(ExtendedPoint::from(GENERATOR) * t);
+
+    let pre_signature = pre_sign(&sk, b"message", &adaptor, &mut rng).unwrap();
+    assert!(pre_verify(&vk, b"message", &adaptor, &pre_signature));
+
+    // A pre-signature is not a signature, and is bound to the adaptor
+    // point.
+    let other = AffinePoint::from(ExtendedPoint::from(GENERATOR) * Fr::random(&mut rng));
+    assert!(!pre_verify(&vk, b"message", &other, &pre_signature));
+    assert!(!vk.verify(
+        b"message",
+        &Signature::from_parts(pre_signature.r(), pre_signature.s)
+    ));
+
+    let signature = pre_signature.adapt(&t);
+    assert!(vk.verify(b"message", &signature));
+    assert_eq!(pre_signature.extract(&signature, &adaptor), Some(t));
+    assert_eq!(pre_signature.extract(&signature, &other), None);
+}
+
+#[test]
+fn test_wrong_secret() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let t = Fr::random(&mut rng);
+    let adaptor = AffinePoint::from(ExtendedPoint::from(GENERATOR) * t);
+    let pre_signature = pre_sign(&sk, b"message", &adaptor, &mut rng).unwrap();
+
+    let signature = pre_signature.adapt(&(t + Fr::one()));
+    assert!(!sk.verifying_key().verify(b"message", &signature));
+    assert_eq!(pre_signature.extract(&signature, &adaptor), None);
+}
+
+#[test]
+fn test_small_order_adaptor() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+
+    assert!(pre_sign(&sk, b"message", &AffinePoint::identity(), &mut rng).is_none());
+    assert!(pre_sign(&sk, b"message", &two_torsion, &mut rng).is_none());
+}
+
+#[test]
+fn test_torsion_adaptor() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let t = Fr::random(&mut rng);
+    let adaptor = AffinePoint::from(ExtendedPoint::from(GENERATOR) * t);
+    let pre_signature = pre_sign(&sk, b"message", &adaptor, &mut rng).unwrap();
+
+    // Adapting with t could never produce the nonce commitment R + T'
+    // for T' = t * G + T, so such adaptor points are rejected outright.
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+    let torsioned = AffinePoint::from(ExtendedPoint::from(adaptor) + two_torsion);
+    assert!(pre_sign(&sk, b"message", &torsioned, &mut rng).is_none());
+    assert!(!pre_verify(
+        &sk.verifying_key(),
+        b"message",
+        &torsioned,
+        &pre_signature
+    ));
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let adaptor = AffinePoint::from(ExtendedPoint::from(GENERATOR) * Fr::random(&mut rng));
+    let pre_signature = pre_sign(&sk, b"message", &adaptor, &mut rng).unwrap();
+
+    assert_eq!(
+        PreSignature::from_bytes(pre_signature.into_bytes()).unwrap(),
+        pre_signature
+    );
+}
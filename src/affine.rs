@@ -109,6 +109,17 @@ impl AffinePoint {
         })
     }
 
+    /// Decodes a point as `DeserializeElement` of the protocol modules,
+    /// rejecting non-canonical encodings and points of small order
+    /// (including the identity).
+    pub(crate) fn deserialize_element(bytes: [u8; 32]) -> CtOption<Self> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+        let point = point.unwrap_or(AffinePoint::identity());
+
+        CtOption::new(point, is_some & !point.is_small_order())
+    }
+
     /// Returns the `u`-coordinate of this point.
     pub fn get_u(&self) -> Fq {
         self.u
@@ -0,0 +1,618 @@
+//! This module implements blind Schnorr signatures, with which a signer
+//! issues signatures that are accepted by the verifier in the `schnorr`
+//! module without learning the message or the resulting signature.
+//!
+//! # Protocol
+//!
+//! The signer with public key `PK = x * G` samples a nonce `k` and sends
+//! the commitment `R = k * G`. The user samples blinding factors `alpha`
+//! and `beta`, computes
+//!
+//! ```text
+//! R' = R + alpha * G + beta * PK
+//! c' = H(R', PK, msg)
+//! ```
+//!
+//! with the challenge `H` of the `schnorr` module, and sends the blinded
+//! challenge `c = c' + beta`. The signer responds with `s = k + c * x`,
+//! which the user checks against `R` before unblinding it into the
+//! signature `(R', s + alpha)`.
+//!
+//! # Concurrent sessions
+//!
+//! Blind Schnorr signatures are not secure when a signer runs many
+//! sessions concurrently: the ROS attack of Benhamouda et al. combines
+//! the responses of enough open sessions into one more signature than
+//! the signer issued. The clause blind Schnorr scheme of Fuchsbauer,
+//! Plouviez and Seurin, implemented by [`ClauseSigner`] and
+//! [`ClauseUser`], prevents this by running two sessions in parallel and
+//! letting the signer pick at random which one it completes.
+//!
+//! Each session state is consumed when the signer responds or the user
+//! unblinds, and cannot be cloned, so a nonce is never used twice.
+
+use core::fmt;
+
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConditionallySelectable};
+use zeroize::Zeroize;
+
+use crate::schnorr::{challenge, nonce_generate, Signature, SigningKey, VerifyingKey};
+use crate::{AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The context string used to derive nonces.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-BLIND-SCHNORR-SHA512-v1";
+
+/// Checks `s * G = R + c * PK`, multiplied by the cofactor.
+fn check_response(pk: &AffinePoint, r: &AffinePoint, c: &Fr, s: &Fr) -> bool {
+    let lhs = ExtendedPoint::from(GENERATOR) * s;
+    let rhs = ExtendedPoint::from(*r) + ExtendedPoint::from(*pk) * c;
+
+    bool::from((lhs - rhs).mul_by_cofactor().is_identity())
+}
+
+/// Decodes a commitment point, rejecting points that are not of prime
+/// order.
+fn deserialize_commitment(bytes: [u8; 32]) -> CtOption<AffinePoint> {
+    AffinePoint::deserialize_element(bytes)
+        .and_then(|point| CtOption::new(point, point.is_torsion_free()))
+}
+
+/// The signer's commitment `R`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Commitment(AffinePoint);
+
+impl Commitment {
+    /// Attempts to interpret the byte representation of a commitment,
+    /// failing if it is not canonically encoded or not of prime order.
+    /// A torsion component of `R` would carry over into `R'` and let the
+    /// signer link the unblinded signature to its session.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<Commitment> {
+        deserialize_commitment(bytes).map(Commitment)
+    }
+
+    /// Converts this commitment into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+/// The user's blinded challenge `c`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlindedChallenge(Fr);
+
+impl BlindedChallenge {
+    /// Attempts to interpret the byte representation of a blinded
+    /// challenge, failing if it is not a canonical encoding of an element
+    /// of `Fr`.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<BlindedChallenge> {
+        Fr::from_bytes(bytes).map(BlindedChallenge)
+    }
+
+    /// Converts this blinded challenge into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+/// The signer's response `s`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Response(Fr);
+
+impl Response {
+    /// Attempts to interpret the byte representation of a response,
+    /// failing if it is not a canonical encoding of an element of `Fr`.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<Response> {
+        Fr::from_bytes(bytes).map(Response)
+    }
+
+    /// Converts this response into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+/// The signer's side of a blind signing session.
+pub struct Signer {
+    verifying_key: VerifyingKey,
+    k: Fr,
+    commitment: Commitment,
+}
+
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Signer")
+            .field("verifying_key", &self.verifying_key)
+            .field("commitment", &self.commitment)
+            .finish()
+    }
+}
+
+impl Drop for Signer {
+    fn drop(&mut self) {
+        self.k.zeroize();
+    }
+}
+
+impl Signer {
+    /// Starts a signing session with `signing_key`.
+    pub fn new<R: RngCore + CryptoRng>(signing_key: &SigningKey, rng: &mut R) -> Signer {
+        let k = nonce_generate(CONTEXT_STRING, signing_key.scalar(), rng);
+
+        Signer {
+            verifying_key: signing_key.verifying_key(),
+            k,
+            commitment: Commitment(AffinePoint::from(ExtendedPoint::from(GENERATOR) * k)),
+        }
+    }
+
+    /// Returns the commitment to be sent to the user.
+    pub fn commitment(&self) -> Commitment {
+        self.commitment
+    }
+
+    /// Responds to the user's blinded challenge, consuming the nonce. This
+    /// returns `None` if `signing_key` is not the key this session was
+    /// started with.
+    pub fn respond(self, signing_key: &SigningKey, c: &BlindedChallenge) -> Option<Response> {
+        if signing_key.verifying_key() != self.verifying_key {
+            return None;
+        }
+
+        Some(Response(self.k + c.0 * signing_key.scalar()))
+    }
+}
+
+/// The blinding of one session, from the user's side.
+struct Blinding {
+    r: AffinePoint,
+    r_prime: AffinePoint,
+    c: Fr,
+    alpha: Fr,
+}
+
+impl Drop for Blinding {
+    fn drop(&mut self) {
+        self.alpha.zeroize();
+    }
+}
+
+impl Blinding {
+    /// Blinds the commitment `r` for `msg` under `pk`.
+    fn new<R: RngCore + CryptoRng>(
+        pk: &AffinePoint,
+        msg: &[u8],
+        r: &AffinePoint,
+        rng: &mut R,
+    ) -> Blinding {
+        let alpha = Fr::random(&mut *rng);
+        let mut beta = Fr::random(&mut *rng);
+        let r_prime = AffinePoint::from(
+            ExtendedPoint::from(*r)
+                + ExtendedPoint::from(GENERATOR) * alpha
+                + ExtendedPoint::from(*pk) * beta,
+        );
+        let c = challenge(&r_prime, pk, msg) + beta;
+        beta.zeroize();
+
+        Blinding {
+            r: *r,
+            r_prime,
+            c,
+            alpha,
+        }
+    }
+
+    /// Checks the response `s` and unblinds it into a signature.
+    fn unblind(&self, pk: &AffinePoint, s: &Fr) -> Option<Signature> {
+        if !check_response(pk, &self.r, &self.c, s) {
+            return None;
+        }
+
+        Some(Signature::from_parts(self.r_prime, s + self.alpha))
+    }
+}
+
+/// The user's side of a blind signing session.
+pub struct User {
+    verifying_key: VerifyingKey,
+    blinding: Blinding,
+}
+
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("User")
+            .field("verifying_key", &self.verifying_key)
+            .finish()
+    }
+}
+
+impl User {
+    /// Starts a session for obtaining a signature on `msg` under
+    /// `verifying_key`, given the signer's commitment, and returns the
+    /// blinded challenge to be sent to the signer.
+    pub fn new<R: RngCore + CryptoRng>(
+        verifying_key: &VerifyingKey,
+        msg: &[u8],
+        commitment: &Commitment,
+        rng: &mut R,
+    ) -> (User, BlindedChallenge) {
+        let blinding = Blinding::new(&verifying_key.to_point(), msg, &commitment.0, rng);
+        let c = BlindedChallenge(blinding.c);
+
+        (
+            User {
+                verifying_key: *verifying_key,
+                blinding,
+            },
+            c,
+        )
+    }
+
+    /// Unblinds the signer's response into a signature on the message.
+    /// This returns `None` if the response is invalid.
+    pub fn finish(self, response: &Response) -> Option<Signature> {
+        self.blinding
+            .unblind(&self.verifying_key.to_point(), &response.0)
+    }
+}
+
+/// The signer's two commitments `(R_0, R_1)` in a clause blind signing
+/// session.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClauseCommitment {
+    r: [AffinePoint; 2],
+}
+
+impl ClauseCommitment {
+    /// Attempts to interpret the 64-byte representation `R_0 || R_1` of
+    /// a clause commitment, failing if either point is not canonically
+    /// encoded or not of prime order.
+    pub fn from_bytes(bytes: [u8; 64]) -> CtOption<ClauseCommitment> {
+        let mut r0 = [0u8; 32];
+        let mut r1 = [0u8; 32];
+        r0.copy_from_slice(&bytes[..32]);
+        r1.copy_from_slice(&bytes[32..]);
+
+        let r0 = deserialize_commitment(r0);
+        let r1 = deserialize_commitment(r1);
+        let is_some = r0.is_some() & r1.is_some();
+
+        CtOption::new(
+            ClauseCommitment {
+                r: [
+                    r0.unwrap_or(AffinePoint::identity()),
+                    r1.unwrap_or(AffinePoint::identity()),
+                ],
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this clause commitment into its 64-byte representation.
+    pub fn into_bytes(&self) -> [u8; 64] {
+        let mut res = [0u8; 64];
+        res[..32].copy_from_slice(&self.r[0].into_bytes());
+        res[32..].copy_from_slice(&self.r[1].into_bytes());
+
+        res
+    }
+}
+
+/// The user's two blinded challenges `(c_0, c_1)` in a clause blind
+/// signing session.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClauseChallenge {
+    c: [Fr; 2],
+}
+
+impl ClauseChallenge {
+    /// Attempts to interpret the 64-byte representation `c_0 || c_1` of
+    /// a clause challenge, failing if either scalar is not canonically
+    /// encoded.
+    pub fn from_bytes(bytes: [u8; 64]) -> CtOption<ClauseChallenge> {
+        let mut c0 = [0u8; 32];
+        let mut c1 = [0u8; 32];
+        c0.copy_from_slice(&bytes[..32]);
+        c1.copy_from_slice(&bytes[32..]);
+
+        let c0 = Fr::from_bytes(c0);
+        let c1 = Fr::from_bytes(c1);
+        let is_some = c0.is_some() & c1.is_some();
+
+        CtOption::new(
+            ClauseChallenge {
+                c: [c0.unwrap_or(Fr::zero()), c1.unwrap_or(Fr::zero())],
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this clause challenge into its 64-byte representation.
+    pub fn into_bytes(&self) -> [u8; 64] {
+        let mut res = [0u8; 64];
+        res[..32].copy_from_slice(&self.c[0].into_bytes());
+        res[32..].copy_from_slice(&self.c[1].into_bytes());
+
+        res
+    }
+}
+
+/// The signer's response `(b, s_b)` in a clause blind signing session,
+/// completing the session selected by the bit `b`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClauseResponse {
+    b: u8,
+    s: Fr,
+}
+
+impl ClauseResponse {
+    /// Attempts to interpret the 33-byte representation `b || s_b` of a
+    /// clause response, failing if `b` is neither `0` nor `1` or `s_b` is
+    /// not canonically encoded.
+    pub fn from_bytes(bytes: [u8; 33]) -> CtOption<ClauseResponse> {
+        let mut s = [0u8; 32];
+        s.copy_from_slice(&bytes[1..]);
+        let s = Fr::from_bytes(s);
+        let is_some = s.is_some() & Choice::from((bytes[0] >> 1 == 0) as u8);
+
+        CtOption::new(
+            ClauseResponse {
+                b: bytes[0] & 1,
+                s: s.unwrap_or(Fr::zero()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this clause response into its 33-byte representation.
+    pub fn into_bytes(&self) -> [u8; 33] {
+        let mut res = [0u8; 33];
+        res[0] = self.b;
+        res[1..].copy_from_slice(&self.s.into_bytes());
+
+        res
+    }
+}
+
+/// The signer's side of a clause blind signing session.
+pub struct ClauseSigner {
+    verifying_key: VerifyingKey,
+    k: [Fr; 2],
+    commitment: ClauseCommitment,
+}
+
+impl fmt::Debug for ClauseSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClauseSigner")
+            .field("verifying_key", &self.verifying_key)
+            .field("commitment", &self.commitment)
+            .finish()
+    }
+}
+
+impl Drop for ClauseSigner {
+    fn drop(&mut self) {
+        self.k[0].zeroize();
+        self.k[1].zeroize();
+    }
+}
+
+impl ClauseSigner {
+    /// Starts a clause signing session with `signing_key`.
+    pub fn new<R: RngCore + CryptoRng>(signing_key: &SigningKey, rng: &mut R) -> ClauseSigner {
+        let k = [
+            nonce_generate(CONTEXT_STRING, signing_key.scalar(), rng),
+            nonce_generate(CONTEXT_STRING, signing_key.scalar(), rng),
+        ];
+        let r = [
+            AffinePoint::from(ExtendedPoint::from(GENERATOR) * k[0]),
+            AffinePoint::from(ExtendedPoint::from(GENERATOR) * k[1]),
+        ];
+
+        ClauseSigner {
+            verifying_key: signing_key.verifying_key(),
+            k,
+            commitment: ClauseCommitment { r },
+        }
+    }
+
+    /// Returns the commitments to be sent to the user.
+    pub fn commitment(&self) -> ClauseCommitment {
+        self.commitment
+    }
+
+    /// Picks one of the two sessions at random and responds to its
+    /// blinded challenge, consuming both nonces. This returns `None` if
+    /// `signing_key` is not the key this session was started with.
+    pub fn respond<R: RngCore + CryptoRng>(
+        self,
+        signing_key: &SigningKey,
+        c: &ClauseChallenge,
+        rng: &mut R,
+    ) -> Option<ClauseResponse> {
+        if signing_key.verifying_key() != self.verifying_key {
+            return None;
+        }
+
+        let b = (rng.next_u32() & 1) as u8;
+        let choice = Choice::from(b);
+        let k = Fr::conditional_select(&self.k[0], &self.k[1], choice);
+        let c = Fr::conditional_select(&c.c[0], &c.c[1], choice);
+
+        Some(ClauseResponse {
+            b,
+            s: k + c * signing_key.scalar(),
+        })
+    }
+}
+
+/// The user's side of a clause blind signing session.
+pub struct ClauseUser {
+    verifying_key: VerifyingKey,
+    blindings: [Blinding; 2],
+}
+
+impl fmt::Debug for ClauseUser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClauseUser")
+            .field("verifying_key", &self.verifying_key)
+            .finish()
+    }
+}
+
+impl ClauseUser {
+    /// Starts a clause session for obtaining a signature on `msg` under
+    /// `verifying_key`, given the signer's commitments, and returns the
+    /// blinded challenges to be sent to the signer. Both sessions are
+    /// blinded independently, so whichever one the signer completes
+    /// yields a signature on `msg`.
+    pub fn new<R: RngCore + CryptoRng>(
+        verifying_key: &VerifyingKey,
+        msg: &[u8],
+        commitment: &ClauseCommitment,
+        rng: &mut R,
+    ) -> (ClauseUser, ClauseChallenge) {
+        let pk = verifying_key.to_point();
+        let blindings = [
+            Blinding::new(&pk, msg, &commitment.r[0], rng),
+            Blinding::new(&pk, msg, &commitment.r[1], rng),
+        ];
+        let c = ClauseChallenge {
+            c: [blindings[0].c, blindings[1].c],
+        };
+
+        (
+            ClauseUser {
+                verifying_key: *verifying_key,
+                blindings,
+            },
+            c,
+        )
+    }
+
+    /// Unblinds the signer's response into a signature on the message.
+    /// This returns `None` if the response is invalid.
+    pub fn finish(self, response: &ClauseResponse) -> Option<Signature> {
+        self.blindings[usize::from(response.b)].unblind(&self.verifying_key.to_point(), &response.s)
+    }
+}
+
+#[test]
+fn test_blind_signature() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let vk = sk.verifying_key();
+
+    let signer = Signer::new(&sk, &mut rng);
+    let commitment = signer.commitment();
+    let (user, c) = User::new(&vk, b"token", &commitment, &mut rng);
+    let response = signer.respond(&sk, &c).unwrap();
+    let signature = user.finish(&response).unwrap();
+
+    assert!(vk.verify(b"token", &signature));
+    assert!(!vk.verify(b"other token", &signature));
+    // The signature is unlinkable to the signer's view of the session.
+    assert_ne!(signature.r(), commitment.0);
+    assert_ne!(signature.z(), response.0);
+}
+
+#[test]
+fn test_clause_blind_signature() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let vk = sk.verifying_key();
+
+    let mut seen = [false; 2];
+    for _ in 0..16 {
+        let signer = ClauseSigner::new(&sk, &mut rng);
+        let (user, c) = ClauseUser::new(&vk, b"token", &signer.commitment(), &mut rng);
+        let response = signer.respond(&sk, &c, &mut rng).unwrap();
+        seen[usize::from(response.b)] = true;
+
+        let signature = user.finish(&response).unwrap();
+        assert!(vk.verify(b"token", &signature));
+    }
+    assert_eq!(seen, [true, true]);
+}
+
+#[test]
+fn test_invalid_response() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let other = SigningKey::new(&mut rng);
+
+    let signer = Signer::new(&sk, &mut rng);
+    let (user, c) = User::new(
+        &sk.verifying_key(),
+        b"token",
+        &signer.commitment(),
+        &mut rng,
+    );
+    assert!(user.finish(&Response(c.0)).is_none());
+
+    // The signer refuses to respond with a different key.
+    assert!(signer.respond(&other, &c).is_none());
+
+    let signer = ClauseSigner::new(&sk, &mut rng);
+    let (user, c) = ClauseUser::new(
+        &sk.verifying_key(),
+        b"token",
+        &signer.commitment(),
+        &mut rng,
+    );
+    let mut response = signer.respond(&sk, &c, &mut rng).unwrap();
+    response.b ^= 1;
+    assert!(user.finish(&response).is_none());
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let signer = ClauseSigner::new(&sk, &mut rng);
+    let commitment = signer.commitment();
+    let (_, c) = ClauseUser::new(&sk.verifying_key(), b"token", &commitment, &mut rng);
+    let response = signer.respond(&sk, &c, &mut rng).unwrap();
+
+    assert_eq!(
+        ClauseCommitment::from_bytes(commitment.into_bytes()).unwrap(),
+        commitment
+    );
+    assert_eq!(ClauseChallenge::from_bytes(c.into_bytes()).unwrap(), c);
+    assert_eq!(
+        ClauseResponse::from_bytes(response.into_bytes()).unwrap(),
+        response
+    );
+
+    let mut bytes = response.into_bytes();
+    bytes[0] = 2;
+    assert!(bool::from(ClauseResponse::from_bytes(bytes).is_none()));
+    assert!(bool::from(
+        Commitment::from_bytes(AffinePoint::identity().into_bytes()).is_none()
+    ));
+}
+
+#[test]
+fn test_torsion_commitment() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let two_torsion = ExtendedPoint::from(AffinePoint::from_raw_unchecked(
+        crate::Fq::zero(),
+        -crate::Fq::one(),
+    ));
+    let commitment = Signer::new(&sk, &mut rng).commitment();
+    let tainted = AffinePoint::from(ExtendedPoint::from(commitment.0) + two_torsion);
+
+    assert!(bool::from(
+        Commitment::from_bytes(commitment.into_bytes()).is_some()
+    ));
+    assert!(bool::from(
+        Commitment::from_bytes(tainted.into_bytes()).is_none()
+    ));
+
+    let mut bytes = ClauseSigner::new(&sk, &mut rng).commitment().into_bytes();
+    assert!(bool::from(ClauseCommitment::from_bytes(bytes).is_some()));
+    bytes[32..].copy_from_slice(&tainted.into_bytes());
+    assert!(bool::from(ClauseCommitment::from_bytes(bytes).is_none()));
+}
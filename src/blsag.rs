@@ -0,0 +1,352 @@
+//! This module implements linkable ring signatures over Doppio in the
+//! form of bLSAG (Back's linkable spontaneous anonymous group
+//! signatures), with a single key per ring member and one response `s_i`
+//! per member.
+//!
+//! A signature on `msg` for the ring of verifying keys `P_0, ..., P_{n-1}`
+//! shows that the signer knows the secret key `x` of one of them, without
+//! revealing which, and carries the key image `I = x * Hp(P)`, where `P`
+//! is the signer's key and `Hp` hashes the encoding of `P` to the curve.
+//! Two signatures by the same key have the same key image, whatever the
+//! ring or message, which reveals double use.
+//!
+//! The signature is `(c_0, s_0, ..., s_{n-1}, I)`. Verification computes,
+//! for every `i`,
+//!
+//! ```text
+//! L_i = s_i * G + c_i * P_i
+//! R_i = s_i * Hp(P_i) + c_i * I
+//! c_{i+1} = H(CONTEXT_STRING || "round" || n || P_0 || ... || P_{n-1} || I || msg || L_i || R_i)
+//! ```
+//!
+//! where `n` is the ring size as a 64-bit little-endian integer, and
+//! accepts if `c_n = c_0`. Hashing to `Fr` reduces the SHA-512 digest as
+//! a little-endian integer.
+//!
+//! CLSAG improves on bLSAG by aggregating several keys per ring member,
+//! such as a spend key and a commitment key, into a single response per
+//! member. With a single key per member, as here, both schemes produce
+//! `c_0`, one response per member and the key image, so CLSAG would add
+//! the aggregation hashes without making signatures any smaller.
+//!
+//! Because of the cofactor `4`, a signer could otherwise publish
+//! `I + T` for a point `T` of small order, with a different encoding
+//! but the same effect in the verification equations, and so escape
+//! linking. Verification therefore rejects key images outside the prime
+//! order subgroup. For the same reason it rejects rings containing a key
+//! `P + T`: by grinding until the challenge at that position is a
+//! multiple of the order of `T`, the owner of `P` could sign for it with
+//! a second key image that does not link to the first.
+
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_scalar;
+use crate::helpers::{read_point, read_scalar};
+use crate::schnorr::{nonce_generate, SigningKey, VerifyingKey};
+use crate::{hash_to_curve, multiscalar_mul_vartime, AffinePoint, ExtendedPoint, Fr, GENERATOR};
+
+/// The context string prefixed to every hash computed by this module.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-BLSAG-SHA512-v1";
+
+/// The domain separation tag of the hash `Hp` used for key images.
+const KEY_IMAGE_DST: &[u8] = b"DOPPIO_XMD:SHA-512_ELL2_RO_BLSAG_KEY_IMAGE";
+
+/// Hashes a verifying key to the curve.
+fn hash_key(key: &VerifyingKey) -> ExtendedPoint {
+    hash_to_curve(&key.into_bytes(), KEY_IMAGE_DST)
+}
+
+/// The key image `x * Hp(P)` of a signing key, which is the same in all
+/// signatures by that key.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyImage(AffinePoint);
+
+impl KeyImage {
+    /// Computes the key image of `sk`, so that its owner can recognize
+    /// signatures made with it.
+    pub fn new(sk: &SigningKey) -> KeyImage {
+        KeyImage(AffinePoint::from(
+            hash_key(&sk.verifying_key()) * sk.scalar(),
+        ))
+    }
+
+    /// Converts this key image into its byte representation, which is
+    /// suitable for storing the set of key images seen so far.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+
+    /// Returns the point underlying this key image.
+    pub fn to_point(&self) -> AffinePoint {
+        self.0
+    }
+}
+
+/// A linkable ring signature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    c_0: Fr,
+    s: Vec<Fr>,
+    key_image: KeyImage,
+}
+
+/// Computes the challenge `c_{i+1}` from `L_i` and `R_i`.
+fn round_challenge(prefix: &[u8], msg: &[u8], l: &AffinePoint, r: &AffinePoint) -> Fr {
+    hash_to_scalar(&[
+        CONTEXT_STRING,
+        b"round",
+        prefix,
+        msg,
+        &l.into_bytes(),
+        &r.into_bytes(),
+    ])
+}
+
+/// Encodes the ring and the key image, which are hashed into every
+/// challenge.
+fn prefix(ring: &[VerifyingKey], key_image: &KeyImage) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(32 * (ring.len() + 1) + 8);
+    bytes.extend_from_slice(&(ring.len() as u64).to_le_bytes());
+    for key in ring {
+        bytes.extend_from_slice(&key.into_bytes());
+    }
+    bytes.extend_from_slice(&key_image.into_bytes());
+
+    bytes
+}
+
+/// Computes `L_i = s_i * G + c_i * P_i` and `R_i = s_i * Hp(P_i) + c_i * I`.
+fn round_points(
+    s: &Fr,
+    c: &Fr,
+    key: &VerifyingKey,
+    hp: &ExtendedPoint,
+    key_image: &KeyImage,
+) -> (AffinePoint, AffinePoint) {
+    let mut points = [
+        multiscalar_mul_vartime(
+            &[*s, *c],
+            &[
+                ExtendedPoint::from(GENERATOR),
+                ExtendedPoint::from(key.to_point()),
+            ],
+        ),
+        multiscalar_mul_vartime(&[*s, *c], &[*hp, ExtendedPoint::from(key_image.0)]),
+    ];
+    let mut points = crate::batch_normalize(&mut points);
+
+    (points.next().unwrap(), points.next().unwrap())
+}
+
+/// Signs `msg` with `sk` on behalf of `ring`, in which the verifying key
+/// of `sk` is at position `index`. This returns `None` if it is not.
+pub fn sign<R: RngCore + CryptoRng>(
+    ring: &[VerifyingKey],
+    index: usize,
+    sk: &SigningKey,
+    msg: &[u8],
+    rng: &mut R,
+) -> Option<Signature> {
+    if ring.get(index) != Some(&sk.verifying_key()) {
+        return None;
+    }
+
+    let n = ring.len();
+    let hp = hash_key(&ring[index]);
+    let key_image = KeyImage(AffinePoint::from(hp * sk.scalar()));
+    let prefix = prefix(ring, &key_image);
+
+    let mut k = nonce_generate(CONTEXT_STRING, sk.scalar(), rng);
+    let mut lr = [ExtendedPoint::from(GENERATOR) * k, hp * k];
+    let mut lr = crate::batch_normalize(&mut lr);
+    let (l, r) = (lr.next().unwrap(), lr.next().unwrap());
+
+    let mut c = vec![Fr::zero(); n];
+    let mut s = vec![Fr::zero(); n];
+    c[(index + 1) % n] = round_challenge(&prefix, msg, &l, &r);
+    for offset in 1..n {
+        let i = (index + offset) % n;
+        s[i] = Fr::random(&mut *rng);
+        let (l, r) = round_points(&s[i], &c[i], &ring[i], &hash_key(&ring[i]), &key_image);
+        c[(i + 1) % n] = round_challenge(&prefix, msg, &l, &r);
+    }
+    s[index] = k - c[index] * sk.scalar();
+    k.zeroize();
+
+    Some(Signature {
+        c_0: c[0],
+        s,
+        key_image,
+    })
+}
+
+/// Checks that `signature` is a valid signature on `msg` by a member of
+/// `ring`. This rejects signatures whose key image is not in the prime
+/// order subgroup or is the identity, and rings containing a key with a
+/// torsion component.
+pub fn verify(ring: &[VerifyingKey], msg: &[u8], signature: &Signature) -> bool {
+    if ring.is_empty()
+        || signature.s.len() != ring.len()
+        || !bool::from(signature.key_image.0.is_prime_order())
+        || ring
+            .iter()
+            .any(|key| !bool::from(key.to_point().is_torsion_free()))
+    {
+        return false;
+    }
+
+    let prefix = prefix(ring, &signature.key_image);
+    let mut c = signature.c_0;
+    for (key, s) in ring.iter().zip(signature.s.iter()) {
+        let (l, r) = round_points(s, &c, key, &hash_key(key), &signature.key_image);
+        c = round_challenge(&prefix, msg, &l, &r);
+    }
+
+    c == signature.c_0
+}
+
+/// Determines whether two signatures were made with the same signing
+/// key. Both signatures should have been verified first.
+pub fn is_linked(a: &Signature, b: &Signature) -> bool {
+    a.key_image == b.key_image
+}
+
+impl Signature {
+    /// Returns the key image of this signature.
+    pub fn key_image(&self) -> KeyImage {
+        self.key_image
+    }
+
+    /// Attempts to interpret the byte representation of a signature,
+    /// which is `I || c_0 || s_0 || ... || s_{n-1}`. This fails if any
+    /// component is not canonically encoded or the ring is empty.
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<Signature> {
+        if bytes.len() < 3 * 32 {
+            return None;
+        }
+
+        let key_image = read_point(&mut bytes)?;
+        let c_0 = read_scalar(&mut bytes)?;
+        let mut s = Vec::with_capacity(bytes.len() / 32);
+        while !bytes.is_empty() {
+            s.push(read_scalar(&mut bytes)?);
+        }
+
+        Some(Signature {
+            c_0,
+            s,
+            key_image: KeyImage(key_image),
+        })
+    }
+
+    /// Converts this signature into its byte representation.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 * (self.s.len() + 2));
+        bytes.extend_from_slice(&self.key_image.into_bytes());
+        bytes.extend_from_slice(&self.c_0.into_bytes());
+        for s in &self.s {
+            bytes.extend_from_slice(&s.into_bytes());
+        }
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+fn ring(rng: &mut rand_chacha::ChaChaRng, n: usize) -> (Vec<SigningKey>, Vec<VerifyingKey>) {
+    let keys: Vec<SigningKey> = (0..n).map(|_| SigningKey::new(&mut *rng)).collect();
+    let ring = keys.iter().map(|sk| sk.verifying_key()).collect();
+
+    (keys, ring)
+}
+
+#[cfg(test)]
+fn ring_with(
+    rng: &mut rand_chacha::ChaChaRng,
+    sk: &SigningKey,
+) -> (Vec<SigningKey>, Vec<VerifyingKey>) {
+    let (keys, mut ring) = ring(rng, 2);
+    ring.insert(0, sk.verifying_key());
+
+    (keys, ring)
+}
+
+#[test]
+fn test_sign_verify() {
+    let mut rng = crate::test_rng();
+    let (keys, ring) = ring(&mut rng, 5);
+
+    for (index, sk) in keys.iter().enumerate() {
+        let signature = sign(&ring, index, sk, b"message", &mut rng).unwrap();
+        assert_eq!(signature.key_image(), KeyImage::new(sk));
+        assert!(verify(&ring, b"message", &signature));
+        assert!(!verify(&ring, b"other message", &signature));
+        assert!(!verify(&ring[1..], b"message", &signature));
+    }
+
+    // The signer must be in the ring at the given position.
+    assert!(sign(&ring, 1, &keys[0], b"message", &mut rng).is_none());
+    assert!(sign(&ring, 5, &keys[0], b"message", &mut rng).is_none());
+}
+
+#[test]
+fn test_linkability() {
+    let mut rng = crate::test_rng();
+    let (keys, ring) = ring(&mut rng, 4);
+    let (_, other_ring) = ring_with(&mut rng, &keys[2]);
+
+    let a = sign(&ring, 2, &keys[2], b"first", &mut rng).unwrap();
+    let b = sign(&other_ring, 0, &keys[2], b"second", &mut rng).unwrap();
+    let c = sign(&ring, 3, &keys[3], b"first", &mut rng).unwrap();
+    assert!(is_linked(&a, &b));
+    assert!(!is_linked(&a, &c));
+}
+
+#[test]
+fn test_torsion_key_image_rejected() {
+    let mut rng = crate::test_rng();
+    let (keys, ring) = ring(&mut rng, 3);
+    let mut signature = sign(&ring, 1, &keys[1], b"message", &mut rng).unwrap();
+
+    // Adding a point of order two to the key image must not produce a
+    // valid signature with an unlinkable key image.
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+    signature.key_image = KeyImage(AffinePoint::from(
+        ExtendedPoint::from(signature.key_image.0) + two_torsion,
+    ));
+    assert!(!verify(&ring, b"message", &signature));
+
+    signature.key_image = KeyImage(AffinePoint::identity());
+    assert!(!verify(&ring, b"message", &signature));
+}
+
+#[test]
+fn test_torsion_ring_member_rejected() {
+    let mut rng = crate::test_rng();
+    let (keys, mut ring) = ring(&mut rng, 3);
+
+    // The owner of P must not be able to sign for P + T with a second,
+    // unlinkable key image.
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+    ring[2] = VerifyingKey::from_point(AffinePoint::from(
+        ExtendedPoint::from(ring[0].to_point()) + two_torsion,
+    ));
+    let signature = sign(&ring, 0, &keys[0], b"message", &mut rng).unwrap();
+    assert!(!verify(&ring, b"message", &signature));
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let (keys, ring) = ring(&mut rng, 3);
+    let signature = sign(&ring, 0, &keys[0], b"message", &mut rng).unwrap();
+
+    let bytes = signature.into_bytes();
+    assert_eq!(bytes.len(), 32 * 5);
+    assert_eq!(Signature::from_bytes(&bytes), Some(signature));
+    assert_eq!(Signature::from_bytes(&bytes[..64]), None);
+    assert_eq!(Signature::from_bytes(&bytes[..bytes.len() - 1]), None);
+}
@@ -0,0 +1,768 @@
+//! This module implements Bulletproofs range proofs over the prime order
+//! subgroup of Doppio, following the protocol of Bünz et al. as
+//! implemented by the dalek Bulletproofs library.
+//!
+//! A value `v` is committed as `V = v * B + gamma * B_blinding`, where
+//! `B` is [`GENERATOR`] and `B_blinding` is the [`pedersen_generator`]
+//! of the `sharing` module, and a [`RangeProof`] shows that `v` lies in
+//! `[0, 2^n)` for `n` one of 8, 16, 32 or 64. A single proof can cover
+//! the commitments to `m` values, for `m` a power of two, with a size of
+//! `2 * log2(n * m) + 9` scalars and points.
+//!
+//! The vector generators `G_i` and `H_i` are derived with
+//! `hash_to_curve` under a dedicated domain separation tag, so no one
+//! knows their discrete logarithms. Challenges are derived with a
+//! [`Transcript`] using the same labels as the dalek implementation, and
+//! all points provided by the prover other than the commitments are
+//! rejected if they are the identity.
+//!
+//! Verification checks every equation of the proof, including the inner
+//! product argument, with a single variable time multiscalar
+//! multiplication, which is multiplied by the cofactor.
+
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_curve_parts;
+use crate::helpers::{read_point, read_scalar, zeroize_all};
+use crate::sharing::{pedersen_generator, powers};
+use crate::{
+    multiscalar_mul, multiscalar_mul_vartime, AffinePoint, ExtendedPoint, Fr, Transcript, GENERATOR,
+};
+
+/// The domain separation tag used to derive the vector generators.
+const GENERATORS_DST: &[u8] = b"DOPPIO_XMD:SHA-512_ELL2_RO_BULLETPROOFS";
+
+/// The generators `B` and `B_blinding` of Pedersen commitments to values.
+#[derive(Clone, Copy, Debug)]
+pub struct PedersenGens {
+    b: ExtendedPoint,
+    b_blinding: ExtendedPoint,
+}
+
+impl Default for PedersenGens {
+    fn default() -> PedersenGens {
+        PedersenGens {
+            b: ExtendedPoint::from(GENERATOR),
+            b_blinding: pedersen_generator(),
+        }
+    }
+}
+
+impl PedersenGens {
+    /// Returns the generator `B` for values.
+    pub fn b(&self) -> ExtendedPoint {
+        self.b
+    }
+
+    /// Returns the generator `B_blinding` for blinding factors.
+    pub fn b_blinding(&self) -> ExtendedPoint {
+        self.b_blinding
+    }
+
+    /// Returns the commitment `value * B + blinding * B_blinding`.
+    pub fn commit(&self, value: Fr, blinding: Fr) -> ExtendedPoint {
+        multiscalar_mul(&[value, blinding], &[self.b, self.b_blinding])
+    }
+}
+
+/// The vector generators `G_i` and `H_i` used by range proofs.
+#[derive(Clone, Debug)]
+pub struct BulletproofGens {
+    g: Vec<ExtendedPoint>,
+    h: Vec<ExtendedPoint>,
+}
+
+impl BulletproofGens {
+    /// Derives `capacity` generators of each kind, which suffices for
+    /// proofs about `m` values of `n` bits whenever `n * m <= capacity`.
+    pub fn new(capacity: usize) -> BulletproofGens {
+        let generator = |label: &[u8], i: usize| {
+            hash_to_curve_parts(&[label, &(i as u64).to_le_bytes()], GENERATORS_DST)
+        };
+
+        BulletproofGens {
+            g: (0..capacity).map(|i| generator(b"G", i)).collect(),
+            h: (0..capacity).map(|i| generator(b"H", i)).collect(),
+        }
+    }
+
+    /// Returns the number of generators of each kind.
+    pub fn capacity(&self) -> usize {
+        self.g.len()
+    }
+
+    /// Returns the generators `G_i`.
+    pub fn g(&self) -> &[ExtendedPoint] {
+        &self.g
+    }
+
+    /// Returns the generators `H_i`.
+    pub fn h(&self) -> &[ExtendedPoint] {
+        &self.h
+    }
+}
+
+/// Returns `sum_i a[i] * b[i]`.
+pub(crate) fn inner_product(a: &[Fr], b: &[Fr]) -> Fr {
+    a.iter()
+        .zip(b.iter())
+        .fold(Fr::zero(), |acc, (a, b)| acc + a * b)
+}
+
+/// A proof that `P = <a, G> + <b, H> + <a, b> * Q` for vectors `a` and
+/// `b` of a power of two length, consisting of `log2(n)` pairs of points
+/// and the final scalars `a` and `b`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct InnerProductProof {
+    l_vec: Vec<AffinePoint>,
+    r_vec: Vec<AffinePoint>,
+    a: Fr,
+    b: Fr,
+}
+
+impl InnerProductProof {
+    /// Proves knowledge of `a` and `b` for `P = <a, g> + <b, h> + <a, b> * q`,
+    /// which the caller must have absorbed into `transcript`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the four vectors do not all have the same length, or if
+    /// that length is not a power of two.
+    pub(crate) fn prove(
+        transcript: &mut Transcript,
+        q: &ExtendedPoint,
+        mut g: Vec<ExtendedPoint>,
+        mut h: Vec<ExtendedPoint>,
+        mut a: Vec<Fr>,
+        mut b: Vec<Fr>,
+    ) -> InnerProductProof {
+        let mut n = g.len();
+        assert!(n.is_power_of_two());
+        assert!(h.len() == n && a.len() == n && b.len() == n);
+
+        transcript.append_message(b"dom-sep", b"ipp v1");
+        transcript.append_u64(b"n", n as u64);
+
+        let mut l_vec = Vec::with_capacity(n.trailing_zeros() as usize);
+        let mut r_vec = Vec::with_capacity(n.trailing_zeros() as usize);
+        while n > 1 {
+            n /= 2;
+            let (a_l, a_r) = a.split_at(n);
+            let (b_l, b_r) = b.split_at(n);
+            let (g_l, g_r) = g.split_at(n);
+            let (h_l, h_r) = h.split_at(n);
+
+            let c_l = inner_product(a_l, b_r);
+            let c_r = inner_product(a_r, b_l);
+            let l = multiscalar_mul(&[a_l, b_r, &[c_l]].concat(), &[g_r, h_l, &[*q]].concat());
+            let r = multiscalar_mul(&[a_r, b_l, &[c_r]].concat(), &[g_l, h_r, &[*q]].concat());
+            let (l, r) = (AffinePoint::from(l), AffinePoint::from(r));
+            transcript.append_point(b"L", &l);
+            transcript.append_point(b"R", &r);
+            l_vec.push(l);
+            r_vec.push(r);
+
+            // The challenge is nonzero except with negligible probability.
+            let u = transcript.challenge_scalar(b"u");
+            let u_inv = u.invert().unwrap();
+
+            let a_next = (0..n).map(|i| a_l[i] * u + a_r[i] * u_inv).collect();
+            let b_next = (0..n).map(|i| b_l[i] * u_inv + b_r[i] * u).collect();
+            let g_next = (0..n)
+                .map(|i| multiscalar_mul_vartime(&[u_inv, u], &[g_l[i], g_r[i]]))
+                .collect();
+            let h_next = (0..n)
+                .map(|i| multiscalar_mul_vartime(&[u, u_inv], &[h_l[i], h_r[i]]))
+                .collect();
+            zeroize_all(&mut a);
+            zeroize_all(&mut b);
+            a = a_next;
+            b = b_next;
+            g = g_next;
+            h = h_next;
+        }
+
+        let proof = InnerProductProof {
+            l_vec,
+            r_vec,
+            a: a[0],
+            b: b[0],
+        };
+        zeroize_all(&mut a);
+        zeroize_all(&mut b);
+
+        proof
+    }
+
+    /// Absorbs the proof into `transcript` and computes the squares of the
+    /// challenges `u_j`, the squares of their inverses, and the scalars
+    /// `s_i` such that the folded generators are `<s, G>` and
+    /// `<s^-1, H>`. This returns `None` if the proof is not for vectors of
+    /// length `n`, or if any of its points is the identity.
+    pub(crate) fn verification_scalars(
+        &self,
+        n: usize,
+        transcript: &mut Transcript,
+    ) -> Option<(Vec<Fr>, Vec<Fr>, Vec<Fr>)> {
+        let lg_n = self.l_vec.len();
+        if lg_n >= 32 || n != 1 << lg_n {
+            return None;
+        }
+
+        transcript.append_message(b"dom-sep", b"ipp v1");
+        transcript.append_u64(b"n", n as u64);
+
+        let mut u_sq = Vec::with_capacity(lg_n);
+        let mut u_inv_sq = Vec::with_capacity(lg_n);
+        let mut all_inv = Fr::one();
+        for (l, r) in self.l_vec.iter().zip(self.r_vec.iter()) {
+            if !transcript.validate_and_append_point(b"L", l)
+                || !transcript.validate_and_append_point(b"R", r)
+            {
+                return None;
+            }
+            let u = transcript.challenge_scalar(b"u");
+            let u_inv = u.invert();
+            if !bool::from(u_inv.is_some()) {
+                return None;
+            }
+            let u_inv = u_inv.unwrap();
+            all_inv *= u_inv;
+            u_sq.push(u.square());
+            u_inv_sq.push(u_inv.square());
+        }
+
+        let mut s = Vec::with_capacity(n);
+        s.push(all_inv);
+        for i in 1..n {
+            let lg_i = (usize::BITS - 1 - i.leading_zeros()) as usize;
+            let k = 1 << lg_i;
+            s.push(s[i - k] * u_sq[(lg_n - 1) - lg_i]);
+        }
+
+        Some((u_sq, u_inv_sq, s))
+    }
+
+    fn serialized_size(&self) -> usize {
+        (2 * self.l_vec.len() + 2) * 32
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        for (l, r) in self.l_vec.iter().zip(self.r_vec.iter()) {
+            bytes.extend_from_slice(&l.into_bytes());
+            bytes.extend_from_slice(&r.into_bytes());
+        }
+        bytes.extend_from_slice(&self.a.into_bytes());
+        bytes.extend_from_slice(&self.b.into_bytes());
+    }
+
+    /// Decodes a proof from `bytes`, which must contain exactly the
+    /// proof.
+    fn read(mut bytes: &[u8]) -> Option<InnerProductProof> {
+        let lg_n = (bytes.len() / 32).checked_sub(2)? / 2;
+        if bytes.len() != (2 * lg_n + 2) * 32 || lg_n >= 32 {
+            return None;
+        }
+
+        let mut l_vec = Vec::with_capacity(lg_n);
+        let mut r_vec = Vec::with_capacity(lg_n);
+        for _ in 0..lg_n {
+            l_vec.push(read_point(&mut bytes)?);
+            r_vec.push(read_point(&mut bytes)?);
+        }
+        let a = read_scalar(&mut bytes)?;
+        let b = read_scalar(&mut bytes)?;
+
+        Some(InnerProductProof { l_vec, r_vec, a, b })
+    }
+}
+
+/// A proof that one or more Pedersen commitments open to values in
+/// `[0, 2^n)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeProof {
+    a: AffinePoint,
+    s: AffinePoint,
+    t_1: AffinePoint,
+    t_2: AffinePoint,
+    t_x: Fr,
+    t_x_blinding: Fr,
+    e_blinding: Fr,
+    ipp: InnerProductProof,
+}
+
+/// Checks the parameters of a proof about `m` values of `n` bits.
+fn check_parameters(bp_gens: &BulletproofGens, n: usize, m: usize) -> bool {
+    (n == 8 || n == 16 || n == 32 || n == 64) && m.is_power_of_two() && n * m <= bp_gens.capacity()
+}
+
+/// Computes `delta(y, z) = (z - z^2) * <1, y^nm> - sum_j z^(j + 3) * <1, 2^n>`.
+fn delta(n: usize, m: usize, y: &Fr, z: &Fr) -> Fr {
+    let sum_y = powers(y, n * m).iter().fold(Fr::zero(), |acc, y| acc + y);
+    let sum_2 = Fr::from(u64::MAX >> (64 - n));
+    let sum_z = powers(z, m).iter().fold(Fr::zero(), |acc, z| acc + z);
+    let z_sq = z.square();
+
+    (z - z_sq) * sum_y - z_sq * z * sum_2 * sum_z
+}
+
+impl RangeProof {
+    /// Proves that `value`, committed with `blinding`, is in `[0, 2^n)`,
+    /// returning the proof and the commitment. This returns `None` under
+    /// the same conditions as [`RangeProof::prove_multiple`].
+    pub fn prove_single<R: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value: u64,
+        blinding: &Fr,
+        n: usize,
+        rng: &mut R,
+    ) -> Option<(RangeProof, AffinePoint)> {
+        RangeProof::prove_multiple(bp_gens, pc_gens, transcript, &[value], &[*blinding], n, rng)
+            .map(|(proof, commitments)| (proof, commitments[0]))
+    }
+
+    /// Proves that each of `values`, committed with the corresponding
+    /// element of `blindings`, is in `[0, 2^n)`, returning the proof and
+    /// the commitments. This returns `None` if `n` is not 8, 16, 32 or
+    /// 64, if the number of values is not a power of two or differs from
+    /// the number of blinding factors, if `bp_gens` has too few
+    /// generators, or if any value is out of range.
+    pub fn prove_multiple<R: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Fr],
+        n: usize,
+        rng: &mut R,
+    ) -> Option<(RangeProof, Vec<AffinePoint>)> {
+        let m = values.len();
+        if !check_parameters(bp_gens, n, m)
+            || blindings.len() != m
+            || (n < 64 && values.iter().any(|v| v >> n != 0))
+        {
+            return None;
+        }
+        let nm = n * m;
+        let g = &bp_gens.g[..nm];
+        let h = &bp_gens.h[..nm];
+
+        transcript.append_message(b"dom-sep", b"rangeproof v1");
+        transcript.append_u64(b"n", n as u64);
+        transcript.append_u64(b"m", m as u64);
+
+        let mut commitments: Vec<ExtendedPoint> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(v, gamma)| pc_gens.commit(Fr::from(*v), *gamma))
+            .collect();
+        let commitments: Vec<AffinePoint> = crate::batch_normalize(&mut commitments).collect();
+        for v in &commitments {
+            transcript.append_point(b"V", v);
+        }
+
+        // a_L holds the bits of the values and a_R = a_L - 1.
+        let mut a_l: Vec<Fr> = values
+            .iter()
+            .flat_map(|v| (0..n).map(move |i| Fr::from((v >> i) & 1)))
+            .collect();
+        let mut a_r: Vec<Fr> = a_l.iter().map(|a| a - Fr::one()).collect();
+
+        let mut alpha = Fr::random(&mut *rng);
+        let a = multiscalar_mul(
+            &[&[alpha], &a_l[..], &a_r[..]].concat(),
+            &[&[pc_gens.b_blinding], g, h].concat(),
+        );
+
+        let mut s_l: Vec<Fr> = (0..nm).map(|_| Fr::random(&mut *rng)).collect();
+        let mut s_r: Vec<Fr> = (0..nm).map(|_| Fr::random(&mut *rng)).collect();
+        let mut rho = Fr::random(&mut *rng);
+        let s = multiscalar_mul(
+            &[&[rho], &s_l[..], &s_r[..]].concat(),
+            &[&[pc_gens.b_blinding], g, h].concat(),
+        );
+
+        let (a, s) = (AffinePoint::from(a), AffinePoint::from(s));
+        transcript.append_point(b"A", &a);
+        transcript.append_point(b"S", &s);
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+
+        // l(X) = (a_L - z) + s_L * X
+        // r(X) = y^i * (a_R + z + s_R * X) + z^(2 + j) * 2^(i - n * j)
+        let y_powers = powers(&y, nm);
+        let z_powers = powers(&z, m + 2);
+        let two_powers = powers(&Fr::from(2), n);
+        let mut l_0: Vec<Fr> = a_l.iter().map(|a| a - z).collect();
+        let mut r_0: Vec<Fr> = (0..nm)
+            .map(|i| y_powers[i] * (a_r[i] + z) + z_powers[2 + i / n] * two_powers[i % n])
+            .collect();
+        let mut r_1: Vec<Fr> = (0..nm).map(|i| y_powers[i] * s_r[i]).collect();
+        let mut t_1 = inner_product(&l_0, &r_1) + inner_product(&s_l, &r_0);
+        let mut t_2 = inner_product(&s_l, &r_1);
+
+        let mut tau_1 = Fr::random(&mut *rng);
+        let mut tau_2 = Fr::random(&mut *rng);
+        let mut t_points = [pc_gens.commit(t_1, tau_1), pc_gens.commit(t_2, tau_2)];
+        let mut t_points = crate::batch_normalize(&mut t_points);
+        let (t_1_point, t_2_point) = (t_points.next().unwrap(), t_points.next().unwrap());
+        transcript.append_point(b"T_1", &t_1_point);
+        transcript.append_point(b"T_2", &t_2_point);
+        let x = transcript.challenge_scalar(b"x");
+
+        let t_x_blinding = blindings
+            .iter()
+            .zip(z_powers[2..].iter())
+            .fold(tau_2 * x.square() + tau_1 * x, |acc, (gamma, z)| {
+                acc + z * gamma
+            });
+        let e_blinding = alpha + rho * x;
+        let l: Vec<Fr> = l_0.iter().zip(s_l.iter()).map(|(l, s)| l + s * x).collect();
+        let r: Vec<Fr> = r_0
+            .iter()
+            .zip(r_1.iter())
+            .map(|(r0, r1)| r0 + r1 * x)
+            .collect();
+        let t_x = inner_product(&l, &r);
+
+        transcript.append_scalar(b"t_x", &t_x);
+        transcript.append_scalar(b"t_x_blinding", &t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &e_blinding);
+        let w = transcript.challenge_scalar(b"w");
+        let q = pc_gens.b * w;
+
+        // The inner product argument is over H'_i = y^-i * H_i.
+        let y_inv = y.invert().unwrap();
+        let h_prime = powers(&y_inv, nm)
+            .iter()
+            .zip(h.iter())
+            .map(|(y, h)| h * y)
+            .collect();
+        let ipp = InnerProductProof::prove(transcript, &q, g.to_vec(), h_prime, l, r);
+
+        for v in [
+            &mut a_l, &mut a_r, &mut s_l, &mut s_r, &mut l_0, &mut r_0, &mut r_1,
+        ] {
+            zeroize_all(v);
+        }
+        for x in [
+            &mut alpha, &mut rho, &mut tau_1, &mut tau_2, &mut t_1, &mut t_2,
+        ] {
+            x.zeroize();
+        }
+
+        Some((
+            RangeProof {
+                a,
+                s,
+                t_1: t_1_point,
+                t_2: t_2_point,
+                t_x,
+                t_x_blinding,
+                e_blinding,
+                ipp,
+            },
+            commitments,
+        ))
+    }
+
+    /// Checks that this is a valid proof that `commitment` opens to a
+    /// value in `[0, 2^n)`.
+    pub fn verify_single(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        commitment: &AffinePoint,
+        n: usize,
+    ) -> bool {
+        self.verify_multiple(bp_gens, pc_gens, transcript, &[*commitment], n)
+    }
+
+    /// Checks that this is a valid proof that each of `commitments`
+    /// opens to a value in `[0, 2^n)`.
+    pub fn verify_multiple(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        commitments: &[AffinePoint],
+        n: usize,
+    ) -> bool {
+        let m = commitments.len();
+        if !check_parameters(bp_gens, n, m) {
+            return false;
+        }
+        let nm = n * m;
+
+        transcript.append_message(b"dom-sep", b"rangeproof v1");
+        transcript.append_u64(b"n", n as u64);
+        transcript.append_u64(b"m", m as u64);
+        for v in commitments {
+            transcript.append_point(b"V", v);
+        }
+
+        if !transcript.validate_and_append_point(b"A", &self.a)
+            || !transcript.validate_and_append_point(b"S", &self.s)
+        {
+            return false;
+        }
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+
+        if !transcript.validate_and_append_point(b"T_1", &self.t_1)
+            || !transcript.validate_and_append_point(b"T_2", &self.t_2)
+        {
+            return false;
+        }
+        let x = transcript.challenge_scalar(b"x");
+
+        transcript.append_scalar(b"t_x", &self.t_x);
+        transcript.append_scalar(b"t_x_blinding", &self.t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &self.e_blinding);
+        let w = transcript.challenge_scalar(b"w");
+
+        let (u_sq, u_inv_sq, s) = match self.ipp.verification_scalars(nm, transcript) {
+            Some(scalars) => scalars,
+            None => return false,
+        };
+
+        // The equation for t(x) and the inner product argument are combined
+        // with a random weight, derived from everything absorbed so far.
+        let c = transcript.clone().challenge_scalar(b"c");
+
+        let y_inv = y.invert().unwrap_or(Fr::zero());
+        let y_inv_powers = powers(&y_inv, nm);
+        let z_powers = powers(&z, m + 2);
+        let two_powers = powers(&Fr::from(2), n);
+        let (a, b) = (self.ipp.a, self.ipp.b);
+
+        let mut scalars = Vec::with_capacity(2 * nm + 2 * u_sq.len() + m + 6);
+        let mut points = Vec::with_capacity(scalars.capacity());
+        let mut push = |scalar: Fr, point: ExtendedPoint| {
+            scalars.push(scalar);
+            points.push(point);
+        };
+
+        push(Fr::one(), ExtendedPoint::from(self.a));
+        push(x, ExtendedPoint::from(self.s));
+        push(c * x, ExtendedPoint::from(self.t_1));
+        push(c * x.square(), ExtendedPoint::from(self.t_2));
+        for ((l, r), (u_sq, u_inv_sq)) in self
+            .ipp
+            .l_vec
+            .iter()
+            .zip(self.ipp.r_vec.iter())
+            .zip(u_sq.iter().zip(u_inv_sq.iter()))
+        {
+            push(*u_sq, ExtendedPoint::from(*l));
+            push(*u_inv_sq, ExtendedPoint::from(*r));
+        }
+        push(
+            -(self.e_blinding + c * self.t_x_blinding),
+            pc_gens.b_blinding,
+        );
+        push(
+            w * (self.t_x - a * b) + c * (delta(n, m, &y, &z) - self.t_x),
+            pc_gens.b,
+        );
+        for (s_i, g) in s.iter().zip(bp_gens.g.iter()) {
+            push(-z - a * s_i, *g);
+        }
+        for (i, h) in bp_gens.h[..nm].iter().enumerate() {
+            let z_and_2 = z_powers[2 + i / n] * two_powers[i % n];
+            push(z + y_inv_powers[i] * (z_and_2 - b * s[nm - 1 - i]), *h);
+        }
+        for (v, z) in commitments.iter().zip(z_powers[2..].iter()) {
+            push(c * z, ExtendedPoint::from(*v));
+        }
+
+        bool::from(
+            multiscalar_mul_vartime(&scalars, &points)
+                .mul_by_cofactor()
+                .is_identity(),
+        )
+    }
+
+    /// Attempts to interpret the byte representation of a range proof,
+    /// which is `A || S || T_1 || T_2 || t_x || t_x_blinding || e_blinding`
+    /// followed by the pairs `L_j || R_j` and the scalars `a || b` of the
+    /// inner product argument.
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<RangeProof> {
+        if bytes.len() < 7 * 32 {
+            return None;
+        }
+
+        let a = read_point(&mut bytes)?;
+        let s = read_point(&mut bytes)?;
+        let t_1 = read_point(&mut bytes)?;
+        let t_2 = read_point(&mut bytes)?;
+        let t_x = read_scalar(&mut bytes)?;
+        let t_x_blinding = read_scalar(&mut bytes)?;
+        let e_blinding = read_scalar(&mut bytes)?;
+        let ipp = InnerProductProof::read(bytes)?;
+
+        Some(RangeProof {
+            a,
+            s,
+            t_1,
+            t_2,
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp,
+        })
+    }
+
+    /// Converts this proof into its byte representation.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(7 * 32 + self.ipp.serialized_size());
+        for p in [&self.a, &self.s, &self.t_1, &self.t_2] {
+            bytes.extend_from_slice(&p.into_bytes());
+        }
+        for x in [&self.t_x, &self.t_x_blinding, &self.e_blinding] {
+            bytes.extend_from_slice(&x.into_bytes());
+        }
+        self.ipp.write(&mut bytes);
+
+        bytes
+    }
+}
+
+#[test]
+fn test_single() {
+    let mut rng = crate::test_rng();
+    let bp_gens = BulletproofGens::new(64);
+    let pc_gens = PedersenGens::default();
+
+    for (n, value) in [(8, 200u64), (64, u64::MAX)].iter() {
+        let blinding = Fr::random(&mut rng);
+        let (proof, commitment) = RangeProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"test"),
+            *value,
+            &blinding,
+            *n,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(
+            ExtendedPoint::from(commitment),
+            pc_gens.commit(Fr::from(*value), blinding)
+        );
+        assert!(proof.verify_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"test"),
+            &commitment,
+            *n
+        ));
+        assert!(!proof.verify_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"other"),
+            &commitment,
+            *n
+        ));
+
+        let other = AffinePoint::from(pc_gens.commit(Fr::from(*value), Fr::random(&mut rng)));
+        assert!(!proof.verify_single(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"test"),
+            &other,
+            *n
+        ));
+    }
+}
+
+#[test]
+fn test_aggregated() {
+    let mut rng = crate::test_rng();
+    let bp_gens = BulletproofGens::new(64);
+    let pc_gens = PedersenGens::default();
+
+    let values = [0u64, 1, 65535, 1234];
+    let blindings: Vec<Fr> = (0..4).map(|_| Fr::random(&mut rng)).collect();
+    let (proof, commitments) = RangeProof::prove_multiple(
+        &bp_gens,
+        &pc_gens,
+        &mut Transcript::new(b"test"),
+        &values,
+        &blindings,
+        16,
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(proof.into_bytes().len(), (2 * 6 + 9) * 32);
+    assert!(proof.verify_multiple(
+        &bp_gens,
+        &pc_gens,
+        &mut Transcript::new(b"test"),
+        &commitments,
+        16
+    ));
+
+    let mut swapped = commitments.clone();
+    swapped.swap(0, 1);
+    assert!(!proof.verify_multiple(
+        &bp_gens,
+        &pc_gens,
+        &mut Transcript::new(b"test"),
+        &swapped,
+        16
+    ));
+}
+
+#[test]
+fn test_invalid_parameters() {
+    let mut rng = crate::test_rng();
+    let bp_gens = BulletproofGens::new(16);
+    let pc_gens = PedersenGens::default();
+    let blinding = Fr::random(&mut rng);
+    let mut prove = |values: &[u64], n| {
+        RangeProof::prove_multiple(
+            &bp_gens,
+            &pc_gens,
+            &mut Transcript::new(b"test"),
+            values,
+            &vec![blinding; values.len()],
+            n,
+            &mut rng,
+        )
+    };
+
+    assert!(prove(&[256], 8).is_none());
+    assert!(prove(&[1], 12).is_none());
+    assert!(prove(&[1, 2, 3], 8).is_none());
+    assert!(prove(&[1], 32).is_none());
+    assert!(prove(&[255, 0], 8).is_some());
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let bp_gens = BulletproofGens::new(8);
+    let pc_gens = PedersenGens::default();
+
+    let (proof, _) = RangeProof::prove_single(
+        &bp_gens,
+        &pc_gens,
+        &mut Transcript::new(b"test"),
+        7,
+        &Fr::random(&mut rng),
+        8,
+        &mut rng,
+    )
+    .unwrap();
+    let bytes = proof.into_bytes();
+    assert_eq!(bytes.len(), (2 * 3 + 9) * 32);
+    assert_eq!(RangeProof::from_bytes(&bytes), Some(proof));
+    assert_eq!(RangeProof::from_bytes(&bytes[..bytes.len() - 32]), None);
+}
@@ -0,0 +1,353 @@
+//! This module implements the CPace balanced password-authenticated key
+//! exchange over the prime order subgroup of Doppio, with explicit key
+//! confirmation.
+//!
+//! Both parties derive the generator
+//!
+//! ```text
+//! G_pw = hash_to_curve(len(pw) || pw || len(ci) || ci || len(sid) || sid)
+//! ```
+//!
+//! under the domain separation tag `"DOPPIO_XMD:SHA-512_ELL2_RO_CPACE"`,
+//! where `pw` is the password, `ci` is a channel identifier (such as the
+//! identities of the parties) and `sid` is a session identifier, and
+//! lengths are 64-bit little endian. The protocol then runs in three
+//! messages:
+//!
+//! 1. the initiator samples `y_a` and sends `Y_a = y_a * G_pw`;
+//! 2. the responder samples `y_b`, computes `K = [4] (y_b * Y_a)` and
+//!    sends `Y_b = y_b * G_pw` together with its confirmation tag;
+//! 3. the initiator computes `K = [4] (y_a * Y_b)`, checks the
+//!    responder's tag and sends its own, which the responder checks.
+//!
+//! A shared point `K` equal to the identity, which is what every
+//! received point of small order produces, is rejected. The tags and the
+//! session key are challenges of a [`Transcript`] that absorbs `sid`,
+//! `Y_a`, `Y_b` and `K`, so that they are bound to the whole exchange.
+//!
+//! Every step consumes the state of its party, so a session cannot be
+//! reused, and all secret values are zeroized when dropped.
+
+use core::fmt;
+
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_curve_parts;
+use crate::{AffinePoint, CtOption, ExtendedPoint, Fr, Transcript};
+
+/// The label of the transcript of every exchange.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-CPACE-SHA512-v1";
+
+/// The domain separation tag used to derive the password generator.
+const HASH_TO_CURVE_DST: &[u8] = b"DOPPIO_XMD:SHA-512_ELL2_RO_CPACE";
+
+/// A message `Y = y * G_pw` of either party.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Message(AffinePoint);
+
+/// A key confirmation tag.
+#[derive(Clone, Copy, Debug)]
+pub struct Confirmation([u8; 32]);
+
+/// The session key output by a successful exchange.
+pub struct SessionKey([u8; 64]);
+
+/// The state of the initiator after sending its message.
+pub struct Initiator {
+    y: Fr,
+    transcript: Transcript,
+}
+
+/// The state of the responder after sending its message, waiting for the
+/// confirmation tag of the initiator.
+pub struct Responder {
+    expected: [u8; 32],
+    key: SessionKey,
+}
+
+/// Derives the password generator `G_pw`.
+fn generator(password: &[u8], ci: &[u8], sid: &[u8]) -> ExtendedPoint {
+    hash_to_curve_parts(
+        &[
+            &(password.len() as u64).to_le_bytes(),
+            password,
+            &(ci.len() as u64).to_le_bytes(),
+            ci,
+            &(sid.len() as u64).to_le_bytes(),
+            sid,
+        ],
+        HASH_TO_CURVE_DST,
+    )
+}
+
+/// Samples `y` and computes the message `y * G_pw`.
+fn share<R: RngCore + CryptoRng>(
+    password: &[u8],
+    ci: &[u8],
+    sid: &[u8],
+    rng: &mut R,
+) -> (Fr, Message) {
+    let y = Fr::random(rng);
+    let message = Message(AffinePoint::from(generator(password, ci, sid) * y));
+
+    (y, message)
+}
+
+/// Returns the transcript bound to `sid` and the initiator's message.
+fn start_transcript(sid: &[u8], initiator: &Message) -> Transcript {
+    let mut transcript = Transcript::new(CONTEXT_STRING);
+    transcript.append_message(b"sid", sid);
+    transcript.append_point(b"Y_a", &initiator.0);
+
+    transcript
+}
+
+/// Computes the shared point `[4] (y * Y)`, absorbs it with the
+/// responder's message and derives the initiator's tag, the responder's
+/// tag and the session key, in this order. This returns `None` if the
+/// shared point is the identity.
+fn key_schedule(
+    mut transcript: Transcript,
+    y: &Fr,
+    theirs: &Message,
+    responder: &Message,
+) -> CtOption<([u8; 32], [u8; 32], SessionKey)> {
+    let shared = (ExtendedPoint::from(theirs.0) * y).mul_by_cofactor();
+    let is_valid = !shared.is_identity();
+    let mut shared = AffinePoint::from(shared).into_bytes();
+
+    transcript.append_point(b"Y_b", &responder.0);
+    transcript.append_message(b"K", &shared);
+    shared.zeroize();
+
+    let mut initiator_tag = [0u8; 32];
+    let mut responder_tag = [0u8; 32];
+    let mut key = [0u8; 64];
+    transcript.challenge_bytes(b"initiator confirmation", &mut initiator_tag);
+    transcript.challenge_bytes(b"responder confirmation", &mut responder_tag);
+    transcript.challenge_bytes(b"session key", &mut key);
+
+    CtOption::new((initiator_tag, responder_tag, SessionKey(key)), is_valid)
+}
+
+impl Initiator {
+    /// Starts an exchange for `password` on the channel `ci` with the
+    /// session identifier `sid`, returning the initiator's state and the
+    /// message to send to the responder.
+    pub fn start<R: RngCore + CryptoRng>(
+        password: &[u8],
+        ci: &[u8],
+        sid: &[u8],
+        rng: &mut R,
+    ) -> (Initiator, Message) {
+        let (y, message) = share(password, ci, sid, rng);
+        let transcript = start_transcript(sid, &message);
+
+        (Initiator { y, transcript }, message)
+    }
+
+    /// Processes the responder's message and confirmation tag, returning
+    /// the session key and the tag to send to the responder. This returns
+    /// `None` if the shared point is the identity or the responder's tag
+    /// is invalid, which happens when the passwords differ.
+    pub fn finish(
+        self,
+        message: &Message,
+        confirmation: &Confirmation,
+    ) -> Option<(SessionKey, Confirmation)> {
+        let res = key_schedule(self.transcript.clone(), &self.y, message, message);
+        if !bool::from(res.is_some()) {
+            return None;
+        }
+
+        let (initiator_tag, mut responder_tag, key) = res.unwrap();
+        let is_valid = responder_tag.ct_eq(&confirmation.0);
+        responder_tag.zeroize();
+
+        if bool::from(is_valid) {
+            Some((key, Confirmation(initiator_tag)))
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for Initiator {
+    fn drop(&mut self) {
+        self.y.zeroize();
+    }
+}
+
+impl fmt::Debug for Initiator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Initiator").finish()
+    }
+}
+
+impl Responder {
+    /// Responds to the initiator's `message` for `password` on the
+    /// channel `ci` with the session identifier `sid`, returning the
+    /// responder's state and the message and confirmation tag to send to
+    /// the initiator. This returns `None` if the shared point is the
+    /// identity.
+    pub fn respond<R: RngCore + CryptoRng>(
+        password: &[u8],
+        ci: &[u8],
+        sid: &[u8],
+        message: &Message,
+        rng: &mut R,
+    ) -> Option<(Responder, Message, Confirmation)> {
+        let (mut y, response) = share(password, ci, sid, rng);
+        let res = key_schedule(start_transcript(sid, message), &y, message, &response);
+        y.zeroize();
+        if !bool::from(res.is_some()) {
+            return None;
+        }
+
+        let (expected, responder_tag, key) = res.unwrap();
+
+        Some((
+            Responder { expected, key },
+            response,
+            Confirmation(responder_tag),
+        ))
+    }
+
+    /// Checks the initiator's confirmation tag, returning the session
+    /// key if it is valid.
+    pub fn finish(self, confirmation: &Confirmation) -> Option<SessionKey> {
+        if bool::from(self.expected.ct_eq(&confirmation.0)) {
+            Some(SessionKey(self.key.0))
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for Responder {
+    fn drop(&mut self) {
+        self.expected.zeroize();
+    }
+}
+
+impl fmt::Debug for Responder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Responder").finish()
+    }
+}
+
+impl Message {
+    /// Attempts to interpret a byte representation of a message, failing
+    /// if it is not the canonical encoding of a curve point.
+    ///
+    /// Small order components are not rejected here; they are cleared by
+    /// the cofactor multiplication, and a message of small order is
+    /// rejected when the shared point is computed.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<Message> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+
+        CtOption::new(Message(point.unwrap_or(AffinePoint::identity())), is_some)
+    }
+
+    /// Converts this message into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+impl Confirmation {
+    /// Interprets a byte representation of a confirmation tag.
+    pub fn from_bytes(bytes: [u8; 32]) -> Confirmation {
+        Confirmation(bytes)
+    }
+
+    /// Converts this confirmation tag into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl SessionKey {
+    /// Returns the bytes of this session key.
+    pub fn as_bytes(&self) -> &[u8; 64] {
+        &self.0
+    }
+}
+
+impl ConstantTimeEq for SessionKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl Drop for SessionKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SessionKey").finish()
+    }
+}
+
+#[test]
+fn test_exchange() {
+    let mut rng = crate::test_rng();
+
+    let (initiator, message) = Initiator::start(b"1234", b"alice bob", b"sid", &mut rng);
+    let (responder, response, confirmation) =
+        Responder::respond(b"1234", b"alice bob", b"sid", &message, &mut rng).unwrap();
+    let (initiator_key, confirmation) = initiator.finish(&response, &confirmation).unwrap();
+    let responder_key = responder.finish(&confirmation).unwrap();
+
+    assert!(bool::from(initiator_key.ct_eq(&responder_key)));
+}
+
+#[test]
+fn test_wrong_password() {
+    let mut rng = crate::test_rng();
+
+    let (initiator, message) = Initiator::start(b"1234", b"alice bob", b"sid", &mut rng);
+    let (_, response, confirmation) =
+        Responder::respond(b"1235", b"alice bob", b"sid", &message, &mut rng).unwrap();
+    assert!(initiator.finish(&response, &confirmation).is_none());
+
+    // A responder rejects the tag of another exchange.
+    let (initiator, message) = Initiator::start(b"1234", b"alice bob", b"sid", &mut rng);
+    let (_, response, confirmation) =
+        Responder::respond(b"1234", b"alice bob", b"sid", &message, &mut rng).unwrap();
+    let (_, confirmation) = initiator.finish(&response, &confirmation).unwrap();
+    let (_, message) = Initiator::start(b"1234", b"alice bob", b"sid", &mut rng);
+    let (responder, _, _) =
+        Responder::respond(b"1234", b"alice bob", b"sid", &message, &mut rng).unwrap();
+    assert!(responder.finish(&confirmation).is_none());
+}
+
+#[test]
+fn test_small_order() {
+    let mut rng = crate::test_rng();
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+
+    for point in [AffinePoint::identity(), two_torsion].iter() {
+        let message = Message(*point);
+        assert!(Responder::respond(b"1234", b"ci", b"sid", &message, &mut rng).is_none());
+
+        let (initiator, _) = Initiator::start(b"1234", b"ci", b"sid", &mut rng);
+        assert!(initiator
+            .finish(&message, &Confirmation([0u8; 32]))
+            .is_none());
+    }
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let (_, message) = Initiator::start(b"1234", b"ci", b"sid", &mut rng);
+
+    assert_eq!(Message::from_bytes(message.into_bytes()).unwrap(), message);
+}
@@ -1,3 +1,4 @@
+use crate::affine::AffinePoint;
 use crate::fq::Fq;
 
 /// `d = -(86649/86650)`
@@ -10,14 +11,33 @@ pub const EDWARDS_D: Fq = Fq::from_raw([
 
 /// `2*d`
 pub const EDWARDS_D2: Fq = Fq::from_raw([
-    0x16b34ccf69bf0be0,
-    0x37e44f22cc2babfe,
-    0x17529def406b3085,
-    0x06263f0b3951c45b,
+    0x2806acbeb51aa65a,
+    0xe5e0ec42c8d005f7,
+    0x37579322bff446e1,
+    0x05508b11f7ffdbb2,
 ]);
 
-/// byte representation of the scalar modulus
+/// little-endian byte representation of the scalar modulus
 pub const FR_MODULUS_BYTES: [u8; 32] = [
-    1, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 244, 155, 43,
-    240, 228, 159, 88, 215, 38, 169, 211, 222, 53, 183, 161, 231,
+    41, 166, 11, 195, 208, 137, 181, 222, 188, 28, 61, 136, 13, 37, 57, 33, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 4,
 ];
+
+/// A generator of the prime order subgroup. This is the cofactor
+/// multiple `[4] P` of the first point `P` obtained by decoding the
+/// little-endian trial encodings `[i, 0, ..., 0]` for `i = 0, 1, ...`
+/// which is not of small order.
+pub const GENERATOR: AffinePoint = AffinePoint::from_raw_unchecked(
+    Fq::from_raw([
+        0xab73960fdedda7e5,
+        0xbc189d25a0a552ac,
+        0x37da65d346bf8eac,
+        0x0e39b4d47b5cdffe,
+    ]),
+    Fq::from_raw([
+        0xc5816fa12a7dc65b,
+        0x7d342ff34876ae82,
+        0xb69110d4bee3bbc9,
+        0x0b98b0a17ab8f927,
+    ]),
+);
@@ -0,0 +1,713 @@
+//! This module implements Pedersen's distributed key generation with
+//! Feldman commitments, proofs of knowledge of the dealt secrets, and
+//! complaint handling in the style of Gennaro et al.
+//!
+//! Each of the `n` participants (with indices `1..=n`) acts as a dealer:
+//!
+//! 1. [`Round1::new`] samples a random polynomial `f_i` of degree
+//!    `t - 1` and broadcasts a [`Round1Package`] containing the Feldman
+//!    commitment to `f_i` and a Schnorr proof of knowledge of `f_i(0)`.
+//! 2. [`Round1::finalize`] checks every received package, disqualifying
+//!    dealers with invalid packages or commitments with a small order
+//!    component, and returns the [`Round2Package`]s
+//!    holding `f_i(j)`, to be sent privately to each participant `j`.
+//! 3. [`Round2::finalize`] checks every received share against its
+//!    dealer's commitment and returns a [`Complaint`] against each dealer
+//!    whose share is missing or invalid, to be broadcast.
+//! 4. [`Round3::respond`] answers each complaint against this participant
+//!    by revealing the disputed share, and [`Round3::finalize`] checks the
+//!    responses to every complaint, disqualifying dealers that fail to
+//!    answer with a valid share. The secret share of participant `j` is
+//!    the sum of `f_i(j)` over the remaining dealers, and the group public
+//!    key is the sum of their committed constant terms.
+//!
+//! Broadcast messages must be delivered consistently to all participants
+//! so that they agree on the set of qualified dealers, and
+//! [`Round2Package`]s must be sent over confidential channels. Each round
+//! is consumed when it is finalized.
+//!
+//! The proof of knowledge is `(R, mu)` with `R = k * G`,
+//! `mu = k + c * f_i(0)` and
+//! `c = Fr::from_bytes_wide(SHA-512("DOPPIO-DKG-SHA512-v1" || "pok" || i || C_i0 || R))`,
+//! where `i` is encoded as 4 little-endian bytes, and it is verified with
+//! the cofactor. Messages are encoded with [`AffinePoint::into_bytes`],
+//! [`Fr::into_bytes`] and little-endian participant indices.
+
+use core::fmt;
+use std::collections::BTreeMap;
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_scalar;
+use crate::helpers::{read_point, read_scalar};
+use crate::schnorr::nonce_generate;
+use crate::sharing::{FeldmanCommitment, Polynomial, Share};
+use crate::{AffinePoint, ExtendedPoint, Fr, GENERATOR};
+
+/// The context string prefixed to every hash computed by this module.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-DKG-SHA512-v1";
+
+/// Computes the challenge of the proof of knowledge of dealer `index`.
+fn pok_challenge(index: u32, constant: &ExtendedPoint, r: &AffinePoint) -> Fr {
+    hash_to_scalar(&[
+        CONTEXT_STRING,
+        b"pok",
+        &index.to_le_bytes(),
+        &AffinePoint::from(*constant).into_bytes(),
+        &r.into_bytes(),
+    ])
+}
+
+/// Reads a little-endian `u32` from the first four bytes of `bytes`.
+fn read_index(bytes: &[u8]) -> u32 {
+    let mut index = [0u8; 4];
+    index.copy_from_slice(&bytes[..4]);
+    u32::from_le_bytes(index)
+}
+
+/// The broadcast message of a dealer in the first round.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round1Package {
+    sender: u32,
+    commitment: FeldmanCommitment,
+    proof_r: AffinePoint,
+    proof_mu: Fr,
+}
+
+impl Round1Package {
+    /// Returns the index of the dealer that sent this package.
+    pub fn sender(&self) -> u32 {
+        self.sender
+    }
+
+    /// Returns the dealer's commitment to its polynomial.
+    pub fn commitment(&self) -> &FeldmanCommitment {
+        &self.commitment
+    }
+
+    /// Checks that every point of the commitment is in the prime order
+    /// subgroup, up to the identity.
+    fn is_torsion_free(&self) -> bool {
+        self.commitment
+            .points()
+            .iter()
+            .all(|p| bool::from(p.is_torsion_free()))
+    }
+
+    /// Checks the proof of knowledge of the committed constant term.
+    fn verify_proof(&self) -> bool {
+        let constant = self.commitment.public_key();
+        let c = pok_challenge(self.sender, &constant, &self.proof_r);
+        let lhs = ExtendedPoint::from(GENERATOR) * self.proof_mu;
+        let rhs = ExtendedPoint::from(self.proof_r) + constant * c;
+
+        bool::from((lhs - rhs).mul_by_cofactor().is_identity())
+            && !bool::from(constant.is_small_order())
+    }
+
+    /// Attempts to interpret the byte representation of a package, which
+    /// is the sender's index, the encoded commitment, and the proof
+    /// `R || mu`.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Round1Package> {
+        if bytes.len() < 4 + 32 + 64 {
+            return None;
+        }
+        let (commitment, mut proof) = bytes[4..].split_at(bytes.len() - 4 - 64);
+
+        Some(Round1Package {
+            sender: read_index(bytes),
+            commitment: FeldmanCommitment::from_bytes(commitment)?,
+            proof_r: read_point(&mut proof)?,
+            proof_mu: read_scalar(&mut proof)?,
+        })
+    }
+
+    /// Converts this package into its byte representation.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        let mut res = self.sender.to_le_bytes().to_vec();
+        res.extend_from_slice(&self.commitment.into_bytes());
+        res.extend_from_slice(&self.proof_r.into_bytes());
+        res.extend_from_slice(&self.proof_mu.into_bytes());
+
+        res
+    }
+}
+
+/// A share `f_i(j)` sent privately from dealer `i` to participant `j`,
+/// or revealed publicly in response to a complaint. The share is
+/// zeroized when dropped.
+#[derive(Clone)]
+pub struct Round2Package {
+    sender: u32,
+    receiver: u32,
+    share: Fr,
+}
+
+impl fmt::Debug for Round2Package {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Round2Package")
+            .field("sender", &self.sender)
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
+
+impl Drop for Round2Package {
+    fn drop(&mut self) {
+        self.share.zeroize();
+    }
+}
+
+impl Round2Package {
+    /// Returns the index of the dealer that sent this package.
+    pub fn sender(&self) -> u32 {
+        self.sender
+    }
+
+    /// Returns the index of the participant this package is for.
+    pub fn receiver(&self) -> u32 {
+        self.receiver
+    }
+
+    /// Attempts to interpret the 40-byte representation of a package,
+    /// which is the sender's and receiver's indices followed by the share.
+    pub fn from_bytes(bytes: [u8; 40]) -> Option<Round2Package> {
+        Some(Round2Package {
+            sender: read_index(&bytes),
+            receiver: read_index(&bytes[4..]),
+            share: read_scalar(&mut &bytes[8..])?,
+        })
+    }
+
+    /// Converts this package into its 40-byte representation.
+    pub fn into_bytes(&self) -> [u8; 40] {
+        let mut res = [0u8; 40];
+        res[..4].copy_from_slice(&self.sender.to_le_bytes());
+        res[4..8].copy_from_slice(&self.receiver.to_le_bytes());
+        res[8..].copy_from_slice(&self.share.into_bytes());
+
+        res
+    }
+}
+
+/// A broadcast complaint by participant `complainer` that it did not
+/// receive a valid share from dealer `accused`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Complaint {
+    complainer: u32,
+    accused: u32,
+}
+
+impl Complaint {
+    /// Returns the index of the participant that raised this complaint.
+    pub fn complainer(&self) -> u32 {
+        self.complainer
+    }
+
+    /// Returns the index of the accused dealer.
+    pub fn accused(&self) -> u32 {
+        self.accused
+    }
+
+    /// Interprets the 8-byte representation of a complaint, which is the
+    /// complainer's and the accused dealer's indices.
+    pub fn from_bytes(bytes: [u8; 8]) -> Complaint {
+        Complaint {
+            complainer: read_index(&bytes),
+            accused: read_index(&bytes[4..]),
+        }
+    }
+
+    /// Converts this complaint into its 8-byte representation.
+    pub fn into_bytes(&self) -> [u8; 8] {
+        let mut res = [0u8; 8];
+        res[..4].copy_from_slice(&self.complainer.to_le_bytes());
+        res[4..].copy_from_slice(&self.accused.to_le_bytes());
+
+        res
+    }
+}
+
+/// The parameters and secret polynomial of a participant.
+struct Dealer {
+    index: u32,
+    threshold: usize,
+    n: u32,
+    polynomial: Polynomial,
+}
+
+impl Dealer {
+    /// Returns the share `f(j)` of this dealer's polynomial for `j`.
+    fn share_for(&self, receiver: u32) -> Round2Package {
+        Round2Package {
+            sender: self.index,
+            receiver,
+            share: *self.polynomial.share(receiver).value(),
+        }
+    }
+
+    /// Checks a share for this participant from a dealer with the given
+    /// commitment.
+    fn verify_share(&self, commitment: &FeldmanCommitment, package: &Round2Package) -> bool {
+        package.receiver == self.index && commitment.verify(&Share::new(self.index, package.share))
+    }
+}
+
+/// The first round of the key generation, in which dealers broadcast
+/// their commitments.
+pub struct Round1 {
+    dealer: Dealer,
+    package: Round1Package,
+}
+
+impl fmt::Debug for Round1 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Round1")
+            .field("index", &self.dealer.index)
+            .field("package", &self.package)
+            .finish()
+    }
+}
+
+impl Round1 {
+    /// Starts the key generation for participant `index` of `n` with the
+    /// given threshold, returning the state and the package to broadcast.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero or larger than `n`, or if `index` is
+    /// not in `1..=n`.
+    pub fn new<R: RngCore + CryptoRng>(
+        index: u32,
+        threshold: usize,
+        n: u32,
+        rng: &mut R,
+    ) -> (Round1, Round1Package) {
+        assert!(threshold > 0 && threshold <= n as usize);
+        assert!(index > 0 && index <= n);
+
+        let polynomial = Polynomial::random(&Fr::random(&mut *rng), threshold, rng);
+        let commitment = polynomial.commit();
+
+        let secret = polynomial.coefficients()[0];
+        let mut k = nonce_generate(CONTEXT_STRING, &secret, rng);
+        let proof_r = AffinePoint::from(ExtendedPoint::from(GENERATOR) * k);
+        let c = pok_challenge(index, &commitment.public_key(), &proof_r);
+        let proof_mu = k + c * secret;
+        k.zeroize();
+
+        let package = Round1Package {
+            sender: index,
+            commitment,
+            proof_r,
+            proof_mu,
+        };
+        let round = Round1 {
+            dealer: Dealer {
+                index,
+                threshold,
+                n,
+                polynomial,
+            },
+            package: package.clone(),
+        };
+
+        (round, package)
+    }
+
+    /// Finishes the first round with the packages broadcast by the other
+    /// dealers. Dealers whose package is missing, has the wrong threshold,
+    /// commits to a point outside the prime order subgroup or has an
+    /// invalid proof of knowledge are disqualified. This
+    /// returns the state for the second round and the shares to send
+    /// privately to each other participant.
+    pub fn finalize(self, packages: &[Round1Package]) -> (Round2, Vec<Round2Package>) {
+        let dealer = self.dealer;
+        let mut commitments = BTreeMap::new();
+        commitments.insert(dealer.index, self.package.commitment);
+
+        for i in (1..=dealer.n).filter(|i| *i != dealer.index) {
+            let package = packages.iter().find(|p| p.sender == i);
+            if let Some(package) = package {
+                if package.commitment.threshold() == dealer.threshold
+                    && package.is_torsion_free()
+                    && package.verify_proof()
+                {
+                    commitments.insert(i, package.commitment.clone());
+                }
+            }
+        }
+
+        let shares = (1..=dealer.n)
+            .filter(|j| *j != dealer.index)
+            .map(|j| dealer.share_for(j))
+            .collect();
+
+        (
+            Round2 {
+                dealer,
+                commitments,
+            },
+            shares,
+        )
+    }
+}
+
+/// The second round of the key generation, in which dealers send shares.
+pub struct Round2 {
+    dealer: Dealer,
+    commitments: BTreeMap<u32, FeldmanCommitment>,
+}
+
+impl fmt::Debug for Round2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Round2")
+            .field("index", &self.dealer.index)
+            .field("qualified", &self.qualified())
+            .finish()
+    }
+}
+
+impl Round2 {
+    /// Returns the indices of the dealers that are currently qualified.
+    pub fn qualified(&self) -> Vec<u32> {
+        self.commitments.keys().copied().collect()
+    }
+
+    /// Finishes the second round with the shares received from the other
+    /// dealers, returning the state for the third round and a complaint
+    /// against every qualified dealer whose share is missing or invalid.
+    pub fn finalize(self, packages: &[Round2Package]) -> (Round3, Vec<Complaint>) {
+        let dealer = self.dealer;
+        let mut shares = BTreeMap::new();
+        let mut complaints = Vec::new();
+
+        for (i, commitment) in self.commitments.iter() {
+            let package = if *i == dealer.index {
+                Some(dealer.share_for(dealer.index))
+            } else {
+                packages.iter().find(|p| p.sender == *i).cloned()
+            };
+
+            match package {
+                Some(package) if dealer.verify_share(commitment, &package) => {
+                    shares.insert(*i, package);
+                }
+                _ => complaints.push(Complaint {
+                    complainer: dealer.index,
+                    accused: *i,
+                }),
+            }
+        }
+
+        (
+            Round3 {
+                dealer,
+                commitments: self.commitments,
+                shares,
+            },
+            complaints,
+        )
+    }
+}
+
+/// The third round of the key generation, in which complaints are
+/// resolved.
+pub struct Round3 {
+    dealer: Dealer,
+    commitments: BTreeMap<u32, FeldmanCommitment>,
+    shares: BTreeMap<u32, Round2Package>,
+}
+
+impl fmt::Debug for Round3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Round3")
+            .field("index", &self.dealer.index)
+            .field("qualified", &self.commitments.keys())
+            .finish()
+    }
+}
+
+impl Round3 {
+    /// Answers every complaint against this participant by revealing the
+    /// share for the complainer, to be broadcast.
+    pub fn respond(&self, complaints: &[Complaint]) -> Vec<Round2Package> {
+        complaints
+            .iter()
+            .filter(|c| c.accused == self.dealer.index)
+            .filter(|c| c.complainer >= 1 && c.complainer <= self.dealer.n)
+            .map(|c| self.dealer.share_for(c.complainer))
+            .collect()
+    }
+
+    /// Finishes the key generation with all broadcast complaints and
+    /// responses. Every accused dealer that did not reveal a valid share
+    /// for the complainer is disqualified. This returns `None` if this
+    /// participant lacks a valid share from a qualified dealer, which
+    /// only happens if the broadcast messages are inconsistent.
+    pub fn finalize(
+        mut self,
+        complaints: &[Complaint],
+        responses: &[Round2Package],
+    ) -> Option<KeyShare> {
+        for complaint in complaints {
+            if complaint.complainer == 0 || complaint.complainer > self.dealer.n {
+                continue;
+            }
+            let commitment = match self.commitments.get(&complaint.accused) {
+                Some(commitment) => commitment,
+                None => continue,
+            };
+
+            let response = responses
+                .iter()
+                .find(|r| r.sender == complaint.accused && r.receiver == complaint.complainer);
+            let share = response.map(|r| Share::new(r.receiver, r.share));
+
+            match (response, share) {
+                (Some(response), Some(share)) if commitment.verify(&share) => {
+                    if complaint.complainer == self.dealer.index {
+                        self.shares.insert(complaint.accused, response.clone());
+                    }
+                }
+                _ => {
+                    self.commitments.remove(&complaint.accused);
+                }
+            }
+        }
+
+        let mut value = Fr::zero();
+        for i in self.commitments.keys() {
+            value += self.shares.get(i)?.share;
+        }
+        let commitment = self
+            .commitments
+            .values()
+            .skip(1)
+            .fold(self.commitments.values().next()?.clone(), |acc, c| {
+                acc.combine(c)
+            });
+
+        Some(KeyShare {
+            share: Share::new(self.dealer.index, value),
+            commitment,
+            qualified: self.commitments.keys().copied().collect(),
+        })
+    }
+}
+
+/// The output of the key generation for a participant: its secret share
+/// and the combined commitment of the qualified dealers, from which the
+/// group public key and the verification key of every participant follow.
+#[derive(Clone, Debug)]
+pub struct KeyShare {
+    share: Share,
+    commitment: FeldmanCommitment,
+    qualified: Vec<u32>,
+}
+
+impl KeyShare {
+    /// Returns this participant's secret share.
+    pub fn share(&self) -> &Share {
+        &self.share
+    }
+
+    /// Returns the sum of the qualified dealers' commitments, which is a
+    /// Feldman commitment to the shared polynomial.
+    pub fn commitment(&self) -> &FeldmanCommitment {
+        &self.commitment
+    }
+
+    /// Returns the indices of the qualified dealers.
+    pub fn qualified(&self) -> &[u32] {
+        &self.qualified
+    }
+
+    /// Returns the group public key `s * G`.
+    pub fn group_public_key(&self) -> ExtendedPoint {
+        self.commitment.public_key()
+    }
+
+    /// Returns the verification key `s_j * G` of participant `j`.
+    pub fn verification_key(&self, index: u32) -> ExtendedPoint {
+        self.commitment.evaluate(index)
+    }
+}
+
+#[cfg(test)]
+fn run_rounds_one_and_two(
+    threshold: usize,
+    n: u32,
+    rng: &mut rand_chacha::ChaChaRng,
+) -> (Vec<Round2>, Vec<Round2Package>) {
+    let (rounds, packages): (Vec<Round1>, Vec<Round1Package>) = (1..=n)
+        .map(|i| Round1::new(i, threshold, n, &mut *rng))
+        .unzip();
+
+    let mut shares = Vec::new();
+    let rounds = rounds
+        .into_iter()
+        .map(|round| {
+            let (round, mut sent) = round.finalize(&packages);
+            shares.append(&mut sent);
+            round
+        })
+        .collect();
+
+    (rounds, shares)
+}
+
+#[cfg(test)]
+fn run_round_three(
+    rounds: Vec<Round2>,
+    shares: &[Round2Package],
+    respond: impl Fn(&Round3, &[Complaint]) -> Vec<Round2Package>,
+) -> Vec<KeyShare> {
+    let mut complaints = Vec::new();
+    let rounds: Vec<Round3> = rounds
+        .into_iter()
+        .map(|round| {
+            let index = round.dealer.index;
+            let received: Vec<Round2Package> = shares
+                .iter()
+                .filter(|s| s.receiver == index)
+                .cloned()
+                .collect();
+            let (round, mut raised) = round.finalize(&received);
+            complaints.append(&mut raised);
+            round
+        })
+        .collect();
+
+    let responses: Vec<Round2Package> = rounds
+        .iter()
+        .flat_map(|round| respond(round, &complaints))
+        .collect();
+
+    rounds
+        .into_iter()
+        .map(|round| round.finalize(&complaints, &responses).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_dkg() {
+    let mut rng = crate::test_rng();
+    let (rounds, shares) = run_rounds_one_and_two(3, 5, &mut rng);
+    let outputs = run_round_three(rounds, &shares, |round, c| round.respond(c));
+
+    let group_key = outputs[0].group_public_key();
+    for output in outputs.iter() {
+        assert_eq!(output.qualified(), &[1, 2, 3, 4, 5]);
+        assert_eq!(output.group_public_key(), group_key);
+        assert!(output.commitment().verify(output.share()));
+        assert_eq!(
+            output.verification_key(output.share().index()),
+            ExtendedPoint::from(GENERATOR) * output.share().value()
+        );
+    }
+
+    let shares: Vec<Share> = outputs.iter().map(|o| o.share().clone()).collect();
+    let secret = crate::sharing::reconstruct(&shares[1..4]).unwrap();
+    assert_eq!(crate::sharing::reconstruct(&shares[..3]), Some(secret));
+    assert_eq!(ExtendedPoint::from(GENERATOR) * secret, group_key);
+}
+
+#[test]
+fn test_complaints() {
+    let mut rng = crate::test_rng();
+    let (rounds, mut shares) = run_rounds_one_and_two(2, 4, &mut rng);
+
+    // Dealer 2 sends a bad share to participant 3 but answers the
+    // complaint honestly, while dealer 4 sends a bad share to participant
+    // 1 and does not answer.
+    for s in shares.iter_mut() {
+        if (s.sender, s.receiver) == (2, 3) || (s.sender, s.receiver) == (4, 1) {
+            s.share += Fr::one();
+        }
+    }
+    let outputs = run_round_three(rounds, &shares, |round, c| {
+        if round.dealer.index == 4 {
+            Vec::new()
+        } else {
+            round.respond(c)
+        }
+    });
+
+    for output in outputs.iter() {
+        assert_eq!(output.qualified(), &[1, 2, 3]);
+        assert_eq!(output.group_public_key(), outputs[0].group_public_key());
+        assert!(output.commitment().verify(output.share()));
+    }
+}
+
+#[test]
+fn test_invalid_round1_package() {
+    let mut rng = crate::test_rng();
+    let (round, _) = Round1::new(1, 2, 3, &mut rng);
+    let (_, mut bad_proof) = Round1::new(2, 2, 3, &mut rng);
+    bad_proof.proof_mu += Fr::one();
+    let (_, wrong_threshold) = Round1::new(3, 3, 3, &mut rng);
+
+    let (round, shares) = round.finalize(&[bad_proof, wrong_threshold]);
+    assert_eq!(round.qualified(), &[1]);
+    assert_eq!(shares.len(), 2);
+}
+
+#[test]
+fn test_torsion_commitment() {
+    let mut rng = crate::test_rng();
+    let torsion = ExtendedPoint::from(AffinePoint::from_raw_unchecked(
+        crate::Fq::zero(),
+        -crate::Fq::one(),
+    ));
+    let (round, _) = Round1::new(1, 2, 3, &mut rng);
+
+    // The proof is redone for the tweaked constant term, so only the
+    // subgroup check can catch it.
+    let (dealer, mut tweaked_constant) = Round1::new(2, 2, 3, &mut rng);
+    let mut points = tweaked_constant.commitment.points().to_vec();
+    points[0] += torsion;
+    tweaked_constant.commitment = FeldmanCommitment::from_points(points);
+    let k = Fr::random(&mut rng);
+    tweaked_constant.proof_r = AffinePoint::from(ExtendedPoint::from(GENERATOR) * k);
+    let c = pok_challenge(
+        2,
+        &tweaked_constant.commitment.public_key(),
+        &tweaked_constant.proof_r,
+    );
+    tweaked_constant.proof_mu = k + c * dealer.dealer.polynomial.coefficients()[0];
+    assert!(tweaked_constant.verify_proof());
+
+    let (_, mut tweaked_coefficient) = Round1::new(3, 2, 3, &mut rng);
+    let mut points = tweaked_coefficient.commitment.points().to_vec();
+    points[1] += torsion;
+    tweaked_coefficient.commitment = FeldmanCommitment::from_points(points);
+    assert!(tweaked_coefficient.verify_proof());
+
+    let (round, _) = round.finalize(&[tweaked_constant, tweaked_coefficient]);
+    assert_eq!(round.qualified(), &[1]);
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let (round, package) = Round1::new(1, 3, 3, &mut rng);
+    assert_eq!(
+        Round1Package::from_bytes(&package.into_bytes()),
+        Some(package.clone())
+    );
+    assert_eq!(Round1Package::from_bytes(&package.into_bytes()[1..]), None);
+
+    let (_, shares) = round.finalize(&[]);
+    let decoded = Round2Package::from_bytes(shares[0].into_bytes()).unwrap();
+    assert_eq!(decoded.into_bytes(), shares[0].into_bytes());
+
+    let complaint = Complaint {
+        complainer: 2,
+        accused: 7,
+    };
+    assert_eq!(Complaint::from_bytes(complaint.into_bytes()), complaint);
+}
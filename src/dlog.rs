@@ -0,0 +1,372 @@
+//! This module provides solvers for discrete logarithms `m` of points
+//! `m * G` where `m` is known to lie in a small interval `[0, bound)`,
+//! such as the plaintexts recovered by exponential ElGamal decryption.
+//!
+//! * [`BabyStepTable`] implements baby-step giant-step with a precomputed
+//!   table of `m_baby` points keyed by their encodings. Solving costs
+//!   about `bound / m_baby` point additions.
+//! * [`KangarooTable`] implements Pollard's kangaroo method with a table
+//!   of precomputed tame distinguished points, following Bernstein and
+//!   Lange, "Computing small discrete logarithms faster". Larger tables
+//!   make solving faster, at the cost of memory and precomputation.
+//!
+//! **Every operation in this module is variable time with respect to
+//! the logarithm being computed.**
+
+use core::fmt;
+use std::collections::HashMap;
+use std::vec::Vec;
+
+use crate::{batch_normalize, AffineNielsPoint, AffinePoint, ExtendedPoint, Fr, GENERATOR};
+
+/// The number of points normalized together with a single inversion.
+const BATCH_SIZE: usize = 256;
+
+/// A precomputed table of baby steps `j * G` for `0 <= j < m_baby`, for
+/// solving discrete logarithms with baby-step giant-step.
+pub struct BabyStepTable {
+    table: HashMap<[u8; 32], u64>,
+    baby_steps: u64,
+    giant_step: AffineNielsPoint,
+}
+
+impl fmt::Debug for BabyStepTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BabyStepTable")
+            .field("baby_steps", &self.baby_steps)
+            .finish()
+    }
+}
+
+impl BabyStepTable {
+    /// Precomputes a table of `baby_steps` points. To solve logarithms
+    /// below `bound` with the fewest additions, choose `baby_steps`
+    /// close to the square root of `bound`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `baby_steps` is zero.
+    pub fn new(baby_steps: u64) -> BabyStepTable {
+        assert!(baby_steps > 0);
+
+        let mut table = HashMap::with_capacity(baby_steps as usize);
+        let generator = GENERATOR.to_niels();
+        let mut acc = ExtendedPoint::identity();
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+        let mut start = 0;
+        while start < baby_steps {
+            batch.clear();
+            let len = core::cmp::min(BATCH_SIZE as u64, baby_steps - start);
+            for _ in 0..len {
+                batch.push(acc);
+                acc += generator;
+            }
+
+            for (j, p) in batch_normalize(&mut batch).enumerate() {
+                table.entry(p.into_bytes()).or_insert(start + j as u64);
+            }
+            start += len;
+        }
+
+        // At this point acc = baby_steps * G.
+        let giant_step = AffinePoint::from(-acc).to_niels();
+
+        BabyStepTable {
+            table,
+            baby_steps,
+            giant_step,
+        }
+    }
+
+    /// Returns the number of baby steps in this table.
+    pub fn baby_steps(&self) -> u64 {
+        self.baby_steps
+    }
+
+    /// Finds `m < bound` such that `target = m * G`, if it exists.
+    ///
+    /// **This operation is variable time.**
+    pub fn solve_vartime(&self, target: &ExtendedPoint, bound: u64) -> Option<u64> {
+        let giant_steps = bound.div_ceil(self.baby_steps);
+        let mut acc = *target;
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+        let mut start = 0;
+        while start < giant_steps {
+            batch.clear();
+            let len = core::cmp::min(BATCH_SIZE as u64, giant_steps - start);
+            for _ in 0..len {
+                batch.push(acc);
+                acc += self.giant_step;
+            }
+
+            for (i, p) in batch_normalize(&mut batch).enumerate() {
+                if let Some(j) = self.table.get(&p.into_bytes()) {
+                    // This can only overflow when m is beyond the bound.
+                    let m = (start + i as u64)
+                        .checked_mul(self.baby_steps)
+                        .and_then(|m| m.checked_add(*j));
+                    return m.filter(|m| *m < bound);
+                }
+            }
+            start += len;
+        }
+
+        None
+    }
+}
+
+/// A deterministic, non-cryptographic generator (SplitMix64) used to
+/// choose jump sizes and starting points, which need not be secret
+/// since the solver is variable time anyway.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+/// The number of distinct jumps used by the kangaroo walks.
+const NUM_JUMPS: usize = 64;
+
+/// The number of kangaroos walked together, so that the normalization
+/// of their positions can be batched.
+const NUM_KANGAROOS: usize = 32;
+
+/// The largest bound supported by [`KangarooTable`]. Positions of
+/// kangaroos are tracked in a `u64` and can reach about `9 * bound`
+/// before a walk is abandoned.
+pub const MAX_KANGAROO_BOUND: u64 = 1 << 60;
+
+/// A kangaroo at `point`, which is `distance * G` past its starting point.
+#[derive(Clone, Copy)]
+struct Kangaroo {
+    point: ExtendedPoint,
+    start: u64,
+    distance: u64,
+    steps: u64,
+}
+
+/// A kangaroo that has finished its walk.
+struct Finished {
+    index: usize,
+    start: u64,
+    distance: u64,
+}
+
+/// A table of precomputed tame distinguished points for solving discrete
+/// logarithms with Pollard's kangaroo method.
+pub struct KangarooTable {
+    table: HashMap<[u8; 32], u64>,
+    bound: u64,
+    dp_mask: u64,
+    max_walk: u64,
+    jumps: Vec<(u64, AffineNielsPoint)>,
+}
+
+impl fmt::Debug for KangarooTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KangarooTable")
+            .field("bound", &self.bound)
+            .field("dp_mask", &self.dp_mask)
+            .field("table_size", &self.table.len())
+            .finish()
+    }
+}
+
+/// Interprets a point encoding as the hash used to select jumps and
+/// identify distinguished points.
+fn walk_hash(encoding: &[u8; 32]) -> u64 {
+    let mut h = [0u8; 8];
+    h.copy_from_slice(&encoding[..8]);
+    u64::from_le_bytes(h)
+}
+
+impl KangarooTable {
+    /// Precomputes a table for logarithms in `[0, bound)` containing
+    /// `table_size` tame distinguished points, where a point is
+    /// distinguished with probability `2^-dp_bits`.
+    ///
+    /// Walks have an average length of about `2^dp_bits`, so
+    /// precomputation costs about `table_size * 2^dp_bits` additions.
+    /// Solving costs roughly `sqrt(bound / table_size)` additions, as
+    /// long as `table_size * 2^(2 * dp_bits)` is not much larger than
+    /// `bound`; beyond that point the table mostly holds duplicates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is zero or larger than [`MAX_KANGAROO_BOUND`],
+    /// or `dp_bits` is larger than 32.
+    pub fn new(bound: u64, dp_bits: u32, table_size: usize) -> KangarooTable {
+        assert!(bound > 0 && bound <= MAX_KANGAROO_BOUND);
+        assert!(dp_bits <= 32);
+
+        let mut rng = SplitMix64(bound ^ (u64::from(dp_bits) << 56));
+        let walk_length = 1u64 << dp_bits;
+
+        // Jumps average about bound / (4 * walk_length).
+        let mean = core::cmp::max(1, bound / (4 * walk_length));
+        let jumps = (0..NUM_JUMPS)
+            .map(|_| {
+                let size = 1 + rng.below(2 * mean);
+                let point = AffinePoint::from(ExtendedPoint::from(GENERATOR) * Fr::from(size));
+                (size, point.to_niels())
+            })
+            .collect();
+
+        let mut table = KangarooTable {
+            table: HashMap::with_capacity(table_size),
+            bound,
+            dp_mask: walk_length - 1,
+            max_walk: 16 * walk_length,
+            jumps,
+        };
+
+        let spawn = |rng: &mut SplitMix64| {
+            let start = rng.below(bound);
+            Kangaroo {
+                point: ExtendedPoint::from(GENERATOR) * Fr::from(start),
+                start,
+                distance: 0,
+                steps: 0,
+            }
+        };
+
+        let mut tame: Vec<Kangaroo> = (0..NUM_KANGAROOS).map(|_| spawn(&mut rng)).collect();
+        let mut attempts = 0;
+        while table.table.len() < table_size && attempts < 64 * table_size + NUM_KANGAROOS {
+            for (kangaroo, dp) in table.step(&mut tame) {
+                attempts += 1;
+                if let Some(encoding) = dp {
+                    table
+                        .table
+                        .insert(encoding, kangaroo.start + kangaroo.distance);
+                }
+                tame[kangaroo.index] = spawn(&mut rng);
+            }
+        }
+
+        table
+    }
+
+    /// Advances every kangaroo by one jump, returning the kangaroos
+    /// that finished their walk along with the distinguished point they
+    /// reached, if any.
+    fn step(&self, kangaroos: &mut [Kangaroo]) -> Vec<(Finished, Option<[u8; 32]>)> {
+        let mut points: Vec<ExtendedPoint> = kangaroos.iter().map(|k| k.point).collect();
+        let mut finished = Vec::new();
+
+        for (index, (kangaroo, p)) in kangaroos
+            .iter_mut()
+            .zip(batch_normalize(&mut points))
+            .enumerate()
+        {
+            let encoding = p.into_bytes();
+            let h = walk_hash(&encoding);
+            let done = Finished {
+                index,
+                start: kangaroo.start,
+                distance: kangaroo.distance,
+            };
+
+            if (h >> 32) & self.dp_mask == 0 {
+                finished.push((done, Some(encoding)));
+            } else if kangaroo.steps >= self.max_walk {
+                // This walk is probably stuck in a cycle.
+                finished.push((done, None));
+            } else {
+                let (size, ref jump) = self.jumps[(h as usize) % NUM_JUMPS];
+                kangaroo.point += jump;
+                kangaroo.distance += size;
+                kangaroo.steps += 1;
+            }
+        }
+
+        finished
+    }
+
+    /// Returns the number of tame distinguished points in this table.
+    pub fn table_size(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Finds `m < bound` such that `target = m * G`, if it exists,
+    /// giving up after `max_walks` wild kangaroo walks.
+    ///
+    /// **This operation is variable time.**
+    pub fn solve_vartime(&self, target: &ExtendedPoint, max_walks: usize) -> Option<u64> {
+        let mut rng = SplitMix64(walk_hash(&AffinePoint::from(*target).into_bytes()));
+
+        let spawn = |rng: &mut SplitMix64| {
+            let start = rng.below(self.bound);
+            Kangaroo {
+                point: target + ExtendedPoint::from(GENERATOR) * Fr::from(start),
+                start,
+                distance: 0,
+                steps: 0,
+            }
+        };
+
+        let mut wild: Vec<Kangaroo> = (0..NUM_KANGAROOS).map(|_| spawn(&mut rng)).collect();
+        let mut walks = 0;
+        while walks < max_walks {
+            for (kangaroo, dp) in self.step(&mut wild) {
+                walks += 1;
+                if let Some(tame) = dp.and_then(|encoding| self.table.get(&encoding)) {
+                    // target + (start + distance) * G = tame * G
+                    let m = tame.checked_sub(kangaroo.start + kangaroo.distance);
+                    if let Some(m) = m.filter(|m| *m < self.bound) {
+                        if ExtendedPoint::from(GENERATOR) * Fr::from(m) == *target {
+                            return Some(m);
+                        }
+                    }
+                }
+                wild[kangaroo.index] = spawn(&mut rng);
+            }
+        }
+
+        None
+    }
+}
+
+#[test]
+fn test_baby_step_giant_step() {
+    let table = BabyStepTable::new(1 << 8);
+    let g = ExtendedPoint::from(GENERATOR);
+
+    for m in [0u64, 1, 255, 256, 257, 40_000, (1 << 16) - 1].iter() {
+        assert_eq!(table.solve_vartime(&(g * Fr::from(*m)), 1 << 16), Some(*m));
+    }
+
+    assert_eq!(table.solve_vartime(&(g * Fr::from(1 << 16)), 1 << 16), None);
+    assert_eq!(table.solve_vartime(&(g * Fr::from(300)), 300), None);
+    assert_eq!(table.solve_vartime(&(g * Fr::from(3)), u64::MAX), Some(3));
+}
+
+#[test]
+fn test_kangaroo() {
+    let bound = 1 << 24;
+    let table = KangarooTable::new(bound, 6, 512);
+    assert!(table.table_size() > 0);
+
+    let g = ExtendedPoint::from(GENERATOR);
+    for m in [0u64, 12_345_678, bound - 1].iter() {
+        assert_eq!(table.solve_vartime(&(g * Fr::from(*m)), 10_000), Some(*m));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_kangaroo_bound_too_large() {
+    KangarooTable::new(MAX_KANGAROO_BOUND + 1, 6, 1);
+}
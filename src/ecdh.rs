@@ -0,0 +1,228 @@
+//! This module provides Diffie-Hellman key agreement over the prime
+//! order subgroup of Doppio.
+//!
+//! Shared points are computed with cofactor multiplication, i.e. as
+//! `[4] (x * P)`, so that any small order component of the peer's public
+//! key is cleared and both parties always derive the same point. A
+//! shared point equal to the identity (which is what every small order
+//! public key produces) is rejected in constant time, and the resulting
+//! [`SharedSecret`] is the encoding of the shared point given by
+//! [`AffinePoint::into_bytes`](crate::AffinePoint::into_bytes). It is
+//! intended to be used as the input keying material of a KDF and should
+//! not be used directly as a key.
+//!
+//! All secret values are zeroized when dropped.
+
+use core::fmt;
+
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::{AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// A Diffie-Hellman public key, which is the point `x * G` for a
+/// secret scalar `x`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PublicKey(AffinePoint);
+
+/// A short-lived Diffie-Hellman secret key. This can only be used for
+/// a single key agreement, as [`diffie_hellman`](EphemeralSecret::diffie_hellman)
+/// consumes it.
+pub struct EphemeralSecret(Fr);
+
+/// A Diffie-Hellman secret key that can be used for any number of key
+/// agreements and can be serialized.
+#[derive(Clone)]
+pub struct StaticSecret(Fr);
+
+/// The result of a Diffie-Hellman key agreement, which is the encoding
+/// of the shared point.
+pub struct SharedSecret([u8; 32]);
+
+/// Computes the cofactor-multiplied shared point and wraps its encoding,
+/// returning `None` if it is the identity.
+fn shared_secret(secret: &Fr, their_public: &PublicKey) -> CtOption<SharedSecret> {
+    let shared = (ExtendedPoint::from(their_public.0) * secret).mul_by_cofactor();
+    let is_identity = shared.is_identity();
+
+    CtOption::new(
+        SharedSecret(AffinePoint::from(shared).into_bytes()),
+        !is_identity,
+    )
+}
+
+impl PublicKey {
+    /// Attempts to interpret a byte representation of a public key,
+    /// failing if it is not the canonical encoding of a curve point.
+    ///
+    /// Small order components are not rejected here; they are cleared
+    /// by the cofactor multiplication during key agreement, and a
+    /// public key of small order is rejected by `diffie_hellman`.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<PublicKey> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+
+        CtOption::new(PublicKey(point.unwrap_or(AffinePoint::identity())), is_some)
+    }
+
+    /// Converts this public key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+
+    /// Returns the point underlying this public key.
+    pub fn to_point(&self) -> AffinePoint {
+        self.0
+    }
+}
+
+impl ConstantTimeEq for PublicKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl<'a> From<&'a EphemeralSecret> for PublicKey {
+    fn from(secret: &'a EphemeralSecret) -> PublicKey {
+        PublicKey(AffinePoint::from(ExtendedPoint::from(GENERATOR) * secret.0))
+    }
+}
+
+impl<'a> From<&'a StaticSecret> for PublicKey {
+    fn from(secret: &'a StaticSecret) -> PublicKey {
+        PublicKey(AffinePoint::from(ExtendedPoint::from(GENERATOR) * secret.0))
+    }
+}
+
+impl EphemeralSecret {
+    /// Samples a new ephemeral secret from the provided RNG.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> EphemeralSecret {
+        EphemeralSecret(Fr::random(rng))
+    }
+
+    /// Performs a key agreement with `their_public`, consuming this
+    /// secret. This returns `None` if the shared point is the identity,
+    /// which happens exactly when `their_public` is of small order.
+    pub fn diffie_hellman(self, their_public: &PublicKey) -> CtOption<SharedSecret> {
+        shared_secret(&self.0, their_public)
+    }
+}
+
+impl Drop for EphemeralSecret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for EphemeralSecret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EphemeralSecret").finish()
+    }
+}
+
+impl StaticSecret {
+    /// Samples a new static secret from the provided RNG.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> StaticSecret {
+        StaticSecret(Fr::random(rng))
+    }
+
+    /// Attempts to interpret a byte representation of a static secret,
+    /// failing if it is not a canonical encoding of an element of `Fr`.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<StaticSecret> {
+        let scalar = Fr::from_bytes(bytes);
+        let is_some = scalar.is_some();
+
+        CtOption::new(StaticSecret(scalar.unwrap_or(Fr::zero())), is_some)
+    }
+
+    /// Converts this secret into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+
+    /// Performs a key agreement with `their_public`. This returns `None`
+    /// if the shared point is the identity, which happens exactly when
+    /// `their_public` is of small order.
+    pub fn diffie_hellman(&self, their_public: &PublicKey) -> CtOption<SharedSecret> {
+        shared_secret(&self.0, their_public)
+    }
+}
+
+impl Drop for StaticSecret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for StaticSecret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticSecret").finish()
+    }
+}
+
+impl SharedSecret {
+    /// Returns the encoding of the shared point, to be used as the
+    /// input keying material of a KDF.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl ConstantTimeEq for SharedSecret {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SharedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedSecret").finish()
+    }
+}
+
+#[test]
+fn test_key_agreement() {
+    let mut rng = crate::test_rng();
+
+    let alice = EphemeralSecret::new(&mut rng);
+    let alice_public = PublicKey::from(&alice);
+    let bob = StaticSecret::new(&mut rng);
+    let bob_public = PublicKey::from(&bob);
+
+    let bob_shared = bob.diffie_hellman(&alice_public).unwrap();
+    let alice_shared = alice.diffie_hellman(&bob_public).unwrap();
+
+    assert!(alice_shared.ct_eq(&bob_shared).unwrap_u8() == 1);
+}
+
+#[test]
+fn test_small_order_public_key_rejected() {
+    let mut rng = crate::test_rng();
+    let secret = StaticSecret::new(&mut rng);
+
+    let identity = PublicKey(AffinePoint::identity());
+    assert!(secret.diffie_hellman(&identity).is_none().unwrap_u8() == 1);
+
+    let two_torsion = PublicKey(AffinePoint::from_raw_unchecked(
+        crate::Fq::zero(),
+        -crate::Fq::one(),
+    ));
+    assert!(secret.diffie_hellman(&two_torsion).is_none().unwrap_u8() == 1);
+}
+
+#[test]
+fn test_static_secret_serialization() {
+    let mut rng = crate::test_rng();
+    let secret = StaticSecret::new(&mut rng);
+    let decoded = StaticSecret::from_bytes(secret.into_bytes()).unwrap();
+
+    assert_eq!(PublicKey::from(&secret), PublicKey::from(&decoded));
+    assert!(StaticSecret::from_bytes([0xff; 32]).is_none().unwrap_u8() == 1);
+}
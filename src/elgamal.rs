@@ -0,0 +1,396 @@
+//! This module implements exponential ElGamal encryption over the prime
+//! order subgroup of Doppio.
+//!
+//! A message `m` in `Fr` is encrypted under the public key `PK = x * G`
+//! as the ciphertext `(r * G, m * G + r * PK)` for random `r`. Points can
+//! also be encrypted directly. Ciphertexts are additively homomorphic:
+//! they can be added, subtracted and multiplied by scalars, and can be
+//! rerandomized without changing the underlying plaintext.
+//!
+//! Decryption recovers the point `m * G`. Recovering `m` itself requires
+//! solving a discrete logarithm, which is only feasible for small `m`.
+
+use core::fmt;
+use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::{batch_normalize, AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// An ElGamal secret key `x`.
+#[derive(Clone)]
+pub struct SecretKey(pub(crate) Fr);
+
+/// An ElGamal public key `x * G`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PublicKey(pub(crate) AffinePoint);
+
+/// An ElGamal ciphertext `(C1, C2) = (r * G, M + r * PK)`.
+#[derive(Clone, Copy, Debug)]
+pub struct Ciphertext {
+    pub(crate) c1: ExtendedPoint,
+    pub(crate) c2: ExtendedPoint,
+}
+
+impl SecretKey {
+    /// Samples a new secret key from the provided RNG.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> SecretKey {
+        SecretKey(Fr::random(rng))
+    }
+
+    /// Attempts to interpret a byte representation of a secret key,
+    /// failing if it is not a canonical encoding of an element of `Fr`.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<SecretKey> {
+        let scalar = Fr::from_bytes(bytes);
+        let is_some = scalar.is_some();
+
+        CtOption::new(SecretKey(scalar.unwrap_or(Fr::zero())), is_some)
+    }
+
+    /// Converts this secret key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+
+    /// Decrypts `ciphertext`, returning the point `M = C2 - x * C1`,
+    /// which is `m * G` for ciphertexts produced by [`PublicKey::encrypt`].
+    pub fn decrypt(&self, ciphertext: &Ciphertext) -> ExtendedPoint {
+        ciphertext.c2 - ciphertext.c1 * self.0
+    }
+
+    /// Decrypts `ciphertext` and attempts to recover a plaintext
+    /// `m < bound` from `m * G` by exhaustive search.
+    ///
+    /// **This operation is variable time with respect to the plaintext,
+    /// and its cost is linear in `m`.** For larger plaintexts, pass the
+    /// result of [`decrypt`](SecretKey::decrypt) to one of the solvers in
+    /// the `dlog` module instead.
+    pub fn decrypt_vartime(&self, ciphertext: &Ciphertext, bound: u64) -> Option<u64> {
+        let target = self.decrypt(ciphertext);
+        let generator = GENERATOR.to_niels();
+
+        let mut acc = ExtendedPoint::identity();
+        for m in 0..bound {
+            if acc == target {
+                return Some(m);
+            }
+            acc += generator;
+        }
+
+        None
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SecretKey").finish()
+    }
+}
+
+impl<'a> From<&'a SecretKey> for PublicKey {
+    fn from(sk: &'a SecretKey) -> PublicKey {
+        PublicKey(AffinePoint::from(ExtendedPoint::from(GENERATOR) * sk.0))
+    }
+}
+
+impl PublicKey {
+    /// Attempts to interpret a byte representation of a public key,
+    /// failing if it is not the canonical encoding of a curve point or
+    /// the point is not of prime order.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<PublicKey> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+
+        let point = point.unwrap_or(AffinePoint::identity());
+
+        CtOption::new(PublicKey(point), is_some & point.is_prime_order())
+    }
+
+    /// Converts this public key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+
+    /// Constructs a public key from a point, such as the group public key
+    /// produced by distributed key generation, failing if the point is
+    /// not of prime order.
+    ///
+    /// The identity and points with a small order component are
+    /// rejected: encrypting to the identity gives `C2 = m * G`, and a
+    /// torsion component `T` leaks `r * T` in every ciphertext.
+    pub fn from_point(point: AffinePoint) -> CtOption<PublicKey> {
+        CtOption::new(PublicKey(point), point.is_prime_order())
+    }
+
+    /// Encrypts `m` as `(r * G, m * G + r * PK)` for random `r`.
+    pub fn encrypt<R: RngCore + CryptoRng>(&self, m: &Fr, rng: &mut R) -> Ciphertext {
+        self.encrypt_point(&(ExtendedPoint::from(GENERATOR) * m), rng)
+    }
+
+    /// Encrypts the point `m` as `(r * G, m + r * PK)` for random `r`.
+    pub fn encrypt_point<R: RngCore + CryptoRng>(
+        &self,
+        m: &ExtendedPoint,
+        rng: &mut R,
+    ) -> Ciphertext {
+        let mut r = Fr::random(rng);
+        let ciphertext = self.encrypt_point_with(m, &r);
+        r.zeroize();
+
+        ciphertext
+    }
+
+    /// Encrypts the point `m` as `(r * G, m + r * PK)` using the
+    /// provided randomness `r`, which must be uniformly random and
+    /// never reused.
+    pub fn encrypt_point_with(&self, m: &ExtendedPoint, r: &Fr) -> Ciphertext {
+        Ciphertext {
+            c1: ExtendedPoint::from(GENERATOR) * r,
+            c2: m + ExtendedPoint::from(self.0) * r,
+        }
+    }
+}
+
+impl ConstantTimeEq for PublicKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl Ciphertext {
+    /// Returns the trivial encryption `(0, M)` of the point `M`, which
+    /// uses no randomness and so hides nothing until it is rerandomized.
+    pub fn trivial(m: &ExtendedPoint) -> Ciphertext {
+        Ciphertext {
+            c1: ExtendedPoint::identity(),
+            c2: *m,
+        }
+    }
+
+    /// Returns the ciphertext components `(C1, C2)`.
+    pub fn components(&self) -> (ExtendedPoint, ExtendedPoint) {
+        (self.c1, self.c2)
+    }
+
+    /// Rerandomizes this ciphertext under `pk` with fresh randomness,
+    /// producing an unlinkable encryption of the same plaintext.
+    pub fn rerandomize<R: RngCore + CryptoRng>(&self, pk: &PublicKey, rng: &mut R) -> Ciphertext {
+        let mut r = Fr::random(rng);
+        let ciphertext = self.rerandomize_with(pk, &r);
+        r.zeroize();
+
+        ciphertext
+    }
+
+    /// Rerandomizes this ciphertext under `pk` by adding an encryption
+    /// of the identity with randomness `r`.
+    pub fn rerandomize_with(&self, pk: &PublicKey, r: &Fr) -> Ciphertext {
+        self + pk.encrypt_point_with(&ExtendedPoint::identity(), r)
+    }
+
+    /// Attempts to interpret the 64-byte representation `C1 || C2` of
+    /// a ciphertext, failing if either point encoding is invalid.
+    pub fn from_bytes(bytes: [u8; 64]) -> CtOption<Ciphertext> {
+        let mut c1 = [0u8; 32];
+        let mut c2 = [0u8; 32];
+        c1.copy_from_slice(&bytes[..32]);
+        c2.copy_from_slice(&bytes[32..]);
+
+        let c1 = AffinePoint::from_bytes(c1);
+        let c2 = AffinePoint::from_bytes(c2);
+        let is_some = c1.is_some() & c2.is_some();
+
+        CtOption::new(
+            Ciphertext {
+                c1: ExtendedPoint::from(c1.unwrap_or(AffinePoint::identity())),
+                c2: ExtendedPoint::from(c2.unwrap_or(AffinePoint::identity())),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this ciphertext into its 64-byte representation
+    /// `C1 || C2`, using [`AffinePoint::into_bytes`] for each point.
+    pub fn into_bytes(&self) -> [u8; 64] {
+        let mut points = [self.c1, self.c2];
+        let mut res = [0u8; 64];
+        for (chunk, p) in res.chunks_mut(32).zip(batch_normalize(&mut points)) {
+            chunk.copy_from_slice(&p.into_bytes());
+        }
+
+        res
+    }
+}
+
+impl ConstantTimeEq for Ciphertext {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.c1.ct_eq(&other.c1) & self.c2.ct_eq(&other.c2)
+    }
+}
+
+impl PartialEq for Ciphertext {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1
+    }
+}
+
+impl ConditionallySelectable for Ciphertext {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Ciphertext {
+            c1: ExtendedPoint::conditional_select(&a.c1, &b.c1, choice),
+            c2: ExtendedPoint::conditional_select(&a.c2, &b.c2, choice),
+        }
+    }
+}
+
+impl Default for Ciphertext {
+    /// Returns the trivial encryption of the identity.
+    fn default() -> Ciphertext {
+        Ciphertext::trivial(&ExtendedPoint::identity())
+    }
+}
+
+impl Neg for Ciphertext {
+    type Output = Ciphertext;
+
+    #[inline]
+    fn neg(self) -> Ciphertext {
+        Ciphertext {
+            c1: -self.c1,
+            c2: -self.c2,
+        }
+    }
+}
+
+impl<'b> Add<&'b Ciphertext> for &Ciphertext {
+    type Output = Ciphertext;
+
+    #[inline]
+    fn add(self, other: &'b Ciphertext) -> Ciphertext {
+        Ciphertext {
+            c1: self.c1 + other.c1,
+            c2: self.c2 + other.c2,
+        }
+    }
+}
+
+impl<'b> Sub<&'b Ciphertext> for &Ciphertext {
+    type Output = Ciphertext;
+
+    #[inline]
+    fn sub(self, other: &'b Ciphertext) -> Ciphertext {
+        Ciphertext {
+            c1: self.c1 - other.c1,
+            c2: self.c2 - other.c2,
+        }
+    }
+}
+
+impl_binops_additive!(Ciphertext, Ciphertext);
+
+impl<'b> Mul<&'b Fr> for &Ciphertext {
+    type Output = Ciphertext;
+
+    #[inline]
+    fn mul(self, other: &'b Fr) -> Ciphertext {
+        Ciphertext {
+            c1: self.c1 * other,
+            c2: self.c2 * other,
+        }
+    }
+}
+
+impl_binops_multiplicative!(Ciphertext, Fr);
+
+#[test]
+fn test_encrypt_decrypt() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let ciphertext = pk.encrypt(&Fr::from(42), &mut rng);
+    assert_eq!(
+        sk.decrypt(&ciphertext),
+        ExtendedPoint::from(GENERATOR) * Fr::from(42)
+    );
+    assert_eq!(sk.decrypt_vartime(&ciphertext, 100), Some(42));
+    assert_eq!(sk.decrypt_vartime(&ciphertext, 42), None);
+
+    let point = crate::hash_to_curve(b"point", b"elgamal test");
+    assert_eq!(sk.decrypt(&pk.encrypt_point(&point, &mut rng)), point);
+}
+
+#[test]
+fn test_homomorphic_operations() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let a = pk.encrypt(&Fr::from(30), &mut rng);
+    let b = pk.encrypt(&Fr::from(12), &mut rng);
+
+    assert_eq!(sk.decrypt_vartime(&(a + b), 100), Some(42));
+    assert_eq!(sk.decrypt_vartime(&(a - b), 100), Some(18));
+    assert_eq!(sk.decrypt_vartime(&(b * Fr::from(3)), 100), Some(36));
+    assert_eq!(sk.decrypt_vartime(&(-b + a), 100), Some(18));
+
+    let mut c = a;
+    c += b;
+    c -= a;
+    c *= Fr::from(2);
+    assert_eq!(sk.decrypt_vartime(&c, 100), Some(24));
+}
+
+#[test]
+fn test_rerandomize() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let a = pk.encrypt(&Fr::from(7), &mut rng);
+    let b = a.rerandomize(&pk, &mut rng);
+
+    assert!(a != b);
+    assert_eq!(sk.decrypt(&a), sk.decrypt(&b));
+    assert_eq!(a.rerandomize_with(&pk, &Fr::zero()), a);
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let a = pk.encrypt(&Fr::from(5), &mut rng);
+    assert_eq!(Ciphertext::from_bytes(a.into_bytes()).unwrap(), a);
+    assert_eq!(PublicKey::from_bytes(pk.into_bytes()).unwrap(), pk);
+
+    let b = Ciphertext::default();
+    assert_eq!(Ciphertext::conditional_select(&a, &b, Choice::from(0)), a);
+    assert_eq!(Ciphertext::conditional_select(&a, &b, Choice::from(1)), b);
+}
+
+#[test]
+fn test_reject_small_order_public_keys() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+    let tweaked = AffinePoint::from(ExtendedPoint::from(pk.0) + two_torsion);
+
+    assert!(bool::from(PublicKey::from_point(pk.0).is_some()));
+    for point in [AffinePoint::identity(), two_torsion, tweaked].iter() {
+        assert!(bool::from(PublicKey::from_point(*point).is_none()));
+        assert!(bool::from(
+            PublicKey::from_bytes(point.into_bytes()).is_none()
+        ));
+    }
+}
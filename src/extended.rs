@@ -128,10 +128,10 @@ impl ExtendedPoint {
 
     /// Determines if this point is of small order.
     pub fn is_small_order(&self) -> Choice {
-        // We only need to perform two doublings, since the 2-torsion
-        // points are (0, 1) and (0, -1), and so we only need to check
-        // that the u-coordinate of the result is zero to see if the
-        // point is small order.
+        // The small order points form the cyclic 4-torsion subgroup, so
+        // two doublings send them into the 2-torsion points (0, 1) and
+        // (0, -1), and we only need to check that the u-coordinate of
+        // the result is zero to see if the point is small order.
         self.double().double().u.ct_eq(&Fq::zero())
     }
 
@@ -149,9 +149,9 @@ impl ExtendedPoint {
         self.is_torsion_free() & (!self.is_identity())
     }
 
-    /// Multiplies this element by the cofactor `8`.
+    /// Multiplies this element by the cofactor `4`.
     pub fn mul_by_cofactor(&self) -> ExtendedPoint {
-        self.double().double().double()
+        self.double().double()
     }
 
     /// Performs a pre-processing step that produces an `ExtendedNielsPoint`
@@ -35,15 +35,3 @@ pub const R3: Fq = Fq([
     0x8065dc6c04ec5b65,
     0x0e530b773599cec7,
 ]);
-
-pub const S: u32 = 2;
-
-/// GENERATOR^t where t * 2^s + 1 = q
-/// with t odd. In other words, this
-/// is a 2^s root of unity.
-pub const ROOT_OF_UNITY: Fq = Fq([
-    0xbe8775dfebbe07d4,
-    0x0ef0565342ce83fe,
-    0x7d3d6d60abc1c27a,
-    0x094a7310e07981e7,
-]);
@@ -2,7 +2,9 @@ use core::fmt;
 use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 use byteorder::{ByteOrder, LittleEndian};
+use rand_core::RngCore;
 use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
 
 use crate::util::{adc, mac, sbb};
 use crate::CtOption;
@@ -59,6 +61,12 @@ impl ConditionallySelectable for Fr {
     }
 }
 
+impl Zeroize for Fr {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl<'a> Neg for &'a Fr {
     type Output = Fr;
 
@@ -213,6 +221,18 @@ impl Fr {
         ])
     }
 
+    /// Samples a uniformly random element of `Fr` by reducing 512
+    /// bits of output from the provided RNG, so that the bias is
+    /// negligible.
+    pub fn random<R: RngCore + ?Sized>(rng: &mut R) -> Fr {
+        let mut bytes = [0u8; 64];
+        rng.fill_bytes(&mut bytes);
+        let res = Fr::from_bytes_wide(bytes);
+        bytes.zeroize();
+
+        res
+    }
+
     fn from_u512(limbs: [u64; 8]) -> Fr {
         // We reduce an arbitrary 512-bit number by decomposing it into two 256-bit digits
         // with the higher bits multiplied by 2^256. Thus, we perform two reductions
@@ -273,19 +293,50 @@ impl Fr {
 
     /// Computes the square root of this element, if it exists.
     pub fn sqrt(&self) -> CtOption<Self> {
-        // Because r = 3 (mod 4)
-        // sqrt can be done with only one exponentiation,
-        // via the computation of  self^((r + 1) // 4) (mod r)
-        let sqrt = self.pow_vartime(&[
-            0xc9aa74f78d6de87a,
-            0xfd26cafc3927d635,
-            0xffffffffffffffff,
-            0x007fffffffffffff,
+        // Tonelli-Shank's algorithm for r mod 16 = 9
+        // https://eprint.iacr.org/2012/685.pdf (page 12, algorithm 5)
+
+        // w = self^((t - 1) // 2)
+        //   = self^0x0040000000000000000000000000000002139250d883d1cbcdeb589d0c30ba62
+        let w = self.pow_vartime(&[
+            0xcdeb589d0c30ba62,
+            0x02139250d883d1cb,
+            0x0000000000000000,
+            0x0040000000000000,
         ]);
 
+        let mut v = S;
+        let mut x = self * w;
+        let mut b = x * w;
+
+        // Initialize z as the 2^S root of unity.
+        let mut z = ROOT_OF_UNITY;
+
+        for max_v in (1..=S).rev() {
+            let mut k = 1;
+            let mut tmp = b.square();
+            let mut j_less_than_v: Choice = 1.into();
+
+            for j in 2..max_v {
+                let tmp_is_one = tmp.ct_eq(&Fr::one());
+                let squared = Fr::conditional_select(&tmp, &z, tmp_is_one).square();
+                tmp = Fr::conditional_select(&squared, &tmp, tmp_is_one);
+                let new_z = Fr::conditional_select(&z, &squared, tmp_is_one);
+                j_less_than_v &= !j.ct_eq(&v);
+                k = u32::conditional_select(&j, &k, tmp_is_one);
+                z = Fr::conditional_select(&z, &new_z, j_less_than_v);
+            }
+
+            let result = x * z;
+            x = Fr::conditional_select(&result, &x, b.ct_eq(&Fr::one()));
+            z = z.square();
+            b *= z;
+            v = k;
+        }
+
         CtOption::new(
-            sqrt,
-            (&sqrt * &sqrt).ct_eq(self), // Only return Some if it's the square root.
+            x,
+            (x * x).ct_eq(self), // Only return Some if it's the square root.
         )
     }
 
@@ -334,10 +385,10 @@ impl Fr {
             }
         }
         let r_minus_2 = [
-            0x26a9d3de35b7a1e5,
-            0xf49b2bf0e49f58d7,
-            0xffffffffffffffff,
-            0x01ffffffffffffff,
+            0xdeb589d0c30ba627,
+            0x2139250d883d1cbc,
+            0x0000000000000000,
+            0x0400000000000000,
         ];
 
         let mut t0 = *self;
@@ -570,7 +621,7 @@ fn test_debug() {
     );
     assert_eq!(
         format!("{:?}", R2),
-        "0x00000000000000000000000000000005b26a078db053946cab1610e5242f0c80"
+        "0x03fffffffffffffffffffffffffffff7d2efe1ab78f5ed85315315a000221be9"
     );
 }
 
@@ -605,16 +656,16 @@ fn test_into_bytes() {
     assert_eq!(
         R2.into_bytes(),
         [
-            128, 12, 47, 36, 229, 16, 22, 171, 108, 148, 83, 176, 141, 7, 106, 178, 5, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            233, 27, 34, 0, 160, 21, 83, 49, 133, 237, 245, 120, 171, 225, 239, 210, 247, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 3,
         ]
     );
 
     assert_eq!(
         (-&Fr::one()).into_bytes(),
         [
-            230, 161, 183, 53, 222, 211, 169, 38, 215, 88, 159, 228, 240, 43, 155, 244, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 1
+            40, 166, 11, 195, 208, 137, 181, 222, 188, 28, 61, 136, 13, 37, 57, 33, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4
         ]
     );
 }
@@ -641,8 +692,8 @@ fn test_from_bytes() {
 
     assert_eq!(
         Fr::from_bytes([
-            128, 12, 47, 36, 229, 16, 22, 171, 108, 148, 83, 176, 141, 7, 106, 178, 5, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            233, 27, 34, 0, 160, 21, 83, 49, 133, 237, 245, 120, 171, 225, 239, 210, 247, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 3,
         ])
         .unwrap(),
         R2
@@ -651,8 +702,8 @@ fn test_from_bytes() {
     // -1 should work
     assert!(
         Fr::from_bytes([
-            230, 161, 183, 53, 222, 211, 169, 38, 215, 88, 159, 228, 240, 43, 155, 244, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 1
+            40, 166, 11, 195, 208, 137, 181, 222, 188, 28, 61, 136, 13, 37, 57, 33, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4
         ])
         .is_some()
         .unwrap_u8()
@@ -662,8 +713,8 @@ fn test_from_bytes() {
     // modulus is invalid
     assert!(
         Fr::from_bytes([
-            183, 44, 247, 214, 94, 14, 151, 208, 130, 16, 200, 204, 147, 32, 104, 166, 0, 59, 52,
-            1, 1, 59, 103, 6, 169, 175, 51, 101, 234, 180, 125, 14
+            41, 166, 11, 195, 208, 137, 181, 222, 188, 28, 61, 136, 13, 37, 57, 33, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4
         ])
         .is_none()
         .unwrap_u8()
@@ -673,8 +724,8 @@ fn test_from_bytes() {
     // Anything larger than the modulus is invalid
     assert!(
         Fr::from_bytes([
-            184, 44, 247, 214, 94, 14, 151, 208, 130, 16, 200, 204, 147, 32, 104, 166, 0, 59, 52,
-            1, 1, 59, 103, 6, 169, 175, 51, 101, 234, 180, 125, 14
+            42, 166, 11, 195, 208, 137, 181, 222, 188, 28, 61, 136, 13, 37, 57, 33, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4
         ])
         .is_none()
         .unwrap_u8()
@@ -683,8 +734,8 @@ fn test_from_bytes() {
 
     assert!(
         Fr::from_bytes([
-            183, 44, 247, 214, 94, 14, 151, 208, 130, 16, 200, 204, 147, 32, 104, 166, 0, 59, 52,
-            1, 1, 59, 104, 6, 169, 175, 51, 101, 234, 180, 125, 14
+            41, 166, 11, 195, 208, 137, 181, 222, 188, 28, 61, 136, 13, 37, 57, 33, 1, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4
         ])
         .is_none()
         .unwrap_u8()
@@ -693,8 +744,8 @@ fn test_from_bytes() {
 
     assert!(
         Fr::from_bytes([
-            183, 44, 247, 214, 94, 14, 151, 208, 130, 16, 200, 204, 147, 32, 104, 166, 0, 59, 52,
-            1, 1, 59, 103, 6, 169, 175, 51, 101, 234, 180, 125, 15
+            41, 166, 11, 195, 208, 137, 181, 222, 188, 28, 61, 136, 13, 37, 57, 33, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5
         ])
         .is_none()
         .unwrap_u8()
@@ -743,9 +794,9 @@ fn test_from_bytes_wide_r2() {
     assert_eq!(
         R2,
         Fr::from_bytes_wide([
-            128, 12, 47, 36, 229, 16, 22, 171, 108, 148, 83, 176, 141, 7, 106, 178, 5, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            233, 27, 34, 0, 160, 21, 83, 49, 133, 237, 245, 120, 171, 225, 239, 210, 247, 255, 255,
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 3, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
         ])
     );
 }
@@ -755,9 +806,9 @@ fn test_from_bytes_wide_negative_one() {
     assert_eq!(
         -&Fr::one(),
         Fr::from_bytes_wide([
-            230, 161, 183, 53, 222, 211, 169, 38, 215, 88, 159, 228, 240, 43, 155, 244, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 1, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            40, 166, 11, 195, 208, 137, 181, 222, 188, 28, 61, 136, 13, 37, 57, 33, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
         ])
     );
 }
@@ -766,10 +817,10 @@ fn test_from_bytes_wide_negative_one() {
 fn test_from_bytes_wide_maximum() {
     assert_eq!(
         Fr([
-            2834717408665115077,
-            13471421789216862413,
-            10747224682082754571,
-            16204507328226524
+            4038433020675011620,
+            16729466786570316316,
+            5081012845327657902,
+            69733460006315468
         ]),
         Fr::from_bytes_wide([0xff; 64])
     );
@@ -785,10 +836,10 @@ fn test_zero() {
 
 #[cfg(test)]
 const LARGEST: Fr = Fr([
-    0x26a9d3de35b7a1e6,
-    0xf49b2bf0e49f58d7,
-    0xffffffffffffffff,
-    0x01ffffffffffffff,
+    0xdeb589d0c30ba628,
+    0x2139250d883d1cbc,
+    0x0000000000000000,
+    0x0400000000000000,
 ]);
 
 #[test]
@@ -799,10 +850,10 @@ fn test_addition() {
     assert_eq!(
         tmp,
         Fr([
-            0x26a9d3de35b7a1e5,
-            0xf49b2bf0e49f58d7,
-            0xffffffffffffffff,
-            0x01ffffffffffffff,
+            0xdeb589d0c30ba627,
+            0x2139250d883d1cbc,
+            0x0000000000000000,
+            0x0400000000000000,
         ])
     );
 
@@ -917,10 +968,10 @@ fn test_inversion() {
 #[test]
 fn test_invert_is_pow() {
     let r_minus_2 = [
-        0x26a9d3de35b7a1e5,
-        0xf49b2bf0e49f58d7,
-        0xffffffffffffffff,
-        0x01ffffffffffffff,
+        0xdeb589d0c30ba627,
+        0x2139250d883d1cbc,
+        0x0000000000000000,
+        0x0400000000000000,
     ];
 
     let mut r1 = R;
@@ -944,10 +995,10 @@ fn test_invert_is_pow() {
 #[test]
 fn test_sqrt() {
     let mut square = Fr([
-        0x26a9d3de35b7a1e5,
-        0xf49b2bf0e49f58d7,
-        0xffffffffffffffff,
-        0x01ffffffffffffff,
+        0xdeb589d0c30ba627,
+        0x2139250d883d1cbc,
+        0x0000000000000000,
+        0x0400000000000000,
     ]);
 
     let mut none_count = 0;
@@ -962,17 +1013,17 @@ fn test_sqrt() {
         square -= Fr::one();
     }
 
-    assert_eq!(44, none_count);
+    assert_eq!(40, none_count);
 }
 
 #[test]
 fn test_from_raw() {
     assert_eq!(
         Fr::from_raw([
-            0xab1610e5242f0c7f,
-            0xb26a078db053946c,
-            0x0000000000000005,
-            0x0000000000000000,
+            0x315315a000221be8,
+            0xd2efe1ab78f5ed85,
+            0xfffffffffffffff7,
+            0x03ffffffffffffff,
         ]),
         Fr::from_raw([0xffffffffffffffff; 4])
     );
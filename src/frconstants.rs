@@ -1,37 +1,48 @@
 pub use crate::fr::Fr;
 
 /// Constant representing the modulus
-/// r = 0x1fffffffffffffffffffffffffffffff49b2bf0e49f58d726a9d3de35b7a1e7
+/// r = 0x040000000000000000000000000000002139250d883d1cbcdeb589d0c30ba629
 pub const MODULUS: Fr = Fr([
-    0x26a9d3de35b7a1e7,
-    0xf49b2bf0e49f58d7,
-    0xffffffffffffffff,
-    0x01ffffffffffffff,
+    0xdeb589d0c30ba629,
+    0x2139250d883d1cbc,
+    0x0000000000000000,
+    0x0400000000000000,
 ]);
 
 /// INV = -(r^{-1} mod 2^64) mod 2^64
-pub const INV: u64 = 0x2a81f20882b21e29;
+pub const INV: u64 = 0xd607d18f52eca9e7;
 
 /// R = 2^256 mod r
 pub const R: Fr = Fr([
-    0xab1610e5242f0c80,
-    0xb26a078db053946c,
-    0x0000000000000005,
-    0x0000000000000000,
+    0x315315a000221be9,
+    0xd2efe1ab78f5ed85,
+    0xfffffffffffffff7,
+    0x03ffffffffffffff,
 ]);
 
 /// R^2 = 2^512 mod r
 pub const R2: Fr = Fr([
-    0x6921bd75f1e321aa,
-    0x016f997a4e557d3f,
-    0xfe677f26b8e821f2,
-    0x007be9f42e0719ec,
+    0xcd70db422d4272e9,
+    0x9f6edbca2c2b63d0,
+    0xa7b0dab63149ca8e,
+    0x0084f028356b8468,
 ]);
 
 /// R^2 = 2^768 mod r
 pub const R3: Fr = Fr([
-    0xd26d015bfa48a645,
-    0x6d5e224bce0e0539,
-    0x9525cfe838da6011,
-    0x003991e9df0f24dc,
+    0x8aa8f13ecba129e4,
+    0x99e1be05d7f0fae4,
+    0x4683624330f3aba6,
+    0x00f7be3660a2a5cc,
+]);
+
+/// 2^S * t = r - 1 with t odd
+pub const S: u32 = 3;
+
+/// ROOT_OF_UNITY = 3^t mod r, a primitive 2^S root of unity
+pub const ROOT_OF_UNITY: Fr = Fr([
+    0x864ec9c4e2987851,
+    0x5945cb8b93fa091d,
+    0xc420cefdc2f9edb0,
+    0x02cb6127cff1716c,
 ]);
@@ -0,0 +1,671 @@
+//! This module implements FROST threshold Schnorr signatures as described
+//! in RFC 9591, producing signatures that are accepted by the
+//! single-signer verifier in the `schnorr` module.
+//!
+//! # Ciphersuite
+//!
+//! The ciphersuite `FROST(Doppio, SHA-512)` uses the following parameters:
+//!
+//! * `contextString` is `"FROST-DOPPIO-SHA512-v1"`.
+//! * The group is the prime order subgroup of Doppio with generator
+//!   [`GENERATOR`]. `SerializeElement` is [`AffinePoint::into_bytes`] and
+//!   `SerializeScalar` is [`Fr::into_bytes`]. `DeserializeElement` rejects
+//!   non-canonical encodings and points of small order (including the
+//!   identity), and every verification equation is multiplied by the
+//!   cofactor `4`.
+//! * `H1`, `H3`, `H4` and `H5` are as in RFC 9591 with SHA-512, where
+//!   hashing to a scalar reduces the 64-byte digest as a little-endian
+//!   integer. `H2` is the challenge of the `schnorr` module, so that
+//!   aggregated signatures verify as ordinary Schnorr signatures.
+//!
+//! Participant identifiers are nonzero `u32`s, mapped to scalars with
+//! `Fr::from`, and match the share indices of the `sharing` module.
+//!
+//! # Protocol
+//!
+//! 1. Each participant calls [`commit`] and sends its
+//!    [`SigningCommitments`] to the coordinator, keeping the
+//!    [`SigningNonces`] secret.
+//! 2. The coordinator builds a [`SigningPackage`] from the commitments
+//!    and the message and sends it to every participant, who calls
+//!    [`sign`], consuming its nonces.
+//! 3. The coordinator calls [`aggregate`] on the signature shares. If the
+//!    result does not verify, [`verify_signature_share`] identifies the
+//!    misbehaving participants.
+
+use core::fmt;
+use std::collections::BTreeMap;
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use subtle::Choice;
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_scalar;
+use crate::schnorr::{self, nonce_generate, Signature, VerifyingKey};
+use crate::sharing::{self, FeldmanCommitment, Share};
+use crate::{AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The `contextString` of this ciphersuite.
+const CONTEXT_STRING: &[u8] = b"FROST-DOPPIO-SHA512-v1";
+
+/// Maps an identifier to its scalar.
+fn identifier_scalar(identifier: u32) -> Fr {
+    Fr::from(u64::from(identifier))
+}
+
+/// `H1`, used to derive binding factors.
+fn h1(msg: &[&[u8]]) -> Fr {
+    let mut input = vec![CONTEXT_STRING, b"rho"];
+    input.extend_from_slice(msg);
+    hash_to_scalar(&input)
+}
+
+/// `H4` and `H5`, which hash `msg` under the given tag.
+fn h_bytes(tag: &[u8], msg: &[u8]) -> [u8; 64] {
+    let mut res = [0u8; 64];
+    res.copy_from_slice(
+        &Sha512::new()
+            .chain_update(CONTEXT_STRING)
+            .chain_update(tag)
+            .chain_update(msg)
+            .finalize(),
+    );
+
+    res
+}
+
+/// The long-lived signing material of a participant.
+#[derive(Clone)]
+pub struct KeyPackage {
+    identifier: u32,
+    signing_share: Fr,
+    verifying_share: AffinePoint,
+    verifying_key: VerifyingKey,
+}
+
+impl fmt::Debug for KeyPackage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyPackage")
+            .field("identifier", &self.identifier)
+            .field("verifying_share", &self.verifying_share)
+            .field("verifying_key", &self.verifying_key)
+            .finish()
+    }
+}
+
+impl Drop for KeyPackage {
+    fn drop(&mut self) {
+        self.signing_share.zeroize();
+    }
+}
+
+impl KeyPackage {
+    /// Constructs the key package of the holder of `share`, after checking
+    /// it against the dealer's `commitment` as in `vss_verify`. This
+    /// returns `None` if the share is inconsistent with the commitment or
+    /// the group public key is of small order.
+    pub fn new(share: &Share, commitment: &FeldmanCommitment) -> Option<KeyPackage> {
+        let verifying_key = AffinePoint::from(commitment.public_key());
+        if !commitment.verify(share) || bool::from(verifying_key.is_small_order()) {
+            return None;
+        }
+
+        Some(KeyPackage {
+            identifier: share.index(),
+            signing_share: *share.value(),
+            verifying_share: AffinePoint::from(ExtendedPoint::from(GENERATOR) * share.value()),
+            verifying_key: VerifyingKey::from_point(verifying_key),
+        })
+    }
+
+    /// Returns the identifier of this participant.
+    pub fn identifier(&self) -> u32 {
+        self.identifier
+    }
+
+    /// Returns the public counterpart of this participant's share.
+    pub fn verifying_share(&self) -> AffinePoint {
+        self.verifying_share
+    }
+
+    /// Returns the group verifying key.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.verifying_key
+    }
+}
+
+/// The public material needed by the coordinator: the group verifying
+/// key and the verifying share of every participant.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicKeyPackage {
+    verifying_shares: BTreeMap<u32, AffinePoint>,
+    verifying_key: VerifyingKey,
+}
+
+impl PublicKeyPackage {
+    /// Derives the public key package for participants `1..=n` from the
+    /// dealer's commitment. This returns `None` if the group public key is
+    /// of small order.
+    pub fn new(commitment: &FeldmanCommitment, n: u32) -> Option<PublicKeyPackage> {
+        let verifying_key = AffinePoint::from(commitment.public_key());
+        if bool::from(verifying_key.is_small_order()) {
+            return None;
+        }
+
+        let verifying_shares = (1..=n)
+            .map(|i| (i, AffinePoint::from(commitment.evaluate(i))))
+            .collect();
+
+        Some(PublicKeyPackage {
+            verifying_shares,
+            verifying_key: VerifyingKey::from_point(verifying_key),
+        })
+    }
+
+    /// Returns the verifying share of the given participant.
+    pub fn verifying_share(&self, identifier: u32) -> Option<AffinePoint> {
+        self.verifying_shares.get(&identifier).copied()
+    }
+
+    /// Returns the group verifying key.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.verifying_key
+    }
+}
+
+/// Generates key packages for participants `1..=n` and the public key
+/// package, sharing `secret` with the given threshold. This is
+/// `trusted_dealer_keygen` from RFC 9591.
+///
+/// # Panics
+///
+/// Panics if `threshold` is zero or larger than `n`, or if `secret` is
+/// zero.
+pub fn trusted_dealer_keygen<R: RngCore + CryptoRng>(
+    secret: &Fr,
+    threshold: usize,
+    n: u32,
+    rng: &mut R,
+) -> (Vec<KeyPackage>, PublicKeyPackage) {
+    assert!(secret != &Fr::zero());
+
+    let (shares, commitment) = sharing::split(secret, threshold, n, rng);
+    let verifying_key = VerifyingKey::from_point(AffinePoint::from(commitment.public_key()));
+    let key_packages = shares
+        .iter()
+        .map(|share| KeyPackage {
+            identifier: share.index(),
+            signing_share: *share.value(),
+            verifying_share: AffinePoint::from(ExtendedPoint::from(GENERATOR) * share.value()),
+            verifying_key,
+        })
+        .collect();
+    let public_key_package = PublicKeyPackage {
+        verifying_shares: shares
+            .iter()
+            .map(|share| {
+                let point = ExtendedPoint::from(GENERATOR) * share.value();
+                (share.index(), AffinePoint::from(point))
+            })
+            .collect(),
+        verifying_key,
+    };
+
+    (key_packages, public_key_package)
+}
+
+/// The secret nonces `(d, e)` of a participant for a single signing
+/// operation. These are consumed by [`sign`] and cannot be reused.
+pub struct SigningNonces {
+    hiding: Fr,
+    binding: Fr,
+    commitments: SigningCommitments,
+}
+
+impl fmt::Debug for SigningNonces {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SigningNonces")
+            .field("commitments", &self.commitments)
+            .finish()
+    }
+}
+
+impl Drop for SigningNonces {
+    fn drop(&mut self) {
+        self.hiding.zeroize();
+        self.binding.zeroize();
+    }
+}
+
+/// The public nonce commitments `(D, E) = (d * G, e * G)` of a participant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SigningCommitments {
+    identifier: u32,
+    hiding: AffinePoint,
+    binding: AffinePoint,
+}
+
+impl SigningCommitments {
+    /// Returns the identifier of the participant that made these
+    /// commitments.
+    pub fn identifier(&self) -> u32 {
+        self.identifier
+    }
+
+    /// Attempts to interpret the 68-byte representation of signing
+    /// commitments, which is the little-endian identifier followed by the
+    /// encodings of `D` and `E`. This fails if the identifier is zero, or
+    /// if either point is not canonically encoded or is of small order.
+    pub fn from_bytes(bytes: [u8; 68]) -> CtOption<SigningCommitments> {
+        let mut identifier = [0u8; 4];
+        let mut hiding = [0u8; 32];
+        let mut binding = [0u8; 32];
+        identifier.copy_from_slice(&bytes[..4]);
+        hiding.copy_from_slice(&bytes[4..36]);
+        binding.copy_from_slice(&bytes[36..]);
+
+        let identifier = u32::from_le_bytes(identifier);
+        let hiding = AffinePoint::deserialize_element(hiding);
+        let binding = AffinePoint::deserialize_element(binding);
+        let is_some = hiding.is_some() & binding.is_some() & Choice::from((identifier != 0) as u8);
+
+        CtOption::new(
+            SigningCommitments {
+                identifier,
+                hiding: hiding.unwrap_or(AffinePoint::identity()),
+                binding: binding.unwrap_or(AffinePoint::identity()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts these commitments into their 68-byte representation.
+    pub fn into_bytes(&self) -> [u8; 68] {
+        let mut res = [0u8; 68];
+        res[..4].copy_from_slice(&self.identifier.to_le_bytes());
+        res[4..36].copy_from_slice(&self.hiding.into_bytes());
+        res[36..].copy_from_slice(&self.binding.into_bytes());
+
+        res
+    }
+}
+
+/// Performs round one for the participant with `key_package`, returning
+/// its secret nonces and the commitments to send to the coordinator.
+pub fn commit<R: RngCore + CryptoRng>(
+    key_package: &KeyPackage,
+    rng: &mut R,
+) -> (SigningNonces, SigningCommitments) {
+    let hiding = nonce_generate(CONTEXT_STRING, &key_package.signing_share, rng);
+    let binding = nonce_generate(CONTEXT_STRING, &key_package.signing_share, rng);
+    let commitments = SigningCommitments {
+        identifier: key_package.identifier,
+        hiding: AffinePoint::from(ExtendedPoint::from(GENERATOR) * hiding),
+        binding: AffinePoint::from(ExtendedPoint::from(GENERATOR) * binding),
+    };
+
+    (
+        SigningNonces {
+            hiding,
+            binding,
+            commitments,
+        },
+        commitments,
+    )
+}
+
+/// The commitments of the participating signers and the message, as sent
+/// by the coordinator in round two.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SigningPackage {
+    commitments: Vec<SigningCommitments>,
+    message: Vec<u8>,
+}
+
+/// A participant's share `z_i` of the signature.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SignatureShare {
+    identifier: u32,
+    share: Fr,
+}
+
+impl SigningPackage {
+    /// Constructs a signing package, sorting the commitments by
+    /// identifier. This returns `None` if `commitments` is empty or
+    /// contains two commitments from the same participant.
+    pub fn new(mut commitments: Vec<SigningCommitments>, message: &[u8]) -> Option<SigningPackage> {
+        commitments.sort_by_key(|c| c.identifier);
+        if commitments.is_empty()
+            || commitments
+                .windows(2)
+                .any(|w| w[0].identifier == w[1].identifier)
+        {
+            return None;
+        }
+
+        Some(SigningPackage {
+            commitments,
+            message: message.to_vec(),
+        })
+    }
+
+    /// Returns the commitments in this package, sorted by identifier.
+    pub fn commitments(&self) -> &[SigningCommitments] {
+        &self.commitments
+    }
+
+    /// Returns the message to be signed.
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    fn identifiers(&self) -> Vec<u32> {
+        self.commitments.iter().map(|c| c.identifier).collect()
+    }
+
+    /// Computes the binding factor of every participant, as in
+    /// `compute_binding_factors`.
+    fn binding_factors(&self, verifying_key: &VerifyingKey) -> Vec<Fr> {
+        let mut encoded_commitments = Vec::with_capacity(96 * self.commitments.len());
+        for c in self.commitments.iter() {
+            encoded_commitments.extend_from_slice(&identifier_scalar(c.identifier).into_bytes());
+            encoded_commitments.extend_from_slice(&c.hiding.into_bytes());
+            encoded_commitments.extend_from_slice(&c.binding.into_bytes());
+        }
+
+        let msg_hash = h_bytes(b"msg", &self.message);
+        let commitment_hash = h_bytes(b"com", &encoded_commitments);
+        let verifying_key = verifying_key.into_bytes();
+
+        self.commitments
+            .iter()
+            .map(|c| {
+                h1(&[
+                    &verifying_key,
+                    &msg_hash,
+                    &commitment_hash,
+                    &identifier_scalar(c.identifier).into_bytes(),
+                ])
+            })
+            .collect()
+    }
+
+    /// Computes the group commitment `R` and the challenge, along with
+    /// the binding factors used to compute them.
+    fn group_commitment(&self, verifying_key: &VerifyingKey) -> (AffinePoint, Fr, Vec<Fr>) {
+        let binding_factors = self.binding_factors(verifying_key);
+
+        let mut scalars = Vec::with_capacity(2 * self.commitments.len());
+        let mut points = Vec::with_capacity(2 * self.commitments.len());
+        for (c, rho) in self.commitments.iter().zip(binding_factors.iter()) {
+            scalars.push(Fr::one());
+            points.push(ExtendedPoint::from(c.hiding));
+            scalars.push(*rho);
+            points.push(ExtendedPoint::from(c.binding));
+        }
+
+        let r = AffinePoint::from(crate::multiscalar_mul_vartime(&scalars, &points));
+        let challenge = schnorr::challenge(&r, &verifying_key.to_point(), &self.message);
+
+        (r, challenge, binding_factors)
+    }
+
+    /// Returns the position of the given participant in this package.
+    fn position(&self, identifier: u32) -> Option<usize> {
+        self.commitments
+            .binary_search_by_key(&identifier, |c| c.identifier)
+            .ok()
+    }
+}
+
+/// Performs round two, consuming `nonces` to produce this participant's
+/// signature share. This returns `None` if the package does not contain
+/// exactly the commitments that were produced alongside `nonces`.
+pub fn sign(
+    signing_package: &SigningPackage,
+    nonces: SigningNonces,
+    key_package: &KeyPackage,
+) -> Option<SignatureShare> {
+    let index = signing_package.position(key_package.identifier)?;
+    if signing_package.commitments[index] != nonces.commitments {
+        return None;
+    }
+
+    let (_, challenge, binding_factors) =
+        signing_package.group_commitment(&key_package.verifying_key);
+    let lambda = sharing::lagrange_coefficients_at_zero(&signing_package.identifiers())?[index];
+
+    let share = nonces.hiding
+        + nonces.binding * binding_factors[index]
+        + lambda * key_package.signing_share * challenge;
+
+    Some(SignatureShare {
+        identifier: key_package.identifier,
+        share,
+    })
+}
+
+/// Checks the signature share of a participant against its verifying
+/// share in `public_key_package`.
+pub fn verify_signature_share(
+    signing_package: &SigningPackage,
+    public_key_package: &PublicKeyPackage,
+    signature_share: &SignatureShare,
+) -> bool {
+    let index = match signing_package.position(signature_share.identifier) {
+        Some(index) => index,
+        None => return false,
+    };
+    let verifying_share = match public_key_package.verifying_share(signature_share.identifier) {
+        Some(point) => point,
+        None => return false,
+    };
+
+    let (_, challenge, binding_factors) =
+        signing_package.group_commitment(&public_key_package.verifying_key);
+    let lambda = match sharing::lagrange_coefficients_at_zero(&signing_package.identifiers()) {
+        Some(l) => l[index],
+        None => return false,
+    };
+
+    let c = &signing_package.commitments[index];
+    let lhs = ExtendedPoint::from(GENERATOR) * signature_share.share;
+    let rhs = crate::multiscalar_mul_vartime(
+        &[Fr::one(), binding_factors[index], lambda * challenge],
+        &[
+            ExtendedPoint::from(c.hiding),
+            ExtendedPoint::from(c.binding),
+            ExtendedPoint::from(verifying_share),
+        ],
+    );
+
+    bool::from((lhs - rhs).mul_by_cofactor().is_identity())
+}
+
+/// Aggregates the signature shares of every participant in the signing
+/// package into a signature, which is verified before it is returned.
+/// This returns `None` if a share is missing or the signature is
+/// invalid, in which case [`verify_signature_share`] can be used to find
+/// the invalid shares.
+pub fn aggregate(
+    signing_package: &SigningPackage,
+    signature_shares: &[SignatureShare],
+    public_key_package: &PublicKeyPackage,
+) -> Option<Signature> {
+    let mut identifiers: Vec<u32> = signature_shares.iter().map(|s| s.identifier).collect();
+    identifiers.sort_unstable();
+    if identifiers != signing_package.identifiers() {
+        return None;
+    }
+
+    let (r, _, _) = signing_package.group_commitment(&public_key_package.verifying_key);
+    let z = signature_shares
+        .iter()
+        .fold(Fr::zero(), |acc, s| acc + s.share);
+    let signature = Signature::from_parts(r, z);
+
+    if public_key_package
+        .verifying_key
+        .verify(&signing_package.message, &signature)
+    {
+        Some(signature)
+    } else {
+        None
+    }
+}
+
+impl SignatureShare {
+    /// Returns the identifier of the participant that produced this share.
+    pub fn identifier(&self) -> u32 {
+        self.identifier
+    }
+
+    /// Attempts to interpret the 36-byte representation of a signature
+    /// share, which is the little-endian identifier followed by the share
+    /// encoded with [`Fr::into_bytes`].
+    pub fn from_bytes(bytes: [u8; 36]) -> CtOption<SignatureShare> {
+        let mut identifier = [0u8; 4];
+        let mut share = [0u8; 32];
+        identifier.copy_from_slice(&bytes[..4]);
+        share.copy_from_slice(&bytes[4..]);
+
+        let identifier = u32::from_le_bytes(identifier);
+        let share = Fr::from_bytes(share);
+        let is_some = share.is_some() & Choice::from((identifier != 0) as u8);
+
+        CtOption::new(
+            SignatureShare {
+                identifier,
+                share: share.unwrap_or(Fr::zero()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this signature share into its 36-byte representation.
+    pub fn into_bytes(&self) -> [u8; 36] {
+        let mut res = [0u8; 36];
+        res[..4].copy_from_slice(&self.identifier.to_le_bytes());
+        res[4..].copy_from_slice(&self.share.into_bytes());
+
+        res
+    }
+}
+
+#[cfg(test)]
+fn run_signing(
+    key_packages: &[&KeyPackage],
+    message: &[u8],
+    rng: &mut rand_chacha::ChaChaRng,
+) -> (SigningPackage, Vec<SignatureShare>) {
+    let (nonces, commitments): (Vec<_>, Vec<_>) =
+        key_packages.iter().map(|kp| commit(kp, &mut *rng)).unzip();
+    let package = SigningPackage::new(commitments, message).unwrap();
+    let shares = nonces
+        .into_iter()
+        .zip(key_packages.iter())
+        .map(|(nonces, kp)| sign(&package, nonces, kp).unwrap())
+        .collect();
+
+    (package, shares)
+}
+
+#[test]
+fn test_sign_aggregate() {
+    let mut rng = crate::test_rng();
+    let secret = Fr::random(&mut rng);
+    let (key_packages, public_key_package) = trusted_dealer_keygen(&secret, 3, 5, &mut rng);
+
+    for signers in [[0, 1, 2], [1, 3, 4], [4, 2, 0]].iter() {
+        let signers: Vec<&KeyPackage> = signers.iter().map(|i| &key_packages[*i]).collect();
+        let (package, shares) = run_signing(&signers, b"message", &mut rng);
+
+        for share in shares.iter() {
+            assert!(verify_signature_share(&package, &public_key_package, share));
+        }
+
+        let signature = aggregate(&package, &shares, &public_key_package).unwrap();
+        assert!(public_key_package
+            .verifying_key()
+            .verify(b"message", &signature));
+    }
+}
+
+#[test]
+fn test_invalid_share_detected() {
+    let mut rng = crate::test_rng();
+    let secret = Fr::random(&mut rng);
+    let (key_packages, public_key_package) = trusted_dealer_keygen(&secret, 2, 3, &mut rng);
+
+    let signers = [&key_packages[0], &key_packages[2]];
+    let (package, mut shares) = run_signing(&signers, b"message", &mut rng);
+    shares[1].share += Fr::one();
+
+    assert!(!verify_signature_share(
+        &package,
+        &public_key_package,
+        &shares[1]
+    ));
+    assert!(aggregate(&package, &shares, &public_key_package).is_none());
+    assert!(aggregate(&package, &shares[..1], &public_key_package).is_none());
+}
+
+#[test]
+fn test_signing_package_checks() {
+    let mut rng = crate::test_rng();
+    let secret = Fr::random(&mut rng);
+    let (key_packages, _) = trusted_dealer_keygen(&secret, 2, 3, &mut rng);
+
+    let (nonces, commitments) = commit(&key_packages[0], &mut rng);
+    let (_, other) = commit(&key_packages[1], &mut rng);
+    assert!(SigningPackage::new(vec![commitments, commitments], b"message").is_none());
+    assert!(SigningPackage::new(vec![], b"message").is_none());
+
+    // The package must contain the commitments made alongside the nonces.
+    let (_, replaced) = commit(&key_packages[0], &mut rng);
+    let package = SigningPackage::new(vec![replaced, other], b"message").unwrap();
+    assert!(sign(&package, nonces, &key_packages[0]).is_none());
+}
+
+#[test]
+fn test_key_package_from_share() {
+    let mut rng = crate::test_rng();
+    let (shares, commitment) = sharing::split(&Fr::random(&mut rng), 2, 3, &mut rng);
+
+    let key_package = KeyPackage::new(&shares[1], &commitment).unwrap();
+    let public_key_package = PublicKeyPackage::new(&commitment, 3).unwrap();
+    assert_eq!(key_package.identifier(), 2);
+    assert_eq!(
+        public_key_package.verifying_share(2),
+        Some(key_package.verifying_share())
+    );
+
+    let bad = Share::new(2, shares[1].value() + Fr::one());
+    assert!(KeyPackage::new(&bad, &commitment).is_none());
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let (key_packages, _) = trusted_dealer_keygen(&Fr::random(&mut rng), 2, 3, &mut rng);
+    let (_, commitments) = commit(&key_packages[0], &mut rng);
+
+    let bytes = commitments.into_bytes();
+    assert_eq!(SigningCommitments::from_bytes(bytes).unwrap(), commitments);
+
+    // A small order nonce commitment is rejected.
+    let mut bad = bytes;
+    bad[4..36].copy_from_slice(&AffinePoint::identity().into_bytes());
+    assert!(SigningCommitments::from_bytes(bad).is_none().unwrap_u8() == 1);
+
+    let share = SignatureShare {
+        identifier: 3,
+        share: Fr::random(&mut rng),
+    };
+    assert_eq!(
+        SignatureShare::from_bytes(share.into_bytes()).unwrap(),
+        share
+    );
+}
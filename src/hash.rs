@@ -0,0 +1,235 @@
+//! This module implements hashing to the prime order subgroup of Doppio
+//! following the random oracle construction of RFC 9380, using the
+//! suite `DOPPIO_XMD:SHA-512_ELL2_RO_`:
+//!
+//! * `expand_message_xmd` with SHA-512 produces 96 uniform bytes, which
+//!   are split into two 48-byte big-endian integers and reduced into `Fq`.
+//! * Each field element is mapped with Elligator 2 to the Montgomery
+//!   curve `B.y^2 = x^3 + A.x^2 + x`, with `A = 346598`, `B = -346600`
+//!   and `Z = 2`, and then to Doppio with the rational map
+//!   `(x, y) => (x / y, (x - 1) / (x + 1))`.
+//! * The two points are added and the sum is multiplied by the cofactor.
+
+use sha2::{Digest, Sha512};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::{ExtendedPoint, Fq, Fr};
+
+/// `A / B` for the Montgomery form of Doppio.
+const MONT_A_OVER_B: Fq = Fq::from_raw([
+    0xca01ab2fad46a996,
+    0x79783b10b234017d,
+    0x8dd5e4c8affd11b8,
+    0x015422c47dfff6ec,
+]);
+
+/// `1 / B^2` for the Montgomery form of Doppio.
+const MONT_INV_B_SQUARED: Fq = Fq::from_raw([
+    0x3ec2e6c1b14894c6,
+    0x96be700e8e7cd3a2,
+    0xe3225459c7cf6c6b,
+    0x0cce63d4c8aa5cd3,
+]);
+
+/// `B = -346600` for the Montgomery form of Doppio.
+const MONT_B: Fq = Fq::from_raw([
+    0x5812631a5cf08a05,
+    0x14def9dea2f79cd6,
+    0x0000000000000000,
+    0x1000000000000000,
+]);
+
+/// Fills `out` using `expand_message_xmd` from RFC 9380 with SHA-512,
+/// where the message is the concatenation of `msg`.
+///
+/// # Panics
+///
+/// Panics if `dst` is longer than 255 bytes or `out` is longer than
+/// 255 * 64 bytes.
+pub(crate) fn expand_message_xmd(msg: &[&[u8]], dst: &[u8], out: &mut [u8]) {
+    assert!(dst.len() <= 255);
+    assert!(out.len() <= 255 * 64);
+
+    let ell = out.len().div_ceil(64);
+    let dst_len = [dst.len() as u8];
+
+    let mut h = Sha512::new();
+    h.update([0u8; 128]);
+    for part in msg {
+        h.update(part);
+    }
+    h.update((out.len() as u16).to_be_bytes());
+    h.update([0u8]);
+    h.update(dst);
+    h.update(dst_len);
+    let b_0 = h.finalize();
+
+    let mut b_i = Sha512::new()
+        .chain_update(b_0)
+        .chain_update([1u8])
+        .chain_update(dst)
+        .chain_update(dst_len)
+        .finalize();
+
+    for i in 1..=ell {
+        let chunk = &mut out[(i - 1) * 64..];
+        let len = core::cmp::min(64, chunk.len());
+        chunk[..len].copy_from_slice(&b_i[..len]);
+
+        if i < ell {
+            let mut xor = [0u8; 64];
+            for ((x, a), b) in xor.iter_mut().zip(b_0.iter()).zip(b_i.iter()) {
+                *x = a ^ b;
+            }
+            b_i = Sha512::new()
+                .chain_update(xor)
+                .chain_update([(i + 1) as u8])
+                .chain_update(dst)
+                .chain_update(dst_len)
+                .finalize();
+        }
+    }
+}
+
+/// Hashes the concatenation of `msg` to an element of `Fr` by reducing
+/// its SHA-512 digest as a little-endian 512-bit integer.
+pub(crate) fn hash_to_scalar(msg: &[&[u8]]) -> Fr {
+    let mut h = Sha512::new();
+    for part in msg {
+        h.update(part);
+    }
+
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&h.finalize());
+    let res = Fr::from_bytes_wide(wide);
+    wide.zeroize();
+
+    res
+}
+
+/// Hashes the concatenation of `msg` to two elements of `Fq`.
+fn hash_to_field(msg: &[&[u8]], dst: &[u8]) -> [Fq; 2] {
+    let mut uniform_bytes = [0u8; 96];
+    expand_message_xmd(msg, dst, &mut uniform_bytes);
+
+    let mut res = [Fq::zero(); 2];
+    for (e, chunk) in res.iter_mut().zip(uniform_bytes.chunks(48)) {
+        // Each chunk is a big-endian integer, which we reduce as a
+        // little-endian 512-bit integer.
+        let mut wide = [0u8; 64];
+        for (w, b) in wide.iter_mut().zip(chunk.iter().rev()) {
+            *w = *b;
+        }
+        *e = Fq::from_bytes_wide(wide);
+    }
+
+    res
+}
+
+/// Maps an element of `Fq` to Doppio using Elligator 2 on the
+/// birationally equivalent Montgomery curve.
+fn map_to_curve_elligator2(u: &Fq) -> ExtendedPoint {
+    let z = Fq::from(2);
+
+    // x1 = -(A / B) / (1 + Z.u^2), or -(A / B) if the denominator is zero
+    let tv = (Fq::one() + z * u.square()).invert();
+    let x1 = -MONT_A_OVER_B * tv.unwrap_or(Fq::one());
+    let gx1 = x1 * (x1.square() + MONT_A_OVER_B * x1 + MONT_INV_B_SQUARED);
+
+    // x2 = -x1 - (A / B)
+    let x2 = -x1 - MONT_A_OVER_B;
+    let gx2 = x2 * (x2.square() + MONT_A_OVER_B * x2 + MONT_INV_B_SQUARED);
+
+    let gx1_is_square = gx1.sqrt().is_some();
+    let x = Fq::conditional_select(&x2, &x1, gx1_is_square);
+    let gx = Fq::conditional_select(&gx2, &gx1, gx1_is_square);
+
+    // Exactly one of gx1 and gx2 is square, and the sign of y
+    // is chosen to be odd precisely when gx1 is square.
+    let y = gx.sqrt().unwrap_or(Fq::zero());
+    let y_is_odd = Choice::from(y.into_bytes()[0] & 1);
+    let y = Fq::conditional_select(&y, &-y, y_is_odd ^ gx1_is_square);
+
+    // Rescale to the Montgomery curve B.t^2 = s^3 + A.s^2 + s.
+    let s = x * MONT_B;
+    let t = y * MONT_B;
+
+    // The rational map (s, t) => (s / t, (s - 1) / (s + 1)) in extended
+    // coordinates, which sends the exceptional points to the identity.
+    let s_plus_one = s + Fq::one();
+    let s_minus_one = s - Fq::one();
+    let p = ExtendedPoint {
+        u: s * s_plus_one,
+        v: s_minus_one * t,
+        z: t * s_plus_one,
+        t1: s,
+        t2: s_minus_one,
+    };
+    let is_exceptional = t.ct_eq(&Fq::zero()) | s_plus_one.ct_eq(&Fq::zero());
+
+    ExtendedPoint::conditional_select(&p, &ExtendedPoint::identity(), is_exceptional)
+}
+
+/// Hashes the concatenation of `msg` to the prime order subgroup.
+pub(crate) fn hash_to_curve_parts(msg: &[&[u8]], dst: &[u8]) -> ExtendedPoint {
+    let [u0, u1] = hash_to_field(msg, dst);
+
+    (map_to_curve_elligator2(&u0) + map_to_curve_elligator2(&u1)).mul_by_cofactor()
+}
+
+/// Hashes `msg` to a point in the prime order subgroup under the domain
+/// separation tag `dst`, using the `DOPPIO_XMD:SHA-512_ELL2_RO_` suite.
+/// This operates in constant time.
+///
+/// # Panics
+///
+/// Panics if `dst` is longer than 255 bytes.
+pub fn hash_to_curve(msg: &[u8], dst: &[u8]) -> ExtendedPoint {
+    hash_to_curve_parts(&[msg], dst)
+}
+
+#[test]
+fn test_expand_message_xmd() {
+    // Test vector from RFC 9380, Appendix K.3
+    let mut out = [0u8; 32];
+    expand_message_xmd(&[b""], b"QUUX-V01-CS02-with-expander-SHA512-256", &mut out);
+    assert_eq!(
+        out,
+        [
+            0x6b, 0x9a, 0x73, 0x12, 0x41, 0x1d, 0x92, 0xf9, 0x21, 0xc6, 0xf6, 0x8c, 0xa0, 0xb6,
+            0x38, 0x07, 0x30, 0xa1, 0xa4, 0xd9, 0x82, 0xc5, 0x07, 0x21, 0x1a, 0x90, 0x96, 0x4c,
+            0x39, 0x41, 0x79, 0xba,
+        ]
+    );
+}
+
+#[test]
+fn test_hash_to_curve() {
+    let dst = b"DOPPIO_XMD:SHA-512_ELL2_RO_TESTGEN";
+    let vectors: [(&[u8], [u8; 32]); 2] = [
+        (
+            b"",
+            [
+                127, 90, 145, 158, 227, 12, 109, 105, 204, 187, 63, 133, 222, 32, 198, 228, 247,
+                160, 195, 107, 38, 100, 123, 64, 201, 169, 179, 222, 79, 170, 199, 3,
+            ],
+        ),
+        (
+            b"abc",
+            [
+                203, 47, 232, 58, 185, 234, 146, 146, 134, 234, 109, 19, 90, 210, 121, 251, 83,
+                204, 115, 140, 238, 182, 224, 236, 201, 151, 228, 112, 243, 52, 209, 138,
+            ],
+        ),
+    ];
+
+    for (msg, expected) in vectors.iter() {
+        let p = hash_to_curve(msg, dst);
+        assert!(p.is_on_curve_vartime());
+        assert!(p.is_small_order().unwrap_u8() == 0);
+        assert_eq!(crate::AffinePoint::from(p).into_bytes(), *expected);
+    }
+
+    assert!(hash_to_curve(b"abc", b"another tag") != hash_to_curve(b"abc", dst));
+}
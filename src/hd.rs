@@ -0,0 +1,722 @@
+//! This module implements hierarchical deterministic derivation of keys
+//! of the prime order subgroup of Doppio, in the style of BIP32.
+//!
+//! Every key has a chain code `c`, and the master key is derived from a
+//! seed of 16 to 64 bytes. Scalars and chain codes are derived from a
+//! key `k` and data `d` as
+//!
+//! ```text
+//! t = Fr::from_bytes_wide(HMAC-SHA512(k, d || 0x00))
+//! c = HMAC-SHA512(k, d || 0x01)[..32]
+//! ```
+//!
+//! The master secret key is `t` for `k = "Doppio seed"` and `d` the seed.
+//! The child `i` of a secret key `x` with public key `X = x * G` and chain
+//! code `c` has the secret key `x + t` and the public key `X + t * G`,
+//! with `k = c` and
+//!
+//! * `d = 0x00 || x || i` for hardened children, where `i >= 2^31`;
+//! * `d = X || i` for normal children, which can therefore also be
+//!   derived from the extended public key alone by watch-only wallets.
+//!
+//! Here `x` and `X` are 32-byte encodings and `i` is 32-bit big endian.
+//! An extended key is serialized in 77 bytes as
+//!
+//! ```text
+//! version (4) || depth (1) || parent fingerprint (4) || child number (4) || c (32) || key (32)
+//! ```
+//!
+//! where the version is `"dprv"` or `"dpub"` and the fingerprint of a
+//! key is the first 4 bytes of the SHA-256 hash of its public key. Keys
+//! can be used as [`schnorr`](crate::schnorr) keys.
+
+use core::fmt;
+use std::vec::Vec;
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::Zeroize;
+
+use crate::schnorr::{SigningKey, VerifyingKey};
+use crate::{AffinePoint, ExtendedPoint, Fr, GENERATOR};
+
+/// The HMAC key used to derive the master key from a seed.
+const MASTER_KEY: &[u8] = b"Doppio seed";
+
+/// The version of serialized extended secret keys.
+const SECRET_VERSION: [u8; 4] = *b"dprv";
+
+/// The version of serialized extended public keys.
+const PUBLIC_VERSION: [u8; 4] = *b"dpub";
+
+/// The size in bytes of a serialized extended key.
+pub const EXTENDED_KEY_SIZE: usize = 77;
+
+/// The first hardened child number, `2^31`.
+pub const HARDENED_OFFSET: u32 = 1 << 31;
+
+/// The number of a child key, which is hardened if it is at least
+/// [`HARDENED_OFFSET`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChildNumber(u32);
+
+/// A sequence of child numbers leading from a key to one of its
+/// descendants, written as `m/44'/0'/1/2` for instance.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<ChildNumber>);
+
+/// An extended secret key, which can derive all of its descendants.
+#[derive(Clone)]
+pub struct ExtendedSecretKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    chain_code: [u8; 32],
+    x: Fr,
+}
+
+/// An extended public key, which can derive the public keys of its
+/// normal descendants.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtendedPublicKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    chain_code: [u8; 32],
+    point: AffinePoint,
+}
+
+/// Computes `HMAC-SHA512(key, data || domain)`.
+fn hmac_sha512(key: &[u8], data: &[u8], domain: u8) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.update(&[domain]);
+
+    mac.finalize().into_bytes().into()
+}
+
+/// Derives a scalar and a chain code from `key` and `data`.
+fn derive(key: &[u8], data: &[u8]) -> (Fr, [u8; 32]) {
+    let mut wide = hmac_sha512(key, data, 0x00);
+    let t = Fr::from_bytes_wide(wide);
+
+    wide = hmac_sha512(key, data, 0x01);
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&wide[..32]);
+    wide.zeroize();
+
+    (t, chain_code)
+}
+
+fn fingerprint(point: &AffinePoint) -> [u8; 4] {
+    let mut res = [0u8; 4];
+    res.copy_from_slice(&Sha256::digest(point.into_bytes())[..4]);
+
+    res
+}
+
+/// Serializes the fields of an extended key.
+fn serialize(
+    version: [u8; 4],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    chain_code: &[u8; 32],
+    key: &[u8; 32],
+) -> [u8; EXTENDED_KEY_SIZE] {
+    let mut res = [0u8; EXTENDED_KEY_SIZE];
+    res[..4].copy_from_slice(&version);
+    res[4] = depth;
+    res[5..9].copy_from_slice(&parent_fingerprint);
+    res[9..13].copy_from_slice(&child_number.0.to_be_bytes());
+    res[13..45].copy_from_slice(chain_code);
+    res[45..].copy_from_slice(key);
+
+    res
+}
+
+/// Deserializes the fields of an extended key with the given `version`,
+/// checking that a master key has no parent.
+#[allow(clippy::type_complexity)]
+fn deserialize(
+    bytes: &[u8; EXTENDED_KEY_SIZE],
+    version: [u8; 4],
+) -> Option<(u8, [u8; 4], ChildNumber, [u8; 32], [u8; 32])> {
+    if bytes[..4] != version {
+        return None;
+    }
+
+    let depth = bytes[4];
+    let mut parent_fingerprint = [0u8; 4];
+    parent_fingerprint.copy_from_slice(&bytes[5..9]);
+    let mut child_number = [0u8; 4];
+    child_number.copy_from_slice(&bytes[9..13]);
+    let child_number = ChildNumber(u32::from_be_bytes(child_number));
+    if depth == 0 && (parent_fingerprint != [0u8; 4] || child_number.0 != 0) {
+        return None;
+    }
+
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&bytes[13..45]);
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&bytes[45..]);
+
+    Some((depth, parent_fingerprint, child_number, chain_code, key))
+}
+
+impl ChildNumber {
+    /// Returns the normal child number `index`, or `None` if `index` is
+    /// at least [`HARDENED_OFFSET`].
+    pub fn normal(index: u32) -> Option<ChildNumber> {
+        if index < HARDENED_OFFSET {
+            Some(ChildNumber(index))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the hardened child number `HARDENED_OFFSET + index`, or
+    /// `None` if `index` is at least [`HARDENED_OFFSET`].
+    pub fn hardened(index: u32) -> Option<ChildNumber> {
+        if index < HARDENED_OFFSET {
+            Some(ChildNumber(HARDENED_OFFSET + index))
+        } else {
+            None
+        }
+    }
+
+    /// Interprets a raw child number.
+    pub fn from_u32(value: u32) -> ChildNumber {
+        ChildNumber(value)
+    }
+
+    /// Returns the raw value of this child number.
+    pub fn to_u32(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether this child number is hardened.
+    pub fn is_hardened(&self) -> bool {
+        self.0 >= HARDENED_OFFSET
+    }
+}
+
+impl fmt::Display for ChildNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_hardened() {
+            write!(f, "{}'", self.0 - HARDENED_OFFSET)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl DerivationPath {
+    /// Creates a path from a sequence of child numbers.
+    pub fn new(children: Vec<ChildNumber>) -> DerivationPath {
+        DerivationPath(children)
+    }
+
+    /// Parses a path such as `m/44'/0'/1/2`, where hardened child numbers
+    /// are marked by `'`, `h` or `H`. This returns `None` if the path does
+    /// not start with `m`, or if an index is empty, has leading zeros or
+    /// is at least [`HARDENED_OFFSET`].
+    pub fn parse(path: &str) -> Option<DerivationPath> {
+        let mut components = path.split('/');
+        if components.next() != Some("m") {
+            return None;
+        }
+
+        components
+            .map(|component| {
+                let (index, is_hardened) = match component.strip_suffix(&['\'', 'h', 'H'][..]) {
+                    Some(index) => (index, true),
+                    None => (component, false),
+                };
+                let is_canonical = !index.is_empty()
+                    && index.bytes().all(|b| b.is_ascii_digit())
+                    && (index == "0" || !index.starts_with('0'));
+                if !is_canonical {
+                    return None;
+                }
+
+                let index = index.parse().ok()?;
+                if is_hardened {
+                    ChildNumber::hardened(index)
+                } else {
+                    ChildNumber::normal(index)
+                }
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(DerivationPath)
+    }
+
+    /// Returns the child numbers of this path.
+    pub fn children(&self) -> &[ChildNumber] {
+        &self.0
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("m")?;
+        for child in &self.0 {
+            write!(f, "/{}", child)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ExtendedSecretKey {
+    /// Derives the master key of `seed`. This returns `None` if `seed` is
+    /// shorter than 16 bytes or longer than 64 bytes, or if the derived
+    /// secret key is zero.
+    pub fn from_seed(seed: &[u8]) -> Option<ExtendedSecretKey> {
+        if seed.len() < 16 || seed.len() > 64 {
+            return None;
+        }
+
+        let (x, chain_code) = derive(MASTER_KEY, seed);
+        if x == Fr::zero() {
+            return None;
+        }
+
+        Some(ExtendedSecretKey {
+            depth: 0,
+            parent_fingerprint: [0u8; 4],
+            child_number: ChildNumber(0),
+            chain_code,
+            x,
+        })
+    }
+
+    fn point(&self) -> AffinePoint {
+        AffinePoint::from(ExtendedPoint::from(GENERATOR) * self.x)
+    }
+
+    /// Derives the child `child_number` of this key. This returns `None`
+    /// if this key is at the maximum depth of 255, or if the child secret
+    /// key is zero, which happens with negligible probability.
+    pub fn derive_child(&self, child_number: ChildNumber) -> Option<ExtendedSecretKey> {
+        let depth = self.depth.checked_add(1)?;
+        let point = self.point();
+
+        let mut data = [0u8; 37];
+        let data = if child_number.is_hardened() {
+            data[1..33].copy_from_slice(&self.x.into_bytes());
+            data[33..].copy_from_slice(&child_number.0.to_be_bytes());
+            &mut data[..]
+        } else {
+            data[..32].copy_from_slice(&point.into_bytes());
+            data[32..36].copy_from_slice(&child_number.0.to_be_bytes());
+            &mut data[..36]
+        };
+        let (mut t, chain_code) = derive(&self.chain_code, data);
+        data.zeroize();
+
+        let x = self.x + t;
+        t.zeroize();
+        if x == Fr::zero() {
+            return None;
+        }
+
+        Some(ExtendedSecretKey {
+            depth,
+            parent_fingerprint: fingerprint(&point),
+            child_number,
+            chain_code,
+            x,
+        })
+    }
+
+    /// Derives the descendant of this key along `path`.
+    pub fn derive_path(&self, path: &DerivationPath) -> Option<ExtendedSecretKey> {
+        let mut key = self.clone();
+        for child_number in path.children() {
+            key = key.derive_child(*child_number)?;
+        }
+
+        Some(key)
+    }
+
+    /// Returns the extended public key of this key.
+    pub fn public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            point: self.point(),
+        }
+    }
+
+    /// Returns the Schnorr signing key of this key.
+    pub fn signing_key(&self) -> SigningKey {
+        SigningKey::from_scalar(self.x)
+    }
+
+    /// Returns the depth of this key, which is 0 for a master key.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Returns the child number of this key.
+    pub fn child_number(&self) -> ChildNumber {
+        self.child_number
+    }
+
+    /// Returns the fingerprint of this key.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.point())
+    }
+
+    /// Returns the fingerprint of the parent of this key.
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    /// Attempts to interpret a serialized extended secret key, failing if
+    /// the version is not `"dprv"`, if a master key has a parent, or if
+    /// the secret key is not a canonical encoding of a nonzero element of
+    /// `Fr`.
+    pub fn from_bytes(bytes: &[u8; EXTENDED_KEY_SIZE]) -> Option<ExtendedSecretKey> {
+        let (depth, parent_fingerprint, child_number, chain_code, mut key) =
+            deserialize(bytes, SECRET_VERSION)?;
+        let x = Fr::from_bytes(key);
+        key.zeroize();
+        if !bool::from(x.is_some()) {
+            return None;
+        }
+        let x = x.unwrap();
+        if x == Fr::zero() {
+            return None;
+        }
+
+        Some(ExtendedSecretKey {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            x,
+        })
+    }
+
+    /// Converts this extended secret key into its serialized form.
+    pub fn into_bytes(&self) -> [u8; EXTENDED_KEY_SIZE] {
+        let mut key = self.x.into_bytes();
+        let res = serialize(
+            SECRET_VERSION,
+            self.depth,
+            self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &key,
+        );
+        key.zeroize();
+
+        res
+    }
+}
+
+impl Drop for ExtendedSecretKey {
+    fn drop(&mut self) {
+        self.x.zeroize();
+        self.chain_code.zeroize();
+    }
+}
+
+impl fmt::Debug for ExtendedSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExtendedSecretKey")
+            .field("depth", &self.depth)
+            .field("parent_fingerprint", &self.parent_fingerprint)
+            .field("child_number", &self.child_number)
+            .finish()
+    }
+}
+
+impl ExtendedPublicKey {
+    /// Derives the public key of the child `child_number` of this key.
+    /// This returns `None` if `child_number` is hardened, if this key is
+    /// at the maximum depth of 255, or if the child public key is the
+    /// identity, which happens with negligible probability.
+    pub fn derive_child(&self, child_number: ChildNumber) -> Option<ExtendedPublicKey> {
+        if child_number.is_hardened() {
+            return None;
+        }
+        let depth = self.depth.checked_add(1)?;
+
+        let mut data = [0u8; 36];
+        data[..32].copy_from_slice(&self.point.into_bytes());
+        data[32..].copy_from_slice(&child_number.0.to_be_bytes());
+        let (t, chain_code) = derive(&self.chain_code, &data);
+
+        let point = ExtendedPoint::from(self.point) + ExtendedPoint::from(GENERATOR) * t;
+        if bool::from(point.is_identity()) {
+            return None;
+        }
+
+        Some(ExtendedPublicKey {
+            depth,
+            parent_fingerprint: fingerprint(&self.point),
+            child_number,
+            chain_code,
+            point: AffinePoint::from(point),
+        })
+    }
+
+    /// Derives the public key of the descendant of this key along
+    /// `path`, which must not contain hardened child numbers.
+    pub fn derive_path(&self, path: &DerivationPath) -> Option<ExtendedPublicKey> {
+        let mut key = *self;
+        for child_number in path.children() {
+            key = key.derive_child(*child_number)?;
+        }
+
+        Some(key)
+    }
+
+    /// Returns the Schnorr verifying key of this key.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey::from_point(self.point)
+    }
+
+    /// Returns the point `x * G` of this key.
+    pub fn to_point(&self) -> AffinePoint {
+        self.point
+    }
+
+    /// Returns the depth of this key, which is 0 for a master key.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Returns the child number of this key.
+    pub fn child_number(&self) -> ChildNumber {
+        self.child_number
+    }
+
+    /// Returns the fingerprint of this key.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.point)
+    }
+
+    /// Returns the fingerprint of the parent of this key.
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    /// Attempts to interpret a serialized extended public key, failing if
+    /// the version is not `"dpub"`, if a master key has a parent, or if
+    /// the public key is not the canonical encoding of a curve point in
+    /// the prime order subgroup. Child keys derived from a point with a
+    /// torsion component would not match those of the secret key.
+    pub fn from_bytes(bytes: &[u8; EXTENDED_KEY_SIZE]) -> Option<ExtendedPublicKey> {
+        let (depth, parent_fingerprint, child_number, chain_code, key) =
+            deserialize(bytes, PUBLIC_VERSION)?;
+        let point = AffinePoint::from_bytes(key);
+        if !bool::from(point.is_some()) {
+            return None;
+        }
+        let point = point.unwrap();
+        if !bool::from(point.is_prime_order()) {
+            return None;
+        }
+
+        Some(ExtendedPublicKey {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            point,
+        })
+    }
+
+    /// Converts this extended public key into its serialized form.
+    pub fn into_bytes(&self) -> [u8; EXTENDED_KEY_SIZE] {
+        serialize(
+            PUBLIC_VERSION,
+            self.depth,
+            self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &self.point.into_bytes(),
+        )
+    }
+}
+
+#[test]
+fn test_vectors() {
+    let seed: Vec<u8> = (0u8..16).collect();
+    let master = ExtendedSecretKey::from_seed(&seed).unwrap();
+    assert_eq!(
+        &master.into_bytes()[..],
+        &[
+            0x64, 0x70, 0x72, 0x76, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x65,
+            0xd0, 0x1a, 0x60, 0xa8, 0x31, 0x36, 0x1f, 0xc3, 0x23, 0x74, 0x18, 0xd4, 0x9b, 0x70,
+            0x88, 0x96, 0xb3, 0xd2, 0xb0, 0x5f, 0x9c, 0x17, 0x7e, 0x59, 0x7d, 0x00, 0x75, 0x20,
+            0xd7, 0xb8, 0xc4, 0x11, 0x76, 0xd0, 0x7f, 0xb1, 0x3c, 0x39, 0x80, 0x82, 0x65, 0xb2,
+            0x6a, 0x3e, 0xe1, 0x0c, 0xae, 0xa6, 0xf3, 0x98, 0x09, 0xcf, 0xf9, 0xeb, 0x24, 0xb5,
+            0x55, 0xd8, 0xf7, 0xba, 0xa2, 0x58, 0x02
+        ][..]
+    );
+    assert_eq!(
+        &master.public_key().into_bytes()[..],
+        &[
+            0x64, 0x70, 0x75, 0x62, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x65,
+            0xd0, 0x1a, 0x60, 0xa8, 0x31, 0x36, 0x1f, 0xc3, 0x23, 0x74, 0x18, 0xd4, 0x9b, 0x70,
+            0x88, 0x96, 0xb3, 0xd2, 0xb0, 0x5f, 0x9c, 0x17, 0x7e, 0x59, 0x7d, 0x00, 0x75, 0x20,
+            0xd7, 0xb8, 0xc4, 0xfd, 0x49, 0x36, 0x53, 0xde, 0x4c, 0x2c, 0x21, 0x75, 0xca, 0x1b,
+            0xd7, 0xf9, 0x78, 0xff, 0xe9, 0x7d, 0x33, 0x10, 0x93, 0xe2, 0x65, 0x8d, 0xe9, 0x1b,
+            0x28, 0x45, 0x41, 0x42, 0x94, 0x3a, 0x81
+        ][..]
+    );
+
+    let path = DerivationPath::parse("m/0'/1/2'/2/1000000000").unwrap();
+    let key = master.derive_path(&path).unwrap();
+    assert_eq!(key.depth(), 5);
+    assert_eq!(key.child_number(), ChildNumber::normal(1000000000).unwrap());
+    assert_eq!(
+        &key.into_bytes()[..],
+        &[
+            0x64, 0x70, 0x72, 0x76, 0x05, 0x08, 0x8c, 0x6c, 0x33, 0x3b, 0x9a, 0xca, 0x00, 0xbe,
+            0xe6, 0xcf, 0x71, 0x39, 0x90, 0xbf, 0x2b, 0x26, 0x67, 0xbf, 0x8f, 0x12, 0xf1, 0x31,
+            0xd9, 0x15, 0xd3, 0x17, 0x4e, 0x97, 0x25, 0xa4, 0x6c, 0x0e, 0x30, 0x21, 0x2f, 0x14,
+            0x1b, 0xed, 0xa0, 0x0e, 0x3c, 0x15, 0x0f, 0xd0, 0x11, 0x61, 0x12, 0xf1, 0x85, 0xd1,
+            0x86, 0x2a, 0xd3, 0x60, 0x24, 0x98, 0x69, 0x0f, 0xf3, 0xd1, 0xdd, 0x30, 0xc8, 0x8e,
+            0x94, 0x1f, 0xbb, 0xd8, 0xb7, 0x2e, 0x00
+        ][..]
+    );
+    assert_eq!(
+        &key.public_key().into_bytes()[..],
+        &[
+            0x64, 0x70, 0x75, 0x62, 0x05, 0x08, 0x8c, 0x6c, 0x33, 0x3b, 0x9a, 0xca, 0x00, 0xbe,
+            0xe6, 0xcf, 0x71, 0x39, 0x90, 0xbf, 0x2b, 0x26, 0x67, 0xbf, 0x8f, 0x12, 0xf1, 0x31,
+            0xd9, 0x15, 0xd3, 0x17, 0x4e, 0x97, 0x25, 0xa4, 0x6c, 0x0e, 0x30, 0x21, 0x2f, 0x14,
+            0x1b, 0xed, 0xa0, 0xc9, 0x48, 0x13, 0x2e, 0x32, 0x7d, 0x7c, 0x76, 0x7e, 0xe7, 0xc2,
+            0xa5, 0x4f, 0xa2, 0x6e, 0xbc, 0x75, 0x86, 0x15, 0x66, 0x37, 0x6c, 0x52, 0xa1, 0x7a,
+            0x65, 0x54, 0xed, 0xc6, 0x13, 0x59, 0x08
+        ][..]
+    );
+}
+
+#[test]
+fn test_public_derivation() {
+    let master = ExtendedSecretKey::from_seed(&[7u8; 32]).unwrap();
+    let account = master
+        .derive_path(&DerivationPath::parse("m/44'/0'").unwrap())
+        .unwrap();
+    let watch_only = account.public_key();
+
+    let path = DerivationPath::parse("m/0/5/1").unwrap();
+    let secret = account.derive_path(&path).unwrap();
+    let public = watch_only.derive_path(&path).unwrap();
+    assert_eq!(secret.public_key(), public);
+    assert_eq!(secret.signing_key().verifying_key(), public.verifying_key());
+    assert_eq!(
+        secret.parent_fingerprint(),
+        account
+            .derive_path(&DerivationPath::parse("m/0/5").unwrap())
+            .unwrap()
+            .fingerprint()
+    );
+
+    // Hardened children cannot be derived from public keys, and they
+    // differ from the normal children with the same index.
+    assert!(watch_only
+        .derive_child(ChildNumber::hardened(0).unwrap())
+        .is_none());
+    assert_ne!(
+        account
+            .derive_child(ChildNumber::hardened(0).unwrap())
+            .unwrap()
+            .public_key(),
+        account
+            .derive_child(ChildNumber::normal(0).unwrap())
+            .unwrap()
+            .public_key()
+    );
+}
+
+#[test]
+fn test_paths() {
+    let path = DerivationPath::parse("m/44'/0h/1H/2/2147483647").unwrap();
+    assert_eq!(
+        path.children(),
+        &[
+            ChildNumber::hardened(44).unwrap(),
+            ChildNumber::hardened(0).unwrap(),
+            ChildNumber::hardened(1).unwrap(),
+            ChildNumber::normal(2).unwrap(),
+            ChildNumber::from_u32(HARDENED_OFFSET - 1),
+        ]
+    );
+    assert_eq!(std::format!("{}", path), "m/44'/0'/1'/2/2147483647");
+    assert!(DerivationPath::parse("m").unwrap().children().is_empty());
+
+    for invalid in [
+        "",
+        "M",
+        "/0",
+        "0/1",
+        "m/",
+        "m//1",
+        "m/01",
+        "m/+1",
+        "m/-1",
+        "m/1''",
+        "m/h",
+        "m/2147483648",
+        "m/2147483648'",
+        "m/4294967296",
+    ]
+    .iter()
+    {
+        assert!(DerivationPath::parse(invalid).is_none(), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_serialization() {
+    let master = ExtendedSecretKey::from_seed(&[7u8; 64]).unwrap();
+    let key = master
+        .derive_path(&DerivationPath::parse("m/1'/2").unwrap())
+        .unwrap();
+    let public = key.public_key();
+
+    let bytes = key.into_bytes();
+    assert_eq!(
+        ExtendedSecretKey::from_bytes(&bytes).unwrap().into_bytes(),
+        bytes
+    );
+    assert_eq!(
+        ExtendedPublicKey::from_bytes(&public.into_bytes()).unwrap(),
+        public
+    );
+    assert!(ExtendedPublicKey::from_bytes(&bytes).is_none());
+    assert!(ExtendedSecretKey::from_bytes(&public.into_bytes()).is_none());
+
+    // A master key cannot have a parent.
+    let mut bytes = master.into_bytes();
+    bytes[5] = 1;
+    assert!(ExtendedSecretKey::from_bytes(&bytes).is_none());
+
+    // Secret keys must be canonical and nonzero, and public keys must be
+    // in the prime order subgroup.
+    let mut bytes = key.into_bytes();
+    bytes[45..].copy_from_slice(&[0xff; 32]);
+    assert!(ExtendedSecretKey::from_bytes(&bytes).is_none());
+    bytes[45..].copy_from_slice(&[0; 32]);
+    assert!(ExtendedSecretKey::from_bytes(&bytes).is_none());
+    let mut bytes = public.into_bytes();
+    bytes[45..].copy_from_slice(&AffinePoint::identity().into_bytes());
+    assert!(ExtendedPublicKey::from_bytes(&bytes).is_none());
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+    let torsioned = ExtendedPoint::from(public.to_point()) + two_torsion;
+    bytes[45..].copy_from_slice(&AffinePoint::from(torsioned).into_bytes());
+    assert!(ExtendedPublicKey::from_bytes(&bytes).is_none());
+
+    assert!(ExtendedSecretKey::from_seed(&[7u8; 15]).is_none());
+    assert!(ExtendedSecretKey::from_seed(&[7u8; 65]).is_none());
+}
@@ -0,0 +1,62 @@
+//! Helpers shared by the protocol modules for decoding proofs and
+//! messages, for clearing secret vectors and for sampling indices.
+
+use rand_core::RngCore;
+use zeroize::Zeroize;
+
+use crate::{AffinePoint, Fr};
+
+/// Zeroizes every scalar of `v`.
+pub(crate) fn zeroize_all(v: &mut [Fr]) {
+    for x in v.iter_mut() {
+        x.zeroize();
+    }
+}
+
+/// Splits 32 bytes off the front of `bytes`, advancing it.
+fn read_bytes(bytes: &mut &[u8]) -> Option<[u8; 32]> {
+    if bytes.len() < 32 {
+        return None;
+    }
+    let (head, tail) = bytes.split_at(32);
+    let mut repr = [0u8; 32];
+    repr.copy_from_slice(head);
+    *bytes = tail;
+
+    Some(repr)
+}
+
+/// Decodes a point from the front of `bytes`, advancing it.
+pub(crate) fn read_point(bytes: &mut &[u8]) -> Option<AffinePoint> {
+    let p = AffinePoint::from_bytes(read_bytes(bytes)?);
+
+    if bool::from(p.is_some()) {
+        Some(p.unwrap())
+    } else {
+        None
+    }
+}
+
+/// Decodes a canonically encoded scalar from the front of `bytes`,
+/// advancing it.
+pub(crate) fn read_scalar(bytes: &mut &[u8]) -> Option<Fr> {
+    let s = Fr::from_bytes(read_bytes(bytes)?);
+
+    if bool::from(s.is_some()) {
+        Some(s.unwrap())
+    } else {
+        None
+    }
+}
+
+/// Returns a uniformly random integer in `0..bound`.
+pub(crate) fn uniform_index<R: RngCore>(rng: &mut R, bound: usize) -> usize {
+    let bound = bound as u64;
+    let zone = u64::MAX - u64::MAX % bound;
+    loop {
+        let v = rng.next_u64();
+        if v < zone {
+            return (v % bound) as usize;
+        }
+    }
+}
@@ -0,0 +1,1123 @@
+//! This module implements hybrid public key encryption in the style of
+//! HPKE (RFC 9180) with a Diffie-Hellman KEM over the prime order
+//! subgroup of Doppio.
+//!
+//! The KEM is `DHKEM(Doppio, HKDF-SHA256)` as specified in Section 4.1 of
+//! RFC 9180, with the identifier [`KEM_ID`], which is not assigned by
+//! IANA, and:
+//!
+//! * `DH(sk, pk)` is the encoding of `[4] (sk * pk)`, failing if it is
+//!   the identity, so that small order components of public keys are
+//!   cleared and public keys of small order are rejected;
+//! * `SerializePublicKey` is [`AffinePoint::into_bytes`](crate::AffinePoint::into_bytes),
+//!   so `Nenc = Npk = 32`, and `Nsk = 32`, `Nsecret = 32`;
+//! * `DeriveKeyPair(ikm)` hashes to `Fr` by reducing 64 bytes of
+//!   `LabeledExpand(LabeledExtract("", "dkp_prk", ikm), "sk", "", 64)`
+//!   with [`Fr::from_bytes_wide`](crate::Fr::from_bytes_wide).
+//!
+//! The KEM is used in the key schedule of RFC 9180 with a pluggable
+//! [`Kdf`] and [`Aead`], which are [`HkdfSha256`] and
+//! [`ChaCha20Poly1305`] by default, in the base, PSK, auth and auth-PSK
+//! modes. A [`SenderContext`] seals messages and a [`ReceiverContext`]
+//! opens them in the same order, and both can export secrets.
+//!
+//! The key schedule is tested against a vector of RFC 9180 for X25519,
+//! while the vectors of the Doppio KEM are self-generated, since it has
+//! no published test vectors.
+
+use core::fmt;
+use core::marker::PhantomData;
+use std::vec::Vec;
+
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::{Key, Nonce, Tag};
+use hkdf::{Hkdf, HkdfExtract};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Sha256, Sha512};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::{AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The identifier of `DHKEM(Doppio, HKDF-SHA256)`, which is not assigned
+/// by IANA.
+pub const KEM_ID: u16 = 0xd0d0;
+
+/// The version label of every labeled extraction and expansion.
+const VERSION_LABEL: &[u8] = b"HPKE-v1";
+
+/// The size in bytes of the shared secret of the KEM.
+const N_SECRET: usize = 32;
+
+/// The mode identifiers of RFC 9180.
+const MODE_BASE: u8 = 0x00;
+const MODE_PSK: u8 = 0x01;
+const MODE_AUTH: u8 = 0x02;
+const MODE_AUTH_PSK: u8 = 0x03;
+
+/// A key derivation function for the HPKE key schedule.
+pub trait Kdf {
+    /// The identifier of the KDF in RFC 9180.
+    const ID: u16;
+    /// The size in bytes of the output of `extract`.
+    const NH: usize;
+
+    /// Extracts a pseudorandom key of `NH` bytes from the concatenation
+    /// of `ikm` with `salt`.
+    fn extract(salt: &[u8], ikm: &[&[u8]]) -> Vec<u8>;
+
+    /// Expands `prk` into `len` bytes for the concatenation of `info`.
+    /// This returns `None` if `len` is too large.
+    fn expand(prk: &[u8], info: &[&[u8]], len: usize) -> Option<Vec<u8>>;
+}
+
+/// An authenticated encryption scheme for the HPKE key schedule.
+pub trait Aead {
+    /// The identifier of the AEAD in RFC 9180.
+    const ID: u16;
+    /// The size in bytes of keys.
+    const NK: usize;
+    /// The size in bytes of nonces, which is at least 8.
+    const NN: usize;
+    /// The size in bytes of authentication tags.
+    const NT: usize;
+
+    /// Encrypts `plaintext` with associated data `aad` under `key` and
+    /// `nonce`, of `NK` and `NN` bytes respectively.
+    fn seal(key: &[u8], nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8>;
+
+    /// Decrypts `ciphertext` with associated data `aad` under `key` and
+    /// `nonce`, of `NK` and `NN` bytes respectively, returning `None` if
+    /// it is not authentic.
+    fn open(key: &[u8], nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// HKDF with SHA-256.
+#[derive(Clone, Copy, Debug)]
+pub struct HkdfSha256;
+
+/// HKDF with SHA-512.
+#[derive(Clone, Copy, Debug)]
+pub struct HkdfSha512;
+
+/// The ChaCha20-Poly1305 AEAD of RFC 8439.
+#[derive(Clone, Copy, Debug)]
+pub struct ChaCha20Poly1305;
+
+impl Kdf for HkdfSha256 {
+    const ID: u16 = 0x0001;
+    const NH: usize = 32;
+
+    fn extract(salt: &[u8], ikm: &[&[u8]]) -> Vec<u8> {
+        let mut extract = HkdfExtract::<Sha256>::new(Some(salt));
+        for part in ikm {
+            extract.input_ikm(part);
+        }
+
+        extract.finalize().0.to_vec()
+    }
+
+    fn expand(prk: &[u8], info: &[&[u8]], len: usize) -> Option<Vec<u8>> {
+        let mut okm = vec![0u8; len];
+        Hkdf::<Sha256>::from_prk(prk)
+            .ok()?
+            .expand_multi_info(info, &mut okm)
+            .ok()?;
+
+        Some(okm)
+    }
+}
+
+impl Kdf for HkdfSha512 {
+    const ID: u16 = 0x0003;
+    const NH: usize = 64;
+
+    fn extract(salt: &[u8], ikm: &[&[u8]]) -> Vec<u8> {
+        let mut extract = HkdfExtract::<Sha512>::new(Some(salt));
+        for part in ikm {
+            extract.input_ikm(part);
+        }
+
+        extract.finalize().0.to_vec()
+    }
+
+    fn expand(prk: &[u8], info: &[&[u8]], len: usize) -> Option<Vec<u8>> {
+        let mut okm = vec![0u8; len];
+        Hkdf::<Sha512>::from_prk(prk)
+            .ok()?
+            .expand_multi_info(info, &mut okm)
+            .ok()?;
+
+        Some(okm)
+    }
+}
+
+/// Copies `bytes` into an array of the expected size.
+fn to_array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut res = [0u8; N];
+    res.copy_from_slice(bytes);
+
+    res
+}
+
+impl Aead for ChaCha20Poly1305 {
+    const ID: u16 = 0x0003;
+    const NK: usize = 32;
+    const NN: usize = 12;
+    const NT: usize = 16;
+
+    fn seal(key: &[u8], nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let mut buffer = plaintext.to_vec();
+        let tag = chacha20poly1305::ChaCha20Poly1305::new(Key::from_slice(key))
+            .encrypt_in_place_detached(Nonce::from_slice(nonce), aad, &mut buffer)
+            .unwrap();
+        buffer.extend_from_slice(&tag);
+
+        buffer
+    }
+
+    fn open(key: &[u8], nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>> {
+        if ciphertext.len() < Self::NT {
+            return None;
+        }
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - Self::NT);
+
+        let mut buffer = ciphertext.to_vec();
+        chacha20poly1305::ChaCha20Poly1305::new(Key::from_slice(key))
+            .decrypt_in_place_detached(
+                Nonce::from_slice(nonce),
+                aad,
+                &mut buffer,
+                Tag::from_slice(tag),
+            )
+            .ok()?;
+
+        Some(buffer)
+    }
+}
+
+fn labeled_extract<K: Kdf>(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> Vec<u8> {
+    K::extract(salt, &[VERSION_LABEL, suite_id, label, ikm])
+}
+
+fn labeled_expand<K: Kdf>(
+    suite_id: &[u8],
+    prk: &[u8],
+    label: &[u8],
+    info: &[u8],
+    len: usize,
+) -> Option<Vec<u8>> {
+    if len > usize::from(u16::MAX) {
+        return None;
+    }
+
+    K::expand(
+        prk,
+        &[
+            &(len as u16).to_be_bytes(),
+            VERSION_LABEL,
+            suite_id,
+            label,
+            info,
+        ],
+        len,
+    )
+}
+
+/// Returns the suite identifier `"KEM" || I2OSP(kem_id, 2)` of a KEM.
+fn kem_suite_id(kem_id: u16) -> [u8; 5] {
+    let mut suite_id = [0u8; 5];
+    suite_id[..3].copy_from_slice(b"KEM");
+    suite_id[3..].copy_from_slice(&kem_id.to_be_bytes());
+
+    suite_id
+}
+
+/// A secret key of the KEM.
+#[derive(Clone)]
+pub struct SecretKey(Fr);
+
+/// A public key of the KEM.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PublicKey(AffinePoint);
+
+/// The encapsulated key `enc`, which is the serialized ephemeral public
+/// key of the sender.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EncappedKey(AffinePoint);
+
+/// A shared secret of the KEM.
+pub struct SharedSecret([u8; N_SECRET]);
+
+impl SecretKey {
+    /// Samples a new secret key from the provided RNG.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> SecretKey {
+        SecretKey(Fr::random(rng))
+    }
+
+    /// Derives a secret key from the input keying material `ikm`, as
+    /// `DeriveKeyPair` does.
+    pub fn derive(ikm: &[u8]) -> SecretKey {
+        let suite_id = kem_suite_id(KEM_ID);
+        let mut prk = labeled_extract::<HkdfSha256>(&suite_id, b"", b"dkp_prk", ikm);
+        let mut bytes = labeled_expand::<HkdfSha256>(&suite_id, &prk, b"sk", b"", 64).unwrap();
+        let mut wide = to_array(&bytes);
+        let sk = SecretKey(Fr::from_bytes_wide(wide));
+
+        prk[..].zeroize();
+        bytes[..].zeroize();
+        wide.zeroize();
+
+        sk
+    }
+
+    /// Attempts to interpret a byte representation of a secret key,
+    /// failing if it is not a canonical encoding of a nonzero element of
+    /// `Fr`. The zero key would make every shared secret the identity.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<SecretKey> {
+        let scalar = Fr::from_bytes(bytes);
+        let is_some = scalar.is_some();
+        let scalar = scalar.unwrap_or(Fr::zero());
+        let is_some = is_some & !scalar.ct_eq(&Fr::zero());
+
+        CtOption::new(
+            SecretKey(Fr::conditional_select(&Fr::one(), &scalar, is_some)),
+            is_some,
+        )
+    }
+
+    /// Converts this secret key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+
+    /// Returns the public key of this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(AffinePoint::from(ExtendedPoint::from(GENERATOR) * self.0))
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SecretKey").finish()
+    }
+}
+
+impl PublicKey {
+    /// Attempts to interpret a byte representation of a public key,
+    /// failing if it is not the canonical encoding of a curve point.
+    ///
+    /// Small order components are not rejected here; they are cleared
+    /// by the cofactor multiplication in `DH`, and a public key of small
+    /// order is rejected when it is used.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<PublicKey> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+
+        CtOption::new(PublicKey(point.unwrap_or(AffinePoint::identity())), is_some)
+    }
+
+    /// Converts this public key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+impl EncappedKey {
+    /// Attempts to interpret a byte representation of an encapsulated
+    /// key, failing if it is not the canonical encoding of a curve point.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<EncappedKey> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+
+        CtOption::new(
+            EncappedKey(point.unwrap_or(AffinePoint::identity())),
+            is_some,
+        )
+    }
+
+    /// Converts this encapsulated key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+impl SharedSecret {
+    /// Returns the bytes of this shared secret.
+    pub fn as_bytes(&self) -> &[u8; N_SECRET] {
+        &self.0
+    }
+}
+
+impl ConstantTimeEq for SharedSecret {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SharedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedSecret").finish()
+    }
+}
+
+/// Computes `DH(sk, pk)`, returning `None` if it is the identity.
+fn dh(sk: &SecretKey, pk: &AffinePoint) -> Option<[u8; 32]> {
+    let shared = (ExtendedPoint::from(*pk) * sk.0).mul_by_cofactor();
+    if bool::from(shared.is_identity()) {
+        return None;
+    }
+
+    Some(AffinePoint::from(shared).into_bytes())
+}
+
+/// Derives the shared secret from the Diffie-Hellman outputs `dh` and the
+/// KEM context, as `ExtractAndExpand` does for the KEM `kem_id`.
+fn extract_and_expand(kem_id: u16, dh: &[u8], kem_context: &[u8]) -> SharedSecret {
+    let suite_id = kem_suite_id(kem_id);
+    let mut prk = labeled_extract::<HkdfSha256>(&suite_id, b"", b"eae_prk", dh);
+    let mut secret =
+        labeled_expand::<HkdfSha256>(&suite_id, &prk, b"shared_secret", kem_context, N_SECRET)
+            .unwrap();
+    let shared_secret = SharedSecret(to_array(&secret));
+    prk[..].zeroize();
+    secret[..].zeroize();
+
+    shared_secret
+}
+
+/// Encapsulates a shared secret to `pk_r` with the ephemeral key `sk_e`,
+/// authenticated with `sk_s` in the auth modes.
+fn encap_with(
+    sk_e: &SecretKey,
+    pk_r: &PublicKey,
+    sk_s: Option<&SecretKey>,
+) -> Option<(SharedSecret, EncappedKey)> {
+    let enc = EncappedKey(sk_e.public_key().0);
+
+    let mut dh_bytes = Vec::with_capacity(64);
+    dh_bytes.extend_from_slice(&dh(sk_e, &pk_r.0)?);
+    let mut kem_context = Vec::with_capacity(96);
+    kem_context.extend_from_slice(&enc.into_bytes());
+    kem_context.extend_from_slice(&pk_r.into_bytes());
+    if let Some(sk_s) = sk_s {
+        dh_bytes.extend_from_slice(&dh(sk_s, &pk_r.0)?);
+        kem_context.extend_from_slice(&sk_s.public_key().into_bytes());
+    }
+
+    let shared_secret = extract_and_expand(KEM_ID, &dh_bytes, &kem_context);
+    dh_bytes[..].zeroize();
+
+    Some((shared_secret, enc))
+}
+
+/// Decapsulates the shared secret of `enc` with `sk_r`, authenticated by
+/// `pk_s` in the auth modes.
+fn decap_with(
+    enc: &EncappedKey,
+    sk_r: &SecretKey,
+    pk_s: Option<&PublicKey>,
+) -> Option<SharedSecret> {
+    let mut dh_bytes = Vec::with_capacity(64);
+    dh_bytes.extend_from_slice(&dh(sk_r, &enc.0)?);
+    let mut kem_context = Vec::with_capacity(96);
+    kem_context.extend_from_slice(&enc.into_bytes());
+    kem_context.extend_from_slice(&sk_r.public_key().into_bytes());
+    if let Some(pk_s) = pk_s {
+        dh_bytes.extend_from_slice(&dh(sk_r, &pk_s.0)?);
+        kem_context.extend_from_slice(&pk_s.into_bytes());
+    }
+
+    let shared_secret = extract_and_expand(KEM_ID, &dh_bytes, &kem_context);
+    dh_bytes[..].zeroize();
+
+    Some(shared_secret)
+}
+
+/// Generates a shared secret and its encapsulation to `pk_r`. This
+/// returns `None` if `pk_r` is of small order.
+pub fn encap<R: RngCore + CryptoRng>(
+    pk_r: &PublicKey,
+    rng: &mut R,
+) -> Option<(SharedSecret, EncappedKey)> {
+    encap_with(&SecretKey::new(rng), pk_r, None)
+}
+
+/// Recovers the shared secret encapsulated in `enc` with `sk_r`. This
+/// returns `None` if `enc` is of small order.
+pub fn decap(enc: &EncappedKey, sk_r: &SecretKey) -> Option<SharedSecret> {
+    decap_with(enc, sk_r, None)
+}
+
+/// Generates a shared secret and its encapsulation to `pk_r`,
+/// authenticated with the sender's key `sk_s`. This returns `None` if
+/// `pk_r` is of small order.
+pub fn auth_encap<R: RngCore + CryptoRng>(
+    pk_r: &PublicKey,
+    sk_s: &SecretKey,
+    rng: &mut R,
+) -> Option<(SharedSecret, EncappedKey)> {
+    encap_with(&SecretKey::new(rng), pk_r, Some(sk_s))
+}
+
+/// Recovers the shared secret encapsulated in `enc` with `sk_r`, checking
+/// that it was authenticated with the secret key of `pk_s`. This returns
+/// `None` if `enc` or `pk_s` is of small order.
+pub fn auth_decap(enc: &EncappedKey, sk_r: &SecretKey, pk_s: &PublicKey) -> Option<SharedSecret> {
+    decap_with(enc, sk_r, Some(pk_s))
+}
+
+/// The state shared by both contexts.
+struct Context<K: Kdf, A: Aead> {
+    key: Vec<u8>,
+    base_nonce: Vec<u8>,
+    exporter_secret: Vec<u8>,
+    seq: u64,
+    suite_id: [u8; 10],
+    _marker: PhantomData<(K, A)>,
+}
+
+impl<K: Kdf, A: Aead> Context<K, A> {
+    /// Runs the key schedule of RFC 9180 for the KEM `kem_id`. This
+    /// returns `None` if a PSK is expected but `psk` or `psk_id` is empty.
+    fn new(
+        kem_id: u16,
+        mode: u8,
+        shared_secret: &SharedSecret,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Option<Context<K, A>> {
+        let expects_psk = mode == MODE_PSK || mode == MODE_AUTH_PSK;
+        if expects_psk && (psk.is_empty() || psk_id.is_empty()) {
+            return None;
+        }
+
+        let mut suite_id = [0u8; 10];
+        suite_id[..4].copy_from_slice(b"HPKE");
+        suite_id[4..6].copy_from_slice(&kem_id.to_be_bytes());
+        suite_id[6..8].copy_from_slice(&K::ID.to_be_bytes());
+        suite_id[8..].copy_from_slice(&A::ID.to_be_bytes());
+
+        let psk_id_hash = labeled_extract::<K>(&suite_id, b"", b"psk_id_hash", psk_id);
+        let info_hash = labeled_extract::<K>(&suite_id, b"", b"info_hash", info);
+        let mut context = vec![mode];
+        context.extend_from_slice(&psk_id_hash);
+        context.extend_from_slice(&info_hash);
+
+        let mut secret = labeled_extract::<K>(&suite_id, &shared_secret.0, b"secret", psk);
+        let key = labeled_expand::<K>(&suite_id, &secret, b"key", &context, A::NK)?;
+        let base_nonce = labeled_expand::<K>(&suite_id, &secret, b"base_nonce", &context, A::NN)?;
+        let exporter_secret = labeled_expand::<K>(&suite_id, &secret, b"exp", &context, K::NH)?;
+        secret[..].zeroize();
+
+        Some(Context {
+            key,
+            base_nonce,
+            exporter_secret,
+            seq: 0,
+            suite_id,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Returns the nonce for the current sequence number and increments
+    /// it, or returns `None` if the sequence number is exhausted.
+    fn next_nonce(&mut self) -> Option<Vec<u8>> {
+        let seq = self.seq;
+        self.seq = self.seq.checked_add(1)?;
+
+        let mut nonce = self.base_nonce.clone();
+        let offset = nonce.len() - 8;
+        for (n, s) in nonce[offset..].iter_mut().zip(seq.to_be_bytes().iter()) {
+            *n ^= s;
+        }
+
+        Some(nonce)
+    }
+
+    fn export(&self, exporter_context: &[u8], len: usize) -> Option<Vec<u8>> {
+        labeled_expand::<K>(
+            &self.suite_id,
+            &self.exporter_secret,
+            b"sec",
+            exporter_context,
+            len,
+        )
+    }
+}
+
+impl<K: Kdf, A: Aead> Drop for Context<K, A> {
+    fn drop(&mut self) {
+        self.key[..].zeroize();
+        self.base_nonce[..].zeroize();
+        self.exporter_secret[..].zeroize();
+    }
+}
+
+/// The context of a sender, which encrypts messages to the receiver.
+pub struct SenderContext<K: Kdf = HkdfSha256, A: Aead = ChaCha20Poly1305>(Context<K, A>);
+
+/// The context of a receiver, which decrypts messages from the sender.
+pub struct ReceiverContext<K: Kdf = HkdfSha256, A: Aead = ChaCha20Poly1305>(Context<K, A>);
+
+impl<K: Kdf, A: Aead> SenderContext<K, A> {
+    fn setup<R: RngCore + CryptoRng>(
+        mode: u8,
+        pk_r: &PublicKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        sk_s: Option<&SecretKey>,
+        rng: &mut R,
+    ) -> Option<(EncappedKey, SenderContext<K, A>)> {
+        SenderContext::setup_with(mode, &SecretKey::new(rng), pk_r, info, psk, psk_id, sk_s)
+    }
+
+    fn setup_with(
+        mode: u8,
+        sk_e: &SecretKey,
+        pk_r: &PublicKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        sk_s: Option<&SecretKey>,
+    ) -> Option<(EncappedKey, SenderContext<K, A>)> {
+        let (shared_secret, enc) = encap_with(sk_e, pk_r, sk_s)?;
+        let context = Context::new(KEM_ID, mode, &shared_secret, info, psk, psk_id)?;
+
+        Some((enc, SenderContext(context)))
+    }
+
+    /// Sets up a context in the base mode to encrypt to `pk_r`, returning
+    /// the encapsulated key to send to the receiver. This returns `None`
+    /// if `pk_r` is of small order.
+    pub fn setup_base<R: RngCore + CryptoRng>(
+        pk_r: &PublicKey,
+        info: &[u8],
+        rng: &mut R,
+    ) -> Option<(EncappedKey, SenderContext<K, A>)> {
+        SenderContext::setup(MODE_BASE, pk_r, info, b"", b"", None, rng)
+    }
+
+    /// Sets up a context in the PSK mode to encrypt to `pk_r` with the
+    /// pre-shared key `psk` identified by `psk_id`. This returns `None`
+    /// if `pk_r` is of small order or `psk` or `psk_id` is empty.
+    pub fn setup_psk<R: RngCore + CryptoRng>(
+        pk_r: &PublicKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        rng: &mut R,
+    ) -> Option<(EncappedKey, SenderContext<K, A>)> {
+        SenderContext::setup(MODE_PSK, pk_r, info, psk, psk_id, None, rng)
+    }
+
+    /// Sets up a context in the auth mode to encrypt to `pk_r`,
+    /// authenticated with the sender's key `sk_s`. This returns `None` if
+    /// `pk_r` is of small order.
+    pub fn setup_auth<R: RngCore + CryptoRng>(
+        pk_r: &PublicKey,
+        info: &[u8],
+        sk_s: &SecretKey,
+        rng: &mut R,
+    ) -> Option<(EncappedKey, SenderContext<K, A>)> {
+        SenderContext::setup(MODE_AUTH, pk_r, info, b"", b"", Some(sk_s), rng)
+    }
+
+    /// Sets up a context in the auth-PSK mode, combining the PSK and auth
+    /// modes.
+    pub fn setup_auth_psk<R: RngCore + CryptoRng>(
+        pk_r: &PublicKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        sk_s: &SecretKey,
+        rng: &mut R,
+    ) -> Option<(EncappedKey, SenderContext<K, A>)> {
+        SenderContext::setup(MODE_AUTH_PSK, pk_r, info, psk, psk_id, Some(sk_s), rng)
+    }
+
+    /// Encrypts `plaintext` with the associated data `aad`. This returns
+    /// `None` if the sequence number is exhausted.
+    pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Option<Vec<u8>> {
+        let nonce = self.0.next_nonce()?;
+
+        Some(A::seal(&self.0.key, &nonce, aad, plaintext))
+    }
+
+    /// Exports a secret of `len` bytes for `exporter_context`. This
+    /// returns `None` if `len` is too large for the KDF.
+    pub fn export(&self, exporter_context: &[u8], len: usize) -> Option<Vec<u8>> {
+        self.0.export(exporter_context, len)
+    }
+}
+
+impl<K: Kdf, A: Aead> ReceiverContext<K, A> {
+    fn setup(
+        mode: u8,
+        enc: &EncappedKey,
+        sk_r: &SecretKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        pk_s: Option<&PublicKey>,
+    ) -> Option<ReceiverContext<K, A>> {
+        let shared_secret = decap_with(enc, sk_r, pk_s)?;
+        let context = Context::new(KEM_ID, mode, &shared_secret, info, psk, psk_id)?;
+
+        Some(ReceiverContext(context))
+    }
+
+    /// Sets up a context in the base mode to decrypt from the sender of
+    /// `enc`. This returns `None` if `enc` is of small order.
+    pub fn setup_base(
+        enc: &EncappedKey,
+        sk_r: &SecretKey,
+        info: &[u8],
+    ) -> Option<ReceiverContext<K, A>> {
+        ReceiverContext::setup(MODE_BASE, enc, sk_r, info, b"", b"", None)
+    }
+
+    /// Sets up a context in the PSK mode to decrypt from the sender of
+    /// `enc`. This returns `None` if `enc` is of small order or `psk` or
+    /// `psk_id` is empty.
+    pub fn setup_psk(
+        enc: &EncappedKey,
+        sk_r: &SecretKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Option<ReceiverContext<K, A>> {
+        ReceiverContext::setup(MODE_PSK, enc, sk_r, info, psk, psk_id, None)
+    }
+
+    /// Sets up a context in the auth mode to decrypt from the sender of
+    /// `enc`, whose public key is `pk_s`. This returns `None` if `enc` or
+    /// `pk_s` is of small order.
+    pub fn setup_auth(
+        enc: &EncappedKey,
+        sk_r: &SecretKey,
+        info: &[u8],
+        pk_s: &PublicKey,
+    ) -> Option<ReceiverContext<K, A>> {
+        ReceiverContext::setup(MODE_AUTH, enc, sk_r, info, b"", b"", Some(pk_s))
+    }
+
+    /// Sets up a context in the auth-PSK mode, combining the PSK and auth
+    /// modes.
+    pub fn setup_auth_psk(
+        enc: &EncappedKey,
+        sk_r: &SecretKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        pk_s: &PublicKey,
+    ) -> Option<ReceiverContext<K, A>> {
+        ReceiverContext::setup(MODE_AUTH_PSK, enc, sk_r, info, psk, psk_id, Some(pk_s))
+    }
+
+    /// Decrypts `ciphertext` with the associated data `aad`. This returns
+    /// `None` if the ciphertext is not authentic or the sequence number is
+    /// exhausted. A failed decryption does not advance the sequence
+    /// number.
+    pub fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>> {
+        let seq = self.0.seq;
+        let nonce = self.0.next_nonce()?;
+        let plaintext = A::open(&self.0.key, &nonce, aad, ciphertext);
+        if plaintext.is_none() {
+            self.0.seq = seq;
+        }
+
+        plaintext
+    }
+
+    /// Exports a secret of `len` bytes for `exporter_context`. This
+    /// returns `None` if `len` is too large for the KDF.
+    pub fn export(&self, exporter_context: &[u8], len: usize) -> Option<Vec<u8>> {
+        self.0.export(exporter_context, len)
+    }
+}
+
+impl<K: Kdf, A: Aead> fmt::Debug for SenderContext<K, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SenderContext")
+            .field("seq", &self.0.seq)
+            .finish()
+    }
+}
+
+impl<K: Kdf, A: Aead> fmt::Debug for ReceiverContext<K, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReceiverContext")
+            .field("seq", &self.0.seq)
+            .finish()
+    }
+}
+
+#[test]
+fn test_kem() {
+    let mut rng = crate::test_rng();
+    let sk_r = SecretKey::new(&mut rng);
+    let sk_s = SecretKey::new(&mut rng);
+    let pk_r = sk_r.public_key();
+    let pk_s = sk_s.public_key();
+
+    let (shared_secret, enc) = encap(&pk_r, &mut rng).unwrap();
+    assert!(bool::from(
+        shared_secret.ct_eq(&decap(&enc, &sk_r).unwrap())
+    ));
+
+    let (shared_secret, enc) = auth_encap(&pk_r, &sk_s, &mut rng).unwrap();
+    assert!(bool::from(
+        shared_secret.ct_eq(&auth_decap(&enc, &sk_r, &pk_s).unwrap())
+    ));
+    assert!(!bool::from(
+        shared_secret.ct_eq(&auth_decap(&enc, &sk_r, &pk_r).unwrap())
+    ));
+
+    // Keys of small order are rejected.
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+    for point in [AffinePoint::identity(), two_torsion].iter() {
+        assert!(encap(&PublicKey(*point), &mut rng).is_none());
+        assert!(decap(&EncappedKey(*point), &sk_r).is_none());
+        assert!(auth_decap(&enc, &sk_r, &PublicKey(*point)).is_none());
+    }
+
+    // The zero secret key is rejected.
+    assert!(bool::from(SecretKey::from_bytes([0; 32]).is_none()));
+    assert!(bool::from(
+        SecretKey::from_bytes(sk_r.into_bytes()).is_some()
+    ));
+}
+
+#[test]
+fn test_modes() {
+    let mut rng = crate::test_rng();
+    let sk_r = SecretKey::new(&mut rng);
+    let sk_s = SecretKey::new(&mut rng);
+    let pk_r = sk_r.public_key();
+    let pk_s = sk_s.public_key();
+    let info = b"test info";
+    let (psk, psk_id) = (b"pre-shared key", b"psk id");
+
+    let contexts: [(SenderContext, ReceiverContext); 4] = [
+        {
+            let (enc, s) = SenderContext::setup_base(&pk_r, info, &mut rng).unwrap();
+            (s, ReceiverContext::setup_base(&enc, &sk_r, info).unwrap())
+        },
+        {
+            let (enc, s) = SenderContext::setup_psk(&pk_r, info, psk, psk_id, &mut rng).unwrap();
+            (
+                s,
+                ReceiverContext::setup_psk(&enc, &sk_r, info, psk, psk_id).unwrap(),
+            )
+        },
+        {
+            let (enc, s) = SenderContext::setup_auth(&pk_r, info, &sk_s, &mut rng).unwrap();
+            (
+                s,
+                ReceiverContext::setup_auth(&enc, &sk_r, info, &pk_s).unwrap(),
+            )
+        },
+        {
+            let (enc, s) =
+                SenderContext::setup_auth_psk(&pk_r, info, psk, psk_id, &sk_s, &mut rng).unwrap();
+            (
+                s,
+                ReceiverContext::setup_auth_psk(&enc, &sk_r, info, psk, psk_id, &pk_s).unwrap(),
+            )
+        },
+    ];
+
+    for (mut sender, mut receiver) in contexts {
+        for i in 0..3u8 {
+            let ciphertext = sender.seal(&[i], b"message").unwrap();
+            assert_eq!(ciphertext.len(), 7 + ChaCha20Poly1305::NT);
+
+            // A failed decryption does not advance the sequence number.
+            assert!(receiver.open(&[i + 1], &ciphertext).is_none());
+            assert_eq!(receiver.open(&[i], &ciphertext).unwrap(), b"message");
+        }
+        assert_eq!(
+            sender.export(b"context", 42).unwrap(),
+            receiver.export(b"context", 42).unwrap()
+        );
+        assert!(sender.export(b"context", 255 * 32 + 1).is_none());
+    }
+
+    // The PSK must match and be provided in the PSK modes.
+    let (enc, mut sender) =
+        SenderContext::<HkdfSha256>::setup_psk(&pk_r, info, psk, psk_id, &mut rng).unwrap();
+    let ciphertext = sender.seal(b"", b"message").unwrap();
+    let mut receiver =
+        ReceiverContext::<HkdfSha256>::setup_psk(&enc, &sk_r, info, b"another key", psk_id)
+            .unwrap();
+    assert!(receiver.open(b"", &ciphertext).is_none());
+    assert!(SenderContext::<HkdfSha256>::setup_psk(&pk_r, info, b"", psk_id, &mut rng).is_none());
+    assert!(ReceiverContext::<HkdfSha256>::setup_psk(&enc, &sk_r, info, psk, b"").is_none());
+
+    // The sender's key must match in the auth modes.
+    let (enc, mut sender) =
+        SenderContext::<HkdfSha256>::setup_auth(&pk_r, info, &sk_s, &mut rng).unwrap();
+    let ciphertext = sender.seal(b"", b"message").unwrap();
+    let mut receiver = ReceiverContext::<HkdfSha256>::setup_auth(&enc, &sk_r, info, &pk_r).unwrap();
+    assert!(receiver.open(b"", &ciphertext).is_none());
+}
+
+#[test]
+fn test_hkdf_sha512() {
+    let mut rng = crate::test_rng();
+    let sk_r = SecretKey::new(&mut rng);
+
+    let (enc, mut sender) =
+        SenderContext::<HkdfSha512>::setup_base(&sk_r.public_key(), b"info", &mut rng).unwrap();
+    let mut receiver = ReceiverContext::<HkdfSha512>::setup_base(&enc, &sk_r, b"info").unwrap();
+    let ciphertext = sender.seal(b"aad", b"message").unwrap();
+    assert_eq!(receiver.open(b"aad", &ciphertext).unwrap(), b"message");
+    assert_eq!(
+        sender.export(b"", 64).unwrap(),
+        receiver.export(b"", 64).unwrap()
+    );
+}
+
+#[test]
+fn test_vectors() {
+    let sk_r = SecretKey::derive(&[0x11; 32]);
+    let sk_e = SecretKey::derive(&[0x22; 32]);
+    let sk_s = SecretKey::derive(&[0x33; 32]);
+    let pk_r = sk_r.public_key();
+    let pk_s = sk_s.public_key();
+    let info = b"doppio hpke test";
+    let plaintext = b"Beauty is truth, truth beauty";
+
+    assert_eq!(
+        sk_r.into_bytes(),
+        [
+            0x6c, 0xae, 0x05, 0x6b, 0x01, 0xa1, 0x46, 0x8c, 0x23, 0xe8, 0x0c, 0xfa, 0xd4, 0x51,
+            0x25, 0x39, 0xe4, 0x27, 0xde, 0x91, 0x22, 0x44, 0xb1, 0x6f, 0xff, 0xbb, 0x8d, 0x0a,
+            0xe5, 0xc0, 0x64, 0x03
+        ]
+    );
+    assert_eq!(
+        pk_r.into_bytes(),
+        [
+            0x17, 0x68, 0xfe, 0xcd, 0x0f, 0x08, 0xeb, 0x8a, 0x80, 0xe8, 0xe8, 0xfd, 0x6e, 0x65,
+            0x5a, 0x95, 0xfd, 0xc0, 0xdd, 0xc3, 0x7c, 0xe4, 0x7b, 0xb1, 0x27, 0xdb, 0xb7, 0x44,
+            0xad, 0x3c, 0xaa, 0x8c
+        ]
+    );
+    assert_eq!(
+        pk_s.into_bytes(),
+        [
+            0x7f, 0x32, 0x2e, 0xa0, 0x2e, 0x7a, 0xd1, 0xe5, 0x7b, 0x42, 0xf5, 0x8b, 0x72, 0x0f,
+            0xc1, 0x4d, 0x6e, 0x3c, 0x4f, 0xbf, 0xc8, 0x9c, 0x2b, 0x86, 0x27, 0x4e, 0xd1, 0xeb,
+            0x7f, 0x76, 0x7e, 0x06
+        ]
+    );
+
+    let (shared_secret, enc) = encap_with(&sk_e, &pk_r, None).unwrap();
+    assert_eq!(
+        enc.into_bytes(),
+        [
+            0xec, 0x58, 0x61, 0x56, 0x67, 0xda, 0x59, 0x48, 0x60, 0x6f, 0x2e, 0x7a, 0xd9, 0xa4,
+            0xf0, 0x8f, 0xe7, 0xf4, 0xa6, 0x1e, 0xd8, 0xeb, 0xc7, 0xf2, 0xc6, 0x37, 0x22, 0xb4,
+            0x5c, 0x1c, 0x87, 0x0b
+        ]
+    );
+    assert_eq!(
+        shared_secret.as_bytes(),
+        &[
+            0x2e, 0xbb, 0x14, 0xc0, 0xcd, 0x93, 0x08, 0x36, 0x09, 0x9b, 0x05, 0xf6, 0xb4, 0xd4,
+            0x80, 0xeb, 0xe5, 0x50, 0x90, 0xfa, 0x26, 0xe2, 0xf4, 0x98, 0xa5, 0xd7, 0x5e, 0xf2,
+            0x15, 0x28, 0x7f, 0xf0
+        ]
+    );
+    let (shared_secret, _) = encap_with(&sk_e, &pk_r, Some(&sk_s)).unwrap();
+    assert_eq!(
+        shared_secret.as_bytes(),
+        &[
+            0x3d, 0x99, 0x7d, 0x3b, 0x9d, 0xfc, 0x82, 0x59, 0x7e, 0x7e, 0x90, 0x75, 0x64, 0x0c,
+            0xd7, 0x04, 0x50, 0xad, 0x21, 0xa0, 0xf2, 0xf5, 0xd7, 0x83, 0xeb, 0x4e, 0xbc, 0xa0,
+            0xfc, 0xba, 0x46, 0x47
+        ]
+    );
+
+    let (psk, psk_id) = (b"pre-shared key", b"psk id");
+    let vectors: [(u8, [u8; 45], [u8; 32]); 4] = [
+        (
+            0,
+            [
+                0xc2, 0x99, 0x46, 0xf7, 0x29, 0xee, 0x14, 0xd1, 0x5b, 0x2a, 0x00, 0x40, 0x6d, 0xb4,
+                0x01, 0x88, 0xc3, 0xec, 0x77, 0x87, 0x63, 0x6e, 0x34, 0x90, 0x38, 0x89, 0xa4, 0x05,
+                0x96, 0x38, 0xed, 0x85, 0x3a, 0xc5, 0x6e, 0x41, 0x88, 0xf6, 0xa0, 0xb6, 0x7b, 0x45,
+                0x6a, 0xb3, 0x19,
+            ],
+            [
+                0x6e, 0xda, 0x56, 0x71, 0xdb, 0xc1, 0x5a, 0x0b, 0x63, 0x43, 0x83, 0x8a, 0xae, 0x76,
+                0x6f, 0xe1, 0xa9, 0xf3, 0xfb, 0x81, 0x70, 0x62, 0x20, 0x62, 0xd0, 0xe3, 0x84, 0x48,
+                0x93, 0x01, 0x0e, 0x7a,
+            ],
+        ),
+        (
+            1,
+            [
+                0x2c, 0x01, 0xe2, 0xd2, 0x01, 0x61, 0x6e, 0x8d, 0xd4, 0x57, 0x4b, 0x89, 0xf4, 0xe8,
+                0x75, 0x90, 0x7b, 0x2c, 0x64, 0x96, 0x09, 0x94, 0x04, 0x69, 0xe0, 0xae, 0xab, 0xb1,
+                0xfd, 0x48, 0x0e, 0xc4, 0x55, 0x6b, 0xde, 0x2d, 0x09, 0x4b, 0x53, 0x81, 0xa6, 0xc6,
+                0x57, 0x8e, 0x0c,
+            ],
+            [
+                0x55, 0x59, 0x5c, 0x6b, 0xec, 0xe4, 0x3d, 0x2d, 0x51, 0x4c, 0xc7, 0x7d, 0xf4, 0x9d,
+                0x20, 0x77, 0xb0, 0xbb, 0x4e, 0x1f, 0x22, 0x60, 0xcf, 0xdc, 0x67, 0x3a, 0xb8, 0xe7,
+                0xb3, 0xb9, 0x3f, 0xed,
+            ],
+        ),
+        (
+            2,
+            [
+                0x52, 0x9e, 0x98, 0x44, 0xf6, 0xfb, 0x42, 0x05, 0x86, 0xe5, 0xaa, 0x48, 0xec, 0x74,
+                0xed, 0x97, 0xe0, 0x31, 0x9e, 0x98, 0x6e, 0xc7, 0xfa, 0x68, 0x41, 0x8e, 0xb4, 0xfb,
+                0x27, 0x3a, 0x3e, 0x63, 0xd5, 0x96, 0x06, 0xbe, 0x5b, 0xce, 0x42, 0xda, 0x3b, 0x40,
+                0xb2, 0x3e, 0x79,
+            ],
+            [
+                0x28, 0xe8, 0x78, 0xf4, 0xcd, 0x2f, 0x4e, 0x42, 0x24, 0x27, 0x33, 0xac, 0x47, 0xda,
+                0x66, 0x31, 0x3b, 0xef, 0x24, 0xac, 0xac, 0x23, 0xef, 0xf4, 0x39, 0x89, 0x18, 0xb5,
+                0x26, 0x09, 0x31, 0x16,
+            ],
+        ),
+        (
+            3,
+            [
+                0xbf, 0x7d, 0xa1, 0x11, 0x95, 0xe5, 0x6d, 0x2f, 0x67, 0xf6, 0x8b, 0xfb, 0x78, 0x46,
+                0x95, 0x68, 0x06, 0x4f, 0x92, 0x4f, 0xb8, 0xb1, 0x67, 0x76, 0xf0, 0xbc, 0xc7, 0xa4,
+                0x08, 0xcf, 0x80, 0x8a, 0x22, 0x46, 0x31, 0x8f, 0xfc, 0x3b, 0x65, 0x9b, 0x98, 0x92,
+                0x06, 0x49, 0x38,
+            ],
+            [
+                0x1f, 0x8e, 0x7a, 0xb2, 0x9f, 0xfe, 0x7c, 0xec, 0x1f, 0x77, 0xf7, 0x74, 0x70, 0xc2,
+                0xb1, 0x2e, 0x57, 0xab, 0xdb, 0xff, 0x50, 0x6b, 0x13, 0xbf, 0x65, 0x93, 0xb1, 0xe4,
+                0x67, 0x2c, 0x78, 0xba,
+            ],
+        ),
+    ];
+    for (mode, ciphertext, exported) in vectors.iter() {
+        let has_psk = *mode == MODE_PSK || *mode == MODE_AUTH_PSK;
+        let (psk, psk_id): (&[u8], &[u8]) = if has_psk { (psk, psk_id) } else { (b"", b"") };
+        let auth = *mode == MODE_AUTH || *mode == MODE_AUTH_PSK;
+
+        let (enc, mut sender) = SenderContext::<HkdfSha256, ChaCha20Poly1305>::setup_with(
+            *mode,
+            &sk_e,
+            &pk_r,
+            info,
+            psk,
+            psk_id,
+            if auth { Some(&sk_s) } else { None },
+        )
+        .unwrap();
+        assert_eq!(
+            &sender.seal(b"Count-0", plaintext).unwrap()[..],
+            &ciphertext[..]
+        );
+        assert_eq!(&sender.export(b"context", 32).unwrap()[..], &exported[..]);
+
+        let mut receiver = ReceiverContext::<HkdfSha256, ChaCha20Poly1305>::setup(
+            *mode,
+            &enc,
+            &sk_r,
+            info,
+            psk,
+            psk_id,
+            if auth { Some(&pk_s) } else { None },
+        )
+        .unwrap();
+        assert_eq!(
+            receiver.open(b"Count-0", ciphertext).unwrap(),
+            &plaintext[..]
+        );
+    }
+}
+
+#[test]
+fn test_rfc9180_vector() {
+    // RFC 9180, Appendix A.2.1: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256,
+    // ChaCha20Poly1305 in the base mode. The X25519 output is fed to the
+    // key schedule that the Doppio KEM uses.
+    let sk_e = [
+        0xf4, 0xec, 0x9b, 0x33, 0xb7, 0x92, 0xc3, 0x72, 0xc1, 0xd2, 0xc2, 0x06, 0x35, 0x07, 0xb6,
+        0x84, 0xef, 0x92, 0x5b, 0x8c, 0x75, 0xa4, 0x2d, 0xbc, 0xbf, 0x57, 0xd6, 0x3c, 0xcd, 0x38,
+        0x16, 0x00,
+    ];
+    let pk_e = [
+        0x1a, 0xfa, 0x08, 0xd3, 0xde, 0xc0, 0x47, 0xa6, 0x43, 0x88, 0x51, 0x63, 0xf1, 0x18, 0x04,
+        0x76, 0xfa, 0x7d, 0xdb, 0x54, 0xc6, 0xa8, 0x02, 0x9e, 0xa3, 0x3f, 0x95, 0x79, 0x6b, 0xf2,
+        0xac, 0x4a,
+    ];
+    let pk_r = [
+        0x43, 0x10, 0xee, 0x97, 0xd8, 0x8c, 0xc1, 0xf0, 0x88, 0xa5, 0x57, 0x6c, 0x77, 0xab, 0x0c,
+        0xf5, 0xc3, 0xac, 0x79, 0x7f, 0x3d, 0x95, 0x13, 0x9c, 0x6c, 0x84, 0xb5, 0x42, 0x9c, 0x59,
+        0x66, 0x2a,
+    ];
+    let info = b"Ode on a Grecian Urn";
+    let plaintext = b"Beauty is truth, truth beauty";
+
+    let dh = x25519_dalek::x25519(sk_e, pk_r);
+    let mut kem_context = pk_e.to_vec();
+    kem_context.extend_from_slice(&pk_r);
+    let shared_secret = extract_and_expand(0x0020, &dh, &kem_context);
+    let mut sender = SenderContext::<HkdfSha256, ChaCha20Poly1305>(
+        Context::new(0x0020, MODE_BASE, &shared_secret, info, b"", b"").unwrap(),
+    );
+
+    let ciphertexts: [[u8; 45]; 2] = [
+        [
+            0x1c, 0x52, 0x50, 0xd8, 0x03, 0x4e, 0xc2, 0xb7, 0x84, 0xba, 0x2c, 0xfd, 0x69, 0xdb,
+            0xdb, 0x8a, 0xf4, 0x06, 0xcf, 0xe3, 0xff, 0x93, 0x8e, 0x13, 0x1f, 0x0d, 0xef, 0x8c,
+            0x8b, 0x60, 0xb4, 0xdb, 0x21, 0x99, 0x3c, 0x62, 0xce, 0x81, 0x88, 0x3d, 0x2d, 0xd1,
+            0xb5, 0x1a, 0x28,
+        ],
+        [
+            0x6b, 0x53, 0xc0, 0x51, 0xe4, 0x19, 0x9c, 0x51, 0x8d, 0xe7, 0x95, 0x94, 0xe1, 0xc4,
+            0xab, 0x18, 0xb9, 0x6f, 0x08, 0x15, 0x49, 0xd4, 0x5c, 0xe0, 0x15, 0xbe, 0x00, 0x20,
+            0x90, 0xbb, 0x11, 0x9e, 0x85, 0x28, 0x53, 0x37, 0xcc, 0x95, 0xba, 0x5f, 0x59, 0x99,
+            0x2d, 0xc9, 0x8c,
+        ],
+    ];
+    for (i, ciphertext) in ciphertexts.iter().enumerate() {
+        let aad = format!("Count-{}", i);
+        assert_eq!(
+            sender.seal(aad.as_bytes(), plaintext).unwrap(),
+            &ciphertext[..]
+        );
+    }
+    assert_eq!(
+        sender.export(b"", 32).unwrap(),
+        [
+            0x4b, 0xbd, 0x62, 0x43, 0xb8, 0xbb, 0x54, 0xce, 0xc3, 0x11, 0xfa, 0xc9, 0xdf, 0x81,
+            0x84, 0x1b, 0x6f, 0xd6, 0x1f, 0x56, 0x53, 0x8a, 0x77, 0x5e, 0x7c, 0x80, 0xa9, 0xf4,
+            0x01, 0x60, 0x60, 0x6e
+        ]
+    );
+    assert_eq!(
+        sender.export(b"TestContext", 32).unwrap(),
+        [
+            0x5a, 0xcb, 0x09, 0x21, 0x11, 0x39, 0xc4, 0x3b, 0x30, 0x90, 0x48, 0x9a, 0x9d, 0xa4,
+            0x33, 0xe8, 0xa3, 0x0e, 0xe7, 0x18, 0x8b, 0xa8, 0xb0, 0xa9, 0xa1, 0xcc, 0xf0, 0xc2,
+            0x29, 0x28, 0x3e, 0x53
+        ]
+    );
+}
@@ -0,0 +1,692 @@
+//! This module implements a transparent polynomial commitment scheme over
+//! Doppio based on the inner product argument, in the style of Halo.
+//!
+//! A polynomial `a(X) = a_0 + a_1 X + ... + a_{d-1} X^{d-1}` is committed
+//! as the Pedersen vector commitment `C = <a, G> + r * H` with a random
+//! blinding factor `r`, where `d` is a power of two and the generators
+//! `G_i`, `H` and `U` are derived with `hash_to_curve`, so no setup is
+//! needed. An [`OpeningProof`] shows that `a(x) = v` for a point `x` and a
+//! value `v`, and consists of `2 * log2(d) + 1` points and two scalars.
+//!
+//! To open `C`, the prover commits to a random polynomial `s` with
+//! `s(x) = 0` as `S`, receives a challenge `xi`, and runs the inner product
+//! argument for `a + xi * s` against `b = (1, x, ..., x^{d-1})`, blinding
+//! every round. Since `b` is public, the verifier folds it in `O(log d)`
+//! operations, and the only linear-time step is computing the final
+//! generator `<s(u), G>` from the round challenges `u_j`. That step is
+//! deferred into a [`Guard`], so many openings can be checked together
+//! with one multiscalar multiplication.
+//!
+//! Several polynomials can be opened at the same point with a single
+//! proof for a random linear combination of them. Challenges are derived
+//! with a [`Transcript`], and verification is multiplied by the cofactor.
+
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::bulletproofs::inner_product;
+use crate::hash::hash_to_curve_parts;
+use crate::helpers::{read_point, read_scalar, zeroize_all};
+use crate::sharing::powers;
+use crate::{
+    batch_normalize, multiscalar_mul, multiscalar_mul_vartime, AffinePoint, ExtendedPoint, Fr,
+    Transcript,
+};
+
+/// The domain separation tag used to derive the generators.
+const GENERATORS_DST: &[u8] = b"DOPPIO_XMD:SHA-512_ELL2_RO_IPA";
+
+/// The public parameters for polynomials with up to `d` coefficients.
+#[derive(Clone, Debug)]
+pub struct Params {
+    g: Vec<ExtendedPoint>,
+    h: ExtendedPoint,
+    u: ExtendedPoint,
+}
+
+/// A proof that a committed polynomial evaluates to a value at a point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpeningProof {
+    s: AffinePoint,
+    l_vec: Vec<AffinePoint>,
+    r_vec: Vec<AffinePoint>,
+    c: Fr,
+    f: Fr,
+}
+
+/// The result of the succinct part of verifying an opening, whose final
+/// check requires a multiscalar multiplication of size `d`.
+#[derive(Clone, Debug)]
+pub struct Guard {
+    challenges: Vec<Fr>,
+    c: Fr,
+    scalars: Vec<Fr>,
+    points: Vec<ExtendedPoint>,
+}
+
+/// Evaluates the polynomial with the given coefficients at `x`.
+pub fn evaluate(coefficients: &[Fr], x: &Fr) -> Fr {
+    coefficients
+        .iter()
+        .rev()
+        .fold(Fr::zero(), |acc, a| acc * x + a)
+}
+
+/// Computes the coefficients `s_i` of the folded generator, which is the
+/// product of `u_j` or `u_j^-1` according to whether bit `j` of `i`,
+/// counting from the most significant, is set.
+fn fold_scalars(challenges: &[Fr], n: usize) -> Vec<Fr> {
+    let lg_n = challenges.len();
+    let u_inv: Vec<Fr> = challenges
+        .iter()
+        .map(|u| u.invert().unwrap_or(Fr::zero()))
+        .collect();
+
+    let mut s = Vec::with_capacity(n);
+    s.push(u_inv.iter().fold(Fr::one(), |acc, u| acc * u));
+    for i in 1..n {
+        let lg_i = (usize::BITS - 1 - i.leading_zeros()) as usize;
+        let k = 1 << lg_i;
+        let u = challenges[(lg_n - 1) - lg_i];
+        s.push(s[i - k] * u.square());
+    }
+
+    s
+}
+
+/// Absorbs the statement of a single opening.
+fn absorb_statement(
+    transcript: &mut Transcript,
+    d: usize,
+    commitment: &AffinePoint,
+    x: &Fr,
+    v: &Fr,
+) {
+    transcript.append_message(b"dom-sep", b"ipa-pcs v1");
+    transcript.append_u64(b"d", d as u64);
+    transcript.append_point(b"C", commitment);
+    transcript.append_scalar(b"x", x);
+    transcript.append_scalar(b"v", v);
+}
+
+/// Absorbs the statement of a batch opening and returns the challenge
+/// `gamma` with which the polynomials are combined.
+fn absorb_batch(transcript: &mut Transcript, commitments: &[AffinePoint], values: &[Fr]) -> Fr {
+    transcript.append_message(b"dom-sep", b"ipa-pcs batch v1");
+    transcript.append_u64(b"k", commitments.len() as u64);
+    for (c, v) in commitments.iter().zip(values.iter()) {
+        transcript.append_point(b"C", c);
+        transcript.append_scalar(b"v", v);
+    }
+
+    transcript.challenge_scalar(b"gamma")
+}
+
+impl Params {
+    /// Derives the parameters for polynomials with up to `d` coefficients.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `d` is not a power of two.
+    pub fn new(d: usize) -> Params {
+        assert!(d.is_power_of_two());
+
+        let generator = |label: &[u8], i: usize| {
+            hash_to_curve_parts(&[label, &(i as u64).to_le_bytes()], GENERATORS_DST)
+        };
+
+        Params {
+            g: (0..d).map(|i| generator(b"G", i)).collect(),
+            h: generator(b"H", 0),
+            u: generator(b"U", 0),
+        }
+    }
+
+    /// Returns the maximum number of coefficients of a committed
+    /// polynomial.
+    pub fn d(&self) -> usize {
+        self.g.len()
+    }
+
+    /// Commits to the polynomial with the given coefficients, starting
+    /// with the constant term, using `blinding` as the blinding factor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `d` coefficients.
+    pub fn commit(&self, coefficients: &[Fr], blinding: &Fr) -> ExtendedPoint {
+        assert!(coefficients.len() <= self.d());
+
+        multiscalar_mul(coefficients, &self.g[..coefficients.len()])
+            + multiscalar_mul(&[*blinding], &[self.h])
+    }
+
+    /// Proves that the polynomial with the given coefficients and
+    /// blinding factor evaluates at `x` to the value in the statement,
+    /// which has already been absorbed.
+    fn open_inner<R: RngCore + CryptoRng>(
+        &self,
+        transcript: &mut Transcript,
+        coefficients: &[Fr],
+        blinding: &Fr,
+        x: &Fr,
+        rng: &mut R,
+    ) -> OpeningProof {
+        let d = self.d();
+
+        // s is a random polynomial with s(x) = 0, which masks a.
+        let mut s: Vec<Fr> = (0..d).map(|_| Fr::random(&mut *rng)).collect();
+        let s_x = evaluate(&s, x);
+        s[0] -= s_x;
+        let mut r_s = Fr::random(&mut *rng);
+        let s_point = AffinePoint::from(self.commit(&s, &r_s));
+        transcript.append_point(b"S", &s_point);
+        let xi = transcript.challenge_scalar(b"xi");
+        let z = transcript.challenge_scalar(b"z");
+        let u_prime = self.u * z;
+
+        let mut a: Vec<Fr> = s
+            .iter()
+            .enumerate()
+            .map(|(i, s)| coefficients.get(i).copied().unwrap_or(Fr::zero()) + xi * s)
+            .collect();
+        let mut f = blinding + xi * r_s;
+        let mut b = powers(x, d);
+        let mut g = self.g.clone();
+        zeroize_all(&mut s);
+        r_s.zeroize();
+
+        let lg_d = d.trailing_zeros() as usize;
+        let mut l_vec = Vec::with_capacity(lg_d);
+        let mut r_vec = Vec::with_capacity(lg_d);
+        let mut n = d;
+        while n > 1 {
+            n /= 2;
+            let (a_lo, a_hi) = a.split_at(n);
+            let (b_lo, b_hi) = b.split_at(n);
+            let (g_lo, g_hi) = g.split_at(n);
+
+            let mut l_blind = Fr::random(&mut *rng);
+            let mut r_blind = Fr::random(&mut *rng);
+            let mut lr = [
+                multiscalar_mul(
+                    &[a_lo, &[inner_product(a_lo, b_hi), l_blind]].concat(),
+                    &[g_hi, &[u_prime, self.h]].concat(),
+                ),
+                multiscalar_mul(
+                    &[a_hi, &[inner_product(a_hi, b_lo), r_blind]].concat(),
+                    &[g_lo, &[u_prime, self.h]].concat(),
+                ),
+            ];
+            let mut lr = batch_normalize(&mut lr);
+            let (l, r) = (lr.next().unwrap(), lr.next().unwrap());
+            transcript.append_point(b"L", &l);
+            transcript.append_point(b"R", &r);
+            l_vec.push(l);
+            r_vec.push(r);
+
+            // The challenge is nonzero except with negligible probability.
+            let u = transcript.challenge_scalar(b"u");
+            let u_inv = u.invert().unwrap();
+
+            f += u.square() * l_blind + u_inv.square() * r_blind;
+            l_blind.zeroize();
+            r_blind.zeroize();
+
+            let a_next = (0..n).map(|i| u * a_lo[i] + u_inv * a_hi[i]).collect();
+            let b_next = (0..n).map(|i| u_inv * b_lo[i] + u * b_hi[i]).collect();
+            let g_next = (0..n)
+                .map(|i| multiscalar_mul_vartime(&[u_inv, u], &[g_lo[i], g_hi[i]]))
+                .collect();
+            zeroize_all(&mut a);
+            a = a_next;
+            b = b_next;
+            g = g_next;
+        }
+
+        let proof = OpeningProof {
+            s: s_point,
+            l_vec,
+            r_vec,
+            c: a[0],
+            f,
+        };
+        zeroize_all(&mut a);
+        f.zeroize();
+
+        proof
+    }
+
+    /// Proves that the polynomial with the given coefficients, committed
+    /// with `blinding`, evaluates to `v` at `x`, returning `v` and the
+    /// proof. The commitment must have been computed with
+    /// [`Params::commit`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `d` coefficients.
+    pub fn open<R: RngCore + CryptoRng>(
+        &self,
+        transcript: &mut Transcript,
+        coefficients: &[Fr],
+        blinding: &Fr,
+        x: &Fr,
+        rng: &mut R,
+    ) -> (Fr, OpeningProof) {
+        assert!(coefficients.len() <= self.d());
+
+        let commitment = AffinePoint::from(self.commit(coefficients, blinding));
+        let v = evaluate(coefficients, x);
+        absorb_statement(transcript, self.d(), &commitment, x, &v);
+
+        (
+            v,
+            self.open_inner(transcript, coefficients, blinding, x, rng),
+        )
+    }
+
+    /// Proves that each of `polynomials`, given as its coefficients and
+    /// blinding factor, evaluates to the corresponding returned value at
+    /// `x`, with a single proof.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any polynomial has more than `d` coefficients.
+    pub fn open_batch<R: RngCore + CryptoRng>(
+        &self,
+        transcript: &mut Transcript,
+        polynomials: &[(&[Fr], Fr)],
+        x: &Fr,
+        rng: &mut R,
+    ) -> (Vec<Fr>, OpeningProof) {
+        let mut commitments: Vec<ExtendedPoint> =
+            polynomials.iter().map(|(a, r)| self.commit(a, r)).collect();
+        let commitments: Vec<AffinePoint> = batch_normalize(&mut commitments).collect();
+        let values: Vec<Fr> = polynomials.iter().map(|(a, _)| evaluate(a, x)).collect();
+        let gamma = absorb_batch(transcript, &commitments, &values);
+
+        let weights = powers(&gamma, polynomials.len());
+        let mut coefficients = vec![Fr::zero(); self.d()];
+        let mut blinding = Fr::zero();
+        for ((a, r), w) in polynomials.iter().zip(weights.iter()) {
+            for (c, a) in coefficients.iter_mut().zip(a.iter()) {
+                *c += w * a;
+            }
+            blinding += w * r;
+        }
+
+        let commitment = multiscalar_mul_vartime(
+            &weights,
+            &commitments
+                .iter()
+                .map(|c| ExtendedPoint::from(*c))
+                .collect::<Vec<_>>(),
+        );
+        let v = evaluate(&coefficients, x);
+        absorb_statement(transcript, self.d(), &AffinePoint::from(commitment), x, &v);
+        let proof = self.open_inner(transcript, &coefficients, &blinding, x, rng);
+        zeroize_all(&mut coefficients);
+        blinding.zeroize();
+
+        (values, proof)
+    }
+
+    /// Performs the succinct part of checking that `proof` shows that
+    /// the polynomial committed as `commitment` evaluates to `v` at `x`,
+    /// where the statement has already been absorbed.
+    fn verify_inner(
+        &self,
+        transcript: &mut Transcript,
+        commitment: &ExtendedPoint,
+        x: &Fr,
+        v: &Fr,
+        proof: &OpeningProof,
+    ) -> Option<Guard> {
+        let lg_d = self.d().trailing_zeros() as usize;
+        if proof.l_vec.len() != lg_d || proof.r_vec.len() != lg_d {
+            return None;
+        }
+
+        if !transcript.validate_and_append_point(b"S", &proof.s) {
+            return None;
+        }
+        let xi = transcript.challenge_scalar(b"xi");
+        let z = transcript.challenge_scalar(b"z");
+
+        let mut challenges = Vec::with_capacity(lg_d);
+        for (l, r) in proof.l_vec.iter().zip(proof.r_vec.iter()) {
+            if !transcript.validate_and_append_point(b"L", l)
+                || !transcript.validate_and_append_point(b"R", r)
+            {
+                return None;
+            }
+            challenges.push(transcript.challenge_scalar(b"u"));
+        }
+
+        // b folds to prod_j (u_j^-1 + u_j * x^(d / 2^(j + 1))).
+        let mut b = Fr::one();
+        let mut x_power = *x;
+        let mut scalars = Vec::with_capacity(2 * lg_d + 4);
+        let mut points = Vec::with_capacity(2 * lg_d + 4);
+        for (j, u) in challenges.iter().enumerate().rev() {
+            let u_inv = u.invert();
+            if !bool::from(u_inv.is_some()) {
+                return None;
+            }
+            let u_inv = u_inv.unwrap();
+            b *= u_inv + u * x_power;
+            x_power = x_power.square();
+
+            scalars.push(u.square());
+            points.push(ExtendedPoint::from(proof.l_vec[j]));
+            scalars.push(u_inv.square());
+            points.push(ExtendedPoint::from(proof.r_vec[j]));
+        }
+
+        // C + xi * S + v * U' + sum_j (u_j^2 * L_j + u_j^-2 * R_j)
+        //   = c * (<s, G> + b * U') + f * H
+        scalars.push(Fr::one());
+        points.push(*commitment);
+        scalars.push(xi);
+        points.push(ExtendedPoint::from(proof.s));
+        scalars.push(z * (v - proof.c * b));
+        points.push(self.u);
+        scalars.push(-proof.f);
+        points.push(self.h);
+
+        Some(Guard {
+            challenges,
+            c: proof.c,
+            scalars,
+            points,
+        })
+    }
+
+    /// Performs the succinct part of checking that `proof` shows that the
+    /// polynomial committed as `commitment` evaluates to `v` at `x`. This
+    /// returns `None` if the proof is malformed, and otherwise a
+    /// [`Guard`] whose check completes the verification.
+    pub fn verify_succinct(
+        &self,
+        transcript: &mut Transcript,
+        commitment: &AffinePoint,
+        x: &Fr,
+        v: &Fr,
+        proof: &OpeningProof,
+    ) -> Option<Guard> {
+        absorb_statement(transcript, self.d(), commitment, x, v);
+
+        self.verify_inner(transcript, &ExtendedPoint::from(*commitment), x, v, proof)
+    }
+
+    /// Checks that `proof` shows that the polynomial committed as
+    /// `commitment` evaluates to `v` at `x`.
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        commitment: &AffinePoint,
+        x: &Fr,
+        v: &Fr,
+        proof: &OpeningProof,
+    ) -> bool {
+        self.verify_succinct(transcript, commitment, x, v, proof)
+            .is_some_and(|guard| guard.check(self))
+    }
+
+    /// Performs the succinct part of checking that `proof` shows that each
+    /// polynomial committed in `commitments` evaluates to the
+    /// corresponding element of `values` at `x`.
+    pub fn verify_batch_succinct(
+        &self,
+        transcript: &mut Transcript,
+        commitments: &[AffinePoint],
+        x: &Fr,
+        values: &[Fr],
+        proof: &OpeningProof,
+    ) -> Option<Guard> {
+        if commitments.is_empty() || commitments.len() != values.len() {
+            return None;
+        }
+
+        let gamma = absorb_batch(transcript, commitments, values);
+        let weights = powers(&gamma, commitments.len());
+        let points: Vec<ExtendedPoint> = commitments
+            .iter()
+            .map(|c| ExtendedPoint::from(*c))
+            .collect();
+        let commitment = multiscalar_mul_vartime(&weights, &points);
+        let v = inner_product(&weights, values);
+        absorb_statement(transcript, self.d(), &AffinePoint::from(commitment), x, &v);
+
+        self.verify_inner(transcript, &commitment, x, &v, proof)
+    }
+
+    /// Checks that `proof` shows that each polynomial committed in
+    /// `commitments` evaluates to the corresponding element of `values`
+    /// at `x`.
+    pub fn verify_batch(
+        &self,
+        transcript: &mut Transcript,
+        commitments: &[AffinePoint],
+        x: &Fr,
+        values: &[Fr],
+        proof: &OpeningProof,
+    ) -> bool {
+        self.verify_batch_succinct(transcript, commitments, x, values, proof)
+            .is_some_and(|guard| guard.check(self))
+    }
+
+    /// Completes the verification of every guard in `guards` with a single
+    /// multiscalar multiplication, combining them with random weights
+    /// from `rng`.
+    pub fn check_guards<R: RngCore>(&self, guards: &[Guard], rng: &mut R) -> bool {
+        let mut g_scalars = vec![Fr::zero(); self.d()];
+        let mut scalars = Vec::new();
+        let mut points = Vec::new();
+
+        for guard in guards {
+            if guard.challenges.len() != self.d().trailing_zeros() as usize {
+                return false;
+            }
+
+            let rho = Fr::random(&mut *rng);
+            let s = fold_scalars(&guard.challenges, self.d());
+            for (g, s) in g_scalars.iter_mut().zip(s.iter()) {
+                *g -= rho * guard.c * s;
+            }
+            scalars.extend(guard.scalars.iter().map(|x| rho * x));
+            points.extend_from_slice(&guard.points);
+        }
+        scalars.extend_from_slice(&g_scalars);
+        points.extend_from_slice(&self.g);
+
+        bool::from(
+            multiscalar_mul_vartime(&scalars, &points)
+                .mul_by_cofactor()
+                .is_identity(),
+        )
+    }
+}
+
+impl Guard {
+    /// Returns the challenges `u_j` of the inner product argument, which
+    /// determine the final generator `<s(u), G>`.
+    pub fn challenges(&self) -> &[Fr] {
+        &self.challenges
+    }
+
+    /// Completes the verification by computing the final generator.
+    pub fn check(&self, params: &Params) -> bool {
+        if self.challenges.len() != params.d().trailing_zeros() as usize {
+            return false;
+        }
+
+        let s = fold_scalars(&self.challenges, params.d());
+        let mut scalars: Vec<Fr> = s.iter().map(|s| -(self.c * s)).collect();
+        let mut points = params.g.clone();
+        scalars.extend_from_slice(&self.scalars);
+        points.extend_from_slice(&self.points);
+
+        bool::from(
+            multiscalar_mul_vartime(&scalars, &points)
+                .mul_by_cofactor()
+                .is_identity(),
+        )
+    }
+}
+
+impl OpeningProof {
+    /// Attempts to interpret the byte representation of an opening proof,
+    /// which is `S` followed by the pairs `L_j || R_j` and the scalars
+    /// `c || f`.
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<OpeningProof> {
+        if bytes.len() < 3 * 32 || bytes.len() % 64 != 32 {
+            return None;
+        }
+        let lg_d = (bytes.len() / 32 - 3) / 2;
+
+        let s = read_point(&mut bytes)?;
+        let mut l_vec = Vec::with_capacity(lg_d);
+        let mut r_vec = Vec::with_capacity(lg_d);
+        for _ in 0..lg_d {
+            l_vec.push(read_point(&mut bytes)?);
+            r_vec.push(read_point(&mut bytes)?);
+        }
+        let c = read_scalar(&mut bytes)?;
+        let f = read_scalar(&mut bytes)?;
+
+        Some(OpeningProof {
+            s,
+            l_vec,
+            r_vec,
+            c,
+            f,
+        })
+    }
+
+    /// Converts this proof into its byte representation.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity((2 * self.l_vec.len() + 3) * 32);
+        bytes.extend_from_slice(&self.s.into_bytes());
+        for (l, r) in self.l_vec.iter().zip(self.r_vec.iter()) {
+            bytes.extend_from_slice(&l.into_bytes());
+            bytes.extend_from_slice(&r.into_bytes());
+        }
+        bytes.extend_from_slice(&self.c.into_bytes());
+        bytes.extend_from_slice(&self.f.into_bytes());
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+fn random_polynomial(rng: &mut rand_chacha::ChaChaRng, len: usize) -> Vec<Fr> {
+    (0..len).map(|_| Fr::random(&mut *rng)).collect()
+}
+
+#[test]
+fn test_open_verify() {
+    let mut rng = crate::test_rng();
+    let params = Params::new(16);
+
+    // Polynomials with fewer than d coefficients are padded with zeros.
+    let a = random_polynomial(&mut rng, 11);
+    let blinding = Fr::random(&mut rng);
+    let commitment = AffinePoint::from(params.commit(&a, &blinding));
+    let x = Fr::random(&mut rng);
+
+    let (v, proof) = params.open(&mut Transcript::new(b"test"), &a, &blinding, &x, &mut rng);
+    assert_eq!(v, evaluate(&a, &x));
+    assert!(params.verify(&mut Transcript::new(b"test"), &commitment, &x, &v, &proof));
+    assert!(!params.verify(
+        &mut Transcript::new(b"test"),
+        &commitment,
+        &x,
+        &(v + Fr::one()),
+        &proof
+    ));
+    assert!(!params.verify(
+        &mut Transcript::new(b"test"),
+        &commitment,
+        &(x + Fr::one()),
+        &v,
+        &proof
+    ));
+}
+
+#[test]
+fn test_batch() {
+    let mut rng = crate::test_rng();
+    let params = Params::new(8);
+
+    let polynomials: Vec<(Vec<Fr>, Fr)> = (0..3)
+        .map(|i| (random_polynomial(&mut rng, 8 - i), Fr::random(&mut rng)))
+        .collect();
+    let commitments: Vec<AffinePoint> = polynomials
+        .iter()
+        .map(|(a, r)| AffinePoint::from(params.commit(a, r)))
+        .collect();
+    let x = Fr::random(&mut rng);
+
+    let borrowed: Vec<(&[Fr], Fr)> = polynomials.iter().map(|(a, r)| (&a[..], *r)).collect();
+    let (values, proof) = params.open_batch(&mut Transcript::new(b"test"), &borrowed, &x, &mut rng);
+    assert!(params.verify_batch(
+        &mut Transcript::new(b"test"),
+        &commitments,
+        &x,
+        &values,
+        &proof
+    ));
+
+    let mut wrong = values.clone();
+    wrong[2] += Fr::one();
+    assert!(!params.verify_batch(
+        &mut Transcript::new(b"test"),
+        &commitments,
+        &x,
+        &wrong,
+        &proof
+    ));
+}
+
+#[test]
+fn test_check_guards() {
+    let mut rng = crate::test_rng();
+    let params = Params::new(8);
+
+    let mut guards = Vec::new();
+    for _ in 0..3 {
+        let a = random_polynomial(&mut rng, 8);
+        let blinding = Fr::random(&mut rng);
+        let commitment = AffinePoint::from(params.commit(&a, &blinding));
+        let x = Fr::random(&mut rng);
+        let (v, proof) = params.open(&mut Transcript::new(b"test"), &a, &blinding, &x, &mut rng);
+
+        guards.push(
+            params
+                .verify_succinct(&mut Transcript::new(b"test"), &commitment, &x, &v, &proof)
+                .unwrap(),
+        );
+    }
+    assert!(params.check_guards(&guards, &mut rng));
+
+    guards[1].c += Fr::one();
+    assert!(!guards[1].check(&params));
+    assert!(!params.check_guards(&guards, &mut rng));
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let params = Params::new(8);
+    let a = random_polynomial(&mut rng, 8);
+    let x = Fr::random(&mut rng);
+
+    let (_, proof) = params.open(&mut Transcript::new(b"test"), &a, &Fr::zero(), &x, &mut rng);
+    let bytes = proof.into_bytes();
+    assert_eq!(bytes.len(), (2 * 3 + 3) * 32);
+    assert_eq!(OpeningProof::from_bytes(&bytes), Some(proof));
+    assert_eq!(OpeningProof::from_bytes(&bytes[32..]), None);
+}
@@ -0,0 +1,926 @@
+//! This module implements keyed-verification anonymous credentials based
+//! on the algebraic MAC `MAC_GGM` of Chase, Meiklejohn and Zaverucha
+//! (CMZ14), for settings where the issuer also verifies credentials.
+//!
+//! # Keys and MACs
+//!
+//! With the generators `G` = [`GENERATOR`] and `H` =
+//! [`pedersen_generator`], a secret key for `n` attributes is
+//! `(x_0, x_0', x_1, ..., x_n)` and its public key is the commitment
+//! `C_x0 = x_0 * G + x_0' * H` together with `X_i = x_i * H`. A
+//! credential on the attributes `m_1, ..., m_n` in `Fr` is a MAC
+//! `(U, U')` with `U = b * G` for a random `b` and
+//! `U' = (x_0 + sum_i x_i * m_i) * U`.
+//!
+//! # Issuance
+//!
+//! The user sends a [`CredentialRequest`] listing every attribute either
+//! in the clear or ElGamal-encrypted as `(r_i * G, m_i * G + r_i * D)`
+//! under a fresh key `D = d * G`, with a proof of knowledge of the
+//! encrypted attributes. The issuer returns `U`, the encryption of `U'`
+//! computed homomorphically, and a proof that both were computed with
+//! its secret key, so the user obtains a credential without revealing
+//! the blinded attributes.
+//!
+//! # Presentation
+//!
+//! To show a credential, the user rerandomizes it to `(a * U, a * U')`,
+//! discloses some attributes and commits to the others as
+//! `C_i = m_i * U + z_i * H`, and commits to `U'` as `C_U' = U' + r * G`.
+//! The verifier computes
+//!
+//! ```text
+//! V = x_0 * U + sum_hidden x_i * C_i + sum_disclosed x_i * m_i * U - C_U'
+//! ```
+//!
+//! with its secret key, and the user proves knowledge of the hidden
+//! attributes and of `z_i` and `r` with `V = sum_hidden z_i * X_i - r * G`,
+//! which only holds if the MAC is valid. Presentations of the same
+//! credential are unlinkable.
+//!
+//! All proofs are sigma protocols from the `sigma` module, and
+//! presentations are bound to a caller-supplied context to prevent
+//! replay.
+
+use core::fmt;
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::helpers::{read_point, read_scalar};
+use crate::sharing::pedersen_generator;
+use crate::sigma::{LinearRelation, Proof};
+use crate::{AffinePoint, ExtendedPoint, Fr, Transcript, GENERATOR};
+
+/// The label of the transcript of every proof.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-KVAC-SHA512-v1";
+
+/// Returns the transcript of a proof of the given kind, bound to
+/// `context`.
+fn proof_transcript(kind: &[u8], context: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(CONTEXT_STRING);
+    transcript.append_message(b"kind", kind);
+    transcript.append_message(b"context", context);
+
+    transcript
+}
+
+/// Returns a random nonzero scalar.
+fn random_nonzero<R: RngCore + CryptoRng>(rng: &mut R) -> Fr {
+    loop {
+        let x = Fr::random(&mut *rng);
+        if x != Fr::zero() {
+            return x;
+        }
+    }
+}
+
+/// The secret key of an issuer.
+#[derive(Clone)]
+pub struct SecretKey {
+    x0: Fr,
+    x0_blinding: Fr,
+    x: Vec<Fr>,
+    public_key: PublicKey,
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("public_key", &self.public_key)
+            .finish()
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.x0.zeroize();
+        self.x0_blinding.zeroize();
+        for x in self.x.iter_mut() {
+            x.zeroize();
+        }
+    }
+}
+
+/// The public key `(C_x0, X_1, ..., X_n)` of an issuer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicKey {
+    c_x0: AffinePoint,
+    x: Vec<AffinePoint>,
+}
+
+impl SecretKey {
+    /// Creates a random secret key for credentials with `attributes`
+    /// attributes.
+    pub fn new<R: RngCore + CryptoRng>(attributes: usize, rng: &mut R) -> SecretKey {
+        let x0 = Fr::random(&mut *rng);
+        let x0_blinding = Fr::random(&mut *rng);
+        let x = (0..attributes).map(|_| Fr::random(&mut *rng)).collect();
+
+        SecretKey::from_scalars(x0, x0_blinding, x)
+    }
+
+    fn from_scalars(x0: Fr, x0_blinding: Fr, x: Vec<Fr>) -> SecretKey {
+        let h = pedersen_generator();
+        let c_x0 = AffinePoint::from(ExtendedPoint::from(GENERATOR) * x0 + h * x0_blinding);
+        let public_key = PublicKey {
+            c_x0,
+            x: x.iter().map(|x_i| AffinePoint::from(h * x_i)).collect(),
+        };
+
+        SecretKey {
+            x0,
+            x0_blinding,
+            x,
+            public_key,
+        }
+    }
+
+    /// Attempts to interpret the byte representation
+    /// `x_0 || x_0' || x_1 || ... || x_n` of a secret key.
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<SecretKey> {
+        if bytes.len() < 64 || bytes.len() % 32 != 0 {
+            return None;
+        }
+
+        let x0 = read_scalar(&mut bytes)?;
+        let x0_blinding = read_scalar(&mut bytes)?;
+        let mut x = Vec::with_capacity(bytes.len() / 32);
+        while !bytes.is_empty() {
+            x.push(read_scalar(&mut bytes)?);
+        }
+
+        Some(SecretKey::from_scalars(x0, x0_blinding, x))
+    }
+
+    /// Converts this secret key into its byte representation.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(32 * (self.x.len() + 2));
+        res.extend_from_slice(&self.x0.into_bytes());
+        res.extend_from_slice(&self.x0_blinding.into_bytes());
+        for x_i in &self.x {
+            res.extend_from_slice(&x_i.into_bytes());
+        }
+
+        res
+    }
+
+    /// Returns the public key corresponding to this secret key.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Issues a credential for `request`, after checking the user's proof
+    /// of knowledge of the blinded attributes. The caller is responsible
+    /// for checking the disclosed attributes, available through
+    /// [`CredentialRequest::attributes`], against its issuance policy.
+    /// This returns `None` if the request is for a different number of
+    /// attributes or its proof is invalid.
+    pub fn issue<R: RngCore + CryptoRng>(
+        &self,
+        request: &CredentialRequest,
+        rng: &mut R,
+    ) -> Option<CredentialResponse> {
+        if request.attributes.len() != self.x.len()
+            || !request_relation(&request.key, &request.attributes)
+                .verify(&mut proof_transcript(b"request", &[]), &request.proof)
+        {
+            return None;
+        }
+
+        let g = ExtendedPoint::from(GENERATOR);
+        let d = ExtendedPoint::from(request.key);
+        let mut b = random_nonzero(rng);
+        let mut r = Fr::random(&mut *rng);
+        let u = g * b;
+
+        // Enc(U') = (r * G + sum_hidden t_i * E_i1,
+        //            b * (x_0 + sum_public x_i * m_i) * G + r * D + sum_hidden t_i * E_i2)
+        // with t_i = b * x_i.
+        let mut exponent = self.x0;
+        let mut e1 = g * r;
+        let mut e2 = d * r;
+        let mut t = Vec::new();
+        let mut witness_t = Vec::new();
+        for (x_i, attribute) in self.x.iter().zip(request.attributes.iter()) {
+            match attribute {
+                RequestAttribute::Public(m) => exponent += x_i * m,
+                RequestAttribute::Encrypted(c1, c2) => {
+                    let t_i = b * x_i;
+                    e1 += ExtendedPoint::from(*c1) * t_i;
+                    e2 += ExtendedPoint::from(*c2) * t_i;
+                    t.push(AffinePoint::from(pedersen_generator() * t_i));
+                    witness_t.push(t_i);
+                }
+            }
+        }
+        e2 += u * exponent;
+
+        let (u, e1, e2) = (
+            AffinePoint::from(u),
+            AffinePoint::from(e1),
+            AffinePoint::from(e2),
+        );
+
+        let mut witness = Vec::with_capacity(self.x.len() + 4 + witness_t.len());
+        witness.push(self.x0);
+        witness.push(self.x0_blinding);
+        witness.extend_from_slice(&self.x);
+        witness.push(b);
+        witness.push(r);
+        witness.append(&mut witness_t);
+        let proof = issuance_relation(&self.public_key, request, &u, &t, &e1, &e2).prove(
+            &mut proof_transcript(b"issuance", &[]),
+            &witness,
+            rng,
+        );
+        for w in witness.iter_mut() {
+            w.zeroize();
+        }
+        exponent.zeroize();
+        b.zeroize();
+        r.zeroize();
+
+        Some(CredentialResponse {
+            u,
+            t,
+            e1,
+            e2,
+            proof,
+        })
+    }
+
+    /// Verifies a presentation bound to `context`, returning the
+    /// attributes it discloses, with `None` for hidden attributes. This
+    /// returns `None` if the presentation is invalid.
+    pub fn verify_presentation(
+        &self,
+        presentation: &Presentation,
+        context: &[u8],
+    ) -> Option<Vec<Option<Fr>>> {
+        if presentation.attributes.len() != self.x.len()
+            || bool::from(presentation.u.is_small_order())
+        {
+            return None;
+        }
+
+        let u = ExtendedPoint::from(presentation.u);
+        let mut v = u * self.x0 - ExtendedPoint::from(presentation.c_u_prime);
+        for (x_i, attribute) in self.x.iter().zip(presentation.attributes.iter()) {
+            v += match attribute {
+                PresentedAttribute::Disclosed(m) => u * (x_i * m),
+                PresentedAttribute::Committed(c) => ExtendedPoint::from(*c) * x_i,
+            };
+        }
+
+        let relation = presentation.relation(&self.public_key, &AffinePoint::from(v));
+        let proof = Proof::from_compact_bytes(&relation, &presentation.proof)?;
+        if !relation.verify(&mut proof_transcript(b"presentation", context), &proof) {
+            return None;
+        }
+
+        Some(presentation.disclosed())
+    }
+}
+
+impl PublicKey {
+    /// Returns the number of attributes of credentials under this key.
+    pub fn attributes(&self) -> usize {
+        self.x.len()
+    }
+
+    /// Attempts to interpret the byte representation
+    /// `C_x0 || X_1 || ... || X_n` of a public key.
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<PublicKey> {
+        if bytes.is_empty() || bytes.len() % 32 != 0 {
+            return None;
+        }
+
+        let c_x0 = read_point(&mut bytes)?;
+        let mut x = Vec::with_capacity(bytes.len() / 32);
+        while !bytes.is_empty() {
+            x.push(read_point(&mut bytes)?);
+        }
+
+        Some(PublicKey { c_x0, x })
+    }
+
+    /// Converts this public key into its byte representation.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(32 * (self.x.len() + 1));
+        res.extend_from_slice(&self.c_x0.into_bytes());
+        for x_i in &self.x {
+            res.extend_from_slice(&x_i.into_bytes());
+        }
+
+        res
+    }
+}
+
+/// An attribute of a credential request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attribute {
+    /// An attribute disclosed to the issuer.
+    Public(Fr),
+    /// An attribute hidden from the issuer.
+    Blinded(Fr),
+}
+
+impl Attribute {
+    fn value(&self) -> Fr {
+        match self {
+            Attribute::Public(m) | Attribute::Blinded(m) => *m,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RequestAttribute {
+    Public(Fr),
+    Encrypted(AffinePoint, AffinePoint),
+}
+
+/// Returns the relation `D = d * G`, `E_i1 = r_i * G` and
+/// `E_i2 = m_i * G + r_i * D` for the blinded attributes, with
+/// witness `(d, m_i, r_i, ...)`.
+fn request_relation(key: &AffinePoint, attributes: &[RequestAttribute]) -> LinearRelation {
+    let mut relation = LinearRelation::new();
+    let d = relation.allocate_scalar();
+    let g = relation.allocate_element(GENERATOR);
+    let key = relation.allocate_element(*key);
+    relation.constrain(key, &[(d, g)]);
+
+    for attribute in attributes {
+        if let RequestAttribute::Encrypted(c1, c2) = attribute {
+            let m = relation.allocate_scalar();
+            let r = relation.allocate_scalar();
+            let c1 = relation.allocate_element(*c1);
+            let c2 = relation.allocate_element(*c2);
+            relation.constrain(c1, &[(r, g)]);
+            relation.constrain(c2, &[(m, g), (r, key)]);
+        }
+    }
+
+    relation
+}
+
+/// A request for a credential, sent by the user to the issuer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CredentialRequest {
+    key: AffinePoint,
+    attributes: Vec<RequestAttribute>,
+    proof: Proof,
+}
+
+/// The issuer's response to a [`CredentialRequest`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CredentialResponse {
+    u: AffinePoint,
+    t: Vec<AffinePoint>,
+    e1: AffinePoint,
+    e2: AffinePoint,
+    proof: Proof,
+}
+
+/// The user's state between requesting and receiving a credential.
+pub struct PendingCredential {
+    d: Fr,
+    attributes: Vec<Fr>,
+    request: CredentialRequest,
+}
+
+impl fmt::Debug for PendingCredential {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PendingCredential")
+            .field("request", &self.request)
+            .finish()
+    }
+}
+
+impl Drop for PendingCredential {
+    fn drop(&mut self) {
+        self.d.zeroize();
+        for m in self.attributes.iter_mut() {
+            m.zeroize();
+        }
+    }
+}
+
+impl CredentialRequest {
+    /// Requests a credential on `attributes` under `public_key`,
+    /// encrypting the blinded attributes. This returns `None` if the
+    /// number of attributes does not match the public key.
+    pub fn new<R: RngCore + CryptoRng>(
+        public_key: &PublicKey,
+        attributes: &[Attribute],
+        rng: &mut R,
+    ) -> Option<(PendingCredential, CredentialRequest)> {
+        if attributes.len() != public_key.x.len() {
+            return None;
+        }
+
+        let g = ExtendedPoint::from(GENERATOR);
+        let d = random_nonzero(rng);
+        let key = AffinePoint::from(g * d);
+
+        let mut witness = vec![d];
+        let mut encrypted = Vec::with_capacity(attributes.len());
+        for attribute in attributes {
+            encrypted.push(match attribute {
+                Attribute::Public(m) => RequestAttribute::Public(*m),
+                Attribute::Blinded(m) => {
+                    let r = Fr::random(&mut *rng);
+                    witness.push(*m);
+                    witness.push(r);
+                    RequestAttribute::Encrypted(
+                        AffinePoint::from(g * r),
+                        AffinePoint::from(g * m + ExtendedPoint::from(key) * r),
+                    )
+                }
+            });
+        }
+
+        let proof = request_relation(&key, &encrypted).prove(
+            &mut proof_transcript(b"request", &[]),
+            &witness,
+            rng,
+        );
+        let request = CredentialRequest {
+            key,
+            attributes: encrypted,
+            proof,
+        };
+        for w in witness.iter_mut() {
+            w.zeroize();
+        }
+
+        Some((
+            PendingCredential {
+                d,
+                attributes: attributes.iter().map(Attribute::value).collect(),
+                request: request.clone(),
+            },
+            request,
+        ))
+    }
+
+    /// Returns the attributes of this request, with `None` for blinded
+    /// attributes.
+    pub fn attributes(&self) -> Vec<Option<Fr>> {
+        self.attributes
+            .iter()
+            .map(|a| match a {
+                RequestAttribute::Public(m) => Some(*m),
+                RequestAttribute::Encrypted(..) => None,
+            })
+            .collect()
+    }
+}
+
+/// Returns the issuance relation for `request`, with witness
+/// `(x_0, x_0', x_1, ..., x_n, b, r, t_i, ...)`.
+fn issuance_relation(
+    public_key: &PublicKey,
+    request: &CredentialRequest,
+    u_point: &AffinePoint,
+    t_points: &[AffinePoint],
+    e1_point: &AffinePoint,
+    e2_point: &AffinePoint,
+) -> LinearRelation {
+    let mut relation = LinearRelation::new();
+    let x0 = relation.allocate_scalar();
+    let x0_blinding = relation.allocate_scalar();
+    let x: Vec<_> = public_key
+        .x
+        .iter()
+        .map(|_| relation.allocate_scalar())
+        .collect();
+    let b = relation.allocate_scalar();
+    let r = relation.allocate_scalar();
+
+    let g = relation.allocate_element(GENERATOR);
+    let h = relation.allocate_element(AffinePoint::from(pedersen_generator()));
+    let c_x0 = relation.allocate_element(public_key.c_x0);
+    relation.constrain(c_x0, &[(x0, g), (x0_blinding, h)]);
+    let x_points: Vec<_> = public_key
+        .x
+        .iter()
+        .map(|x_i| relation.allocate_element(*x_i))
+        .collect();
+    for (x_i, x_point) in x.iter().zip(x_points.iter()) {
+        relation.constrain(*x_point, &[(*x_i, h)]);
+    }
+
+    let u = relation.allocate_element(*u_point);
+    relation.constrain(u, &[(b, g)]);
+
+    let key = relation.allocate_element(request.key);
+    let mut e1_terms = vec![(r, g)];
+    let mut e2_terms = vec![(x0, u), (r, key)];
+    let mut t = t_points.iter();
+    for ((x_i, x_point), attribute) in x.iter().zip(x_points.iter()).zip(request.attributes.iter())
+    {
+        match attribute {
+            RequestAttribute::Public(m) => {
+                let m_u =
+                    relation.allocate_element(AffinePoint::from(ExtendedPoint::from(*u_point) * m));
+                e2_terms.push((*x_i, m_u));
+            }
+            RequestAttribute::Encrypted(c1, c2) => {
+                // T_i = b * X_i = t_i * H, so that t_i = b * x_i.
+                let t_i = relation.allocate_scalar();
+                let t_point =
+                    relation.allocate_element(t.next().copied().unwrap_or(AffinePoint::identity()));
+                relation.constrain(t_point, &[(b, *x_point)]);
+                relation.constrain(t_point, &[(t_i, h)]);
+
+                let c1 = relation.allocate_element(*c1);
+                let c2 = relation.allocate_element(*c2);
+                e1_terms.push((t_i, c1));
+                e2_terms.push((t_i, c2));
+            }
+        }
+    }
+
+    let e1 = relation.allocate_element(*e1_point);
+    let e2 = relation.allocate_element(*e2_point);
+    relation.constrain(e1, &e1_terms);
+    relation.constrain(e2, &e2_terms);
+
+    relation
+}
+
+impl PendingCredential {
+    /// Checks the issuer's proof and decrypts the credential. This
+    /// returns `None` if the response is invalid.
+    pub fn finalize(
+        self,
+        public_key: &PublicKey,
+        response: &CredentialResponse,
+    ) -> Option<Credential> {
+        let hidden = self
+            .request
+            .attributes
+            .iter()
+            .filter(|a| matches!(a, RequestAttribute::Encrypted(..)))
+            .count();
+        if response.t.len() != hidden
+            || bool::from(response.u.is_small_order())
+            || !issuance_relation(
+                public_key,
+                &self.request,
+                &response.u,
+                &response.t,
+                &response.e1,
+                &response.e2,
+            )
+            .verify(&mut proof_transcript(b"issuance", &[]), &response.proof)
+        {
+            return None;
+        }
+
+        let u_prime = ExtendedPoint::from(response.e2) - ExtendedPoint::from(response.e1) * self.d;
+
+        Some(Credential {
+            u: response.u,
+            u_prime: AffinePoint::from(u_prime),
+            attributes: self.attributes.clone(),
+        })
+    }
+}
+
+/// A credential, which is a MAC `(U, U')` on its attributes.
+#[derive(Clone)]
+pub struct Credential {
+    u: AffinePoint,
+    u_prime: AffinePoint,
+    attributes: Vec<Fr>,
+}
+
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credential").finish()
+    }
+}
+
+impl Drop for Credential {
+    fn drop(&mut self) {
+        for m in self.attributes.iter_mut() {
+            m.zeroize();
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PresentedAttribute {
+    Disclosed(Fr),
+    Committed(AffinePoint),
+}
+
+/// A presentation of a credential, disclosing some of its attributes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Presentation {
+    u: AffinePoint,
+    c_u_prime: AffinePoint,
+    attributes: Vec<PresentedAttribute>,
+    proof: Vec<u8>,
+}
+
+impl Credential {
+    /// Returns the attributes of this credential.
+    pub fn attributes(&self) -> &[Fr] {
+        &self.attributes
+    }
+
+    /// Presents this credential, disclosing the attributes for which
+    /// `disclose` is `true` and binding the presentation to `context`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `disclose` is not the number of attributes
+    /// of this credential.
+    pub fn present<R: RngCore + CryptoRng>(
+        &self,
+        public_key: &PublicKey,
+        disclose: &[bool],
+        context: &[u8],
+        rng: &mut R,
+    ) -> Presentation {
+        assert_eq!(disclose.len(), self.attributes.len());
+
+        let g = ExtendedPoint::from(GENERATOR);
+        let h = pedersen_generator();
+        let a = random_nonzero(rng);
+        let u = ExtendedPoint::from(self.u) * a;
+        let u_prime = ExtendedPoint::from(self.u_prime) * a;
+
+        // V = sum_hidden z_i * X_i - r * G
+        let mut r = Fr::random(&mut *rng);
+        let mut v = -(g * r);
+        let mut m_witness = Vec::new();
+        let mut z_witness = Vec::new();
+        let mut attributes = Vec::with_capacity(self.attributes.len());
+        for ((m, disclosed), x_i) in self
+            .attributes
+            .iter()
+            .zip(disclose.iter())
+            .zip(public_key.x.iter())
+        {
+            attributes.push(if *disclosed {
+                PresentedAttribute::Disclosed(*m)
+            } else {
+                let z = Fr::random(&mut *rng);
+                v += ExtendedPoint::from(*x_i) * z;
+                m_witness.push(*m);
+                z_witness.push(z);
+                PresentedAttribute::Committed(AffinePoint::from(u * m + h * z))
+            });
+        }
+
+        let mut presentation = Presentation {
+            u: AffinePoint::from(u),
+            c_u_prime: AffinePoint::from(u_prime + g * r),
+            attributes,
+            proof: Vec::new(),
+        };
+
+        let mut witness = m_witness;
+        witness.append(&mut z_witness);
+        witness.push(-r);
+        presentation.proof = presentation
+            .relation(public_key, &AffinePoint::from(v))
+            .prove(
+                &mut proof_transcript(b"presentation", context),
+                &witness,
+                rng,
+            )
+            .into_compact_bytes();
+        for w in witness.iter_mut() {
+            w.zeroize();
+        }
+        r.zeroize();
+
+        presentation
+    }
+}
+
+impl Presentation {
+    /// Returns the presentation relation `C_i = m_i * U + z_i * H` for the
+    /// hidden attributes and `V = sum_hidden z_i * X_i + r * G`, with
+    /// witness `(m_i, ..., z_i, ..., r)`.
+    fn relation(&self, public_key: &PublicKey, v: &AffinePoint) -> LinearRelation {
+        let hidden: Vec<(AffinePoint, AffinePoint)> = self
+            .attributes
+            .iter()
+            .zip(public_key.x.iter())
+            .filter_map(|(attribute, x_i)| match attribute {
+                PresentedAttribute::Committed(c) => Some((*c, *x_i)),
+                PresentedAttribute::Disclosed(_) => None,
+            })
+            .collect();
+
+        let mut relation = LinearRelation::new();
+        let m: Vec<_> = hidden.iter().map(|_| relation.allocate_scalar()).collect();
+        let z: Vec<_> = hidden.iter().map(|_| relation.allocate_scalar()).collect();
+        let r = relation.allocate_scalar();
+
+        let g = relation.allocate_element(GENERATOR);
+        let h = relation.allocate_element(AffinePoint::from(pedersen_generator()));
+        let u = relation.allocate_element(self.u);
+        let v = relation.allocate_element(*v);
+        let mut v_terms = vec![(r, g)];
+        for ((m_i, z_i), (c, x_i)) in m.iter().zip(z.iter()).zip(hidden.iter()) {
+            let c = relation.allocate_element(*c);
+            let x_i = relation.allocate_element(*x_i);
+            relation.constrain(c, &[(*m_i, u), (*z_i, h)]);
+            v_terms.push((*z_i, x_i));
+        }
+        relation.constrain(v, &v_terms);
+
+        relation
+    }
+
+    /// Returns the attributes disclosed by this presentation, with `None`
+    /// for hidden attributes.
+    pub fn disclosed(&self) -> Vec<Option<Fr>> {
+        self.attributes
+            .iter()
+            .map(|a| match a {
+                PresentedAttribute::Disclosed(m) => Some(*m),
+                PresentedAttribute::Committed(_) => None,
+            })
+            .collect()
+    }
+
+    /// Attempts to interpret the byte representation of a presentation of
+    /// a credential with `attributes` attributes.
+    pub fn from_bytes(mut bytes: &[u8], attributes: usize) -> Option<Presentation> {
+        let u = read_point(&mut bytes)?;
+        let c_u_prime = read_point(&mut bytes)?;
+
+        let mut presented = Vec::with_capacity(attributes);
+        for _ in 0..attributes {
+            let (tag, rest) = bytes.split_first()?;
+            bytes = rest;
+            presented.push(match tag {
+                0 => PresentedAttribute::Committed(read_point(&mut bytes)?),
+                1 => PresentedAttribute::Disclosed(read_scalar(&mut bytes)?),
+                _ => return None,
+            });
+        }
+
+        Some(Presentation {
+            u,
+            c_u_prime,
+            attributes: presented,
+            proof: bytes.to_vec(),
+        })
+    }
+
+    /// Converts this presentation into its byte representation
+    /// `U || C_U' || a_1 || ... || a_n || proof`, where each attribute
+    /// `a_i` is either `0x00 || C_i` or `0x01 || m_i`, and the proof is in
+    /// the compact encoding of the `sigma` module.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(64 + 33 * self.attributes.len() + self.proof.len());
+        res.extend_from_slice(&self.u.into_bytes());
+        res.extend_from_slice(&self.c_u_prime.into_bytes());
+        for attribute in &self.attributes {
+            match attribute {
+                PresentedAttribute::Committed(c) => {
+                    res.push(0);
+                    res.extend_from_slice(&c.into_bytes());
+                }
+                PresentedAttribute::Disclosed(m) => {
+                    res.push(1);
+                    res.extend_from_slice(&m.into_bytes());
+                }
+            }
+        }
+        res.extend_from_slice(&self.proof);
+
+        res
+    }
+}
+
+#[cfg(test)]
+fn issue_credential(
+    sk: &SecretKey,
+    attributes: &[Attribute],
+    rng: &mut rand_chacha::ChaChaRng,
+) -> Credential {
+    let (pending, request) = CredentialRequest::new(sk.public_key(), attributes, rng).unwrap();
+    let response = sk.issue(&request, rng).unwrap();
+
+    pending.finalize(sk.public_key(), &response).unwrap()
+}
+
+#[test]
+fn test_issue_and_present() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(3, &mut rng);
+    let attributes = [Fr::from(18), Fr::from(42), Fr::from(7)];
+
+    let credential = issue_credential(
+        &sk,
+        &[
+            Attribute::Public(attributes[0]),
+            Attribute::Blinded(attributes[1]),
+            Attribute::Blinded(attributes[2]),
+        ],
+        &mut rng,
+    );
+    assert_eq!(credential.attributes(), &attributes[..]);
+
+    let presentation = credential.present(sk.public_key(), &[true, false, false], b"ctx", &mut rng);
+    assert_eq!(
+        sk.verify_presentation(&presentation, b"ctx"),
+        Some(vec![Some(attributes[0]), None, None])
+    );
+    assert_eq!(sk.verify_presentation(&presentation, b"other ctx"), None);
+
+    // Presentations are unlinkable.
+    let other = credential.present(sk.public_key(), &[true, false, false], b"ctx", &mut rng);
+    assert_ne!(other.u, presentation.u);
+}
+
+#[test]
+fn test_request_hides_attributes() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(2, &mut rng);
+
+    let (_, request) = CredentialRequest::new(
+        sk.public_key(),
+        &[
+            Attribute::Public(Fr::one()),
+            Attribute::Blinded(Fr::from(5)),
+        ],
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(request.attributes(), vec![Some(Fr::one()), None]);
+
+    assert!(CredentialRequest::new(sk.public_key(), &[], &mut rng).is_none());
+    let other = SecretKey::new(3, &mut rng);
+    assert!(other.issue(&request, &mut rng).is_none());
+}
+
+#[test]
+fn test_forged_presentation() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(2, &mut rng);
+    let other = SecretKey::new(2, &mut rng);
+    let credential = issue_credential(
+        &other,
+        &[Attribute::Public(Fr::one()), Attribute::Public(Fr::one())],
+        &mut rng,
+    );
+
+    // A credential from another issuer is rejected.
+    let presentation = credential.present(sk.public_key(), &[true, true], b"", &mut rng);
+    assert_eq!(sk.verify_presentation(&presentation, b""), None);
+
+    // Changing a disclosed attribute is detected.
+    let credential = issue_credential(
+        &sk,
+        &[Attribute::Public(Fr::one()), Attribute::Public(Fr::one())],
+        &mut rng,
+    );
+    let mut presentation = credential.present(sk.public_key(), &[true, false], b"", &mut rng);
+    presentation.attributes[0] = PresentedAttribute::Disclosed(Fr::from(2));
+    assert_eq!(sk.verify_presentation(&presentation, b""), None);
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(2, &mut rng);
+    let credential = issue_credential(
+        &sk,
+        &[
+            Attribute::Public(Fr::one()),
+            Attribute::Blinded(Fr::from(3)),
+        ],
+        &mut rng,
+    );
+
+    assert_eq!(
+        SecretKey::from_bytes(&sk.into_bytes())
+            .unwrap()
+            .public_key(),
+        sk.public_key()
+    );
+    assert_eq!(
+        PublicKey::from_bytes(&sk.public_key().into_bytes()).as_ref(),
+        Some(sk.public_key())
+    );
+
+    let presentation = credential.present(sk.public_key(), &[false, true], b"", &mut rng);
+    let bytes = presentation.into_bytes();
+    assert_eq!(Presentation::from_bytes(&bytes, 2), Some(presentation));
+
+    let mut bytes = bytes;
+    bytes[64] = 2;
+    assert_eq!(Presentation::from_bytes(&bytes, 2), None);
+}
@@ -8,6 +8,40 @@
 //! * `Fq`, which is the base field of Jubjub
 //! * `Fr`, which is the scalar field of Jubjub
 //! * `batch_normalize` for converting many `ExtendedPoint`s into `AffinePoint`s efficiently.
+//! * `GENERATOR`, a generator of the prime order subgroup.
+//! * `hash_to_curve` for hashing arbitrary messages to the prime order subgroup.
+//! * `multiscalar_mul` / `multiscalar_mul_vartime` for computing sums of scalar multiples.
+//! * `Transcript` for deriving Fiat–Shamir challenges, optionally compatible with Merlin.
+//! * `ecdh`, Diffie-Hellman key agreement with cofactor multiplication.
+//! * `schnorr`, Schnorr signatures.
+//! * `vrf`, an ECVRF in the style of RFC 9381.
+//! * `elgamal`, additively homomorphic exponential ElGamal encryption.
+//! * `adaptor`, adaptor signatures for Schnorr signatures.
+//! * `blind`, blind Schnorr signatures, including a variant secure under
+//!   concurrent sessions.
+//! * `cpace`, the CPace password-authenticated key exchange.
+//! * `dlog`, solvers for small discrete logarithms (requires the `std` feature).
+//! * `sharing`, verifiable Shamir secret sharing (requires the `std` feature).
+//! * `frost`, FROST threshold Schnorr signatures (requires the `std` feature).
+//! * `musig`, MuSig2 multi-signatures (requires the `std` feature).
+//! * `dkg`, Pedersen distributed key generation (requires the `std` feature).
+//! * `threshold`, threshold ElGamal decryption (requires the `std` feature).
+//! * `sigma`, sigma protocols for linear relations (requires the `std` feature).
+//! * `bulletproofs`, Bulletproofs range proofs (requires the `std` feature).
+//! * `ipa`, a polynomial commitment scheme based on the inner product argument (requires the `std` feature).
+//! * `blsag`, bLSAG linkable ring signatures (requires the `std` feature).
+//! * `oprf`, oblivious pseudorandom functions in the style of RFC 9497 (requires the
+//!   `std` feature).
+//! * `kvac`, keyed-verification anonymous credentials (requires the `std` feature).
+//! * `shuffle`, verifiable shuffles of ElGamal ciphertexts (requires the `std` feature).
+//! * `ot`, base oblivious transfers for OT extension (requires the `std` feature).
+//! * `psi`, Diffie-Hellman private set intersection (requires the `std` feature).
+//! * `hpke`, hybrid public key encryption with a Doppio DHKEM in the style of RFC 9180
+//!   (requires the `std` feature).
+//! * `noise`, Noise protocol handshakes with Doppio Diffie-Hellman (requires the `std`
+//!   feature).
+//! * `hd`, hierarchical deterministic key derivation in the style of BIP32 (requires the
+//!   `std` feature).
 //!
 //! # Constant Time
 //!
@@ -59,6 +93,61 @@ mod extended;
 pub use extended::{ExtendedNielsPoint, ExtendedPoint};
 mod completed;
 
+mod hash;
+pub use hash::hash_to_curve;
+mod msm;
+pub use msm::{multiscalar_mul, multiscalar_mul_vartime};
+#[cfg(feature = "std")]
+mod helpers;
+mod strobe;
+mod transcript;
+pub use transcript::Transcript;
+
+pub mod adaptor;
+pub mod blind;
+pub mod cpace;
+pub mod ecdh;
+pub mod elgamal;
+pub mod schnorr;
+pub mod vrf;
+
+#[cfg(feature = "std")]
+pub mod blsag;
+#[cfg(feature = "std")]
+pub mod bulletproofs;
+#[cfg(feature = "std")]
+pub mod dkg;
+#[cfg(feature = "std")]
+pub mod dlog;
+#[cfg(feature = "std")]
+pub mod frost;
+#[cfg(feature = "std")]
+pub mod hd;
+#[cfg(feature = "std")]
+pub mod hpke;
+#[cfg(feature = "std")]
+pub mod ipa;
+#[cfg(feature = "std")]
+pub mod kvac;
+#[cfg(feature = "std")]
+pub mod musig;
+#[cfg(feature = "std")]
+pub mod noise;
+#[cfg(feature = "std")]
+pub mod oprf;
+#[cfg(feature = "std")]
+pub mod ot;
+#[cfg(feature = "std")]
+pub mod psi;
+#[cfg(feature = "std")]
+pub mod sharing;
+#[cfg(feature = "std")]
+pub mod shuffle;
+#[cfg(feature = "std")]
+pub mod sigma;
+#[cfg(feature = "std")]
+pub mod threshold;
+
 impl_binops_additive!(ExtendedPoint, AffineNielsPoint);
 
 impl_binops_additive!(ExtendedPoint, AffinePoint);
@@ -160,16 +249,16 @@ fn test_extended_niels_point_identity() {
 fn test_assoc() {
     let p = ExtendedPoint::from(AffinePoint {
         u: Fq([
-            0xc0115cb656ae4839,
-            0x623dc3ff81d64c26,
-            0x5868e739b5794f2c,
-            0x23bd4fbb18d39c9c,
+            0x34e92c4ba5bdb674,
+            0xb9d663b8bb5eeb16,
+            0xe6bea5c789efc7ef,
+            0x043eb4903affe18f,
         ]),
         v: Fq([
-            0x7588ee6d6dd40deb,
-            0x9d6d7a23ebdb7c4c,
-            0x46462e26d4edb8c7,
-            0x10b4c1517ca82e9b,
+            0x0740efe46d17c33f,
+            0x582bf8400e63d659,
+            0x54a3072bd275ad35,
+            0x065e366c6d20840d,
         ]),
     })
     .mul_by_cofactor();
@@ -186,12 +275,17 @@ fn test_assoc() {
 fn test_batch_normalize() {
     let mut p = ExtendedPoint::from(AffinePoint {
         u: Fq([
-            0xcf79566f8c7b540e,
-            0x1335d8bfac696f54,
-            0x47e200f70b9f4d65,
-            0x024c79f53d1d12a7,
+            0xb206f090fe4a62d1,
+            0x5ee48d1e488b03bf,
+            0xf8803987f36bdfc5,
+            0x05dcab1632b0e9c6,
+        ]),
+        v: Fq([
+            0x8b549b6b92dbf2db,
+            0x9e6a3befc3837d04,
+            0x5be47412a2feea03,
+            0x0cfde7b84dab2c29,
         ]),
-        v: Fq([0, 0, 0, 0]),
     })
     .mul_by_cofactor();
 
@@ -220,97 +314,56 @@ fn test_batch_normalize() {
     }
 }
 
+#[cfg(test)]
+pub(crate) fn test_rng() -> rand_chacha::ChaChaRng {
+    use rand_core::SeedableRng;
+
+    rand_chacha::ChaChaRng::from_seed([
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+        25, 26, 27, 28, 29, 30, 31,
+    ])
+}
+
 #[cfg(test)]
 const FULL_GENERATOR: AffinePoint = AffinePoint::from_raw_unchecked(
     Fq::from_raw([
-        0x1f9669e1a4eccab3,
-        0x081aca58c6212db8,
-        0x2388ac6d0efcf0c7,
-        0x04c7b1198069b852,
+        0x1a3b28fcbdd50bba,
+        0x3cc6cf6a95cd5b19,
+        0x1f307b9a4bdbbc37,
+        0x01644aba2a3d49b4,
     ]),
-    Fq::from_raw([0x0, 0x0, 0x0, 0x0]),
+    Fq::from_raw([0x2, 0x0, 0x0, 0x0]),
 );
 
 #[cfg(test)]
-const EIGHT_TORSION: [AffinePoint; 8] = [
-    AffinePoint::from_raw_unchecked(
-        Fq::from_raw([
-            0xd92e6a7927200d43,
-            0x7aa41ac43dae8582,
-            0xeaaae086a16618d1,
-            0x71d4df38ba9e7973,
-        ]),
-        Fq::from_raw([
-            0xff0d2068eff496dd,
-            0x9106ee90f384a4a1,
-            0x16a13035ad4d7266,
-            0x4958bdb21966982e,
-        ]),
-    ),
+const FOUR_TORSION: [AffinePoint; 4] = [
     AffinePoint::from_raw_unchecked(
         Fq::from_raw([
-            0xfffeffff00000001,
-            0x67baa40089fb5bfe,
-            0xa5e80b39939ed334,
-            0x73eda753299d7d47,
+            0x998aed3a7137cc19,
+            0x05eea38b602918d7,
+            0x82c2929f543e3d86,
+            0x06b58cef1f867e18,
         ]),
         Fq::from_raw([0x0, 0x0, 0x0, 0x0]),
     ),
-    AffinePoint::from_raw_unchecked(
-        Fq::from_raw([
-            0xd92e6a7927200d43,
-            0x7aa41ac43dae8582,
-            0xeaaae086a16618d1,
-            0x71d4df38ba9e7973,
-        ]),
-        Fq::from_raw([
-            0xf2df96100b6924,
-            0xc2b6b5720c79b75d,
-            0x1c98a7d25c54659e,
-            0x2a94e9a11036e51a,
-        ]),
-    ),
     AffinePoint::from_raw_unchecked(
         Fq::from_raw([0x0, 0x0, 0x0, 0x0]),
         Fq::from_raw([
-            0xffffffff00000000,
-            0x53bda402fffe5bfe,
-            0x3339d80809a1d805,
-            0x73eda753299d7d48,
+            0x5812631a5cf5d3ec,
+            0x14def9dea2f79cd6,
+            0x0000000000000000,
+            0x1000000000000000,
         ]),
     ),
     AffinePoint::from_raw_unchecked(
         Fq::from_raw([
-            0x26d19585d8dff2be,
-            0xd919893ec24fd67c,
-            0x488ef781683bbf33,
-            0x218c81a6eff03d4,
+            0xbe8775dfebbe07d4,
+            0x0ef0565342ce83fe,
+            0x7d3d6d60abc1c27a,
+            0x094a7310e07981e7,
         ]),
-        Fq::from_raw([
-            0xf2df96100b6924,
-            0xc2b6b5720c79b75d,
-            0x1c98a7d25c54659e,
-            0x2a94e9a11036e51a,
-        ]),
-    ),
-    AffinePoint::from_raw_unchecked(
-        Fq::from_raw([0x1000000000000, 0xec03000276030000, 0x8d51ccce760304d0, 0x0]),
         Fq::from_raw([0x0, 0x0, 0x0, 0x0]),
     ),
-    AffinePoint::from_raw_unchecked(
-        Fq::from_raw([
-            0x26d19585d8dff2be,
-            0xd919893ec24fd67c,
-            0x488ef781683bbf33,
-            0x218c81a6eff03d4,
-        ]),
-        Fq::from_raw([
-            0xff0d2068eff496dd,
-            0x9106ee90f384a4a1,
-            0x16a13035ad4d7266,
-            0x4958bdb21966982e,
-        ]),
-    ),
     AffinePoint::from_raw_unchecked(
         Fq::from_raw([0x0, 0x0, 0x0, 0x0]),
         Fq::from_raw([0x1, 0x0, 0x0, 0x0]),
@@ -318,7 +371,7 @@ const EIGHT_TORSION: [AffinePoint; 8] = [
 ];
 
 #[test]
-fn find_eight_torsion() {
+fn find_four_torsion() {
     let g = ExtendedPoint::from(FULL_GENERATOR);
     assert!(g.is_small_order().unwrap_u8() == 0);
     let g = g.multiply(&FR_MODULUS_BYTES);
@@ -326,7 +379,7 @@ fn find_eight_torsion() {
 
     let mut cur = g;
 
-    for (i, point) in EIGHT_TORSION.iter().enumerate() {
+    for (i, point) in FOUR_TORSION.iter().enumerate() {
         let tmp = AffinePoint::from(cur);
         if &tmp != point {
             panic!("{}th torsion point should be {:?}", i, tmp);
@@ -349,9 +402,7 @@ fn find_curve_generator() {
             assert!(b.is_small_order().unwrap_u8() == 1);
             let b = b.double();
             assert!(b.is_small_order().unwrap_u8() == 1);
-            let b = b.double();
-            assert!(b.is_small_order().unwrap_u8() == 1);
-            if b.is_identity().unwrap_u8() == 0 {
+            if a.is_small_order().unwrap_u8() == 0 && b.is_identity().unwrap_u8() == 0 {
                 let b = b.double();
                 assert!(b.is_small_order().unwrap_u8() == 1);
                 assert!(b.is_identity().unwrap_u8() == 1);
@@ -369,15 +420,15 @@ fn find_curve_generator() {
 
 #[test]
 fn test_small_order() {
-    for point in EIGHT_TORSION.iter() {
+    for point in FOUR_TORSION.iter() {
         assert!(point.is_small_order().unwrap_u8() == 1);
     }
 }
 
 #[test]
 fn test_is_identity() {
-    let a = EIGHT_TORSION[0].mul_by_cofactor();
-    let b = EIGHT_TORSION[1].mul_by_cofactor();
+    let a = FOUR_TORSION[0].mul_by_cofactor();
+    let b = ExtendedPoint::from(FOUR_TORSION[0]) + ExtendedPoint::from(FOUR_TORSION[2]);
 
     assert_eq!(a.u, b.u);
     assert_eq!(a.v, a.z);
@@ -388,7 +439,7 @@ fn test_is_identity() {
     assert!(a.is_identity().unwrap_u8() == 1);
     assert!(b.is_identity().unwrap_u8() == 1);
 
-    for point in EIGHT_TORSION.iter() {
+    for point in FOUR_TORSION.iter() {
         assert!(point.mul_by_cofactor().is_identity().unwrap_u8() == 1);
     }
 }
@@ -404,12 +455,17 @@ fn test_mul_consistency() {
 
     let p = ExtendedPoint::from(AffinePoint {
         u: Fq([
-            0xcf79566f8c7b540e,
-            0x1335d8bfac696f54,
-            0x47e200f70b9f4d65,
-            0x024c79f53d1d12a7,
+            0x42494fa2766b231d,
+            0xab3d6d9d1d78d629,
+            0x32b49050437bf5ff,
+            0x07f60ba0d504d18b,
+        ]),
+        v: Fq([
+            0x640548b03c69898b,
+            0x822064f6e73a6eda,
+            0x66b81551f4d0fa6b,
+            0x0ef33e2755c0fb75,
         ]),
-        v: Fq([0, 0, 0, 0]),
     })
     .mul_by_cofactor();
     assert_eq!(p * c, (p * a) * b);
@@ -422,68 +478,68 @@ fn test_serialization_consistency() {
 
     let v = vec![
         [
-            203, 85, 12, 213, 56, 234, 12, 193, 19, 132, 128, 64, 142, 110, 170, 185, 179, 108, 97,
-            63, 13, 211, 247, 120, 79, 219, 110, 234, 131, 123, 19, 215,
+            91, 198, 125, 42, 161, 111, 129, 197, 130, 174, 118, 72, 243, 47, 52, 125, 201, 187,
+            227, 190, 212, 16, 145, 182, 39, 249, 184, 122, 161, 176, 152, 139,
         ],
         [
-            113, 154, 240, 230, 224, 198, 208, 170, 104, 15, 59, 126, 151, 222, 233, 195, 203, 195,
-            167, 129, 89, 121, 240, 142, 51, 166, 64, 250, 184, 202, 154, 177,
+            43, 175, 162, 246, 61, 52, 23, 157, 176, 241, 181, 88, 140, 177, 15, 245, 11, 108, 205,
+            172, 188, 43, 51, 161, 106, 155, 156, 231, 11, 183, 127, 137,
         ],
         [
-            197, 41, 93, 209, 203, 55, 164, 174, 88, 0, 90, 199, 1, 156, 149, 141, 240, 29, 14, 82,
-            86, 225, 126, 129, 186, 157, 148, 162, 219, 51, 156, 199,
+            243, 162, 9, 125, 81, 1, 47, 215, 105, 162, 35, 227, 184, 182, 91, 55, 95, 214, 164,
+            56, 60, 253, 40, 240, 46, 99, 161, 80, 237, 52, 28, 143,
         ],
         [
-            182, 117, 250, 241, 81, 196, 199, 227, 151, 74, 243, 17, 221, 97, 200, 139, 192, 83,
-            231, 35, 214, 14, 95, 69, 130, 201, 4, 116, 177, 19, 179, 0,
+            105, 106, 75, 155, 252, 232, 19, 231, 120, 6, 3, 128, 29, 122, 180, 82, 13, 176, 19,
+            100, 93, 15, 135, 42, 251, 65, 126, 21, 151, 235, 119, 8,
         ],
         [
-            118, 41, 29, 200, 60, 189, 119, 252, 78, 40, 230, 18, 208, 221, 38, 214, 176, 250, 4,
-            10, 77, 101, 26, 216, 193, 198, 226, 84, 25, 177, 230, 185,
+            121, 224, 79, 87, 209, 88, 127, 118, 224, 5, 203, 180, 145, 0, 74, 167, 141, 76, 186,
+            236, 195, 161, 146, 160, 210, 137, 183, 237, 242, 69, 237, 131,
         ],
         [
-            226, 189, 227, 208, 112, 117, 136, 98, 72, 38, 211, 167, 254, 82, 174, 113, 112, 166,
-            138, 171, 166, 113, 52, 251, 129, 197, 138, 45, 195, 7, 61, 140,
+            171, 35, 143, 131, 245, 224, 148, 144, 195, 9, 143, 24, 113, 98, 93, 206, 29, 178, 59,
+            74, 196, 98, 198, 214, 105, 165, 219, 55, 196, 46, 119, 133,
         ],
         [
-            38, 198, 156, 196, 146, 225, 55, 163, 138, 178, 157, 128, 115, 135, 204, 215, 0, 33,
-            171, 20, 60, 32, 142, 209, 33, 233, 125, 146, 207, 12, 16, 24,
+            230, 182, 114, 41, 52, 178, 73, 196, 190, 3, 217, 195, 137, 91, 203, 106, 194, 201,
+            139, 71, 199, 90, 227, 13, 193, 177, 89, 149, 22, 35, 4, 5,
         ],
         [
-            17, 187, 231, 83, 165, 36, 232, 184, 140, 205, 195, 252, 166, 85, 59, 86, 3, 226, 211,
-            67, 179, 29, 238, 181, 102, 142, 58, 63, 57, 89, 174, 138,
+            211, 155, 121, 232, 139, 122, 241, 48, 115, 60, 175, 221, 218, 237, 214, 173, 247, 147,
+            155, 189, 71, 56, 203, 67, 38, 72, 222, 122, 132, 118, 16, 15,
         ],
         [
-            210, 159, 80, 16, 181, 39, 221, 204, 224, 144, 145, 79, 54, 231, 8, 140, 142, 216, 93,
-            190, 183, 116, 174, 63, 33, 242, 177, 118, 148, 40, 241, 203,
+            13, 111, 213, 188, 83, 196, 197, 41, 5, 164, 104, 251, 243, 85, 235, 225, 38, 87, 170,
+            12, 159, 78, 147, 128, 144, 247, 143, 118, 187, 221, 140, 15,
         ],
         [
-            0, 143, 107, 102, 149, 187, 27, 124, 18, 10, 98, 28, 113, 123, 121, 185, 29, 152, 14,
-            130, 149, 28, 87, 35, 135, 135, 153, 54, 112, 53, 54, 68,
+            185, 9, 226, 189, 177, 73, 222, 194, 251, 66, 87, 179, 233, 162, 152, 11, 42, 154, 77,
+            226, 242, 160, 37, 108, 49, 239, 45, 201, 179, 192, 134, 143,
         ],
         [
-            178, 131, 85, 160, 214, 51, 208, 157, 196, 152, 247, 93, 202, 56, 81, 239, 155, 122,
-            59, 188, 237, 253, 11, 169, 208, 236, 12, 4, 163, 211, 88, 97,
+            204, 250, 229, 231, 198, 50, 193, 91, 203, 97, 90, 52, 164, 208, 165, 66, 13, 60, 34,
+            108, 103, 183, 210, 54, 125, 196, 51, 16, 8, 65, 208, 7,
         ],
         [
-            246, 194, 231, 195, 159, 101, 180, 133, 80, 21, 185, 220, 195, 115, 144, 12, 90, 150,
-            44, 117, 8, 156, 168, 248, 206, 41, 60, 82, 67, 75, 57, 67,
+            87, 171, 197, 88, 121, 45, 172, 246, 199, 124, 7, 158, 175, 213, 157, 52, 198, 140,
+            128, 203, 111, 158, 211, 165, 164, 138, 26, 202, 81, 211, 63, 13,
         ],
         [
-            212, 205, 171, 153, 113, 16, 194, 241, 224, 43, 177, 110, 190, 248, 22, 201, 208, 166,
-            2, 83, 134, 130, 85, 129, 166, 136, 185, 191, 163, 38, 54, 10,
+            54, 82, 25, 91, 83, 154, 88, 235, 123, 95, 125, 35, 207, 239, 125, 124, 57, 168, 129,
+            51, 17, 171, 235, 202, 241, 51, 150, 81, 49, 96, 196, 128,
         ],
         [
-            8, 60, 190, 39, 153, 222, 119, 23, 142, 237, 12, 110, 146, 9, 19, 219, 143, 64, 161,
-            99, 199, 77, 39, 148, 70, 213, 246, 227, 150, 178, 237, 178,
+            223, 188, 125, 105, 114, 41, 100, 194, 109, 67, 142, 180, 243, 169, 97, 142, 14, 147,
+            235, 33, 61, 193, 199, 59, 207, 194, 156, 2, 239, 235, 186, 136,
         ],
         [
-            11, 114, 217, 160, 101, 37, 100, 220, 56, 114, 42, 31, 138, 33, 84, 157, 214, 167, 73,
-            233, 115, 81, 124, 134, 15, 31, 181, 60, 184, 130, 175, 159,
+            123, 93, 20, 237, 54, 29, 215, 124, 201, 178, 210, 191, 253, 209, 130, 5, 201, 204, 11,
+            99, 152, 164, 156, 22, 32, 131, 83, 21, 10, 115, 182, 141,
         ],
         [
-            141, 238, 235, 202, 241, 32, 210, 10, 127, 230, 54, 31, 146, 80, 247, 9, 107, 124, 0,
-            26, 203, 16, 237, 34, 214, 147, 133, 15, 29, 236, 37, 88,
+            182, 51, 176, 127, 54, 186, 46, 67, 167, 18, 106, 48, 155, 79, 138, 102, 170, 46, 39,
+            233, 35, 201, 254, 216, 172, 227, 5, 218, 8, 182, 130, 141,
         ],
     ];
 
@@ -497,3 +553,25 @@ fn test_serialization_consistency() {
         p = p + &gen;
     }
 }
+
+#[test]
+fn test_d2_is_double_d() {
+    assert_eq!(EDWARDS_D2, EDWARDS_D.double());
+}
+
+#[test]
+fn test_generator() {
+    assert!(GENERATOR.is_on_curve_vartime());
+    assert!(GENERATOR.is_small_order().unwrap_u8() == 0);
+    assert!(
+        ExtendedPoint::from(GENERATOR)
+            .multiply(&FR_MODULUS_BYTES)
+            .is_identity()
+            .unwrap_u8()
+            == 1
+    );
+    assert_eq!(
+        ExtendedPoint::from(GENERATOR) * -Fr::one(),
+        -ExtendedPoint::from(GENERATOR)
+    );
+}
@@ -0,0 +1,193 @@
+//! Multiscalar multiplication, which computes `sum_i scalars[i] * points[i]`
+//! with the doublings shared across all terms.
+
+use subtle::{Choice, ConditionallySelectable};
+
+use crate::{ExtendedNielsPoint, ExtendedPoint, Fr};
+
+/// The number of terms processed together; each chunk needs its own
+/// sequence of doublings, but no heap allocation is required.
+const CHUNK_SIZE: usize = 16;
+
+/// The width of the non-adjacent form used by the variable time
+/// multiscalar multiplication.
+const NAF_WIDTH: usize = 5;
+
+/// Computes `sum_i scalars[i] * points[i]`. This operates in constant
+/// time with respect to the scalars and points.
+///
+/// # Panics
+///
+/// Panics if `scalars` and `points` have different lengths.
+pub fn multiscalar_mul(scalars: &[Fr], points: &[ExtendedPoint]) -> ExtendedPoint {
+    assert_eq!(scalars.len(), points.len());
+
+    let zero = ExtendedNielsPoint::identity();
+    let mut res = ExtendedPoint::identity();
+
+    for (scalars, points) in scalars.chunks(CHUNK_SIZE).zip(points.chunks(CHUNK_SIZE)) {
+        let mut bytes = [[0u8; 32]; CHUNK_SIZE];
+        let mut bases = [zero; CHUNK_SIZE];
+        for ((b, base), (s, p)) in bytes
+            .iter_mut()
+            .zip(bases.iter_mut())
+            .zip(scalars.iter().zip(points.iter()))
+        {
+            *b = s.into_bytes();
+            *base = p.to_niels();
+        }
+
+        // This is the same double-and-add as `ExtendedPoint::multiply`,
+        // interleaved across the chunk. The leading four bits are always
+        // unset for Fr.
+        let mut acc = ExtendedPoint::identity();
+        for i in (0..252).rev() {
+            acc = acc.double();
+            for (b, base) in bytes.iter().zip(bases.iter()).take(scalars.len()) {
+                let bit = Choice::from((b[i / 8] >> (i % 8)) & 1);
+                acc += ExtendedNielsPoint::conditional_select(&zero, base, bit);
+            }
+        }
+
+        res += acc;
+    }
+
+    res
+}
+
+/// Computes the width-`w` non-adjacent form of a scalar, given as the
+/// little-endian bytes of an integer below `2^255`.
+fn non_adjacent_form(bytes: &[u8; 32], w: usize) -> [i8; 256] {
+    let mut x = [0u64; 5];
+    for (limb, chunk) in x.iter_mut().zip(bytes.chunks(8)) {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(buf);
+    }
+
+    let width = 1u64 << w;
+    let window_mask = width - 1;
+
+    let mut naf = [0i8; 256];
+    let mut pos = 0;
+    let mut carry = 0;
+    while pos < 256 {
+        let limb = pos / 64;
+        let bit = pos % 64;
+        let bit_buf = if bit < 64 - w {
+            x[limb] >> bit
+        } else {
+            (x[limb] >> bit) | (x[limb + 1] << (64 - bit))
+        };
+
+        let window = carry + (bit_buf & window_mask);
+        if window & 1 == 0 {
+            pos += 1;
+            continue;
+        }
+
+        if window < width / 2 {
+            carry = 0;
+            naf[pos] = window as i8;
+        } else {
+            carry = 1;
+            naf[pos] = (window as i8).wrapping_sub(width as i8);
+        }
+        pos += w;
+    }
+
+    naf
+}
+
+/// Computes `sum_i scalars[i] * points[i]`.
+///
+/// **This operation is variable time with respect to the scalars and
+/// points.** It should only be used when they are all public.
+///
+/// # Panics
+///
+/// Panics if `scalars` and `points` have different lengths.
+pub fn multiscalar_mul_vartime(scalars: &[Fr], points: &[ExtendedPoint]) -> ExtendedPoint {
+    assert_eq!(scalars.len(), points.len());
+
+    const TABLE_SIZE: usize = 1 << (NAF_WIDTH - 2);
+    let mut res = ExtendedPoint::identity();
+
+    for (scalars, points) in scalars.chunks(CHUNK_SIZE).zip(points.chunks(CHUNK_SIZE)) {
+        let mut nafs = [[0i8; 256]; CHUNK_SIZE];
+        // tables[j][k] = (2k + 1) * points[j]
+        let mut tables = [[ExtendedNielsPoint::identity(); TABLE_SIZE]; CHUNK_SIZE];
+        for ((naf, table), (s, p)) in nafs
+            .iter_mut()
+            .zip(tables.iter_mut())
+            .zip(scalars.iter().zip(points.iter()))
+        {
+            *naf = non_adjacent_form(&s.into_bytes(), NAF_WIDTH);
+
+            let double = p.double().to_niels();
+            let mut acc = *p;
+            table[0] = acc.to_niels();
+            for entry in table.iter_mut().skip(1) {
+                acc += double;
+                *entry = acc.to_niels();
+            }
+        }
+
+        let top = nafs
+            .iter()
+            .take(scalars.len())
+            .filter_map(|naf| naf.iter().rposition(|d| *d != 0))
+            .max();
+
+        let mut acc = ExtendedPoint::identity();
+        if let Some(top) = top {
+            for i in (0..=top).rev() {
+                acc = acc.double();
+                for (naf, table) in nafs.iter().zip(tables.iter()).take(scalars.len()) {
+                    let digit = naf[i];
+                    if digit > 0 {
+                        acc += table[(digit / 2) as usize];
+                    } else if digit < 0 {
+                        acc -= table[(-digit / 2) as usize];
+                    }
+                }
+            }
+        }
+
+        res += acc;
+    }
+
+    res
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_multiscalar_mul() {
+    let mut rng = crate::test_rng();
+    let g = ExtendedPoint::from(crate::GENERATOR);
+
+    for n in [0, 1, 2, 15, 16, 17, 40].iter() {
+        let scalars: std::vec::Vec<Fr> = (0..*n).map(|_| Fr::random(&mut rng)).collect();
+        let points: std::vec::Vec<ExtendedPoint> =
+            (0..*n).map(|_| g * Fr::random(&mut rng)).collect();
+
+        let expected = scalars
+            .iter()
+            .zip(points.iter())
+            .fold(ExtendedPoint::identity(), |acc, (s, p)| acc + p * s);
+
+        assert_eq!(multiscalar_mul(&scalars, &points), expected);
+        assert_eq!(multiscalar_mul_vartime(&scalars, &points), expected);
+    }
+}
+
+#[test]
+fn test_multiscalar_mul_edge_cases() {
+    let g = ExtendedPoint::from(crate::GENERATOR);
+    let scalars = [Fr::zero(), Fr::one(), -Fr::one(), Fr::from(31)];
+    let points = [g, g.double(), g, ExtendedPoint::identity()];
+
+    let expected = g * -Fr::one() + g.double();
+    assert_eq!(multiscalar_mul(&scalars, &points), expected);
+    assert_eq!(multiscalar_mul_vartime(&scalars, &points), expected);
+}
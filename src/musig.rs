@@ -0,0 +1,575 @@
+//! This module implements MuSig2 multi-signatures over the prime order
+//! subgroup of Doppio, in the style of BIP 327, producing signatures that
+//! are accepted by the single-signer verifier in the `schnorr` module.
+//!
+//! # Key aggregation
+//!
+//! For the ordered list of public keys `P_1, ..., P_n`, let
+//! `L = H_list(P_1 || ... || P_n)`. The coefficient of `P_i` is
+//! `a_i = H_coef(L || P_i)`, except that the first key differing from
+//! `P_1` gets the coefficient `1`. The aggregate key is
+//! `Q = sum_i a_i * P_i`, which can then be tweaked by adding `t * G`
+//! for public scalars `t`.
+//!
+//! # Signing
+//!
+//! Each signer samples two nonces `k_1, k_2` and publishes
+//! `R_1 = k_1 * G, R_2 = k_2 * G`. Once all public nonces are known, the
+//! aggregate nonce `(sum R_1, sum R_2)` determines the coefficient
+//! `b = H_non(aggnonce || Q || msg)` and the nonce `R = R_1 + b * R_2`
+//! (or `G` if this is the identity), and with the challenge `c` of the
+//! `schnorr` module each signer outputs the partial signature
+//! `s_i = k_1 + b * k_2 + c * a_i * x_i`. The signature is `(R, s)` with
+//! `s = sum_i s_i + c * t` for the accumulated tweak `t`.
+//!
+//! Hashes are SHA-512 over `"DOPPIO-MUSIG2-SHA512-v1"` followed by a
+//! tag and the input, reduced as little-endian integers, and points are
+//! encoded with [`AffinePoint::into_bytes`].
+//!
+//! # State machine
+//!
+//! Signing is driven by [`FirstRound`] and [`SecondRound`]. Both are
+//! consumed when the round is finalized and neither can be cloned, so the
+//! secret nonces of a session are used for at most one partial signature.
+
+use core::fmt;
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_scalar;
+use crate::schnorr::{self, nonce_generate, Signature, SigningKey, VerifyingKey};
+use crate::{multiscalar_mul_vartime, AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The context string prefixed to every hash computed by this module.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-MUSIG2-SHA512-v1";
+
+/// The aggregate key of an ordered list of public keys, along with the
+/// key aggregation coefficients and any tweaks applied to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyAggContext {
+    public_keys: Vec<VerifyingKey>,
+    coefficients: Vec<Fr>,
+    aggregate_key: AffinePoint,
+    tweak: Fr,
+}
+
+impl KeyAggContext {
+    /// Aggregates the ordered list `public_keys`. This returns `None` if
+    /// the list is empty or the aggregate key is of small order.
+    pub fn new(public_keys: &[VerifyingKey]) -> Option<KeyAggContext> {
+        let first = public_keys.first()?;
+
+        let encoded: Vec<[u8; 32]> = public_keys.iter().map(|pk| pk.into_bytes()).collect();
+        let mut list = Vec::with_capacity(encoded.len() + 2);
+        list.push(CONTEXT_STRING);
+        list.push(b"keyagg list" as &[u8]);
+        list.extend(encoded.iter().map(|pk| &pk[..]));
+        let list_hash = hash_to_scalar(&list).into_bytes();
+
+        let second = public_keys.iter().find(|pk| *pk != first);
+        let coefficients: Vec<Fr> = public_keys
+            .iter()
+            .zip(encoded.iter())
+            .map(|(pk, encoded)| {
+                if Some(pk) == second {
+                    Fr::one()
+                } else {
+                    hash_to_scalar(&[CONTEXT_STRING, b"keyagg coef", &list_hash, encoded])
+                }
+            })
+            .collect();
+
+        let points: Vec<ExtendedPoint> = public_keys
+            .iter()
+            .map(|pk| ExtendedPoint::from(pk.to_point()))
+            .collect();
+        let aggregate_key = AffinePoint::from(multiscalar_mul_vartime(&coefficients, &points));
+        if bool::from(aggregate_key.is_small_order()) {
+            return None;
+        }
+
+        Some(KeyAggContext {
+            public_keys: public_keys.to_vec(),
+            coefficients,
+            aggregate_key,
+            tweak: Fr::zero(),
+        })
+    }
+
+    /// Returns a context whose aggregate key is tweaked by adding
+    /// `tweak * G`. This returns `None` if the tweaked key is of small
+    /// order.
+    pub fn tweak(&self, tweak: &Fr) -> Option<KeyAggContext> {
+        let aggregate_key = AffinePoint::from(
+            ExtendedPoint::from(self.aggregate_key) + ExtendedPoint::from(GENERATOR) * tweak,
+        );
+        if bool::from(aggregate_key.is_small_order()) {
+            return None;
+        }
+
+        Some(KeyAggContext {
+            public_keys: self.public_keys.clone(),
+            coefficients: self.coefficients.clone(),
+            aggregate_key,
+            tweak: self.tweak + tweak,
+        })
+    }
+
+    /// Returns the ordered list of public keys.
+    pub fn public_keys(&self) -> &[VerifyingKey] {
+        &self.public_keys
+    }
+
+    /// Returns the (possibly tweaked) aggregate key, under which the
+    /// resulting signatures verify.
+    pub fn aggregate_key(&self) -> VerifyingKey {
+        VerifyingKey::from_point(self.aggregate_key)
+    }
+
+    /// Returns the key aggregation coefficient of the signer at `index`.
+    pub fn coefficient(&self, index: usize) -> Option<Fr> {
+        self.coefficients.get(index).copied()
+    }
+}
+
+/// The public nonces `(R_1, R_2)` of a signer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PublicNonce {
+    r1: AffinePoint,
+    r2: AffinePoint,
+}
+
+impl PublicNonce {
+    /// Attempts to interpret the 64-byte representation `R_1 || R_2` of a
+    /// public nonce, failing if either point is not canonically encoded or
+    /// is of small order.
+    pub fn from_bytes(bytes: [u8; 64]) -> CtOption<PublicNonce> {
+        let mut r1 = [0u8; 32];
+        let mut r2 = [0u8; 32];
+        r1.copy_from_slice(&bytes[..32]);
+        r2.copy_from_slice(&bytes[32..]);
+
+        let r1 = AffinePoint::deserialize_element(r1);
+        let r2 = AffinePoint::deserialize_element(r2);
+        let is_some = r1.is_some() & r2.is_some();
+
+        CtOption::new(
+            PublicNonce {
+                r1: r1.unwrap_or(AffinePoint::identity()),
+                r2: r2.unwrap_or(AffinePoint::identity()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this public nonce into its 64-byte representation.
+    pub fn into_bytes(&self) -> [u8; 64] {
+        let mut res = [0u8; 64];
+        res[..32].copy_from_slice(&self.r1.into_bytes());
+        res[32..].copy_from_slice(&self.r2.into_bytes());
+
+        res
+    }
+}
+
+/// A signer's partial signature `s_i`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PartialSignature(Fr);
+
+impl PartialSignature {
+    /// Attempts to interpret the byte representation of a partial
+    /// signature, failing if it is not a canonical encoding of an element
+    /// of `Fr`.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<PartialSignature> {
+        let s = Fr::from_bytes(bytes);
+        let is_some = s.is_some();
+
+        CtOption::new(PartialSignature(s.unwrap_or(Fr::zero())), is_some)
+    }
+
+    /// Converts this partial signature into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+/// The values shared by every signer once all public nonces are known.
+#[derive(Clone, Debug)]
+struct Session {
+    nonce_coefficient: Fr,
+    r: AffinePoint,
+    challenge: Fr,
+}
+
+impl Session {
+    fn new(ctx: &KeyAggContext, nonces: &[PublicNonce], message: &[u8]) -> Session {
+        let mut r1 = ExtendedPoint::identity();
+        let mut r2 = ExtendedPoint::identity();
+        for nonce in nonces {
+            r1 += nonce.r1;
+            r2 += nonce.r2;
+        }
+        let (r1, r2) = (AffinePoint::from(r1), AffinePoint::from(r2));
+
+        let nonce_coefficient = hash_to_scalar(&[
+            CONTEXT_STRING,
+            b"noncecoef",
+            &r1.into_bytes(),
+            &r2.into_bytes(),
+            &ctx.aggregate_key.into_bytes(),
+            message,
+        ]);
+
+        let r = ExtendedPoint::from(r1) + ExtendedPoint::from(r2) * nonce_coefficient;
+        let r = if bool::from(r.is_identity()) {
+            GENERATOR
+        } else {
+            AffinePoint::from(r)
+        };
+        let challenge = schnorr::challenge(&r, &ctx.aggregate_key, message);
+
+        Session {
+            nonce_coefficient,
+            r,
+            challenge,
+        }
+    }
+
+    /// Checks `s * G = R_1 + b * R_2 + c * a * P`, multiplied by the
+    /// cofactor.
+    fn verify(
+        &self,
+        ctx: &KeyAggContext,
+        index: usize,
+        nonce: &PublicNonce,
+        signature: &PartialSignature,
+    ) -> bool {
+        let lhs = ExtendedPoint::from(GENERATOR) * signature.0;
+        let rhs = multiscalar_mul_vartime(
+            &[
+                Fr::one(),
+                self.nonce_coefficient,
+                self.challenge * ctx.coefficients[index],
+            ],
+            &[
+                ExtendedPoint::from(nonce.r1),
+                ExtendedPoint::from(nonce.r2),
+                ExtendedPoint::from(ctx.public_keys[index].to_point()),
+            ],
+        );
+
+        bool::from((lhs - rhs).mul_by_cofactor().is_identity())
+    }
+}
+
+/// The first round of a signing session, in which signers exchange
+/// public nonces.
+pub struct FirstRound {
+    ctx: KeyAggContext,
+    index: usize,
+    k1: Fr,
+    k2: Fr,
+    nonces: Vec<Option<PublicNonce>>,
+}
+
+impl fmt::Debug for FirstRound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FirstRound")
+            .field("index", &self.index)
+            .field("nonces", &self.nonces)
+            .finish()
+    }
+}
+
+impl Drop for FirstRound {
+    fn drop(&mut self) {
+        self.k1.zeroize();
+        self.k2.zeroize();
+    }
+}
+
+impl FirstRound {
+    /// Starts a signing session for the signer at `index` in `ctx`, whose
+    /// signing key is `signing_key`. This returns `None` if `index` is out
+    /// of range or `signing_key` does not match the public key at `index`.
+    pub fn new<R: RngCore + CryptoRng>(
+        ctx: &KeyAggContext,
+        index: usize,
+        signing_key: &SigningKey,
+        rng: &mut R,
+    ) -> Option<FirstRound> {
+        if ctx.public_keys.get(index)? != &signing_key.verifying_key() {
+            return None;
+        }
+
+        let k1 = nonce_generate(CONTEXT_STRING, signing_key.scalar(), rng);
+        let k2 = nonce_generate(CONTEXT_STRING, signing_key.scalar(), rng);
+        let mut nonces = vec![None; ctx.public_keys.len()];
+        nonces[index] = Some(PublicNonce {
+            r1: AffinePoint::from(ExtendedPoint::from(GENERATOR) * k1),
+            r2: AffinePoint::from(ExtendedPoint::from(GENERATOR) * k2),
+        });
+
+        Some(FirstRound {
+            ctx: ctx.clone(),
+            index,
+            k1,
+            k2,
+            nonces,
+        })
+    }
+
+    /// Returns this signer's public nonce, to be sent to the other signers.
+    pub fn our_public_nonce(&self) -> PublicNonce {
+        self.nonces[self.index].unwrap()
+    }
+
+    /// Records the public nonce of the signer at `index`. This returns
+    /// `false` if `index` is out of range, is this signer's own index, or
+    /// already has a different nonce.
+    pub fn receive_nonce(&mut self, index: usize, nonce: PublicNonce) -> bool {
+        if index == self.index {
+            return false;
+        }
+
+        match self.nonces.get_mut(index) {
+            Some(slot @ None) => {
+                *slot = Some(nonce);
+                true
+            }
+            Some(Some(existing)) => *existing == nonce,
+            None => false,
+        }
+    }
+
+    /// Returns whether the public nonces of all signers are known.
+    pub fn is_complete(&self) -> bool {
+        self.nonces.iter().all(|n| n.is_some())
+    }
+
+    /// Finishes the first round by producing this signer's partial
+    /// signature on `message`, consuming the secret nonces. This returns
+    /// `None` if a public nonce is missing or `signing_key` is not the key
+    /// this session was started with.
+    pub fn finalize(self, signing_key: &SigningKey, message: &[u8]) -> Option<SecondRound> {
+        if self.ctx.public_keys[self.index] != signing_key.verifying_key() {
+            return None;
+        }
+        let nonces: Vec<PublicNonce> = self.nonces.iter().copied().collect::<Option<_>>()?;
+
+        let session = Session::new(&self.ctx, &nonces, message);
+        let ours = PartialSignature(
+            self.k1
+                + self.k2 * session.nonce_coefficient
+                + session.challenge * self.ctx.coefficients[self.index] * signing_key.scalar(),
+        );
+
+        let mut signatures = vec![None; nonces.len()];
+        signatures[self.index] = Some(ours);
+
+        Some(SecondRound {
+            ctx: self.ctx.clone(),
+            index: self.index,
+            nonces,
+            session,
+            signatures,
+        })
+    }
+}
+
+/// The second round of a signing session, in which signers exchange
+/// partial signatures.
+#[derive(Debug)]
+pub struct SecondRound {
+    ctx: KeyAggContext,
+    index: usize,
+    nonces: Vec<PublicNonce>,
+    session: Session,
+    signatures: Vec<Option<PartialSignature>>,
+}
+
+impl SecondRound {
+    /// Returns this signer's partial signature, to be sent to the other
+    /// signers or to an aggregator.
+    pub fn our_signature(&self) -> PartialSignature {
+        self.signatures[self.index].unwrap()
+    }
+
+    /// Verifies and records the partial signature of the signer at
+    /// `index`. This returns `false` if `index` is out of range or the
+    /// partial signature is invalid.
+    pub fn receive_signature(&mut self, index: usize, signature: PartialSignature) -> bool {
+        if index >= self.signatures.len()
+            || !self
+                .session
+                .verify(&self.ctx, index, &self.nonces[index], &signature)
+        {
+            return false;
+        }
+
+        self.signatures[index] = Some(signature);
+        true
+    }
+
+    /// Returns whether the partial signatures of all signers are known.
+    pub fn is_complete(&self) -> bool {
+        self.signatures.iter().all(|s| s.is_some())
+    }
+
+    /// Aggregates the partial signatures into a signature under the
+    /// aggregate key. This returns `None` if a partial signature is
+    /// missing.
+    pub fn finalize(self) -> Option<Signature> {
+        let s = self
+            .signatures
+            .iter()
+            .try_fold(Fr::zero(), |acc, s| s.map(|s| acc + s.0))?;
+
+        Some(Signature::from_parts(
+            self.session.r,
+            s + self.session.challenge * self.ctx.tweak,
+        ))
+    }
+}
+
+impl ConstantTimeEq for PartialSignature {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+#[cfg(test)]
+fn run_session(
+    ctx: &KeyAggContext,
+    keys: &[SigningKey],
+    message: &[u8],
+    rng: &mut rand_chacha::ChaChaRng,
+) -> Signature {
+    let mut first: Vec<FirstRound> = keys
+        .iter()
+        .enumerate()
+        .map(|(i, sk)| FirstRound::new(ctx, i, sk, &mut *rng).unwrap())
+        .collect();
+    let nonces: Vec<PublicNonce> = first.iter().map(|r| r.our_public_nonce()).collect();
+    for round in first.iter_mut() {
+        for (j, nonce) in nonces.iter().enumerate() {
+            if j != round.index {
+                assert!(round.receive_nonce(j, *nonce));
+            }
+        }
+        assert!(round.is_complete());
+    }
+
+    let mut second: Vec<SecondRound> = first
+        .into_iter()
+        .zip(keys.iter())
+        .map(|(round, sk)| round.finalize(sk, message).unwrap())
+        .collect();
+    let signatures: Vec<PartialSignature> = second.iter().map(|r| r.our_signature()).collect();
+    for round in second.iter_mut() {
+        for (j, signature) in signatures.iter().enumerate() {
+            if j != round.index {
+                assert!(round.receive_signature(j, *signature));
+            }
+        }
+    }
+
+    let signatures: Vec<Signature> = second
+        .into_iter()
+        .map(|round| round.finalize().unwrap())
+        .collect();
+    assert!(signatures.windows(2).all(|w| w[0] == w[1]));
+
+    signatures[0]
+}
+
+#[test]
+fn test_key_aggregation() {
+    let mut rng = crate::test_rng();
+    let keys: Vec<VerifyingKey> = (0..3)
+        .map(|_| SigningKey::new(&mut rng).verifying_key())
+        .collect();
+
+    let ctx = KeyAggContext::new(&keys).unwrap();
+    assert_eq!(ctx.coefficient(1), Some(Fr::one()));
+    assert!(ctx.coefficient(0) != Some(Fr::one()));
+    assert_eq!(KeyAggContext::new(&keys).unwrap(), ctx);
+
+    let reordered = [keys[1], keys[0], keys[2]];
+    assert!(KeyAggContext::new(&reordered).unwrap().aggregate_key() != ctx.aggregate_key());
+    assert!(KeyAggContext::new(&[]).is_none());
+}
+
+#[test]
+fn test_sign() {
+    let mut rng = crate::test_rng();
+    let keys: Vec<SigningKey> = (0..3).map(|_| SigningKey::new(&mut rng)).collect();
+    let public_keys: Vec<VerifyingKey> = keys.iter().map(|sk| sk.verifying_key()).collect();
+    let ctx = KeyAggContext::new(&public_keys).unwrap();
+
+    let signature = run_session(&ctx, &keys, b"message", &mut rng);
+    assert!(ctx.aggregate_key().verify(b"message", &signature));
+    assert!(!ctx.aggregate_key().verify(b"other message", &signature));
+}
+
+#[test]
+fn test_sign_tweaked() {
+    let mut rng = crate::test_rng();
+    let keys: Vec<SigningKey> = (0..2).map(|_| SigningKey::new(&mut rng)).collect();
+    let public_keys: Vec<VerifyingKey> = keys.iter().map(|sk| sk.verifying_key()).collect();
+    let ctx = KeyAggContext::new(&public_keys)
+        .unwrap()
+        .tweak(&Fr::random(&mut rng))
+        .unwrap();
+
+    let signature = run_session(&ctx, &keys, b"message", &mut rng);
+    assert!(ctx.aggregate_key().verify(b"message", &signature));
+}
+
+#[test]
+fn test_invalid_partial_signature() {
+    let mut rng = crate::test_rng();
+    let keys: Vec<SigningKey> = (0..2).map(|_| SigningKey::new(&mut rng)).collect();
+    let public_keys: Vec<VerifyingKey> = keys.iter().map(|sk| sk.verifying_key()).collect();
+    let ctx = KeyAggContext::new(&public_keys).unwrap();
+
+    assert!(FirstRound::new(&ctx, 0, &keys[1], &mut rng).is_none());
+    assert!(FirstRound::new(&ctx, 2, &keys[0], &mut rng).is_none());
+
+    let mut a = FirstRound::new(&ctx, 0, &keys[0], &mut rng).unwrap();
+    let b = FirstRound::new(&ctx, 1, &keys[1], &mut rng).unwrap();
+    assert!(!a.receive_nonce(0, b.our_public_nonce()));
+    assert!(a.receive_nonce(1, b.our_public_nonce()));
+    assert!(!a.receive_nonce(1, a.our_public_nonce()));
+
+    let mut a = a.finalize(&keys[0], b"message").unwrap();
+    let forged = PartialSignature(a.our_signature().0 + Fr::one());
+    assert!(!a.receive_signature(1, forged));
+    assert!(!a.receive_signature(5, forged));
+    assert!(!a.is_complete());
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let ctx = KeyAggContext::new(&[sk.verifying_key()]).unwrap();
+    let round = FirstRound::new(&ctx, 0, &sk, &mut rng).unwrap();
+
+    let nonce = round.our_public_nonce();
+    assert_eq!(PublicNonce::from_bytes(nonce.into_bytes()).unwrap(), nonce);
+
+    let mut bad = nonce.into_bytes();
+    bad[32..].copy_from_slice(&AffinePoint::identity().into_bytes());
+    assert!(PublicNonce::from_bytes(bad).is_none().unwrap_u8() == 1);
+
+    let round = round.finalize(&sk, b"message").unwrap();
+    let signature = round.our_signature();
+    assert_eq!(
+        PartialSignature::from_bytes(signature.into_bytes()).unwrap(),
+        signature
+    );
+}
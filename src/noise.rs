@@ -0,0 +1,961 @@
+//! This module implements the handshakes of the Noise protocol framework
+//! (revision 34) with Diffie-Hellman over the prime order subgroup of
+//! Doppio, for the `XX` and `IK` patterns of the suites
+//! `Noise_XX_Doppio_ChaChaPoly_BLAKE2s` and
+//! `Noise_IK_Doppio_ChaChaPoly_BLAKE2s`.
+//!
+//! The `DH` function is the cofactored key agreement of
+//! [`ecdh`](crate::ecdh), so `DHLEN = 32` and public keys are encoded
+//! with [`PublicKey::into_bytes`](crate::ecdh::PublicKey::into_bytes). A
+//! key agreement with a public key of small order fails, which aborts
+//! the handshake. Static and ephemeral keys are both [`StaticSecret`]s.
+//!
+//! A [`HandshakeState`] writes and reads the handshake messages in turn,
+//! each carrying a payload, and is then split into the two
+//! [`CipherState`]s that encrypt the transport messages. Any failure
+//! while writing or reading a handshake message leaves the handshake in
+//! an unusable state, so it must then be aborted.
+//!
+//! The symmetric state is tested against the cacophony vector of
+//! `Noise_XX_25519_ChaChaPoly_BLAKE2s`, while the handshake vectors over
+//! Doppio are self-generated.
+
+use core::fmt;
+use std::vec::Vec;
+
+use blake2::{Blake2s256, Digest};
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce, Tag};
+use hkdf::SimpleHkdf;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::ecdh::{PublicKey, StaticSecret};
+
+/// The size in bytes of an encoded public key.
+pub const DH_LEN: usize = 32;
+
+/// The size in bytes of the output of the hash function.
+pub const HASH_LEN: usize = 32;
+
+/// The size in bytes of a cipher key.
+const KEY_SIZE: usize = 32;
+
+/// The size in bytes of an encoded nonce.
+const NONCE_SIZE: usize = 12;
+
+/// The size in bytes of an authentication tag.
+const TAG_SIZE: usize = 16;
+
+/// The maximum size in bytes of a Noise message.
+pub const MAX_MESSAGE_LEN: usize = 65535;
+
+/// A handshake pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandshakePattern {
+    /// The `XX` pattern, in which both parties transmit their static
+    /// keys during the handshake.
+    ///
+    /// ```text
+    /// -> e
+    /// <- e, ee, s, es
+    /// -> s, se
+    /// ```
+    XX,
+    /// The `IK` pattern, in which the initiator knows the responder's
+    /// static key in advance and transmits its own immediately.
+    ///
+    /// ```text
+    /// <- s
+    /// ...
+    /// -> e, es, s, ss
+    /// <- e, ee, se
+    /// ```
+    IK,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    E,
+    S,
+    EE,
+    ES,
+    SE,
+    SS,
+}
+
+impl HandshakePattern {
+    /// Returns the protocol name of this pattern.
+    pub fn protocol_name(&self) -> &'static str {
+        match self {
+            HandshakePattern::XX => "Noise_XX_Doppio_ChaChaPoly_BLAKE2s",
+            HandshakePattern::IK => "Noise_IK_Doppio_ChaChaPoly_BLAKE2s",
+        }
+    }
+
+    fn messages(&self) -> &'static [&'static [Token]] {
+        use Token::*;
+
+        match self {
+            HandshakePattern::XX => &[&[E], &[E, EE, S, ES], &[S, SE]],
+            HandshakePattern::IK => &[&[E, ES, S, SS], &[E, EE, SE]],
+        }
+    }
+
+    /// Returns whether the responder's static key is known to the
+    /// initiator before the handshake.
+    fn has_responder_premessage(&self) -> bool {
+        *self == HandshakePattern::IK
+    }
+}
+
+/// Computes `HASH` of the concatenation of `parts`.
+fn hash(parts: &[&[u8]]) -> [u8; HASH_LEN] {
+    let mut hasher = Blake2s256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+
+    hasher.finalize().into()
+}
+
+/// Computes `HKDF(chaining_key, ikm, 2)`.
+fn hkdf(chaining_key: &[u8; HASH_LEN], ikm: &[u8]) -> ([u8; HASH_LEN], [u8; HASH_LEN]) {
+    let mut okm = [0u8; 2 * HASH_LEN];
+    SimpleHkdf::<Blake2s256>::new(Some(chaining_key), ikm)
+        .expand(&[], &mut okm)
+        .unwrap();
+
+    let mut output1 = [0u8; HASH_LEN];
+    let mut output2 = [0u8; HASH_LEN];
+    output1.copy_from_slice(&okm[..HASH_LEN]);
+    output2.copy_from_slice(&okm[HASH_LEN..]);
+    okm.zeroize();
+
+    (output1, output2)
+}
+
+/// The key and nonce that encrypt messages in one direction.
+#[derive(Clone)]
+pub struct CipherState {
+    key: Option<[u8; KEY_SIZE]>,
+    nonce: u64,
+}
+
+impl CipherState {
+    fn new(key: Option<[u8; KEY_SIZE]>) -> CipherState {
+        CipherState { key, nonce: 0 }
+    }
+
+    /// Encodes `nonce` as 32 zero bits followed by its 64-bit little
+    /// endian encoding.
+    fn encode_nonce(nonce: u64) -> [u8; NONCE_SIZE] {
+        let mut res = [0u8; NONCE_SIZE];
+        res[4..].copy_from_slice(&nonce.to_le_bytes());
+
+        res
+    }
+
+    /// Encrypts `plaintext` with the associated data `ad`, or returns it
+    /// unchanged if no key has been set yet. This returns `None` if the
+    /// nonce is exhausted.
+    pub fn encrypt_with_ad(&mut self, ad: &[u8], plaintext: &[u8]) -> Option<Vec<u8>> {
+        let key = match &self.key {
+            Some(key) => key,
+            None => return Some(plaintext.to_vec()),
+        };
+        // The maximum nonce is reserved for rekeying.
+        if self.nonce == u64::MAX {
+            return None;
+        }
+
+        let mut buffer = plaintext.to_vec();
+        let tag = ChaCha20Poly1305::new(Key::from_slice(key))
+            .encrypt_in_place_detached(
+                Nonce::from_slice(&CipherState::encode_nonce(self.nonce)),
+                ad,
+                &mut buffer,
+            )
+            .ok()?;
+        buffer.extend_from_slice(&tag);
+        self.nonce += 1;
+
+        Some(buffer)
+    }
+
+    /// Decrypts `ciphertext` with the associated data `ad`, or returns it
+    /// unchanged if no key has been set yet. This returns `None` if the
+    /// ciphertext is not authentic, in which case the nonce is not
+    /// incremented, or if the nonce is exhausted.
+    pub fn decrypt_with_ad(&mut self, ad: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>> {
+        let key = match &self.key {
+            Some(key) => key,
+            None => return Some(ciphertext.to_vec()),
+        };
+        if self.nonce == u64::MAX || ciphertext.len() < TAG_SIZE {
+            return None;
+        }
+
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_SIZE);
+        let mut buffer = ciphertext.to_vec();
+        ChaCha20Poly1305::new(Key::from_slice(key))
+            .decrypt_in_place_detached(
+                Nonce::from_slice(&CipherState::encode_nonce(self.nonce)),
+                ad,
+                &mut buffer,
+                Tag::from_slice(tag),
+            )
+            .ok()?;
+        self.nonce += 1;
+
+        Some(buffer)
+    }
+
+    /// Replaces the key with the first 32 bytes of the encryption of 32
+    /// zero bytes under the maximum nonce, as `REKEY` does. This does
+    /// nothing if no key has been set yet.
+    pub fn rekey(&mut self) {
+        if let Some(key) = &mut self.key {
+            let mut buffer = [0u8; KEY_SIZE];
+            ChaCha20Poly1305::new(Key::from_slice(key))
+                .encrypt_in_place_detached(
+                    Nonce::from_slice(&CipherState::encode_nonce(u64::MAX)),
+                    b"",
+                    &mut buffer,
+                )
+                .unwrap();
+            *key = buffer;
+            buffer.zeroize();
+        }
+    }
+}
+
+impl Drop for CipherState {
+    fn drop(&mut self) {
+        if let Some(key) = &mut self.key {
+            key.zeroize();
+        }
+    }
+}
+
+impl fmt::Debug for CipherState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CipherState")
+            .field("nonce", &self.nonce)
+            .finish()
+    }
+}
+
+/// The chaining key, the handshake hash and the cipher state of a
+/// handshake.
+struct SymmetricState {
+    chaining_key: [u8; HASH_LEN],
+    h: [u8; HASH_LEN],
+    cipher: CipherState,
+}
+
+impl SymmetricState {
+    fn new(protocol_name: &[u8]) -> SymmetricState {
+        let mut h = [0u8; HASH_LEN];
+        if protocol_name.len() <= HASH_LEN {
+            h[..protocol_name.len()].copy_from_slice(protocol_name);
+        } else {
+            h = hash(&[protocol_name]);
+        }
+
+        SymmetricState {
+            chaining_key: h,
+            h,
+            cipher: CipherState::new(None),
+        }
+    }
+
+    fn mix_key(&mut self, ikm: &[u8]) {
+        let (chaining_key, key) = hkdf(&self.chaining_key, ikm);
+        self.chaining_key = chaining_key;
+        self.cipher = CipherState::new(Some(key));
+    }
+
+    fn mix_hash(&mut self, data: &[u8]) {
+        self.h = hash(&[&self.h, data]);
+    }
+
+    fn encrypt_and_hash(&mut self, plaintext: &[u8]) -> Option<Vec<u8>> {
+        let ciphertext = self.cipher.encrypt_with_ad(&self.h, plaintext)?;
+        self.mix_hash(&ciphertext);
+
+        Some(ciphertext)
+    }
+
+    fn decrypt_and_hash(&mut self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        let plaintext = self.cipher.decrypt_with_ad(&self.h, ciphertext)?;
+        self.mix_hash(ciphertext);
+
+        Some(plaintext)
+    }
+
+    fn split(&self) -> (CipherState, CipherState) {
+        let (key1, key2) = hkdf(&self.chaining_key, b"");
+
+        (CipherState::new(Some(key1)), CipherState::new(Some(key2)))
+    }
+}
+
+impl Drop for SymmetricState {
+    fn drop(&mut self) {
+        self.chaining_key.zeroize();
+    }
+}
+
+/// The state of one party during a handshake.
+pub struct HandshakeState {
+    pattern: HandshakePattern,
+    initiator: bool,
+    symmetric: SymmetricState,
+    s: StaticSecret,
+    e: Option<StaticSecret>,
+    rs: Option<PublicKey>,
+    re: Option<PublicKey>,
+    message_index: usize,
+}
+
+impl HandshakeState {
+    fn new(
+        pattern: HandshakePattern,
+        initiator: bool,
+        prologue: &[u8],
+        s: StaticSecret,
+        rs: Option<PublicKey>,
+    ) -> HandshakeState {
+        let mut symmetric = SymmetricState::new(pattern.protocol_name().as_bytes());
+        symmetric.mix_hash(prologue);
+        if pattern.has_responder_premessage() {
+            let responder_static = if initiator {
+                rs.unwrap()
+            } else {
+                PublicKey::from(&s)
+            };
+            symmetric.mix_hash(&responder_static.into_bytes());
+        }
+
+        HandshakeState {
+            pattern,
+            initiator,
+            symmetric,
+            s,
+            e: None,
+            rs,
+            re: None,
+            message_index: 0,
+        }
+    }
+
+    /// Starts a handshake as the initiator with the static key `s` and
+    /// the `prologue`, which both parties must agree on. The responder's
+    /// static key `rs` must be provided exactly when the pattern requires
+    /// it to be known in advance; otherwise this returns `None`.
+    pub fn initiator(
+        pattern: HandshakePattern,
+        prologue: &[u8],
+        s: StaticSecret,
+        rs: Option<PublicKey>,
+    ) -> Option<HandshakeState> {
+        if pattern.has_responder_premessage() != rs.is_some() {
+            return None;
+        }
+
+        Some(HandshakeState::new(pattern, true, prologue, s, rs))
+    }
+
+    /// Starts a handshake as the responder with the static key `s` and
+    /// the `prologue`, which both parties must agree on.
+    pub fn responder(
+        pattern: HandshakePattern,
+        prologue: &[u8],
+        s: StaticSecret,
+    ) -> HandshakeState {
+        HandshakeState::new(pattern, false, prologue, s, None)
+    }
+
+    /// Returns whether it is this party's turn to write a message.
+    fn is_my_turn(&self) -> bool {
+        self.initiator == (self.message_index % 2 == 0)
+    }
+
+    fn tokens(&self) -> Option<&'static [Token]> {
+        self.pattern.messages().get(self.message_index).copied()
+    }
+
+    /// Performs the key agreement of `token` and mixes the result into
+    /// the chaining key, returning `None` if a key is missing or the
+    /// shared point is the identity.
+    fn mix_dh(&mut self, token: Token) -> Option<()> {
+        // The local key comes from the first letter of the token for the
+        // initiator and from the second one for the responder.
+        let (local_is_e, remote_is_e) = match token {
+            Token::EE => (true, true),
+            Token::ES => (self.initiator, !self.initiator),
+            Token::SE => (!self.initiator, self.initiator),
+            Token::SS => (false, false),
+            Token::E | Token::S => unreachable!(),
+        };
+        let local = if local_is_e {
+            self.e.as_ref()?
+        } else {
+            &self.s
+        };
+        let remote = if remote_is_e { self.re? } else { self.rs? };
+
+        let shared = local.diffie_hellman(&remote);
+        if !bool::from(shared.is_some()) {
+            return None;
+        }
+        self.symmetric.mix_key(shared.unwrap().as_bytes());
+
+        Some(())
+    }
+
+    fn write_message_with(&mut self, payload: &[u8], e: Option<StaticSecret>) -> Option<Vec<u8>> {
+        if !self.is_my_turn() {
+            return None;
+        }
+        let tokens = self.tokens()?;
+
+        let mut message = Vec::new();
+        let mut e = e;
+        for token in tokens {
+            match token {
+                Token::E => {
+                    let e = e.take()?;
+                    let public = PublicKey::from(&e).into_bytes();
+                    message.extend_from_slice(&public);
+                    self.symmetric.mix_hash(&public);
+                    self.e = Some(e);
+                }
+                Token::S => {
+                    let public = PublicKey::from(&self.s).into_bytes();
+                    message.extend_from_slice(&self.symmetric.encrypt_and_hash(&public)?);
+                }
+                _ => self.mix_dh(*token)?,
+            }
+        }
+        message.extend_from_slice(&self.symmetric.encrypt_and_hash(payload)?);
+        if message.len() > MAX_MESSAGE_LEN {
+            return None;
+        }
+        self.message_index += 1;
+
+        Some(message)
+    }
+
+    /// Writes the next handshake message carrying `payload`. This returns
+    /// `None` if it is not this party's turn, the handshake is finished,
+    /// a key agreement fails or the message would be too long.
+    pub fn write_message<R: RngCore + CryptoRng>(
+        &mut self,
+        payload: &[u8],
+        rng: &mut R,
+    ) -> Option<Vec<u8>> {
+        let e = match self.tokens() {
+            Some(tokens) if tokens.contains(&Token::E) => Some(StaticSecret::new(rng)),
+            _ => None,
+        };
+
+        self.write_message_with(payload, e)
+    }
+
+    /// Reads the next handshake message, returning its payload. This
+    /// returns `None` if it is not the other party's turn, the handshake
+    /// is finished, the message is malformed or not authentic, or a key
+    /// agreement fails.
+    pub fn read_message(&mut self, message: &[u8]) -> Option<Vec<u8>> {
+        if self.is_my_turn() || message.len() > MAX_MESSAGE_LEN {
+            return None;
+        }
+        let tokens = self.tokens()?;
+
+        let mut message = message;
+        for token in tokens {
+            match token {
+                Token::E => {
+                    if message.len() < DH_LEN {
+                        return None;
+                    }
+                    let (public, rest) = message.split_at(DH_LEN);
+                    self.re = Some(decode_public_key(public)?);
+                    self.symmetric.mix_hash(public);
+                    message = rest;
+                }
+                Token::S => {
+                    let len = if self.symmetric.cipher.key.is_some() {
+                        DH_LEN + TAG_SIZE
+                    } else {
+                        DH_LEN
+                    };
+                    if message.len() < len {
+                        return None;
+                    }
+                    let (ciphertext, rest) = message.split_at(len);
+                    let public = self.symmetric.decrypt_and_hash(ciphertext)?;
+                    self.rs = Some(decode_public_key(&public)?);
+                    message = rest;
+                }
+                _ => self.mix_dh(*token)?,
+            }
+        }
+        let payload = self.symmetric.decrypt_and_hash(message)?;
+        self.message_index += 1;
+
+        Some(payload)
+    }
+
+    /// Returns whether all handshake messages have been written or read.
+    pub fn is_finished(&self) -> bool {
+        self.message_index == self.pattern.messages().len()
+    }
+
+    /// Returns the static key of the other party, if it is known.
+    pub fn remote_static(&self) -> Option<PublicKey> {
+        self.rs
+    }
+
+    /// Returns the handshake hash `h`, which both parties share once the
+    /// handshake is finished and which can be used for channel binding.
+    pub fn handshake_hash(&self) -> [u8; HASH_LEN] {
+        self.symmetric.h
+    }
+
+    /// Splits a finished handshake into the cipher states that encrypt
+    /// the messages sent and received by this party, in this order. This
+    /// returns `None` if the handshake is not finished.
+    pub fn split(self) -> Option<(CipherState, CipherState)> {
+        if !self.is_finished() {
+            return None;
+        }
+
+        let (initiator_to_responder, responder_to_initiator) = self.symmetric.split();
+        if self.initiator {
+            Some((initiator_to_responder, responder_to_initiator))
+        } else {
+            Some((responder_to_initiator, initiator_to_responder))
+        }
+    }
+}
+
+impl fmt::Debug for HandshakeState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HandshakeState")
+            .field("pattern", &self.pattern)
+            .field("initiator", &self.initiator)
+            .field("message_index", &self.message_index)
+            .finish()
+    }
+}
+
+fn decode_public_key(bytes: &[u8]) -> Option<PublicKey> {
+    let mut encoding = [0u8; DH_LEN];
+    encoding.copy_from_slice(bytes);
+    let public = PublicKey::from_bytes(encoding);
+    if bool::from(public.is_some()) {
+        Some(public.unwrap())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+fn handshake(
+    pattern: HandshakePattern,
+    initiator: &mut HandshakeState,
+    responder: &mut HandshakeState,
+) {
+    let mut rng = crate::test_rng();
+    let (mut writer, mut reader) = (initiator, responder);
+    for i in 0..pattern.messages().len() {
+        let payload = [i as u8; 3];
+        let message = writer.write_message(&payload, &mut rng).unwrap();
+        assert_eq!(reader.read_message(&message).unwrap(), payload);
+        core::mem::swap(&mut writer, &mut reader);
+    }
+}
+
+#[test]
+fn test_handshakes() {
+    let mut rng = crate::test_rng();
+    let initiator_static = StaticSecret::new(&mut rng);
+    let responder_static = StaticSecret::new(&mut rng);
+    let initiator_public = PublicKey::from(&initiator_static);
+    let responder_public = PublicKey::from(&responder_static);
+
+    for pattern in [HandshakePattern::XX, HandshakePattern::IK].iter() {
+        let rs = if *pattern == HandshakePattern::IK {
+            Some(responder_public)
+        } else {
+            None
+        };
+        let mut initiator =
+            HandshakeState::initiator(*pattern, b"prologue", initiator_static.clone(), rs).unwrap();
+        let mut responder =
+            HandshakeState::responder(*pattern, b"prologue", responder_static.clone());
+        handshake(*pattern, &mut initiator, &mut responder);
+
+        assert!(initiator.is_finished() && responder.is_finished());
+        assert_eq!(initiator.remote_static(), Some(responder_public));
+        assert_eq!(responder.remote_static(), Some(initiator_public));
+        assert_eq!(initiator.handshake_hash(), responder.handshake_hash());
+        assert!(initiator.write_message(b"", &mut rng).is_none());
+
+        let (mut initiator_send, mut initiator_receive) = initiator.split().unwrap();
+        let (mut responder_send, mut responder_receive) = responder.split().unwrap();
+        for _ in 0..2 {
+            let ciphertext = initiator_send.encrypt_with_ad(b"ad", b"ping").unwrap();
+            assert!(responder_receive
+                .decrypt_with_ad(b"da", &ciphertext)
+                .is_none());
+            assert_eq!(
+                responder_receive
+                    .decrypt_with_ad(b"ad", &ciphertext)
+                    .unwrap(),
+                b"ping"
+            );
+            let ciphertext = responder_send.encrypt_with_ad(b"", b"pong").unwrap();
+            assert_eq!(
+                initiator_receive.decrypt_with_ad(b"", &ciphertext).unwrap(),
+                b"pong"
+            );
+        }
+
+        initiator_send.rekey();
+        let ciphertext = initiator_send.encrypt_with_ad(b"", b"ping").unwrap();
+        assert!(responder_receive
+            .decrypt_with_ad(b"", &ciphertext)
+            .is_none());
+        responder_receive.rekey();
+        assert_eq!(
+            responder_receive.decrypt_with_ad(b"", &ciphertext).unwrap(),
+            b"ping"
+        );
+    }
+}
+
+#[test]
+fn test_handshake_failures() {
+    let mut rng = crate::test_rng();
+    let initiator_static = StaticSecret::new(&mut rng);
+    let responder_static = StaticSecret::new(&mut rng);
+    let responder_public = PublicKey::from(&responder_static);
+    let pattern = HandshakePattern::IK;
+
+    // The responder's static key must be given exactly for `IK`.
+    assert!(HandshakeState::initiator(pattern, b"", initiator_static.clone(), None).is_none());
+    assert!(HandshakeState::initiator(
+        HandshakePattern::XX,
+        b"",
+        initiator_static.clone(),
+        Some(responder_public)
+    )
+    .is_none());
+
+    // Messages must be written in turn, and the handshake must be
+    // finished before it is split.
+    let mut initiator = HandshakeState::initiator(
+        pattern,
+        b"",
+        initiator_static.clone(),
+        Some(responder_public),
+    )
+    .unwrap();
+    let mut responder = HandshakeState::responder(pattern, b"", responder_static.clone());
+    assert!(responder.write_message(b"", &mut rng).is_none());
+    let message = initiator.write_message(b"payload", &mut rng).unwrap();
+    assert!(initiator.read_message(&message).is_none());
+    assert!(initiator.split().is_none());
+
+    // Tampered and truncated messages are rejected.
+    let mut tampered = message.clone();
+    tampered[40] ^= 1;
+    assert!(
+        HandshakeState::responder(pattern, b"", responder_static.clone())
+            .read_message(&tampered)
+            .is_none()
+    );
+    assert!(
+        HandshakeState::responder(pattern, b"", responder_static.clone())
+            .read_message(&message[..DH_LEN + 10])
+            .is_none()
+    );
+
+    // The prologues and the responder's static key must match.
+    assert!(
+        HandshakeState::responder(pattern, b"other", responder_static.clone())
+            .read_message(&message)
+            .is_none()
+    );
+    assert!(
+        HandshakeState::responder(pattern, b"", StaticSecret::new(&mut rng))
+            .read_message(&message)
+            .is_none()
+    );
+    assert!(responder.read_message(&message).is_some());
+
+    // Ephemeral keys of small order make the key agreements fail.
+    let two_torsion = crate::AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+    let mut message = two_torsion.into_bytes().to_vec();
+    assert!(
+        HandshakeState::responder(pattern, b"", responder_static.clone())
+            .read_message(&message)
+            .is_none()
+    );
+
+    let mut responder =
+        HandshakeState::responder(HandshakePattern::XX, b"", responder_static.clone());
+    message.extend_from_slice(b"payload");
+    assert_eq!(responder.read_message(&message).unwrap(), b"payload");
+    assert!(responder.write_message(b"", &mut rng).is_none());
+}
+
+#[test]
+fn test_vectors() {
+    fn secret(byte: u8) -> StaticSecret {
+        let mut bytes = [byte; 32];
+        bytes[31] = 0;
+        StaticSecret::from_bytes(bytes).unwrap()
+    }
+    let prologue = b"doppio noise test";
+
+    let mut initiator =
+        HandshakeState::initiator(HandshakePattern::XX, prologue, secret(1), None).unwrap();
+    let mut responder = HandshakeState::responder(HandshakePattern::XX, prologue, secret(2));
+    let message = initiator
+        .write_message_with(b"hello", Some(secret(3)))
+        .unwrap();
+    assert_eq!(
+        message,
+        &[
+            0x58, 0x22, 0x22, 0xc6, 0x63, 0xf3, 0x54, 0xb9, 0x41, 0x0f, 0x8c, 0xfb, 0x6a, 0x58,
+            0x63, 0xdc, 0x82, 0x60, 0xde, 0x50, 0xc1, 0x75, 0xbe, 0xaf, 0xc2, 0x13, 0x1b, 0xc2,
+            0x57, 0xc6, 0x2b, 0x03, 0x68, 0x65, 0x6c, 0x6c, 0x6f
+        ][..]
+    );
+    assert_eq!(responder.read_message(&message).unwrap(), b"hello");
+    let message = responder
+        .write_message_with(b"hello yourself", Some(secret(4)))
+        .unwrap();
+    assert_eq!(
+        message,
+        &[
+            0xa7, 0x11, 0xe1, 0x3f, 0xc7, 0xe5, 0xce, 0xce, 0xd2, 0xa0, 0xda, 0x83, 0xdb, 0xc9,
+            0x9f, 0xb7, 0x0f, 0x23, 0xa9, 0xdb, 0x72, 0x85, 0xfe, 0xc0, 0xb6, 0xbf, 0x2a, 0xf9,
+            0x86, 0x4d, 0x9d, 0x02, 0x0a, 0x28, 0xa6, 0x5c, 0x7a, 0xb0, 0xee, 0xde, 0x7e, 0x44,
+            0x9a, 0x50, 0x62, 0x05, 0x1a, 0xcd, 0x3f, 0x02, 0xed, 0x95, 0xf1, 0x42, 0xc1, 0xd9,
+            0x2c, 0x34, 0xdd, 0x34, 0xa9, 0xc4, 0x47, 0x50, 0x75, 0xbd, 0x7f, 0xf0, 0xb3, 0x05,
+            0x69, 0x6c, 0x7f, 0xb2, 0x73, 0x19, 0x1b, 0xf9, 0x86, 0xda, 0x22, 0xc9, 0xc3, 0x67,
+            0x08, 0x4f, 0x5c, 0x31, 0x7b, 0x75, 0x20, 0x0b, 0x84, 0x8a, 0xeb, 0x13, 0x73, 0xd8,
+            0x2b, 0x74, 0x3a, 0x26, 0xb2, 0xdb, 0x22, 0xfe, 0x5b, 0x97, 0xe7, 0x1b
+        ][..]
+    );
+    assert_eq!(initiator.read_message(&message).unwrap(), b"hello yourself");
+    let message = initiator
+        .write_message_with(b"nice to meet you", None)
+        .unwrap();
+    assert_eq!(
+        message,
+        &[
+            0x4a, 0xe9, 0x2d, 0x7b, 0x39, 0xc1, 0x37, 0x8d, 0xf8, 0xb6, 0x80, 0xdf, 0x72, 0x4e,
+            0x40, 0xbd, 0xf8, 0xef, 0xec, 0xe5, 0xd6, 0x70, 0x19, 0xe7, 0x27, 0xfe, 0x88, 0x88,
+            0x45, 0xd3, 0x23, 0x03, 0xb1, 0x38, 0xf3, 0xf0, 0x42, 0x41, 0x68, 0x05, 0xae, 0x8f,
+            0x05, 0x2d, 0xf8, 0xe6, 0xf0, 0x4a, 0x0b, 0xfb, 0xd3, 0x5b, 0xdc, 0xa3, 0x84, 0x1c,
+            0xb6, 0xfd, 0x9c, 0x60, 0x50, 0x47, 0x8d, 0x5d, 0x91, 0x84, 0x1d, 0xf1, 0x72, 0xd9,
+            0x61, 0x29, 0x71, 0x3c, 0x3b, 0xee, 0x5c, 0xb3, 0xb7, 0x83
+        ][..]
+    );
+    assert_eq!(
+        responder.read_message(&message).unwrap(),
+        b"nice to meet you"
+    );
+    assert_eq!(
+        responder.handshake_hash(),
+        [
+            0x79, 0x82, 0x79, 0x5a, 0xe2, 0x6b, 0xab, 0xc2, 0xff, 0x8b, 0x05, 0xc0, 0x2f, 0xf9,
+            0xed, 0x98, 0xb9, 0xcf, 0xc7, 0x93, 0x0a, 0x2b, 0x83, 0x33, 0x6d, 0x90, 0x79, 0x42,
+            0x3d, 0x4c, 0x3f, 0xf0
+        ]
+    );
+    let (mut send, _) = initiator.split().unwrap();
+    let (_, mut receive) = responder.split().unwrap();
+    let ciphertext = send.encrypt_with_ad(b"", b"transport message").unwrap();
+    assert_eq!(
+        ciphertext,
+        &[
+            0xe7, 0x80, 0x17, 0x58, 0xd5, 0x98, 0x0f, 0x64, 0xf8, 0x7a, 0xc3, 0xd7, 0x28, 0x29,
+            0x84, 0x38, 0x42, 0xd6, 0x8d, 0xc5, 0xa6, 0xb6, 0x12, 0x0d, 0xad, 0x3d, 0xe3, 0x5d,
+            0xd9, 0x8a, 0xa2, 0x2d, 0xbe
+        ][..]
+    );
+    assert_eq!(
+        receive.decrypt_with_ad(b"", &ciphertext).unwrap(),
+        b"transport message"
+    );
+
+    let responder_public = PublicKey::from(&secret(2));
+    let mut initiator = HandshakeState::initiator(
+        HandshakePattern::IK,
+        prologue,
+        secret(1),
+        Some(responder_public),
+    )
+    .unwrap();
+    let mut responder = HandshakeState::responder(HandshakePattern::IK, prologue, secret(2));
+    let message = initiator
+        .write_message_with(b"hello", Some(secret(3)))
+        .unwrap();
+    assert_eq!(
+        message,
+        &[
+            0x58, 0x22, 0x22, 0xc6, 0x63, 0xf3, 0x54, 0xb9, 0x41, 0x0f, 0x8c, 0xfb, 0x6a, 0x58,
+            0x63, 0xdc, 0x82, 0x60, 0xde, 0x50, 0xc1, 0x75, 0xbe, 0xaf, 0xc2, 0x13, 0x1b, 0xc2,
+            0x57, 0xc6, 0x2b, 0x03, 0xa6, 0x53, 0x6f, 0x34, 0xc7, 0xf9, 0x27, 0x9b, 0xbd, 0xfd,
+            0x54, 0xf8, 0x19, 0x4c, 0xe6, 0x46, 0x18, 0x26, 0x00, 0x01, 0xb5, 0x13, 0xb5, 0xdd,
+            0xc4, 0x96, 0x1f, 0xd3, 0xef, 0x98, 0x6c, 0x1a, 0xae, 0x86, 0x20, 0x71, 0x8c, 0x70,
+            0x66, 0x01, 0x5e, 0x3b, 0xef, 0x70, 0x13, 0xfe, 0x25, 0x59, 0xa6, 0x7e, 0xd8, 0x9a,
+            0x9e, 0x07, 0x7f, 0xe7, 0x98, 0x7e, 0xfa, 0x7a, 0xe9, 0xf0, 0x46, 0x95, 0x8b, 0xf8,
+            0xba, 0x84, 0x62
+        ][..]
+    );
+    assert_eq!(responder.read_message(&message).unwrap(), b"hello");
+    let message = responder
+        .write_message_with(b"hello yourself", Some(secret(4)))
+        .unwrap();
+    assert_eq!(
+        message,
+        &[
+            0xa7, 0x11, 0xe1, 0x3f, 0xc7, 0xe5, 0xce, 0xce, 0xd2, 0xa0, 0xda, 0x83, 0xdb, 0xc9,
+            0x9f, 0xb7, 0x0f, 0x23, 0xa9, 0xdb, 0x72, 0x85, 0xfe, 0xc0, 0xb6, 0xbf, 0x2a, 0xf9,
+            0x86, 0x4d, 0x9d, 0x02, 0xe9, 0x05, 0x02, 0xc9, 0xf9, 0x6c, 0xc6, 0x2b, 0x2e, 0x4e,
+            0x96, 0x3b, 0x8d, 0x68, 0xdd, 0x89, 0x88, 0xbe, 0x78, 0xca, 0x83, 0xd2, 0xe0, 0x65,
+            0x7c, 0x6b, 0xdf, 0x17, 0x49, 0x5e
+        ][..]
+    );
+    assert_eq!(initiator.read_message(&message).unwrap(), b"hello yourself");
+    assert_eq!(
+        initiator.handshake_hash(),
+        [
+            0xae, 0xdc, 0x23, 0x0c, 0x7d, 0x95, 0xce, 0xba, 0x5f, 0xc1, 0x1b, 0xa1, 0xbd, 0x59,
+            0x92, 0x56, 0x6f, 0x3b, 0x6a, 0x79, 0x07, 0xd1, 0x54, 0x9c, 0x19, 0x98, 0xd8, 0x52,
+            0x45, 0x6a, 0xba, 0x4b
+        ]
+    );
+}
+
+#[test]
+fn test_cacophony_vector() {
+    // The Noise_XX_25519_ChaChaPoly_BLAKE2s vector of cacophony, with the
+    // X25519 outputs fed to the symmetric state.
+    fn x25519(sk: &[u8; 32], pk: &[u8; 32]) -> [u8; 32] {
+        x25519_dalek::x25519(*sk, *pk)
+    }
+    fn public(sk: &[u8; 32]) -> [u8; 32] {
+        x25519(sk, &x25519_dalek::X25519_BASEPOINT_BYTES)
+    }
+    let init_static = [
+        0xe6, 0x1e, 0xf9, 0x91, 0x9c, 0xde, 0x45, 0xdd, 0x5f, 0x82, 0x16, 0x64, 0x04, 0xbd, 0x08,
+        0xe3, 0x8b, 0xce, 0xb5, 0xdf, 0xdf, 0xde, 0xd0, 0xa3, 0x4c, 0x8d, 0xf7, 0xed, 0x54, 0x22,
+        0x14, 0xd1,
+    ];
+    let init_ephemeral = [
+        0x89, 0x3e, 0x28, 0xb9, 0xdc, 0x6c, 0xa8, 0xd6, 0x11, 0xab, 0x66, 0x47, 0x54, 0xb8, 0xce,
+        0xb7, 0xba, 0xc5, 0x11, 0x73, 0x49, 0xa4, 0x43, 0x9a, 0x6b, 0x05, 0x69, 0xda, 0x97, 0x7c,
+        0x46, 0x4a,
+    ];
+    let resp_static = [
+        0x4a, 0x3a, 0xcb, 0xfd, 0xb1, 0x63, 0xde, 0xc6, 0x51, 0xdf, 0xa3, 0x19, 0x4d, 0xec, 0xe6,
+        0x76, 0xd4, 0x37, 0x02, 0x9c, 0x62, 0xa4, 0x08, 0xb4, 0xc5, 0xea, 0x91, 0x14, 0x24, 0x6e,
+        0x48, 0x93,
+    ];
+    let resp_ephemeral = [
+        0xbb, 0xdb, 0x4c, 0xdb, 0xd3, 0x09, 0xf1, 0xa1, 0xf2, 0xe1, 0x45, 0x69, 0x67, 0xfe, 0x28,
+        0x8c, 0xad, 0xd6, 0xf7, 0x12, 0xd6, 0x5d, 0xc7, 0xb7, 0x79, 0x3d, 0x5e, 0x63, 0xda, 0x6b,
+        0x37, 0x5b,
+    ];
+
+    let mut symmetric = SymmetricState::new(b"Noise_XX_25519_ChaChaPoly_BLAKE2s");
+    symmetric.mix_hash(b"John Galt");
+
+    // -> e
+    let mut message = public(&init_ephemeral).to_vec();
+    symmetric.mix_hash(&public(&init_ephemeral));
+    message.extend(symmetric.encrypt_and_hash(b"Ludwig von Mises").unwrap());
+    assert_eq!(
+        message,
+        &[
+            0xca, 0x35, 0xde, 0xf5, 0xae, 0x56, 0xce, 0xc3, 0x3d, 0xc2, 0x03, 0x67, 0x31, 0xab,
+            0x14, 0x89, 0x6b, 0xc4, 0xc7, 0x5d, 0xbb, 0x07, 0xa6, 0x1f, 0x87, 0x9f, 0x8e, 0x3a,
+            0xfa, 0x4c, 0x79, 0x44, 0x4c, 0x75, 0x64, 0x77, 0x69, 0x67, 0x20, 0x76, 0x6f, 0x6e,
+            0x20, 0x4d, 0x69, 0x73, 0x65, 0x73
+        ][..]
+    );
+
+    // <- e, ee, s, es
+    let mut message = public(&resp_ephemeral).to_vec();
+    symmetric.mix_hash(&public(&resp_ephemeral));
+    symmetric.mix_key(&x25519(&init_ephemeral, &public(&resp_ephemeral)));
+    message.extend(symmetric.encrypt_and_hash(&public(&resp_static)).unwrap());
+    symmetric.mix_key(&x25519(&init_ephemeral, &public(&resp_static)));
+    message.extend(symmetric.encrypt_and_hash(b"Murray Rothbard").unwrap());
+    assert_eq!(
+        message,
+        &[
+            0x95, 0xeb, 0xc6, 0x0d, 0x2b, 0x1f, 0xa6, 0x72, 0xc1, 0xf4, 0x6a, 0x8a, 0xa2, 0x65,
+            0xef, 0x51, 0xbf, 0xe3, 0x8e, 0x7c, 0xcb, 0x39, 0xec, 0x5b, 0xe3, 0x40, 0x69, 0xf1,
+            0x44, 0x80, 0x88, 0x43, 0x7c, 0x36, 0x5e, 0xb3, 0x62, 0xa1, 0xc9, 0x91, 0xb0, 0x55,
+            0x7f, 0xe8, 0xa7, 0xfb, 0x18, 0x7d, 0x99, 0x34, 0x67, 0x65, 0xd9, 0x3e, 0xc6, 0x3d,
+            0xb6, 0xc1, 0xb0, 0x15, 0x04, 0xeb, 0xee, 0xc5, 0x5a, 0x22, 0x98, 0xd2, 0xdb, 0xff,
+            0x80, 0xef, 0xf0, 0x34, 0xd2, 0x05, 0x95, 0x15, 0x3f, 0x63, 0xa1, 0x96, 0xa6, 0xce,
+            0xad, 0x1e, 0x11, 0xb2, 0xbb, 0x13, 0xe3, 0x36, 0xfa, 0x13, 0x61, 0x6d, 0xd3, 0xe8,
+            0xb0, 0xa0, 0x70, 0xc8, 0x82, 0xed, 0x3f, 0x1a, 0x78, 0xc7, 0xc0, 0x6c, 0x93
+        ][..]
+    );
+
+    // -> s, se
+    let mut message = symmetric.encrypt_and_hash(&public(&init_static)).unwrap();
+    symmetric.mix_key(&x25519(&init_static, &public(&resp_ephemeral)));
+    message.extend(symmetric.encrypt_and_hash(b"F. A. Hayek").unwrap());
+    assert_eq!(
+        message,
+        &[
+            0x46, 0xc3, 0x30, 0x7d, 0xe8, 0x3b, 0x01, 0x42, 0x58, 0x71, 0x7d, 0x97, 0x78, 0x1c,
+            0x1f, 0x50, 0x93, 0x6d, 0x8b, 0x7d, 0x50, 0xc0, 0x72, 0x2a, 0x17, 0x39, 0x65, 0x4d,
+            0x10, 0x39, 0x2d, 0x41, 0x5b, 0x67, 0x0c, 0x11, 0x4f, 0x79, 0xb9, 0xa4, 0xf8, 0x05,
+            0x41, 0x57, 0x0f, 0x77, 0xce, 0x88, 0x80, 0x2e, 0xfa, 0x42, 0x20, 0xcf, 0xf7, 0x33,
+            0xe7, 0xb5, 0x66, 0x8b, 0xa3, 0x80, 0x59, 0xec, 0x90, 0x4b, 0x4b, 0x8e, 0xef, 0x94,
+            0x48, 0x08, 0x5f, 0xaf, 0x51
+        ][..]
+    );
+    assert_eq!(
+        symmetric.h,
+        [
+            0x6c, 0x4c, 0x56, 0xcf, 0x71, 0x61, 0x2f, 0x72, 0xd0, 0x5c, 0xeb, 0x96, 0xc0, 0x15,
+            0x5e, 0x6f, 0x4e, 0xa5, 0x4a, 0x26, 0xb5, 0x04, 0xc9, 0x3d, 0xe6, 0x32, 0xa2, 0xdb,
+            0x4a, 0x49, 0xd2, 0x00
+        ]
+    );
+
+    let (mut initiator, mut responder) = symmetric.split();
+    assert_eq!(
+        responder.encrypt_with_ad(b"", b"Carl Menger").unwrap(),
+        &[
+            0xd5, 0xe8, 0x3a, 0xdf, 0xaa, 0xc5, 0xdc, 0x32, 0x4a, 0x68, 0xf1, 0x86, 0x2d, 0xf5,
+            0x45, 0x49, 0xe5, 0x6d, 0x20, 0x9f, 0xba, 0x70, 0x72, 0x05, 0xf3, 0x28, 0xb2
+        ][..]
+    );
+    assert_eq!(
+        initiator
+            .encrypt_with_ad(b"", b"Jean-Baptiste Say")
+            .unwrap(),
+        &[
+            0xd1, 0x02, 0xc9, 0x02, 0x9b, 0x1f, 0x55, 0xc7, 0x88, 0xf5, 0x61, 0xba, 0x77, 0x37,
+            0xaf, 0xbc, 0xce, 0xf9, 0xc9, 0xf1, 0xbf, 0x2f, 0x23, 0x81, 0x67, 0xfd, 0x40, 0xba,
+            0x9c, 0x1c, 0x13, 0x48, 0x67
+        ][..]
+    );
+}
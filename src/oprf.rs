@@ -0,0 +1,1077 @@
+//! This module implements oblivious pseudorandom functions over the prime
+//! order subgroup of Doppio in the style of RFC 9497, in the base (OPRF),
+//! verifiable (VOPRF) and partially-oblivious (POPRF) modes.
+//!
+//! # Specification
+//!
+//! The ciphersuite identifier is `"doppio-SHA512"`, so the context string
+//! of a mode is `"OPRFV1-" || I2OSP(mode, 1) || "-doppio-SHA512"`.
+//! Everything follows RFC 9497 with the following group operations:
+//!
+//! * The group is the prime order subgroup of Doppio with generator
+//!   [`GENERATOR`], and `Hash` is SHA-512, so `Nh = 64`.
+//! * `HashToGroup` is [`hash_to_curve`](crate::hash_to_curve) with the
+//!   domain separation tag `"HashToGroup-" || contextString`.
+//! * `HashToScalar` expands its input to 64 bytes with
+//!   `expand_message_xmd` using SHA-512 and the domain separation tag
+//!   `"HashToScalar-" || contextString`, unless another tag is given, and
+//!   reduces them with [`Fr::from_bytes_wide`].
+//! * `SerializeElement` is [`AffinePoint::into_bytes`], and
+//!   `DeserializeElement` additionally rejects the identity and points
+//!   outside the prime order subgroup.
+//! * `SerializeScalar` is [`Fr::into_bytes`], which is little-endian, and
+//!   `DeserializeScalar` rejects non-canonical encodings.
+//!
+//! In particular `DeriveKeyPair`, the batched DLEQ proofs computed with
+//! `ComputeCompositesFast` and `ComputeComposites`, and the `Finalize`
+//! hashes are exactly those of RFC 9497.
+//!
+//! The RFC has no test vectors for this ciphersuite, so the vectors here
+//! are self-generated. The tests additionally rebuild the hash inputs of
+//! the proofs and of the POPRF tweak from the byte layouts of the RFC.
+
+use core::convert::TryFrom;
+use core::fmt;
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use crate::hash::{expand_message_xmd, hash_to_curve_parts};
+use crate::{multiscalar_mul_vartime, AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The ciphersuite identifier.
+pub const IDENTIFIER: &[u8] = b"doppio-SHA512";
+
+/// An RFC 9497 protocol variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// The base mode, in which the client learns the output but cannot
+    /// verify it.
+    Oprf,
+    /// The verifiable mode, in which the server proves that it evaluated
+    /// with the key of its public key.
+    Voprf,
+    /// The partially-oblivious mode, which is verifiable and additionally
+    /// binds a public input known to both parties.
+    Poprf,
+}
+
+impl Mode {
+    /// Returns the context string `"OPRFV1-" || I2OSP(mode, 1) || "-" ||
+    /// IDENTIFIER` of this mode.
+    fn context_string(self) -> [u8; 22] {
+        let mut res = [0u8; 22];
+        res[..7].copy_from_slice(b"OPRFV1-");
+        res[7] = match self {
+            Mode::Oprf => 0x00,
+            Mode::Voprf => 0x01,
+            Mode::Poprf => 0x02,
+        };
+        res[8] = b'-';
+        res[9..].copy_from_slice(IDENTIFIER);
+
+        res
+    }
+
+    /// Hashes `input` to the group.
+    fn hash_to_group(self, input: &[u8]) -> ExtendedPoint {
+        hash_to_curve_parts(
+            &[input],
+            &[b"HashToGroup-", &self.context_string()[..]].concat(),
+        )
+    }
+
+    /// Hashes the concatenation of `msg` to a scalar under the domain
+    /// separation tag `"HashToScalar-" || contextString`.
+    fn hash_to_scalar(self, msg: &[&[u8]]) -> Fr {
+        hash_to_scalar_with_dst(
+            msg,
+            &[b"HashToScalar-", &self.context_string()[..]].concat(),
+        )
+    }
+}
+
+/// Hashes the concatenation of `msg` to a scalar under the domain
+/// separation tag `dst`.
+fn hash_to_scalar_with_dst(msg: &[&[u8]], dst: &[u8]) -> Fr {
+    let mut wide = [0u8; 64];
+    expand_message_xmd(msg, dst, &mut wide);
+    let res = Fr::from_bytes_wide(wide);
+    wide.zeroize();
+
+    res
+}
+
+/// Returns `I2OSP(len, 2)`, or `None` if `len` does not fit in two bytes.
+fn i2osp2(len: usize) -> Option<[u8; 2]> {
+    u16::try_from(len).ok().map(u16::to_be_bytes)
+}
+
+/// Decodes a point, additionally rejecting points outside the prime
+/// order subgroup.
+fn deserialize_element(bytes: [u8; 32]) -> CtOption<AffinePoint> {
+    AffinePoint::deserialize_element(bytes)
+        .and_then(|point| CtOption::new(point, point.is_torsion_free()))
+}
+
+/// Computes the `Finalize` hash of `input`, `info` (in the POPRF mode)
+/// and the unblinded element.
+fn finalize_hash(input: &[u8], info: Option<&[u8]>, element: &AffinePoint) -> Option<[u8; 64]> {
+    let element = element.into_bytes();
+
+    let mut h = Sha512::new()
+        .chain_update(i2osp2(input.len())?)
+        .chain_update(input);
+    if let Some(info) = info {
+        h.update(i2osp2(info.len())?);
+        h.update(info);
+    }
+    h.update(i2osp2(element.len())?);
+    h.update(element);
+    h.update(b"Finalize");
+
+    let mut res = [0u8; 64];
+    res.copy_from_slice(&h.finalize());
+
+    Some(res)
+}
+
+/// Returns the scalar `m` and the tweaked key `m * G + pk` for `info` in
+/// the POPRF mode, or `None` if `info` is too long or the tweaked key is
+/// the identity.
+fn tweak(pk: &AffinePoint, info: &[u8]) -> Option<(Fr, AffinePoint)> {
+    let m = Mode::Poprf.hash_to_scalar(&[b"Info", &i2osp2(info.len())?, info]);
+    let tweaked = ExtendedPoint::from(GENERATOR) * m + ExtendedPoint::from(*pk);
+    if bool::from(tweaked.is_identity()) {
+        return None;
+    }
+
+    Some((m, AffinePoint::from(tweaked)))
+}
+
+/// The secret key of a server.
+#[derive(Clone)]
+pub struct SecretKey {
+    sk: Fr,
+    public_key: PublicKey,
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("public_key", &self.public_key)
+            .finish()
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.sk.zeroize();
+    }
+}
+
+/// The public key `pkS = skS * G` of a server.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PublicKey(AffinePoint);
+
+/// A blinded input `blind * HashToGroup(input)`, sent by the client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlindedElement(AffinePoint);
+
+/// A blinded input evaluated by the server.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EvaluatedElement(AffinePoint);
+
+/// A batched DLEQ proof `(c, s)` that evaluated elements were computed
+/// with the server's key.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Proof {
+    c: Fr,
+    s: Fr,
+}
+
+impl SecretKey {
+    /// Creates a secret key with a random nonzero scalar.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> SecretKey {
+        loop {
+            let sk = Fr::random(&mut *rng);
+            if sk != Fr::zero() {
+                return SecretKey::from_scalar(sk);
+            }
+        }
+    }
+
+    fn from_scalar(sk: Fr) -> SecretKey {
+        let public_key = PublicKey(AffinePoint::from(ExtendedPoint::from(GENERATOR) * sk));
+
+        SecretKey { sk, public_key }
+    }
+
+    /// Deterministically derives a secret key for `mode` from `seed` and
+    /// `info` with `DeriveKeyPair`. This returns `None` if `info` is
+    /// longer than 65535 bytes or every candidate scalar is zero.
+    pub fn derive(mode: Mode, seed: &[u8; 32], info: &[u8]) -> Option<SecretKey> {
+        let info_len = i2osp2(info.len())?;
+        let dst = [b"DeriveKeyPair" as &[u8], &mode.context_string()].concat();
+
+        for counter in 0..=255u8 {
+            let sk = hash_to_scalar_with_dst(&[seed, &info_len, info, &[counter]], &dst);
+            if sk != Fr::zero() {
+                return Some(SecretKey::from_scalar(sk));
+            }
+        }
+
+        None
+    }
+
+    /// Attempts to interpret the byte representation of a secret key,
+    /// failing if it is not a canonical encoding of a nonzero element of
+    /// `Fr`.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<SecretKey> {
+        let sk = Fr::from_bytes(bytes);
+        let is_some = sk.is_some();
+        let sk = sk.unwrap_or(Fr::one());
+        let is_nonzero = !sk.ct_eq(&Fr::zero());
+
+        CtOption::new(SecretKey::from_scalar(sk), is_some & is_nonzero)
+    }
+
+    /// Converts this secret key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.sk.into_bytes()
+    }
+
+    /// Returns the public key corresponding to this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    /// Evaluates a blinded element in the OPRF mode.
+    pub fn blind_evaluate(&self, blinded: &BlindedElement) -> EvaluatedElement {
+        EvaluatedElement(AffinePoint::from(ExtendedPoint::from(blinded.0) * self.sk))
+    }
+
+    /// Evaluates blinded elements in the VOPRF mode, along with a proof
+    /// covering all of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 65535 blinded elements.
+    pub fn blind_evaluate_verifiable<R: RngCore + CryptoRng>(
+        &self,
+        blinded: &[BlindedElement],
+        rng: &mut R,
+    ) -> (Vec<EvaluatedElement>, Proof) {
+        self.blind_evaluate_verifiable_with(blinded, Fr::random(rng))
+    }
+
+    fn blind_evaluate_verifiable_with(
+        &self,
+        blinded: &[BlindedElement],
+        r: Fr,
+    ) -> (Vec<EvaluatedElement>, Proof) {
+        let c: Vec<AffinePoint> = blinded.iter().map(|b| b.0).collect();
+        let d: Vec<AffinePoint> = blinded.iter().map(|b| self.blind_evaluate(b).0).collect();
+        let proof = generate_proof(Mode::Voprf, &self.sk, &self.public_key.0, &c, &d, r);
+
+        (d.into_iter().map(EvaluatedElement).collect(), proof)
+    }
+
+    /// Evaluates blinded elements with the public input `info` in the
+    /// POPRF mode, along with a proof covering all of them. This returns
+    /// `None` if `info` is longer than 65535 bytes or maps to the
+    /// inverse of this key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 65535 blinded elements.
+    pub fn blind_evaluate_partial<R: RngCore + CryptoRng>(
+        &self,
+        blinded: &[BlindedElement],
+        info: &[u8],
+        rng: &mut R,
+    ) -> Option<(Vec<EvaluatedElement>, Proof)> {
+        self.blind_evaluate_partial_with(blinded, info, Fr::random(rng))
+    }
+
+    fn blind_evaluate_partial_with(
+        &self,
+        blinded: &[BlindedElement],
+        info: &[u8],
+        r: Fr,
+    ) -> Option<(Vec<EvaluatedElement>, Proof)> {
+        let (m, tweaked) = tweak(&self.public_key.0, info)?;
+        let mut t = self.sk + m;
+        if t == Fr::zero() {
+            return None;
+        }
+        let t_inv = t.invert().unwrap();
+
+        let evaluated: Vec<AffinePoint> = blinded
+            .iter()
+            .map(|b| AffinePoint::from(ExtendedPoint::from(b.0) * t_inv))
+            .collect();
+        let c: Vec<AffinePoint> = blinded.iter().map(|b| b.0).collect();
+        let proof = generate_proof(Mode::Poprf, &t, &tweaked, &evaluated, &c, r);
+        t.zeroize();
+
+        Some((evaluated.into_iter().map(EvaluatedElement).collect(), proof))
+    }
+
+    /// Computes the PRF output on `input` directly, as the client would
+    /// obtain it in `mode`. The public input `info` is only used in the
+    /// POPRF mode. This returns `None` if `input` or `info` is longer
+    /// than 65535 bytes, `input` hashes to the identity, or `info` maps
+    /// to the inverse of this key.
+    pub fn evaluate(&self, mode: Mode, input: &[u8], info: &[u8]) -> Option<[u8; 64]> {
+        let element = mode.hash_to_group(input);
+        if bool::from(element.is_identity()) {
+            return None;
+        }
+
+        match mode {
+            Mode::Oprf | Mode::Voprf => {
+                finalize_hash(input, None, &AffinePoint::from(element * self.sk))
+            }
+            Mode::Poprf => {
+                let (m, _) = tweak(&self.public_key.0, info)?;
+                let t = self.sk + m;
+                if t == Fr::zero() {
+                    return None;
+                }
+                let t_inv = t.invert().unwrap();
+
+                finalize_hash(input, Some(info), &AffinePoint::from(element * t_inv))
+            }
+        }
+    }
+}
+
+impl PublicKey {
+    /// Attempts to interpret the byte representation of a public key,
+    /// failing if it is not canonically encoded or not a nonidentity
+    /// element of the prime order subgroup.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<PublicKey> {
+        deserialize_element(bytes).map(PublicKey)
+    }
+
+    /// Converts this public key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+impl BlindedElement {
+    /// Attempts to interpret the byte representation of a blinded
+    /// element, failing if it is not canonically encoded or not a
+    /// nonidentity element of the prime order subgroup.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<BlindedElement> {
+        deserialize_element(bytes).map(BlindedElement)
+    }
+
+    /// Converts this blinded element into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+impl EvaluatedElement {
+    /// Attempts to interpret the byte representation of an evaluated
+    /// element, failing if it is not canonically encoded or not a
+    /// nonidentity element of the prime order subgroup.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<EvaluatedElement> {
+        deserialize_element(bytes).map(EvaluatedElement)
+    }
+
+    /// Converts this evaluated element into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+impl Proof {
+    /// Attempts to interpret the 64-byte representation `c || s` of a
+    /// proof, failing if either scalar is not canonically encoded.
+    pub fn from_bytes(bytes: [u8; 64]) -> CtOption<Proof> {
+        let mut c = [0u8; 32];
+        let mut s = [0u8; 32];
+        c.copy_from_slice(&bytes[..32]);
+        s.copy_from_slice(&bytes[32..]);
+
+        let c = Fr::from_bytes(c);
+        let s = Fr::from_bytes(s);
+        let is_some = c.is_some() & s.is_some();
+
+        CtOption::new(
+            Proof {
+                c: c.unwrap_or(Fr::zero()),
+                s: s.unwrap_or(Fr::zero()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this proof into its 64-byte representation `c || s`.
+    pub fn into_bytes(&self) -> [u8; 64] {
+        let mut res = [0u8; 64];
+        res[..32].copy_from_slice(&self.c.into_bytes());
+        res[32..].copy_from_slice(&self.s.into_bytes());
+
+        res
+    }
+}
+
+/// Returns the weights `d_i` of `ComputeComposites`, so that the
+/// composite elements are `M = sum_i d_i * C_i` and `Z = sum_i d_i * D_i`.
+fn composite_weights(mode: Mode, b: &AffinePoint, c: &[AffinePoint], d: &[AffinePoint]) -> Vec<Fr> {
+    let context = mode.context_string();
+    let seed_dst = [b"Seed-" as &[u8], &context].concat();
+    let seed = Sha512::new()
+        .chain_update([0, 32])
+        .chain_update(b.into_bytes())
+        .chain_update((seed_dst.len() as u16).to_be_bytes())
+        .chain_update(&seed_dst)
+        .finalize();
+
+    c.iter()
+        .zip(d.iter())
+        .enumerate()
+        .map(|(i, (c_i, d_i))| {
+            mode.hash_to_scalar(&[
+                &[0, 64],
+                &seed,
+                &(i as u16).to_be_bytes(),
+                &[0, 32],
+                &c_i.into_bytes(),
+                &[0, 32],
+                &d_i.into_bytes(),
+                b"Composite",
+            ])
+        })
+        .collect()
+}
+
+/// Computes the challenge over `B`, `M`, `Z`, `t2` and `t3`.
+fn proof_challenge(mode: Mode, points: [&AffinePoint; 5]) -> Fr {
+    let encoded: Vec<[u8; 32]> = points.iter().map(|p| p.into_bytes()).collect();
+    let mut msg: Vec<&[u8]> = Vec::with_capacity(11);
+    for p in encoded.iter() {
+        msg.push(&[0, 32]);
+        msg.push(p);
+    }
+    msg.push(b"Challenge");
+
+    mode.hash_to_scalar(&msg)
+}
+
+/// Proves that `D_i = k * C_i` for all `i` and `B = k * G` with the
+/// nonce `r`.
+///
+/// # Panics
+///
+/// Panics if there are more than 65535 elements.
+fn generate_proof(
+    mode: Mode,
+    k: &Fr,
+    b: &AffinePoint,
+    c: &[AffinePoint],
+    d: &[AffinePoint],
+    mut r: Fr,
+) -> Proof {
+    assert!(c.len() <= usize::from(u16::MAX));
+
+    let weights = composite_weights(mode, b, c, d);
+    let points: Vec<ExtendedPoint> = c.iter().map(|c_i| ExtendedPoint::from(*c_i)).collect();
+    let m = multiscalar_mul_vartime(&weights, &points);
+    let z = m * k;
+
+    let t2 = AffinePoint::from(ExtendedPoint::from(GENERATOR) * r);
+    let t3 = AffinePoint::from(m * r);
+    let challenge = proof_challenge(
+        mode,
+        [b, &AffinePoint::from(m), &AffinePoint::from(z), &t2, &t3],
+    );
+    let s = r - challenge * k;
+    r.zeroize();
+
+    Proof { c: challenge, s }
+}
+
+/// Verifies a proof that `D_i = k * C_i` for all `i` and `B = k * G`.
+fn verify_proof(
+    mode: Mode,
+    b: &AffinePoint,
+    c: &[AffinePoint],
+    d: &[AffinePoint],
+    proof: &Proof,
+) -> bool {
+    if c.len() > usize::from(u16::MAX) {
+        return false;
+    }
+
+    let weights = composite_weights(mode, b, c, d);
+    let c_points: Vec<ExtendedPoint> = c.iter().map(|c_i| ExtendedPoint::from(*c_i)).collect();
+    let d_points: Vec<ExtendedPoint> = d.iter().map(|d_i| ExtendedPoint::from(*d_i)).collect();
+    let m = multiscalar_mul_vartime(&weights, &c_points);
+    let z = multiscalar_mul_vartime(&weights, &d_points);
+
+    let t2 = multiscalar_mul_vartime(
+        &[proof.s, proof.c],
+        &[ExtendedPoint::from(GENERATOR), ExtendedPoint::from(*b)],
+    );
+    let t3 = multiscalar_mul_vartime(&[proof.s, proof.c], &[m, z]);
+
+    proof_challenge(
+        mode,
+        [
+            b,
+            &AffinePoint::from(m),
+            &AffinePoint::from(z),
+            &AffinePoint::from(t2),
+            &AffinePoint::from(t3),
+        ],
+    ) == proof.c
+}
+
+/// The client's state for one input, holding the input and its blind.
+pub struct ClientState {
+    mode: Mode,
+    input: Vec<u8>,
+    blind: Fr,
+    blinded: BlindedElement,
+}
+
+impl fmt::Debug for ClientState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClientState")
+            .field("mode", &self.mode)
+            .field("blinded", &self.blinded)
+            .finish()
+    }
+}
+
+impl Drop for ClientState {
+    fn drop(&mut self) {
+        self.input.zeroize();
+        self.blind.zeroize();
+    }
+}
+
+impl ClientState {
+    /// Blinds `input` for `mode` with a random nonzero scalar, returning
+    /// the state needed to finalize and the blinded element to send to
+    /// the server. This returns `None` if `input` is longer than 65535
+    /// bytes or hashes to the identity.
+    pub fn blind<R: RngCore + CryptoRng>(
+        mode: Mode,
+        input: &[u8],
+        rng: &mut R,
+    ) -> Option<(ClientState, BlindedElement)> {
+        loop {
+            let blind = Fr::random(&mut *rng);
+            if blind != Fr::zero() {
+                return ClientState::blind_with(mode, input, blind);
+            }
+        }
+    }
+
+    fn blind_with(mode: Mode, input: &[u8], blind: Fr) -> Option<(ClientState, BlindedElement)> {
+        i2osp2(input.len())?;
+        let element = mode.hash_to_group(input);
+        if bool::from(element.is_identity()) {
+            return None;
+        }
+        let blinded = BlindedElement(AffinePoint::from(element * blind));
+
+        Some((
+            ClientState {
+                mode,
+                input: input.to_vec(),
+                blind,
+                blinded,
+            },
+            blinded,
+        ))
+    }
+
+    /// Returns the mode this input was blinded for.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Unblinds an evaluated element.
+    fn unblind(&self, evaluated: &EvaluatedElement) -> AffinePoint {
+        let blind_inv = self.blind.invert().unwrap();
+
+        AffinePoint::from(ExtendedPoint::from(evaluated.0) * blind_inv)
+    }
+
+    /// Finalizes the OPRF mode, computing the PRF output from the
+    /// server's evaluation. This returns `None` if this input was not
+    /// blinded for the OPRF mode.
+    pub fn finalize(&self, evaluated: &EvaluatedElement) -> Option<[u8; 64]> {
+        if self.mode != Mode::Oprf {
+            return None;
+        }
+
+        finalize_hash(&self.input, None, &self.unblind(evaluated))
+    }
+
+    /// Finalizes the VOPRF mode for a batch of inputs, verifying the
+    /// server's proof against its public key before computing the PRF
+    /// outputs. This returns `None` if an input was not blinded for the
+    /// VOPRF mode, the numbers of states and evaluated elements differ,
+    /// or the proof is invalid.
+    pub fn finalize_verifiable(
+        states: &[ClientState],
+        evaluated: &[EvaluatedElement],
+        proof: &Proof,
+        public_key: &PublicKey,
+    ) -> Option<Vec<[u8; 64]>> {
+        if states.len() != evaluated.len() || states.iter().any(|s| s.mode != Mode::Voprf) {
+            return None;
+        }
+
+        let c: Vec<AffinePoint> = states.iter().map(|s| s.blinded.0).collect();
+        let d: Vec<AffinePoint> = evaluated.iter().map(|e| e.0).collect();
+        if !verify_proof(Mode::Voprf, &public_key.0, &c, &d, proof) {
+            return None;
+        }
+
+        states
+            .iter()
+            .zip(evaluated.iter())
+            .map(|(s, e)| finalize_hash(&s.input, None, &s.unblind(e)))
+            .collect()
+    }
+
+    /// Finalizes the POPRF mode for a batch of inputs and the public
+    /// input `info`, verifying the server's proof against its public key
+    /// before computing the PRF outputs. This returns `None` if an input
+    /// was not blinded for the POPRF mode, the numbers of states and
+    /// evaluated elements differ, `info` is invalid, or the proof is
+    /// invalid.
+    pub fn finalize_partial(
+        states: &[ClientState],
+        evaluated: &[EvaluatedElement],
+        proof: &Proof,
+        public_key: &PublicKey,
+        info: &[u8],
+    ) -> Option<Vec<[u8; 64]>> {
+        if states.len() != evaluated.len() || states.iter().any(|s| s.mode != Mode::Poprf) {
+            return None;
+        }
+
+        let (_, tweaked) = tweak(&public_key.0, info)?;
+        let c: Vec<AffinePoint> = evaluated.iter().map(|e| e.0).collect();
+        let d: Vec<AffinePoint> = states.iter().map(|s| s.blinded.0).collect();
+        if !verify_proof(Mode::Poprf, &tweaked, &c, &d, proof) {
+            return None;
+        }
+
+        states
+            .iter()
+            .zip(evaluated.iter())
+            .map(|(s, e)| finalize_hash(&s.input, Some(info), &s.unblind(e)))
+            .collect()
+    }
+}
+
+#[test]
+fn test_oprf() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+
+    let (state, blinded) = ClientState::blind(Mode::Oprf, b"input", &mut rng).unwrap();
+    let output = state.finalize(&sk.blind_evaluate(&blinded)).unwrap();
+    assert_eq!(Some(output), sk.evaluate(Mode::Oprf, b"input", b""));
+    assert_ne!(Some(output), sk.evaluate(Mode::Oprf, b"other input", b""));
+}
+
+#[test]
+fn test_voprf() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+    let pk = sk.public_key();
+
+    let inputs: [&[u8]; 3] = [b"first", b"second", b"third"];
+    let (states, blinded): (Vec<_>, Vec<_>) = inputs
+        .iter()
+        .map(|input| ClientState::blind(Mode::Voprf, input, &mut rng).unwrap())
+        .unzip();
+    let (evaluated, proof) = sk.blind_evaluate_verifiable(&blinded, &mut rng);
+
+    let outputs = ClientState::finalize_verifiable(&states, &evaluated, &proof, &pk).unwrap();
+    for (input, output) in inputs.iter().zip(outputs.iter()) {
+        assert_eq!(Some(*output), sk.evaluate(Mode::Voprf, input, b""));
+    }
+
+    // The proof binds the server's key and every evaluated element.
+    let other = SecretKey::new(&mut rng).public_key();
+    assert!(ClientState::finalize_verifiable(&states, &evaluated, &proof, &other).is_none());
+    let mut swapped = evaluated.clone();
+    swapped.swap(0, 1);
+    assert!(ClientState::finalize_verifiable(&states, &swapped, &proof, &pk).is_none());
+}
+
+#[test]
+fn test_poprf() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+    let pk = sk.public_key();
+
+    let (state, blinded) = ClientState::blind(Mode::Poprf, b"input", &mut rng).unwrap();
+    let (evaluated, proof) = sk
+        .blind_evaluate_partial(&[blinded], b"info", &mut rng)
+        .unwrap();
+    let states = [state];
+
+    let outputs = ClientState::finalize_partial(&states, &evaluated, &proof, &pk, b"info").unwrap();
+    assert_eq!(
+        Some(outputs[0]),
+        sk.evaluate(Mode::Poprf, b"input", b"info")
+    );
+    assert!(
+        ClientState::finalize_partial(&states, &evaluated, &proof, &pk, b"other info").is_none()
+    );
+}
+
+#[test]
+fn test_mode_mismatch() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+
+    let (state, blinded) = ClientState::blind(Mode::Voprf, b"input", &mut rng).unwrap();
+    assert_eq!(state.mode(), Mode::Voprf);
+    let (evaluated, proof) = sk.blind_evaluate_verifiable(&[blinded], &mut rng);
+    assert!(state.finalize(&evaluated[0]).is_none());
+
+    let states = [state];
+    assert!(
+        ClientState::finalize_partial(&states, &evaluated, &proof, &sk.public_key(), b"").is_none()
+    );
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+    let (_, blinded) = ClientState::blind(Mode::Voprf, b"input", &mut rng).unwrap();
+    let (_, proof) = sk.blind_evaluate_verifiable(&[blinded], &mut rng);
+
+    assert_eq!(Proof::from_bytes(proof.into_bytes()).unwrap(), proof);
+    assert_eq!(
+        SecretKey::from_bytes(sk.into_bytes()).unwrap().public_key(),
+        sk.public_key()
+    );
+    assert!(bool::from(SecretKey::from_bytes([0; 32]).is_none()));
+
+    let identity = AffinePoint::identity().into_bytes();
+    assert!(bool::from(BlindedElement::from_bytes(identity).is_none()));
+    assert!(bool::from(EvaluatedElement::from_bytes(identity).is_none()));
+    assert!(bool::from(PublicKey::from_bytes(identity).is_none()));
+}
+
+#[test]
+fn test_vectors() {
+    let seed = [0xa3; 32];
+    let vectors: [(Mode, [u8; 32], [u8; 32]); 3] = [
+        (
+            Mode::Oprf,
+            [
+                201, 29, 164, 81, 100, 25, 158, 6, 72, 226, 253, 217, 73, 94, 59, 237, 189, 137,
+                236, 165, 88, 170, 151, 238, 131, 196, 219, 194, 158, 225, 218, 1,
+            ],
+            [
+                13, 191, 171, 124, 55, 0, 188, 104, 157, 76, 215, 73, 23, 4, 38, 200, 104, 30, 95,
+                41, 98, 14, 179, 235, 0, 64, 229, 209, 132, 180, 202, 129,
+            ],
+        ),
+        (
+            Mode::Voprf,
+            [
+                187, 34, 91, 88, 84, 48, 57, 83, 184, 228, 97, 187, 26, 186, 209, 240, 249, 87, 94,
+                14, 252, 255, 135, 80, 171, 101, 124, 237, 92, 171, 68, 3,
+            ],
+            [
+                176, 112, 23, 107, 34, 107, 74, 160, 108, 74, 75, 111, 169, 186, 96, 42, 237, 217,
+                91, 165, 210, 254, 126, 245, 175, 104, 222, 154, 63, 205, 38, 8,
+            ],
+        ),
+        (
+            Mode::Poprf,
+            [
+                179, 236, 74, 41, 252, 24, 37, 42, 78, 74, 88, 219, 208, 21, 155, 168, 182, 247,
+                132, 107, 139, 143, 20, 2, 111, 7, 99, 203, 153, 80, 209, 3,
+            ],
+            [
+                200, 1, 3, 44, 163, 6, 36, 45, 127, 146, 222, 86, 190, 221, 220, 153, 57, 209, 178,
+                208, 243, 105, 173, 134, 155, 199, 153, 130, 242, 66, 39, 129,
+            ],
+        ),
+    ];
+
+    for (mode, sk, pk) in vectors.iter() {
+        let derived = SecretKey::derive(*mode, &seed, b"test key").unwrap();
+        assert_eq!(derived.into_bytes(), *sk);
+        assert_eq!(derived.public_key().into_bytes(), *pk);
+    }
+
+    let sk = SecretKey::derive(Mode::Oprf, &seed, b"test key").unwrap();
+    let (_, blinded) =
+        ClientState::blind_with(Mode::Oprf, &[0x00], Fr::from(0x1234_5678_9abc_def0)).unwrap();
+    assert_eq!(
+        blinded.into_bytes(),
+        [
+            32, 247, 146, 50, 90, 242, 104, 216, 42, 106, 145, 41, 235, 69, 25, 87, 219, 140, 222,
+            78, 236, 208, 74, 116, 128, 98, 229, 201, 181, 21, 171, 129
+        ]
+    );
+    assert_eq!(
+        sk.blind_evaluate(&blinded).into_bytes(),
+        [
+            252, 165, 197, 48, 146, 150, 151, 46, 37, 178, 149, 226, 113, 55, 73, 140, 135, 213,
+            70, 188, 186, 21, 171, 250, 66, 5, 136, 184, 17, 37, 29, 138
+        ]
+    );
+    assert_eq!(
+        sk.evaluate(Mode::Oprf, &[0x00], b"").unwrap()[..],
+        [
+            248, 97, 138, 161, 104, 70, 5, 172, 216, 5, 133, 30, 73, 145, 133, 103, 83, 44, 20,
+            133, 32, 229, 86, 113, 23, 178, 226, 75, 90, 203, 56, 193, 40, 170, 51, 72, 27, 14,
+            168, 77, 109, 84, 209, 58, 20, 79, 173, 34, 140, 237, 180, 85, 49, 195, 8, 0, 113, 14,
+            108, 89, 27, 23, 99, 83
+        ][..]
+    );
+}
+
+#[test]
+fn test_verifiable_vectors() {
+    let seed = [0xa3; 32];
+    let inputs: [&[u8]; 2] = [&[0x00], &[0x5a; 17]];
+    let blinds = [
+        Fr::from(0x1234_5678_9abc_def0),
+        Fr::from(0x0fed_cba9_8765_4321),
+    ];
+    let r = Fr::from(0x0123_4567_89ab_cdef);
+
+    // A batch of two inputs in the VOPRF mode.
+    let sk = SecretKey::derive(Mode::Voprf, &seed, b"test key").unwrap();
+    let (states, blinded): (Vec<_>, Vec<_>) = inputs
+        .iter()
+        .zip(blinds.iter())
+        .map(|(input, blind)| ClientState::blind_with(Mode::Voprf, input, *blind).unwrap())
+        .unzip();
+    let expected_blinded: [[u8; 32]; 2] = [
+        [
+            22, 224, 141, 160, 13, 71, 142, 255, 32, 144, 238, 13, 190, 250, 163, 28, 85, 253, 28,
+            71, 15, 90, 196, 199, 91, 110, 170, 234, 24, 153, 76, 141,
+        ],
+        [
+            239, 63, 74, 129, 21, 252, 99, 128, 37, 245, 55, 174, 230, 253, 107, 238, 155, 80, 243,
+            117, 99, 201, 72, 26, 249, 9, 82, 202, 81, 96, 153, 5,
+        ],
+    ];
+    for (blinded, expected) in blinded.iter().zip(expected_blinded.iter()) {
+        assert_eq!(blinded.into_bytes(), *expected);
+    }
+    let (evaluated, proof) = sk.blind_evaluate_verifiable_with(&blinded, r);
+    let expected_evaluated: [[u8; 32]; 2] = [
+        [
+            32, 145, 191, 231, 131, 36, 170, 126, 194, 248, 156, 56, 104, 13, 238, 66, 103, 223,
+            240, 116, 150, 33, 70, 62, 101, 241, 34, 13, 124, 197, 3, 137,
+        ],
+        [
+            97, 5, 154, 115, 179, 120, 231, 109, 223, 35, 90, 144, 125, 187, 155, 2, 217, 50, 21,
+            175, 172, 157, 206, 69, 131, 251, 227, 161, 110, 125, 112, 133,
+        ],
+    ];
+    for (evaluated, expected) in evaluated.iter().zip(expected_evaluated.iter()) {
+        assert_eq!(evaluated.into_bytes(), *expected);
+    }
+    assert_eq!(
+        proof.into_bytes()[..],
+        [
+            205, 76, 202, 239, 239, 165, 109, 3, 189, 255, 6, 253, 176, 220, 185, 146, 195, 4, 197,
+            186, 69, 251, 102, 130, 139, 211, 238, 187, 88, 88, 64, 1, 34, 120, 143, 231, 29, 2,
+            45, 147, 243, 40, 6, 123, 157, 198, 210, 46, 239, 30, 54, 213, 190, 49, 248, 243, 19,
+            220, 173, 228, 169, 182, 145, 0
+        ][..]
+    );
+    let outputs =
+        ClientState::finalize_verifiable(&states, &evaluated, &proof, &sk.public_key()).unwrap();
+    let expected_outputs: [[u8; 64]; 2] = [
+        [
+            86, 248, 180, 44, 161, 0, 65, 93, 10, 50, 211, 93, 19, 113, 7, 231, 96, 210, 16, 32,
+            66, 80, 12, 191, 115, 103, 141, 226, 112, 245, 213, 73, 120, 246, 132, 250, 94, 231,
+            13, 79, 57, 44, 169, 111, 251, 84, 132, 179, 58, 185, 241, 194, 162, 28, 129, 34, 192,
+            78, 220, 232, 28, 204, 242, 250,
+        ],
+        [
+            244, 187, 174, 82, 140, 137, 16, 84, 222, 226, 196, 17, 173, 51, 254, 72, 240, 210,
+            171, 248, 173, 54, 113, 215, 150, 120, 77, 247, 16, 152, 124, 216, 163, 17, 248, 182,
+            109, 212, 129, 31, 254, 134, 121, 139, 195, 49, 242, 176, 5, 151, 133, 153, 230, 39,
+            91, 247, 17, 91, 110, 69, 236, 84, 24, 168,
+        ],
+    ];
+    assert_eq!(outputs, expected_outputs);
+
+    // One input in the POPRF mode.
+    let sk = SecretKey::derive(Mode::Poprf, &seed, b"test key").unwrap();
+    let info = b"test info";
+    let (_, tweaked) = tweak(&sk.public_key().0, info).unwrap();
+    assert_eq!(
+        tweaked.into_bytes(),
+        [
+            122, 130, 11, 57, 215, 120, 234, 123, 160, 117, 195, 185, 185, 12, 246, 7, 13, 155,
+            113, 152, 106, 155, 185, 23, 189, 121, 25, 21, 119, 45, 98, 128
+        ]
+    );
+    let (state, blinded) = ClientState::blind_with(Mode::Poprf, inputs[0], blinds[0]).unwrap();
+    assert_eq!(
+        blinded.into_bytes(),
+        [
+            173, 93, 212, 74, 180, 151, 27, 232, 213, 21, 139, 112, 230, 105, 79, 132, 48, 142,
+            246, 131, 148, 64, 77, 113, 58, 119, 236, 190, 63, 205, 29, 143
+        ]
+    );
+    let (evaluated, proof) = sk.blind_evaluate_partial_with(&[blinded], info, r).unwrap();
+    assert_eq!(
+        evaluated[0].into_bytes(),
+        [
+            85, 128, 103, 74, 13, 63, 175, 247, 172, 181, 243, 228, 27, 192, 138, 202, 230, 106,
+            48, 190, 90, 187, 141, 180, 157, 246, 59, 50, 212, 230, 162, 5
+        ]
+    );
+    assert_eq!(
+        proof.into_bytes()[..],
+        [
+            68, 70, 0, 1, 7, 76, 127, 72, 116, 59, 60, 19, 130, 153, 239, 43, 108, 55, 20, 176,
+            229, 72, 248, 153, 171, 91, 199, 166, 78, 193, 23, 0, 19, 170, 91, 130, 176, 208, 22,
+            24, 165, 158, 36, 178, 165, 117, 120, 105, 157, 127, 143, 23, 213, 212, 227, 116, 37,
+            44, 201, 51, 163, 107, 46, 2
+        ][..]
+    );
+    let outputs =
+        ClientState::finalize_partial(&[state], &evaluated, &proof, &sk.public_key(), info)
+            .unwrap();
+    assert_eq!(
+        outputs[0][..],
+        [
+            61, 77, 221, 16, 181, 251, 5, 41, 22, 92, 127, 246, 162, 241, 199, 174, 183, 157, 255,
+            102, 74, 129, 116, 73, 213, 124, 172, 45, 117, 211, 176, 233, 114, 98, 18, 18, 141,
+            190, 238, 235, 224, 224, 234, 237, 51, 146, 243, 145, 114, 150, 55, 198, 140, 48, 34,
+            182, 25, 56, 117, 160, 217, 250, 181, 121
+        ][..]
+    );
+}
+
+#[test]
+fn test_spec_layout() {
+    // Recomputes the proofs from the byte layouts of RFC 9497, Section
+    // 2.2, with every hash input written out as one buffer.
+    fn hash_to_scalar(mode: Mode, msg: &[u8]) -> Fr {
+        let dst = [b"HashToScalar-" as &[u8], &mode.context_string()].concat();
+        hash_to_scalar_with_dst(&[msg], &dst)
+    }
+    fn composites(
+        mode: Mode,
+        b: &AffinePoint,
+        c: &[AffinePoint],
+        d: &[AffinePoint],
+    ) -> (ExtendedPoint, ExtendedPoint) {
+        let seed_dst = [b"Seed-" as &[u8], &mode.context_string()].concat();
+        let mut h1_input = vec![0, 32];
+        h1_input.extend_from_slice(&b.into_bytes());
+        h1_input.extend_from_slice(&[0, seed_dst.len() as u8]);
+        h1_input.extend_from_slice(&seed_dst);
+        let seed = Sha512::digest(&h1_input);
+
+        let mut m = ExtendedPoint::identity();
+        let mut z = ExtendedPoint::identity();
+        for (i, (c_i, d_i)) in c.iter().zip(d.iter()).enumerate() {
+            let mut h2_input = vec![0, 64];
+            h2_input.extend_from_slice(&seed);
+            h2_input.extend_from_slice(&[0, i as u8, 0, 32]);
+            h2_input.extend_from_slice(&c_i.into_bytes());
+            h2_input.extend_from_slice(&[0, 32]);
+            h2_input.extend_from_slice(&d_i.into_bytes());
+            h2_input.extend_from_slice(b"Composite");
+            let d_i_weight = hash_to_scalar(mode, &h2_input);
+            m += ExtendedPoint::from(*c_i) * d_i_weight;
+            z += ExtendedPoint::from(*d_i) * d_i_weight;
+        }
+
+        (m, z)
+    }
+    fn check_proof(
+        mode: Mode,
+        k: &Fr,
+        b: &AffinePoint,
+        c: &[AffinePoint],
+        d: &[AffinePoint],
+        r: &Fr,
+        proof: &Proof,
+    ) {
+        let (m, z) = composites(mode, b, c, d);
+        assert_eq!(AffinePoint::from(m * k), AffinePoint::from(z));
+        let t2 = ExtendedPoint::from(GENERATOR) * r;
+        let t3 = m * r;
+
+        let mut challenge_transcript = Vec::new();
+        for point in [ExtendedPoint::from(*b), m, z, t2, t3].iter() {
+            challenge_transcript.extend_from_slice(&[0, 32]);
+            challenge_transcript.extend_from_slice(&AffinePoint::from(*point).into_bytes());
+        }
+        challenge_transcript.extend_from_slice(b"Challenge");
+        let challenge = hash_to_scalar(mode, &challenge_transcript);
+
+        assert_eq!(proof.c, challenge);
+        assert_eq!(proof.s, r - challenge * k);
+    }
+
+    let seed = [0xa3; 32];
+    let blinds = [
+        Fr::from(0x1234_5678_9abc_def0),
+        Fr::from(0x0fed_cba9_8765_4321),
+    ];
+    let r = Fr::from(0x0123_4567_89ab_cdef);
+    let blinded: Vec<BlindedElement> = [b"first" as &[u8], b"second"]
+        .iter()
+        .zip(blinds.iter())
+        .map(|(input, blind)| {
+            ClientState::blind_with(Mode::Voprf, input, *blind)
+                .unwrap()
+                .1
+        })
+        .collect();
+    let c: Vec<AffinePoint> = blinded.iter().map(|b| b.0).collect();
+
+    // In the VOPRF mode, D_i = skS * C_i and B = pkS.
+    let sk = SecretKey::derive(Mode::Voprf, &seed, b"test key").unwrap();
+    let (evaluated, proof) = sk.blind_evaluate_verifiable_with(&blinded, r);
+    let d: Vec<AffinePoint> = evaluated.iter().map(|e| e.0).collect();
+    check_proof(Mode::Voprf, &sk.sk, &sk.public_key.0, &c, &d, &r, &proof);
+
+    // In the POPRF mode, m = HashToScalar("Info" || I2OSP(len(info), 2)
+    // || info), t = skS + m, the evaluated elements are t^-1 * C_i and
+    // the proof is for t with B = t * G, the evaluated elements first.
+    let sk = SecretKey::derive(Mode::Poprf, &seed, b"test key").unwrap();
+    let info = b"test info";
+    let mut framed_info = b"Info".to_vec();
+    framed_info.extend_from_slice(&[0, info.len() as u8]);
+    framed_info.extend_from_slice(info);
+    let t = sk.sk + hash_to_scalar(Mode::Poprf, &framed_info);
+    let tweaked = AffinePoint::from(ExtendedPoint::from(GENERATOR) * t);
+    assert_eq!(tweak(&sk.public_key.0, info).unwrap().1, tweaked);
+
+    let (evaluated, proof) = sk.blind_evaluate_partial_with(&blinded, info, r).unwrap();
+    let d: Vec<AffinePoint> = evaluated.iter().map(|e| e.0).collect();
+    for (c_i, d_i) in c.iter().zip(d.iter()) {
+        assert_eq!(AffinePoint::from(ExtendedPoint::from(*d_i) * t), *c_i);
+    }
+    check_proof(Mode::Poprf, &t, &tweaked, &d, &c, &r, &proof);
+}
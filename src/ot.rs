@@ -0,0 +1,378 @@
+//! This module implements 1-out-of-2 oblivious transfer over the prime
+//! order subgroup of Doppio, following the "Simplest OT" protocol of
+//! Chou and Orlandi. It is intended to produce the base OTs of an OT
+//! extension.
+//!
+//! The sender samples `a` and sends `A = a * G`, which can be reused
+//! for a whole batch of OTs. For each OT with choice bit `c`, the
+//! receiver samples `b` and sends
+//!
+//! ```text
+//! B = b * G + c * [4] A
+//! ```
+//!
+//! The receiver learns the key derived from `b * [4] A`, and the sender
+//! derives the keys of both choices from `[4] (a * (B - i * [4] A))`
+//! for `i` in `{0, 1}`. Every key is
+//!
+//! ```text
+//! SHA-512("DOPPIO-SIMPLEST-OT-SHA512-v1" || index || A || B || shared)[..32]
+//! ```
+//!
+//! where `index` is the little endian 64-bit index of the OT among
+//! those using the same `A`, and `shared` is the encoding of the shared
+//! point. Because the receiver uses `[4] A`, its message is independent
+//! of its choice even if `A` has a small order component, and because
+//! the sender multiplies by the cofactor, small order components of `B`
+//! are cleared. Messages whose shared points are the identity are
+//! rejected.
+//!
+//! The choice bits are [`Choice`]s and are only used through constant
+//! time selection. The batched APIs normalize all points of a batch
+//! together with [`batch_normalize`].
+
+use core::fmt;
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::{batch_normalize, AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The context string used to derive keys.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-SIMPLEST-OT-SHA512-v1";
+
+/// The message `A = a * G` of the sender.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SenderMessage(AffinePoint);
+
+/// The message `B` of the receiver for one OT.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReceiverMessage(AffinePoint);
+
+/// A key derived by an OT. The sender obtains a pair of keys and the
+/// receiver the key of its choice.
+pub struct Key([u8; 32]);
+
+/// The state of the sender, holding the secret `a`.
+pub struct Sender {
+    a: Fr,
+    message: SenderMessage,
+    a4: ExtendedPoint,
+}
+
+/// Derives the key of the OT at `index` from its messages and shared
+/// point.
+fn derive_key(index: u64, a: &AffinePoint, b: &AffinePoint, shared: &AffinePoint) -> Key {
+    let mut shared = shared.into_bytes();
+    let digest = Sha512::new()
+        .chain_update(CONTEXT_STRING)
+        .chain_update(index.to_le_bytes())
+        .chain_update(a.into_bytes())
+        .chain_update(b.into_bytes())
+        .chain_update(shared)
+        .finalize();
+    shared.zeroize();
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&digest[..32]);
+
+    Key(key)
+}
+
+impl SenderMessage {
+    /// Attempts to interpret a byte representation of a sender message,
+    /// failing if it is not the canonical encoding of a curve point.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<SenderMessage> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+
+        CtOption::new(
+            SenderMessage(point.unwrap_or(AffinePoint::identity())),
+            is_some,
+        )
+    }
+
+    /// Converts this message into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+impl ReceiverMessage {
+    /// Attempts to interpret a byte representation of a receiver
+    /// message, failing if it is not the canonical encoding of a curve
+    /// point.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<ReceiverMessage> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+
+        CtOption::new(
+            ReceiverMessage(point.unwrap_or(AffinePoint::identity())),
+            is_some,
+        )
+    }
+
+    /// Converts this message into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+impl Sender {
+    /// Samples a new sender from the provided RNG.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Sender {
+        let a = Fr::random(rng);
+        let point = ExtendedPoint::from(GENERATOR) * a;
+
+        Sender {
+            a,
+            message: SenderMessage(AffinePoint::from(point)),
+            a4: point.mul_by_cofactor(),
+        }
+    }
+
+    /// Returns the message `A` to send to the receiver.
+    pub fn message(&self) -> SenderMessage {
+        self.message
+    }
+
+    /// Computes the shared points `[4] (a * (B - i * [4] A))` for each
+    /// message, returning them with whether none of them is the
+    /// identity.
+    fn shared_points(&self, messages: &[ReceiverMessage]) -> (Vec<AffinePoint>, Choice) {
+        let mut is_valid = Choice::from(1u8);
+        let mut points = Vec::with_capacity(2 * messages.len());
+        for message in messages {
+            let b = ExtendedPoint::from(message.0);
+            for shared in [b * self.a, (b - self.a4) * self.a].iter() {
+                let shared = shared.mul_by_cofactor();
+                is_valid &= !shared.is_identity();
+                points.push(shared);
+            }
+        }
+
+        (batch_normalize(&mut points).collect(), is_valid)
+    }
+
+    /// Derives the keys of both choices for the OT at `index` from the
+    /// receiver's message. This returns `None` if one of the shared
+    /// points is the identity.
+    pub fn keys(&self, index: u64, message: &ReceiverMessage) -> CtOption<[Key; 2]> {
+        let (shared, is_valid) = self.shared_points(core::slice::from_ref(message));
+        let keys = [
+            derive_key(index, &self.message.0, &message.0, &shared[0]),
+            derive_key(index, &self.message.0, &message.0, &shared[1]),
+        ];
+
+        CtOption::new(keys, is_valid)
+    }
+
+    /// Derives the keys of both choices for a batch of OTs, where the
+    /// OT at position `i` of `messages` has the index `i`. This returns
+    /// `None` if one of the shared points is the identity.
+    pub fn keys_batch(&self, messages: &[ReceiverMessage]) -> Option<Vec<[Key; 2]>> {
+        let (shared, is_valid) = self.shared_points(messages);
+        if !bool::from(is_valid) {
+            return None;
+        }
+
+        Some(
+            messages
+                .iter()
+                .zip(shared.chunks(2))
+                .enumerate()
+                .map(|(i, (message, shared))| {
+                    [
+                        derive_key(i as u64, &self.message.0, &message.0, &shared[0]),
+                        derive_key(i as u64, &self.message.0, &message.0, &shared[1]),
+                    ]
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        self.a.zeroize();
+    }
+}
+
+impl fmt::Debug for Sender {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+/// Computes the receiver's messages `B` and shared points `b * [4] A`
+/// for each choice, interleaved, returning them with whether `[4] A` is
+/// not the identity.
+fn receiver_points<R: RngCore + CryptoRng>(
+    message: &SenderMessage,
+    choices: &[Choice],
+    rng: &mut R,
+) -> (Vec<AffinePoint>, Choice) {
+    let a4 = ExtendedPoint::from(message.0).mul_by_cofactor();
+    let is_valid = !a4.is_identity();
+
+    let mut points = Vec::with_capacity(2 * choices.len());
+    for choice in choices {
+        let mut b = Fr::random(&mut *rng);
+        let offset = ExtendedPoint::conditional_select(&ExtendedPoint::identity(), &a4, *choice);
+        points.push(ExtendedPoint::from(GENERATOR) * b + offset);
+        points.push(a4 * b);
+        b.zeroize();
+    }
+
+    (batch_normalize(&mut points).collect(), is_valid)
+}
+
+/// Runs the receiver's side of the OT at `index` with the choice bit
+/// `choice`, returning the message to send to the sender and the key of
+/// the chosen message. This returns `None` if `[4] A` is the identity.
+pub fn receive<R: RngCore + CryptoRng>(
+    message: &SenderMessage,
+    index: u64,
+    choice: Choice,
+    rng: &mut R,
+) -> CtOption<(ReceiverMessage, Key)> {
+    let (points, is_valid) = receiver_points(message, &[choice], rng);
+    let key = derive_key(index, &message.0, &points[0], &points[1]);
+
+    CtOption::new((ReceiverMessage(points[0]), key), is_valid)
+}
+
+/// Runs the receiver's side of a batch of OTs, where the OT at position
+/// `i` of `choices` has the index `i`, returning the messages to send to
+/// the sender and the keys of the chosen messages. This returns `None`
+/// if `[4] A` is the identity.
+pub fn receive_batch<R: RngCore + CryptoRng>(
+    message: &SenderMessage,
+    choices: &[Choice],
+    rng: &mut R,
+) -> Option<(Vec<ReceiverMessage>, Vec<Key>)> {
+    let (points, is_valid) = receiver_points(message, choices, rng);
+    if !bool::from(is_valid) {
+        return None;
+    }
+
+    Some(
+        points
+            .chunks(2)
+            .enumerate()
+            .map(|(i, points)| {
+                (
+                    ReceiverMessage(points[0]),
+                    derive_key(i as u64, &message.0, &points[0], &points[1]),
+                )
+            })
+            .unzip(),
+    )
+}
+
+impl Key {
+    /// Returns the bytes of this key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl ConstantTimeEq for Key {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Key").finish()
+    }
+}
+
+#[test]
+fn test_ot() {
+    let mut rng = crate::test_rng();
+    let sender = Sender::new(&mut rng);
+
+    for choice in 0..2u8 {
+        let (message, key) = receive(&sender.message(), 5, Choice::from(choice), &mut rng).unwrap();
+        let keys = sender.keys(5, &message).unwrap();
+
+        let chosen = usize::from(choice);
+        assert!(bool::from(key.ct_eq(&keys[chosen])));
+        assert!(!bool::from(key.ct_eq(&keys[1 - chosen])));
+
+        // Keys are bound to the index of the OT.
+        let other = sender.keys(6, &message).unwrap();
+        assert!(!bool::from(key.ct_eq(&other[chosen])));
+    }
+}
+
+#[test]
+fn test_batch() {
+    let mut rng = crate::test_rng();
+    let sender = Sender::new(&mut rng);
+    let choices: Vec<Choice> = (0..10u8)
+        .map(|i| Choice::from((i % 3 == 0) as u8))
+        .collect();
+
+    let (messages, keys) = receive_batch(&sender.message(), &choices, &mut rng).unwrap();
+    let sender_keys = sender.keys_batch(&messages).unwrap();
+    assert_eq!(sender_keys.len(), choices.len());
+
+    for (i, choice) in choices.iter().enumerate() {
+        let chosen = usize::from(choice.unwrap_u8());
+        assert!(bool::from(keys[i].ct_eq(&sender_keys[i][chosen])));
+        assert!(!bool::from(keys[i].ct_eq(&sender_keys[i][1 - chosen])));
+
+        // The batched and single APIs agree.
+        let single = sender.keys(i as u64, &messages[i]).unwrap();
+        assert!(bool::from(single[chosen].ct_eq(&sender_keys[i][chosen])));
+    }
+}
+
+#[test]
+fn test_small_order() {
+    let mut rng = crate::test_rng();
+    let sender = Sender::new(&mut rng);
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+
+    for point in [AffinePoint::identity(), two_torsion].iter() {
+        let message = SenderMessage(*point);
+        assert!(bool::from(
+            receive(&message, 0, Choice::from(1), &mut rng).is_none()
+        ));
+        assert!(receive_batch(&message, &[Choice::from(0)], &mut rng).is_none());
+
+        let message = ReceiverMessage(*point);
+        assert!(bool::from(sender.keys(0, &message).is_none()));
+        assert!(sender.keys_batch(&[message]).is_none());
+    }
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let sender = Sender::new(&mut rng);
+    let (message, _) = receive(&sender.message(), 0, Choice::from(1), &mut rng).unwrap();
+
+    assert_eq!(
+        SenderMessage::from_bytes(sender.message().into_bytes()).unwrap(),
+        sender.message()
+    );
+    assert_eq!(
+        ReceiverMessage::from_bytes(message.into_bytes()).unwrap(),
+        message
+    );
+}
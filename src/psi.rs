@@ -0,0 +1,258 @@
+//! This module implements Diffie-Hellman private set intersection over
+//! the prime order subgroup of Doppio, secure against semi-honest
+//! parties.
+//!
+//! Each party holds a [`SecretKey`] `k` and hashes its items to points
+//! with `hash_to_curve` under the domain separation tag
+//! `"DOPPIO_XMD:SHA-512_ELL2_RO_PSI"`. The protocol between Alice and Bob
+//! is:
+//!
+//! 1. Alice sends the blinded items `k_A * H(a_i)`;
+//! 2. Bob sends the blinded items `k_B * H(b_j)` together with Alice's
+//!    items evaluated under `k_B`, `[4] (k_B * k_A * H(a_i))`, in the
+//!    order in which they were received;
+//! 3. Alice evaluates Bob's items under `k_A` and compares the results
+//!    with [`intersection`], learning which of Alice's items Bob holds.
+//!
+//! In the cardinality-only mode, Bob shuffles Alice's evaluated items
+//! with [`SecretKey::evaluate_shuffled`], so that Alice only learns the
+//! size of the intersection with [`cardinality`].
+//!
+//! Evaluated items are compared through the canonical encodings given
+//! by [`AffinePoint::into_bytes`](crate::AffinePoint::into_bytes), which
+//! are computed for a whole set at once with [`batch_normalize`].
+//! Blinded items of small order are rejected when decoded, and the
+//! evaluation is multiplied by the cofactor so that the small order
+//! components of other points reveal nothing about the secret keys.
+
+use core::fmt;
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use subtle::{ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_curve_parts;
+use crate::helpers::uniform_index;
+use crate::{batch_normalize, AffinePoint, CtOption, ExtendedPoint, Fr};
+
+/// The domain separation tag used to hash items to points.
+const HASH_TO_CURVE_DST: &[u8] = b"DOPPIO_XMD:SHA-512_ELL2_RO_PSI";
+
+/// The secret key `k` of a party, used to blind its own items and to
+/// evaluate the blinded items of the other party.
+#[derive(Clone)]
+pub struct SecretKey(Fr);
+
+/// An item blinded under the key of its owner, `k * H(item)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlindedItem(AffinePoint);
+
+/// A blinded item evaluated under the key of the other party,
+/// represented by the encoding of `[4] (k_B * k_A * H(item))`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EvaluatedItem([u8; 32]);
+
+impl SecretKey {
+    /// Samples a new secret key from the provided RNG.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> SecretKey {
+        SecretKey(Fr::random(rng))
+    }
+
+    /// Attempts to interpret a byte representation of a secret key,
+    /// failing if it is not a canonical encoding of a nonzero element of
+    /// `Fr`. The zero key would map every item to the identity.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<SecretKey> {
+        let scalar = Fr::from_bytes(bytes);
+        let is_some = scalar.is_some();
+        let scalar = scalar.unwrap_or(Fr::zero());
+        let is_some = is_some & !scalar.ct_eq(&Fr::zero());
+
+        CtOption::new(
+            SecretKey(Fr::conditional_select(&Fr::one(), &scalar, is_some)),
+            is_some,
+        )
+    }
+
+    /// Converts this secret key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+
+    /// Blinds `items` under this key, preserving their order.
+    pub fn blind<I: AsRef<[u8]>>(&self, items: &[I]) -> Vec<BlindedItem> {
+        let mut points: Vec<ExtendedPoint> = items
+            .iter()
+            .map(|item| hash_to_curve_parts(&[item.as_ref()], HASH_TO_CURVE_DST) * self.0)
+            .collect();
+
+        batch_normalize(&mut points).map(BlindedItem).collect()
+    }
+
+    /// Evaluates the blinded items of the other party under this key,
+    /// preserving their order so that the other party learns which of
+    /// its items are in the intersection.
+    pub fn evaluate(&self, blinded: &[BlindedItem]) -> Vec<EvaluatedItem> {
+        let mut points: Vec<ExtendedPoint> = blinded
+            .iter()
+            .map(|item| (ExtendedPoint::from(item.0) * self.0).mul_by_cofactor())
+            .collect();
+
+        batch_normalize(&mut points)
+            .map(|p| EvaluatedItem(p.into_bytes()))
+            .collect()
+    }
+
+    /// Evaluates the blinded items of the other party under this key and
+    /// shuffles the result, so that the other party only learns the size
+    /// of the intersection.
+    pub fn evaluate_shuffled<R: RngCore + CryptoRng>(
+        &self,
+        blinded: &[BlindedItem],
+        rng: &mut R,
+    ) -> Vec<EvaluatedItem> {
+        let mut evaluated = self.evaluate(blinded);
+        for i in (1..evaluated.len()).rev() {
+            evaluated.swap(i, uniform_index(rng, i + 1));
+        }
+
+        evaluated
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SecretKey").finish()
+    }
+}
+
+impl BlindedItem {
+    /// Attempts to interpret a byte representation of a blinded item,
+    /// failing if it is not the canonical encoding of a curve point or
+    /// if the point is of small order.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<BlindedItem> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+        let point = point.unwrap_or(AffinePoint::identity());
+
+        CtOption::new(BlindedItem(point), is_some & !point.is_small_order())
+    }
+
+    /// Converts this blinded item into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+}
+
+impl EvaluatedItem {
+    /// Interprets a byte representation of an evaluated item.
+    pub fn from_bytes(bytes: [u8; 32]) -> EvaluatedItem {
+        EvaluatedItem(bytes)
+    }
+
+    /// Converts this evaluated item into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+/// Returns the indices of the items in `own`, the evaluations of our
+/// items by the other party, that also appear in `theirs`, our
+/// evaluations of the items of the other party.
+pub fn intersection(own: &[EvaluatedItem], theirs: &[EvaluatedItem]) -> Vec<usize> {
+    let mut theirs = theirs.to_vec();
+    theirs.sort_unstable();
+
+    own.iter()
+        .enumerate()
+        .filter(|(_, item)| theirs.binary_search(item).is_ok())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns the size of the intersection of the sets of evaluated items
+/// `own` and `theirs`, which may be shuffled.
+pub fn cardinality(own: &[EvaluatedItem], theirs: &[EvaluatedItem]) -> usize {
+    intersection(own, theirs).len()
+}
+
+#[cfg(test)]
+fn test_sets() -> (Vec<&'static str>, Vec<&'static str>) {
+    (
+        vec!["alice", "bob", "carol", "dave", "erin", "frank"],
+        vec!["grace", "erin", "heidi", "bob", "ivan", "alice", "judy"],
+    )
+}
+
+#[test]
+fn test_intersection() {
+    let mut rng = crate::test_rng();
+    let (alice_items, bob_items) = test_sets();
+    let alice = SecretKey::new(&mut rng);
+    let bob = SecretKey::new(&mut rng);
+
+    let alice_blinded = alice.blind(&alice_items);
+    let bob_blinded = bob.blind(&bob_items);
+    let alice_evaluated = bob.evaluate(&alice_blinded);
+    let bob_evaluated = alice.evaluate(&bob_blinded);
+
+    assert_eq!(
+        intersection(&alice_evaluated, &bob_evaluated),
+        vec![0, 1, 4]
+    );
+    assert_eq!(
+        intersection(&bob_evaluated, &alice_evaluated),
+        vec![1, 3, 5]
+    );
+
+    // Items blinded under a different key do not match.
+    let other = SecretKey::new(&mut rng);
+    let other_evaluated = other.evaluate(&bob_blinded);
+    assert!(intersection(&alice_evaluated, &other_evaluated).is_empty());
+}
+
+#[test]
+fn test_cardinality() {
+    let mut rng = crate::test_rng();
+    let (alice_items, bob_items) = test_sets();
+    let alice = SecretKey::new(&mut rng);
+    let bob = SecretKey::new(&mut rng);
+
+    let alice_evaluated = bob.evaluate_shuffled(&alice.blind(&alice_items), &mut rng);
+    let bob_evaluated = alice.evaluate(&bob.blind(&bob_items));
+    assert_eq!(alice_evaluated.len(), alice_items.len());
+    assert_eq!(cardinality(&alice_evaluated, &bob_evaluated), 3);
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let key = SecretKey::new(&mut rng);
+    let blinded = key.blind(&["alice"]);
+
+    assert_eq!(
+        BlindedItem::from_bytes(blinded[0].into_bytes()).unwrap(),
+        blinded[0]
+    );
+    assert_eq!(
+        SecretKey::from_bytes(key.into_bytes())
+            .unwrap()
+            .blind(&["alice"]),
+        blinded
+    );
+    assert!(bool::from(SecretKey::from_bytes([0; 32]).is_none()));
+
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+    assert!(bool::from(
+        BlindedItem::from_bytes(AffinePoint::identity().into_bytes()).is_none()
+    ));
+    assert!(bool::from(
+        BlindedItem::from_bytes(two_torsion.into_bytes()).is_none()
+    ));
+}
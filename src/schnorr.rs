@@ -0,0 +1,283 @@
+//! This module implements Schnorr signatures over the prime order subgroup
+//! of Doppio.
+//!
+//! A signature on `msg` under the public key `PK = x * G` is `(R, z)` with
+//! `R = k * G` and `z = k + c * x`, where the challenge is
+//!
+//! ```text
+//! c = Fr::from_bytes_wide(SHA-512(CONTEXT_STRING || "chal" || R || PK || msg))
+//! ```
+//!
+//! and points are encoded with [`AffinePoint::into_bytes`]. A signature is
+//! encoded as `R || z` in 64 bytes. The nonce `k` is derived as in
+//! RFC 9591 by hashing 32 random bytes together with the secret key, so
+//! a weak RNG does not leak the key.
+//!
+//! Verification checks `[4](z * G) = [4](R + c * PK)`, so small order
+//! components of `R` and `PK` have no effect on the outcome, and public
+//! keys of small order are rejected when decoded. This is the
+//! single-signer verifier for signatures produced by the `frost` and
+//! `musig` modules.
+
+use core::fmt;
+
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_scalar;
+use crate::{AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The context string prefixed to every hash computed by this module.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-SCHNORR-SHA512-v1";
+
+/// Computes the challenge `c` for the nonce commitment `r`, the public
+/// key `pk` and the message `msg`.
+pub(crate) fn challenge(r: &AffinePoint, pk: &AffinePoint, msg: &[u8]) -> Fr {
+    hash_to_scalar(&[
+        CONTEXT_STRING,
+        b"chal",
+        &r.into_bytes(),
+        &pk.into_bytes(),
+        msg,
+    ])
+}
+
+/// Derives a nonce from 32 random bytes and `secret`, following
+/// `nonce_generate` from RFC 9591.
+pub(crate) fn nonce_generate<R: RngCore + CryptoRng>(
+    context: &[u8],
+    secret: &Fr,
+    rng: &mut R,
+) -> Fr {
+    let mut random_bytes = [0u8; 32];
+    rng.fill_bytes(&mut random_bytes);
+    let mut secret_bytes = secret.into_bytes();
+
+    let nonce = hash_to_scalar(&[context, b"nonce", &random_bytes, &secret_bytes]);
+    random_bytes.zeroize();
+    secret_bytes.zeroize();
+
+    nonce
+}
+
+/// A Schnorr signing key.
+#[derive(Clone)]
+pub struct SigningKey {
+    x: Fr,
+    verifying_key: VerifyingKey,
+}
+
+/// A Schnorr verifying key, which is the point `x * G`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VerifyingKey(AffinePoint);
+
+/// A Schnorr signature `(R, z)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Signature {
+    r: AffinePoint,
+    z: Fr,
+}
+
+impl SigningKey {
+    /// Samples a new signing key from the provided RNG.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> SigningKey {
+        SigningKey::from_scalar(Fr::random(rng))
+    }
+
+    pub(crate) fn from_scalar(x: Fr) -> SigningKey {
+        let verifying_key = VerifyingKey(AffinePoint::from(ExtendedPoint::from(GENERATOR) * x));
+
+        SigningKey { x, verifying_key }
+    }
+
+    /// Attempts to interpret a byte representation of a signing key,
+    /// failing if it is not a canonical encoding of a nonzero element
+    /// of `Fr`.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<SigningKey> {
+        let scalar = Fr::from_bytes(bytes);
+        let is_some = scalar.is_some();
+        let scalar = scalar.unwrap_or(Fr::zero());
+        let is_some = is_some & !scalar.ct_eq(&Fr::zero());
+
+        CtOption::new(
+            SigningKey::from_scalar(Fr::conditional_select(&Fr::one(), &scalar, is_some)),
+            is_some,
+        )
+    }
+
+    /// Converts this signing key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.x.into_bytes()
+    }
+
+    /// Returns the secret scalar `x` of this signing key.
+    pub(crate) fn scalar(&self) -> &Fr {
+        &self.x
+    }
+
+    /// Returns the verifying key for this signing key.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.verifying_key
+    }
+
+    /// Signs `msg`, using the provided RNG for the nonce.
+    pub fn sign<R: RngCore + CryptoRng>(&self, msg: &[u8], rng: &mut R) -> Signature {
+        let mut k = nonce_generate(CONTEXT_STRING, &self.x, rng);
+        let r = AffinePoint::from(ExtendedPoint::from(GENERATOR) * k);
+        let c = challenge(&r, &self.verifying_key.0, msg);
+        let z = k + c * self.x;
+        k.zeroize();
+
+        Signature { r, z }
+    }
+}
+
+impl Drop for SigningKey {
+    fn drop(&mut self) {
+        self.x.zeroize();
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("verifying_key", &self.verifying_key)
+            .finish()
+    }
+}
+
+impl<'a> From<&'a SigningKey> for VerifyingKey {
+    fn from(sk: &'a SigningKey) -> VerifyingKey {
+        sk.verifying_key
+    }
+}
+
+impl VerifyingKey {
+    /// Attempts to interpret a byte representation of a verifying key,
+    /// failing if it is not the canonical encoding of a curve point or
+    /// if the point is of small order.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<VerifyingKey> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some() & !point.unwrap_or(AffinePoint::identity()).is_small_order();
+
+        CtOption::new(
+            VerifyingKey(point.unwrap_or(AffinePoint::identity())),
+            is_some,
+        )
+    }
+
+    /// Constructs a verifying key from a point, which the caller must
+    /// ensure is not of small order.
+    #[cfg(feature = "std")]
+    pub(crate) fn from_point(point: AffinePoint) -> VerifyingKey {
+        VerifyingKey(point)
+    }
+
+    /// Converts this verifying key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+
+    /// Returns the point underlying this verifying key.
+    pub fn to_point(&self) -> AffinePoint {
+        self.0
+    }
+
+    /// Checks that `signature` is a valid signature on `msg` under this
+    /// verifying key.
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> bool {
+        let c = challenge(&signature.r, &self.0, msg);
+        let lhs = ExtendedPoint::from(GENERATOR) * signature.z;
+        let rhs = ExtendedPoint::from(signature.r) + ExtendedPoint::from(self.0) * c;
+
+        bool::from((lhs - rhs).mul_by_cofactor().is_identity())
+    }
+}
+
+impl ConstantTimeEq for VerifyingKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl Signature {
+    /// Constructs a signature from its components.
+    pub(crate) fn from_parts(r: AffinePoint, z: Fr) -> Signature {
+        Signature { r, z }
+    }
+
+    /// Returns the nonce commitment `R` of this signature.
+    pub fn r(&self) -> AffinePoint {
+        self.r
+    }
+
+    /// Returns the response `z` of this signature.
+    pub fn z(&self) -> Fr {
+        self.z
+    }
+
+    /// Attempts to interpret the 64-byte representation `R || z` of a
+    /// signature, failing if either component is not canonically encoded.
+    pub fn from_bytes(bytes: [u8; 64]) -> CtOption<Signature> {
+        let mut r = [0u8; 32];
+        let mut z = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        z.copy_from_slice(&bytes[32..]);
+
+        let r = AffinePoint::from_bytes(r);
+        let z = Fr::from_bytes(z);
+        let is_some = r.is_some() & z.is_some();
+
+        CtOption::new(
+            Signature {
+                r: r.unwrap_or(AffinePoint::identity()),
+                z: z.unwrap_or(Fr::zero()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this signature into its 64-byte representation `R || z`.
+    pub fn into_bytes(&self) -> [u8; 64] {
+        let mut res = [0u8; 64];
+        res[..32].copy_from_slice(&self.r.into_bytes());
+        res[32..].copy_from_slice(&self.z.into_bytes());
+
+        res
+    }
+}
+
+#[test]
+fn test_sign_verify() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let vk = sk.verifying_key();
+
+    let signature = sk.sign(b"message", &mut rng);
+    assert!(vk.verify(b"message", &signature));
+    assert!(!vk.verify(b"other message", &signature));
+
+    let other = SigningKey::new(&mut rng).verifying_key();
+    assert!(!other.verify(b"message", &signature));
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let sk = SigningKey::new(&mut rng);
+    let decoded = SigningKey::from_bytes(sk.into_bytes()).unwrap();
+    assert_eq!(decoded.verifying_key(), sk.verifying_key());
+    assert!(SigningKey::from_bytes([0u8; 32]).is_none().unwrap_u8() == 1);
+
+    let vk = VerifyingKey::from_bytes(sk.verifying_key().into_bytes()).unwrap();
+    assert_eq!(vk, sk.verifying_key());
+    let identity = AffinePoint::identity().into_bytes();
+    assert!(VerifyingKey::from_bytes(identity).is_none().unwrap_u8() == 1);
+
+    let signature = sk.sign(b"message", &mut rng);
+    assert_eq!(
+        Signature::from_bytes(signature.into_bytes()).unwrap(),
+        signature
+    );
+}
@@ -0,0 +1,698 @@
+//! This module implements Shamir secret sharing over `Fr`, along with
+//! Feldman and Pedersen commitments that make the sharing verifiable.
+//!
+//! A secret `s` is shared with threshold `t` by sampling a polynomial
+//! `f(x) = s + a_1 x + ... + a_{t-1} x^{t-1}` and giving the party with
+//! index `i` the share `f(i)`. Any `t` shares determine `s` through
+//! Lagrange interpolation at zero, while fewer reveal nothing about it.
+//! Indices are nonzero `u32`s.
+//!
+//! * A [`FeldmanCommitment`] to `f` is the list of points `a_j * G`. It
+//!   reveals `s * G`, which is usually the public key being shared.
+//! * A [`PedersenCommitment`] to `f` with a blinding polynomial `g` is the
+//!   list of points `a_j * G + b_j * H`, where `H` is
+//!   [`pedersen_generator`]. It hides `s` unconditionally.
+//!
+//! Shares are checked against commitments with a single multiscalar
+//! multiplication, and the check is multiplied by the cofactor, so
+//! small order components in received commitments have no effect.
+//!
+//! Shares can be refreshed by adding shares of a random polynomial with
+//! a zero constant term, which changes every share but not the secret.
+
+use core::fmt;
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::helpers::read_point;
+use crate::{
+    hash_to_curve, multiscalar_mul, multiscalar_mul_vartime, CtOption, ExtendedPoint, Fr, GENERATOR,
+};
+
+/// The domain separation tag used to derive [`pedersen_generator`].
+const PEDERSEN_DST: &[u8] = b"DOPPIO_XMD:SHA-512_ELL2_RO_PEDERSEN_H";
+
+/// Returns the generator `H` used for the blinding polynomial of Pedersen
+/// commitments. It is obtained with [`hash_to_curve`], so its discrete
+/// logarithm with respect to `G` is unknown.
+pub fn pedersen_generator() -> ExtendedPoint {
+    hash_to_curve(b"H", PEDERSEN_DST)
+}
+
+/// A polynomial over `Fr`, whose coefficients are zeroized when dropped.
+#[derive(Clone)]
+pub struct Polynomial {
+    coefficients: Vec<Fr>,
+}
+
+impl fmt::Debug for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Polynomial")
+            .field("threshold", &self.threshold())
+            .finish()
+    }
+}
+
+impl Drop for Polynomial {
+    fn drop(&mut self) {
+        for c in self.coefficients.iter_mut() {
+            c.zeroize();
+        }
+    }
+}
+
+impl Polynomial {
+    /// Samples a random polynomial of degree `threshold - 1` with the
+    /// constant term `secret`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero.
+    pub fn random<R: RngCore + CryptoRng>(
+        secret: &Fr,
+        threshold: usize,
+        rng: &mut R,
+    ) -> Polynomial {
+        assert!(threshold > 0);
+
+        let mut coefficients = Vec::with_capacity(threshold);
+        coefficients.push(*secret);
+        coefficients.extend((1..threshold).map(|_| Fr::random(&mut *rng)));
+
+        Polynomial { coefficients }
+    }
+
+    /// Samples a random polynomial of degree `threshold - 1` with a zero
+    /// constant term, for refreshing shares.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is zero.
+    pub fn random_refresh<R: RngCore + CryptoRng>(threshold: usize, rng: &mut R) -> Polynomial {
+        Polynomial::random(&Fr::zero(), threshold, rng)
+    }
+
+    /// Constructs a polynomial from its coefficients, starting with the
+    /// constant term.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `coefficients` is empty.
+    pub fn from_coefficients(coefficients: Vec<Fr>) -> Polynomial {
+        assert!(!coefficients.is_empty());
+
+        Polynomial { coefficients }
+    }
+
+    /// Returns the coefficients of this polynomial, starting with the
+    /// constant term.
+    pub fn coefficients(&self) -> &[Fr] {
+        &self.coefficients
+    }
+
+    /// Returns the number of shares needed to recover the constant term,
+    /// which is the degree plus one.
+    pub fn threshold(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// Evaluates this polynomial at `x` using Horner's rule.
+    pub fn evaluate(&self, x: &Fr) -> Fr {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Fr::zero(), |acc, c| acc * x + c)
+    }
+
+    /// Returns the share of the party with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is zero, as that share is the secret itself.
+    pub fn share(&self, index: u32) -> Share {
+        assert!(index != 0);
+
+        Share {
+            index,
+            value: self.evaluate(&Fr::from(u64::from(index))),
+        }
+    }
+
+    /// Returns the shares for the parties with indices `1..=n`.
+    pub fn shares(&self, n: u32) -> Vec<Share> {
+        (1..=n).map(|i| self.share(i)).collect()
+    }
+
+    /// Returns the Feldman commitment `a_j * G` to this polynomial.
+    pub fn commit(&self) -> FeldmanCommitment {
+        let g = ExtendedPoint::from(GENERATOR);
+
+        FeldmanCommitment {
+            points: self.coefficients.iter().map(|a| g * a).collect(),
+        }
+    }
+
+    /// Returns the Pedersen commitment `a_j * G + b_j * H` to this
+    /// polynomial, where `b_j` are the coefficients of `blinding`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `blinding` does not have the same threshold.
+    pub fn commit_pedersen(&self, blinding: &Polynomial) -> PedersenCommitment {
+        assert_eq!(self.threshold(), blinding.threshold());
+
+        let g = ExtendedPoint::from(GENERATOR);
+        let h = pedersen_generator();
+
+        PedersenCommitment {
+            points: self
+                .coefficients
+                .iter()
+                .zip(blinding.coefficients.iter())
+                .map(|(a, b)| multiscalar_mul(&[*a, *b], &[g, h]))
+                .collect(),
+        }
+    }
+}
+
+/// A share `f(i)` of a secret, held by the party with index `i`. The
+/// value is zeroized when dropped.
+#[derive(Clone)]
+pub struct Share {
+    index: u32,
+    value: Fr,
+}
+
+impl fmt::Debug for Share {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Share").field("index", &self.index).finish()
+    }
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl Share {
+    /// Constructs a share from an index and a value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is zero.
+    pub fn new(index: u32, value: Fr) -> Share {
+        assert!(index != 0);
+
+        Share { index, value }
+    }
+
+    /// Returns the index of the party holding this share.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the value of this share.
+    pub fn value(&self) -> &Fr {
+        &self.value
+    }
+
+    /// Adds `update`, a share of a polynomial with a zero constant term
+    /// for the same party, to this share. This returns `None` if the
+    /// indices differ.
+    pub fn refresh(&self, update: &Share) -> Option<Share> {
+        if self.index != update.index {
+            return None;
+        }
+
+        Some(Share {
+            index: self.index,
+            value: self.value + update.value,
+        })
+    }
+
+    /// Attempts to interpret the 36-byte representation of a share,
+    /// which is the little-endian index followed by the value encoded
+    /// with [`Fr::into_bytes`]. This fails if the index is zero or the
+    /// value is not canonically encoded.
+    pub fn from_bytes(bytes: [u8; 36]) -> CtOption<Share> {
+        let mut index = [0u8; 4];
+        let mut value = [0u8; 32];
+        index.copy_from_slice(&bytes[..4]);
+        value.copy_from_slice(&bytes[4..]);
+
+        let index = u32::from_le_bytes(index);
+        let value = Fr::from_bytes(value);
+        let is_some = value.is_some() & subtle::Choice::from((index != 0) as u8);
+
+        CtOption::new(
+            Share {
+                index,
+                value: value.unwrap_or(Fr::zero()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this share into its 36-byte representation.
+    pub fn into_bytes(&self) -> [u8; 36] {
+        let mut res = [0u8; 36];
+        res[..4].copy_from_slice(&self.index.to_le_bytes());
+        res[4..].copy_from_slice(&self.value.into_bytes());
+
+        res
+    }
+}
+
+/// Returns `[1, x, x^2, ..., x^(len - 1)]`.
+pub(crate) fn powers(x: &Fr, len: usize) -> Vec<Fr> {
+    let mut res = Vec::with_capacity(len);
+    let mut acc = Fr::one();
+    for _ in 0..len {
+        res.push(acc);
+        acc *= x;
+    }
+
+    res
+}
+
+/// Evaluates a committed polynomial in the exponent at `index`, and checks
+/// whether the result is equal to `sum_k scalars[k] * bases[k]` after
+/// clearing the cofactor.
+fn check_commitment(
+    points: &[ExtendedPoint],
+    index: u32,
+    scalars: &[Fr],
+    bases: &[ExtendedPoint],
+) -> bool {
+    let powers = powers(&Fr::from(u64::from(index)), points.len());
+    let expected = multiscalar_mul_vartime(&powers, points);
+    let actual = multiscalar_mul(scalars, bases);
+
+    bool::from((expected - actual).mul_by_cofactor().is_identity())
+}
+
+/// Encodes a list of points as the concatenation of their encodings.
+fn points_into_bytes(points: &[ExtendedPoint]) -> Vec<u8> {
+    let mut points = points.to_vec();
+    let mut res = Vec::with_capacity(32 * points.len());
+    for p in crate::batch_normalize(&mut points) {
+        res.extend_from_slice(&p.into_bytes());
+    }
+
+    res
+}
+
+/// Decodes a nonempty concatenation of point encodings.
+fn points_from_bytes(mut bytes: &[u8]) -> Option<Vec<ExtendedPoint>> {
+    if bytes.is_empty() || bytes.len() % 32 != 0 {
+        return None;
+    }
+
+    let mut points = Vec::with_capacity(bytes.len() / 32);
+    while !bytes.is_empty() {
+        points.push(ExtendedPoint::from(read_point(&mut bytes)?));
+    }
+
+    Some(points)
+}
+
+/// A Feldman commitment `[a_0 * G, ..., a_{t-1} * G]` to a polynomial.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeldmanCommitment {
+    points: Vec<ExtendedPoint>,
+}
+
+impl FeldmanCommitment {
+    /// Constructs a commitment from its points, starting with the
+    /// commitment to the constant term.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` is empty.
+    pub fn from_points(points: Vec<ExtendedPoint>) -> FeldmanCommitment {
+        assert!(!points.is_empty());
+
+        FeldmanCommitment { points }
+    }
+
+    /// Returns the points of this commitment.
+    pub fn points(&self) -> &[ExtendedPoint] {
+        &self.points
+    }
+
+    /// Returns the number of shares needed to recover the secret.
+    pub fn threshold(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns the commitment `s * G` to the shared secret.
+    pub fn public_key(&self) -> ExtendedPoint {
+        self.points[0]
+    }
+
+    /// Returns `f(index) * G`, the public counterpart of the share of
+    /// the party with the given index.
+    pub fn evaluate(&self, index: u32) -> ExtendedPoint {
+        let scalars = powers(&Fr::from(u64::from(index)), self.points.len());
+
+        multiscalar_mul_vartime(&scalars, &self.points)
+    }
+
+    /// Checks that `share` is consistent with this commitment.
+    pub fn verify(&self, share: &Share) -> bool {
+        let g = ExtendedPoint::from(GENERATOR);
+
+        check_commitment(&self.points, share.index, &[share.value], &[g])
+    }
+
+    /// Returns the commitment to the sum of the committed polynomials,
+    /// which is what shares refreshed with [`Share::refresh`] and
+    /// sums of shares verify against.
+    pub fn combine(&self, other: &FeldmanCommitment) -> FeldmanCommitment {
+        let (long, short) = if self.points.len() >= other.points.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        let mut points = long.points.clone();
+        for (p, q) in points.iter_mut().zip(short.points.iter()) {
+            *p += q;
+        }
+
+        FeldmanCommitment { points }
+    }
+
+    /// Attempts to interpret the byte representation of a commitment,
+    /// which is the concatenation of the encodings of its points.
+    pub fn from_bytes(bytes: &[u8]) -> Option<FeldmanCommitment> {
+        points_from_bytes(bytes).map(|points| FeldmanCommitment { points })
+    }
+
+    /// Converts this commitment into its byte representation.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        points_into_bytes(&self.points)
+    }
+}
+
+/// A Pedersen commitment `[a_0 * G + b_0 * H, ..., a_{t-1} * G + b_{t-1} * H]`
+/// to a polynomial and a blinding polynomial.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PedersenCommitment {
+    points: Vec<ExtendedPoint>,
+}
+
+impl PedersenCommitment {
+    /// Constructs a commitment from its points, starting with the
+    /// commitment to the constant terms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` is empty.
+    pub fn from_points(points: Vec<ExtendedPoint>) -> PedersenCommitment {
+        assert!(!points.is_empty());
+
+        PedersenCommitment { points }
+    }
+
+    /// Returns the points of this commitment.
+    pub fn points(&self) -> &[ExtendedPoint] {
+        &self.points
+    }
+
+    /// Returns the number of shares needed to recover the secret.
+    pub fn threshold(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Checks that `share` and the corresponding share `blinding` of the
+    /// blinding polynomial are consistent with this commitment.
+    pub fn verify(&self, share: &Share, blinding: &Share) -> bool {
+        if share.index != blinding.index {
+            return false;
+        }
+
+        let g = ExtendedPoint::from(GENERATOR);
+        let h = pedersen_generator();
+
+        check_commitment(
+            &self.points,
+            share.index,
+            &[share.value, blinding.value],
+            &[g, h],
+        )
+    }
+
+    /// Attempts to interpret the byte representation of a commitment,
+    /// which is the concatenation of the encodings of its points.
+    pub fn from_bytes(bytes: &[u8]) -> Option<PedersenCommitment> {
+        points_from_bytes(bytes).map(|points| PedersenCommitment { points })
+    }
+
+    /// Converts this commitment into its byte representation.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        points_into_bytes(&self.points)
+    }
+}
+
+/// Splits `secret` into `n` shares with the given threshold, returning
+/// the shares for indices `1..=n` and a Feldman commitment to them.
+///
+/// # Panics
+///
+/// Panics if `threshold` is zero or larger than `n`.
+pub fn split<R: RngCore + CryptoRng>(
+    secret: &Fr,
+    threshold: usize,
+    n: u32,
+    rng: &mut R,
+) -> (Vec<Share>, FeldmanCommitment) {
+    assert!(threshold <= n as usize);
+
+    let f = Polynomial::random(secret, threshold, rng);
+
+    (f.shares(n), f.commit())
+}
+
+/// Splits `secret` into `n` shares with the given threshold using
+/// Pedersen-VSS, returning the shares and blinding shares for indices
+/// `1..=n` and a Pedersen commitment to them.
+///
+/// # Panics
+///
+/// Panics if `threshold` is zero or larger than `n`.
+pub fn split_pedersen<R: RngCore + CryptoRng>(
+    secret: &Fr,
+    threshold: usize,
+    n: u32,
+    rng: &mut R,
+) -> (Vec<(Share, Share)>, PedersenCommitment) {
+    assert!(threshold <= n as usize);
+
+    let f = Polynomial::random(secret, threshold, rng);
+    let blinding = Polynomial::random(&Fr::random(&mut *rng), threshold, rng);
+    let shares = (1..=n).map(|i| (f.share(i), blinding.share(i))).collect();
+
+    (shares, f.commit_pedersen(&blinding))
+}
+
+/// Refreshes `shares`, which must share a secret with the given
+/// threshold, by adding shares of a random polynomial with a zero
+/// constant term. The returned Feldman commitment to that polynomial
+/// can be [combined](FeldmanCommitment::combine) with the original one
+/// to verify the refreshed shares.
+///
+/// # Panics
+///
+/// Panics if `threshold` is zero.
+pub fn refresh<R: RngCore + CryptoRng>(
+    shares: &[Share],
+    threshold: usize,
+    rng: &mut R,
+) -> (Vec<Share>, FeldmanCommitment) {
+    let update = Polynomial::random_refresh(threshold, rng);
+    let shares = shares
+        .iter()
+        .map(|s| Share {
+            index: s.index,
+            value: s.value + update.evaluate(&Fr::from(u64::from(s.index))),
+        })
+        .collect();
+
+    (shares, update.commit())
+}
+
+/// Returns the Lagrange coefficients `l_i(at)` for the points `x_i`, so
+/// that `f(at) = sum_i l_i(at) f(x_i)` for any polynomial `f` of degree
+/// less than `xs.len()`. This returns `None` if the points are not
+/// distinct.
+pub fn lagrange_coefficients(xs: &[Fr], at: &Fr) -> Option<Vec<Fr>> {
+    xs.iter()
+        .enumerate()
+        .map(|(i, x_i)| {
+            let mut num = Fr::one();
+            let mut den = Fr::one();
+            for (j, x_j) in xs.iter().enumerate() {
+                if i != j {
+                    num *= at - x_j;
+                    den *= x_i - x_j;
+                }
+            }
+
+            let inv = den.invert();
+            if bool::from(inv.is_some()) {
+                Some(num * inv.unwrap())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the Lagrange coefficients at zero for the parties with the
+/// given indices, which combine their shares into the secret. This
+/// returns `None` if the indices are not distinct.
+pub fn lagrange_coefficients_at_zero(indices: &[u32]) -> Option<Vec<Fr>> {
+    let xs: Vec<Fr> = indices.iter().map(|i| Fr::from(u64::from(*i))).collect();
+
+    lagrange_coefficients(&xs, &Fr::zero())
+}
+
+/// Interpolates the shared polynomial at `at` from `shares`. The result
+/// is only meaningful if there are at least as many shares as the
+/// threshold. This returns `None` if the share indices are not distinct.
+pub fn interpolate(shares: &[Share], at: &Fr) -> Option<Fr> {
+    let xs: Vec<Fr> = shares
+        .iter()
+        .map(|s| Fr::from(u64::from(s.index)))
+        .collect();
+    let coefficients = lagrange_coefficients(&xs, at)?;
+
+    Some(
+        coefficients
+            .iter()
+            .zip(shares.iter())
+            .fold(Fr::zero(), |acc, (l, s)| acc + l * s.value),
+    )
+}
+
+/// Recovers the secret from `shares` by interpolating at zero. This
+/// returns `None` if the share indices are not distinct.
+pub fn reconstruct(shares: &[Share]) -> Option<Fr> {
+    interpolate(shares, &Fr::zero())
+}
+
+#[test]
+fn test_reconstruct() {
+    let mut rng = crate::test_rng();
+    let secret = Fr::random(&mut rng);
+    let (shares, _) = split(&secret, 3, 5, &mut rng);
+
+    assert_eq!(reconstruct(&shares[..3]), Some(secret));
+    assert_eq!(reconstruct(&shares[2..]), Some(secret));
+    assert_eq!(reconstruct(&shares), Some(secret));
+    assert!(reconstruct(&shares[..2]) != Some(secret));
+
+    let duplicated = [shares[0].clone(), shares[0].clone(), shares[1].clone()];
+    assert_eq!(reconstruct(&duplicated), None);
+}
+
+#[test]
+fn test_interpolate() {
+    let mut rng = crate::test_rng();
+    let f = Polynomial::random(&Fr::random(&mut rng), 4, &mut rng);
+    let shares = [f.share(2), f.share(3), f.share(5), f.share(8)];
+
+    for x in [
+        Fr::from(1),
+        Fr::from(3),
+        Fr::from(1000),
+        Fr::random(&mut rng),
+    ]
+    .iter()
+    {
+        assert_eq!(interpolate(&shares, x), Some(f.evaluate(x)));
+    }
+
+    let l = lagrange_coefficients_at_zero(&[2, 3, 5, 8]).unwrap();
+    let secret = l
+        .iter()
+        .zip(shares.iter())
+        .fold(Fr::zero(), |acc, (l, s)| acc + l * s.value());
+    assert_eq!(secret, f.coefficients()[0]);
+}
+
+#[test]
+fn test_feldman() {
+    let mut rng = crate::test_rng();
+    let secret = Fr::random(&mut rng);
+    let (shares, commitment) = split(&secret, 3, 5, &mut rng);
+
+    assert_eq!(
+        commitment.public_key(),
+        ExtendedPoint::from(GENERATOR) * secret
+    );
+    for share in shares.iter() {
+        assert!(commitment.verify(share));
+        assert_eq!(
+            commitment.evaluate(share.index()),
+            ExtendedPoint::from(GENERATOR) * share.value()
+        );
+    }
+
+    let bad = Share::new(2, shares[1].value() + Fr::one());
+    assert!(!commitment.verify(&bad));
+    let wrong_index = Share::new(3, *shares[1].value());
+    assert!(!commitment.verify(&wrong_index));
+}
+
+#[test]
+fn test_pedersen() {
+    let mut rng = crate::test_rng();
+    let secret = Fr::random(&mut rng);
+    let (shares, commitment) = split_pedersen(&secret, 2, 4, &mut rng);
+
+    for (share, blinding) in shares.iter() {
+        assert!(commitment.verify(share, blinding));
+    }
+    assert!(!commitment.verify(&shares[0].0, &shares[1].1));
+
+    let secret_shares: Vec<Share> = shares.iter().map(|(s, _)| s.clone()).collect();
+    assert_eq!(reconstruct(&secret_shares[1..3]), Some(secret));
+}
+
+#[test]
+fn test_refresh() {
+    let mut rng = crate::test_rng();
+    let secret = Fr::random(&mut rng);
+    let (shares, commitment) = split(&secret, 3, 5, &mut rng);
+    let (refreshed, update) = refresh(&shares, 3, &mut rng);
+
+    assert!(update.public_key().is_identity().unwrap_u8() == 1);
+    assert_eq!(reconstruct(&refreshed[1..4]), Some(secret));
+
+    let combined = commitment.combine(&update);
+    for (old, new) in shares.iter().zip(refreshed.iter()) {
+        assert!(old.value() != new.value());
+        assert!(combined.verify(new));
+    }
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let (shares, commitment) = split(&Fr::random(&mut rng), 3, 5, &mut rng);
+
+    let share = Share::from_bytes(shares[4].into_bytes()).unwrap();
+    assert_eq!(share.index(), 5);
+    assert_eq!(share.value(), shares[4].value());
+    assert!(Share::from_bytes([0u8; 36]).is_none().unwrap_u8() == 1);
+
+    let decoded = FeldmanCommitment::from_bytes(&commitment.into_bytes()).unwrap();
+    assert_eq!(decoded, commitment);
+    assert_eq!(FeldmanCommitment::from_bytes(&[]), None);
+    assert_eq!(FeldmanCommitment::from_bytes(&[0u8; 33]), None);
+}
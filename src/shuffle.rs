@@ -0,0 +1,1417 @@
+//! This module implements the verifiable shuffle of ElGamal ciphertexts
+//! of Bayer and Groth ("Efficient Zero-Knowledge Argument for Correctness
+//! of a Shuffle", EUROCRYPT 2012) over the prime order subgroup of
+//! Doppio.
+//!
+//! A shuffle of the ciphertexts `C_1, ..., C_N` under the public key `PK`
+//! outputs `C'_i = C_pi(i) + Enc(0; rho_i)` for a secret permutation `pi`
+//! and randomness `rho_i`. The N ciphertexts are arranged in an `m x n`
+//! matrix with `m` the largest divisor of `N` not exceeding `sqrt(N)`,
+//! and a [`ShuffleProof`] consists of `O(m)` points and `O(n)` scalars.
+//! A prime `N` falls back to `m = 1` and `n = N`, so its proof has `O(N)`
+//! scalars and needs a [`CommitmentKey`] of capacity `N`.
+//!
+//!
+//! * the prover commits to `a_i = pi(i)` and, given a challenge `x`, to
+//!   `b_i = x^pi(i)`, using Pedersen vector commitments of length `n`;
+//! * a product argument shows that `prod_i (y * a_i + b_i - z)` equals
+//!   `prod_i (y * i + x^i - z)` for challenges `y` and `z`, so `a` is a
+//!   permutation and `b` follows it. It consists of a Hadamard argument,
+//!   built on a zero argument, and a single value product argument;
+//! * a multi-exponentiation argument shows that
+//!   `sum_i x^i * C_i = Enc(0; rho) + sum_i b_i * C'_i` for some `rho`.
+//!
+//! The commitment generators `G_1, ..., G_n` are derived with
+//! `hash_to_curve` under a dedicated domain separation tag and `H` is the
+//! [`pedersen_generator`], so no one knows their discrete logarithms.
+//! Challenges are derived with a [`Transcript`] bound to the public key
+//! and all ciphertexts. Verification combines every equation of the
+//! proof with powers of a weight derived from the whole proof and checks
+//! them with a single variable time multiscalar multiplication, which is
+//! multiplied by the cofactor.
+
+use core::fmt;
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::elgamal::{Ciphertext, PublicKey};
+use crate::hash::hash_to_curve_parts;
+use crate::helpers::{read_point, read_scalar, uniform_index, zeroize_all};
+use crate::sharing::{pedersen_generator, powers};
+use crate::{
+    batch_normalize, multiscalar_mul, multiscalar_mul_vartime, ExtendedPoint, Fr, Transcript,
+    GENERATOR,
+};
+
+/// The label of the transcript of every proof.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-SHUFFLE-SHA512-v1";
+
+/// The domain separation tag used to derive the commitment generators.
+const GENERATORS_DST: &[u8] = b"DOPPIO_XMD:SHA-512_ELL2_RO_SHUFFLE";
+
+/// The generators `G_1, ..., G_n` and `H` of Pedersen commitments to
+/// vectors of length at most `n`.
+#[derive(Clone, Debug)]
+pub struct CommitmentKey {
+    g: Vec<ExtendedPoint>,
+    h: ExtendedPoint,
+}
+
+impl CommitmentKey {
+    /// Derives a commitment key for vectors of length at most
+    /// `capacity`. Proofs for `N` ciphertexts need a capacity of at least
+    /// `n = N / m`, which never exceeds `N` and equals it when `N` is
+    /// prime.
+    pub fn new(capacity: usize) -> CommitmentKey {
+        CommitmentKey {
+            g: (0..capacity)
+                .map(|i| hash_to_curve_parts(&[b"G", &(i as u64).to_le_bytes()], GENERATORS_DST))
+                .collect(),
+            h: pedersen_generator(),
+        }
+    }
+
+    /// Returns the maximum length of committed vectors.
+    pub fn capacity(&self) -> usize {
+        self.g.len()
+    }
+
+    /// Returns `sum_j a_j * G_j + r * H`.
+    fn commit(&self, a: &[Fr], r: &Fr) -> ExtendedPoint {
+        let mut scalars = a.to_vec();
+        scalars.push(*r);
+        let mut points = self.g[..a.len()].to_vec();
+        points.push(self.h);
+        let res = multiscalar_mul(&scalars, &points);
+        zeroize_all(&mut scalars);
+
+        res
+    }
+}
+
+/// Returns the dimensions `(m, n)` of the matrix of `len` ciphertexts.
+fn dimensions(len: usize) -> (usize, usize) {
+    let m = (1..=len)
+        .take_while(|m| m * m <= len)
+        .filter(|m| len % m == 0)
+        .last()
+        .unwrap_or(1);
+
+    (m, len / m)
+}
+
+fn random_vector<R: RngCore + CryptoRng>(len: usize, rng: &mut R) -> Vec<Fr> {
+    (0..len).map(|_| Fr::random(&mut *rng)).collect()
+}
+
+/// Returns `sum_j a_j * b_j * y^(j + 1)`, the bilinear map of the zero
+/// argument, given the powers `y^0, y^1, ...`.
+fn bilinear(a: &[Fr], b: &[Fr], y_powers: &[Fr]) -> Fr {
+    a.iter()
+        .zip(b.iter())
+        .zip(y_powers[1..].iter())
+        .fold(Fr::zero(), |acc, ((a, b), y)| acc + a * b * y)
+}
+
+/// Returns `sum_i c_i * x_i` for vectors `c` and `x` of equal length.
+fn linear_combination(c: &[Vec<Fr>], x: &[Fr]) -> Vec<Fr> {
+    let mut res = vec![Fr::zero(); c[0].len()];
+    for (c_i, x_i) in c.iter().zip(x.iter()) {
+        for (r, c) in res.iter_mut().zip(c_i.iter()) {
+            *r += c * x_i;
+        }
+    }
+
+    res
+}
+
+/// Returns `sum_i x_i * C_i` for ciphertexts `C_i`, in constant time.
+fn ciphertext_msm(scalars: &[Fr], ciphertexts: &[&Ciphertext]) -> Ciphertext {
+    let (c1, c2): (Vec<ExtendedPoint>, Vec<ExtendedPoint>) =
+        ciphertexts.iter().map(|c| c.components()).unzip();
+    let pk_part = multiscalar_mul(scalars, &c1);
+    let m_part = multiscalar_mul(scalars, &c2);
+
+    Ciphertext {
+        c1: pk_part,
+        c2: m_part,
+    }
+}
+
+/// Absorbs points into `transcript`, normalizing them together.
+fn append_points(transcript: &mut Transcript, label: &[u8], points: &[ExtendedPoint]) {
+    let mut points = points.to_vec();
+    for p in batch_normalize(&mut points) {
+        transcript.append_point(label, &p);
+    }
+}
+
+fn append_scalars(transcript: &mut Transcript, label: &[u8], scalars: &[Fr]) {
+    for s in scalars {
+        transcript.append_scalar(label, s);
+    }
+}
+
+/// Returns the transcript bound to a shuffle of `inputs` into `outputs`.
+fn statement_transcript(
+    pk: &PublicKey,
+    inputs: &[Ciphertext],
+    outputs: &[Ciphertext],
+    m: usize,
+    n: usize,
+) -> Transcript {
+    let mut transcript = Transcript::new(CONTEXT_STRING);
+    transcript.append_message(b"pk", &pk.into_bytes());
+    transcript.append_u64(b"m", m as u64);
+    transcript.append_u64(b"n", n as u64);
+    for c in inputs {
+        transcript.append_message(b"input", &c.into_bytes());
+    }
+    for c in outputs {
+        transcript.append_message(b"output", &c.into_bytes());
+    }
+
+    transcript
+}
+
+/// The equations checked by the verifier, combined with powers of a
+/// weight so that they are checked with one multiscalar multiplication.
+struct Equations {
+    weight: Fr,
+    w: Fr,
+    g: Vec<Fr>,
+    h: Fr,
+    scalars: Vec<Fr>,
+    points: Vec<ExtendedPoint>,
+}
+
+impl Equations {
+    fn new(weight: Fr, n: usize) -> Equations {
+        Equations {
+            weight,
+            w: Fr::one(),
+            g: vec![Fr::zero(); n],
+            h: Fr::zero(),
+            scalars: Vec::new(),
+            points: Vec::new(),
+        }
+    }
+
+    /// Adds `s * P` to the current equation.
+    fn add(&mut self, s: Fr, p: ExtendedPoint) {
+        self.scalars.push(self.w * s);
+        self.points.push(p);
+    }
+
+    /// Subtracts `com(a; r)` from the current equation.
+    fn sub_commitment(&mut self, a: &[Fr], r: &Fr) {
+        for (g, a) in self.g.iter_mut().zip(a.iter()) {
+            *g -= self.w * a;
+        }
+        self.h -= self.w * r;
+    }
+
+    /// Starts the next equation.
+    fn next(&mut self) {
+        self.w *= self.weight;
+    }
+
+    /// Checks that every equation holds.
+    fn check(mut self, ck: &CommitmentKey) -> bool {
+        self.scalars.extend_from_slice(&self.g);
+        self.points.extend_from_slice(&ck.g[..self.g.len()]);
+        self.scalars.push(self.h);
+        self.points.push(ck.h);
+
+        bool::from(
+            multiscalar_mul_vartime(&self.scalars, &self.points)
+                .mul_by_cofactor()
+                .is_identity(),
+        )
+    }
+}
+
+/// Writes points and scalars of a proof in order.
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn points(&mut self, points: &[ExtendedPoint]) {
+        let mut points = points.to_vec();
+        for p in batch_normalize(&mut points) {
+            self.0.extend_from_slice(&p.into_bytes());
+        }
+    }
+
+    fn scalars(&mut self, scalars: &[Fr]) {
+        for s in scalars {
+            self.0.extend_from_slice(&s.into_bytes());
+        }
+    }
+}
+
+/// Reads points and scalars of a proof in order. The caller checks the
+/// total length beforehand.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn point(&mut self) -> Option<ExtendedPoint> {
+        read_point(&mut self.0).map(ExtendedPoint::from)
+    }
+
+    fn points(&mut self, len: usize) -> Option<Vec<ExtendedPoint>> {
+        (0..len).map(|_| self.point()).collect()
+    }
+
+    fn scalar(&mut self) -> Option<Fr> {
+        read_scalar(&mut self.0)
+    }
+
+    fn scalars(&mut self, len: usize) -> Option<Vec<Fr>> {
+        (0..len).map(|_| self.scalar()).collect()
+    }
+}
+
+/// An argument that committed vectors `a_1, ..., a_m` and `b_1, ..., b_m`
+/// satisfy `sum_i a_i * b_i = 0` for the bilinear map `*`.
+#[derive(Clone, Debug, PartialEq)]
+struct ZeroArgument {
+    c_a0: ExtendedPoint,
+    c_b_last: ExtendedPoint,
+    /// The commitments `c_D_k` for `k` in `0..=2m` except `m + 1`, which
+    /// is the identity.
+    c_d: Vec<ExtendedPoint>,
+    a: Vec<Fr>,
+    b: Vec<Fr>,
+    r: Fr,
+    s: Fr,
+    t: Fr,
+}
+
+impl ZeroArgument {
+    #[allow(clippy::too_many_arguments)]
+    fn prove<R: RngCore + CryptoRng>(
+        ck: &CommitmentKey,
+        transcript: &mut Transcript,
+        a: &[Vec<Fr>],
+        r: &[Fr],
+        b: &[Vec<Fr>],
+        s: &[Fr],
+        y: &Fr,
+        rng: &mut R,
+    ) -> ZeroArgument {
+        let m = a.len();
+        let n = a[0].len();
+        let y_powers = powers(y, n + 1);
+
+        // A = (a_0, a_1, ..., a_m) and B = (b_1, ..., b_m, b_{m+1}).
+        let mut a_full = vec![random_vector(n, rng)];
+        a_full.extend_from_slice(a);
+        let mut r_full = vec![Fr::random(&mut *rng)];
+        r_full.extend_from_slice(r);
+        let mut b_full = b.to_vec();
+        b_full.push(random_vector(n, rng));
+        let mut s_full = s.to_vec();
+        s_full.push(Fr::random(&mut *rng));
+
+        // d_k = sum_{m + 1 + i - j = k} a_i * b_j
+        let mut d = vec![Fr::zero(); 2 * m + 1];
+        for (i, a_i) in a_full.iter().enumerate() {
+            for (j, b_j) in b_full.iter().enumerate() {
+                d[m + i - j] += bilinear(a_i, b_j, &y_powers);
+            }
+        }
+        let mut t = random_vector(2 * m + 1, rng);
+        t[m + 1] = Fr::zero();
+
+        let c_a0 = ck.commit(&a_full[0], &r_full[0]);
+        let c_b_last = ck.commit(&b_full[m], &s_full[m]);
+        let c_d: Vec<ExtendedPoint> = (0..=2 * m)
+            .filter(|k| *k != m + 1)
+            .map(|k| ck.commit(&[d[k]], &t[k]))
+            .collect();
+        append_points(transcript, b"zero c_a0", &[c_a0, c_b_last]);
+        append_points(transcript, b"zero c_d", &c_d);
+        let x = transcript.challenge_scalar(b"zero x");
+
+        let x_powers = powers(&x, 2 * m + 2);
+        let x_rev: Vec<Fr> = (0..=m).map(|j| x_powers[m - j]).collect();
+        let res = ZeroArgument {
+            c_a0,
+            c_b_last,
+            c_d,
+            a: linear_combination(&a_full, &x_powers[..=m]),
+            b: linear_combination(&b_full, &x_rev),
+            r: r_full
+                .iter()
+                .zip(x_powers.iter())
+                .fold(Fr::zero(), |acc, (r, x)| acc + r * x),
+            s: s_full
+                .iter()
+                .zip(x_rev.iter())
+                .fold(Fr::zero(), |acc, (s, x)| acc + s * x),
+            t: t.iter()
+                .zip(x_powers.iter())
+                .fold(Fr::zero(), |acc, (t, x)| acc + t * x),
+        };
+
+        zeroize_all(&mut a_full[0]);
+        zeroize_all(&mut b_full[m]);
+        zeroize_all(&mut d);
+        zeroize_all(&mut t);
+        zeroize_all(&mut r_full);
+        zeroize_all(&mut s_full);
+
+        res
+    }
+
+    fn absorb(&self, transcript: &mut Transcript) -> Fr {
+        append_points(transcript, b"zero c_a0", &[self.c_a0, self.c_b_last]);
+        append_points(transcript, b"zero c_d", &self.c_d);
+
+        transcript.challenge_scalar(b"zero x")
+    }
+
+    fn append_responses(&self, transcript: &mut Transcript) {
+        append_scalars(transcript, b"zero a", &self.a);
+        append_scalars(transcript, b"zero b", &self.b);
+        append_scalars(transcript, b"zero r", &[self.r, self.s, self.t]);
+    }
+
+    /// Adds the equations of this argument for the commitments `c_A_i`
+    /// and `c_B_i` to `eqs`.
+    fn equations(
+        &self,
+        c_a: &[ExtendedPoint],
+        c_b: &[ExtendedPoint],
+        x: &Fr,
+        y: &Fr,
+        eqs: &mut Equations,
+    ) {
+        let m = c_a.len();
+        let x_powers = powers(x, 2 * m + 2);
+        let y_powers = powers(y, self.a.len() + 1);
+
+        // c_A_0 + sum_i x^i c_A_i = com(a; r)
+        eqs.add(Fr::one(), self.c_a0);
+        for (c, x) in c_a.iter().zip(x_powers[1..].iter()) {
+            eqs.add(*x, *c);
+        }
+        eqs.sub_commitment(&self.a, &self.r);
+        eqs.next();
+
+        // sum_j x^(m + 1 - j) c_B_j = com(b; s)
+        for (j, c) in c_b.iter().enumerate() {
+            eqs.add(x_powers[m - j], *c);
+        }
+        eqs.add(Fr::one(), self.c_b_last);
+        eqs.sub_commitment(&self.b, &self.s);
+        eqs.next();
+
+        // sum_k x^k c_D_k = com(a * b; t)
+        let ks = (0..=2 * m).filter(|k| *k != m + 1);
+        for (k, c) in ks.zip(self.c_d.iter()) {
+            eqs.add(x_powers[k], *c);
+        }
+        eqs.sub_commitment(&[bilinear(&self.a, &self.b, &y_powers)], &self.t);
+        eqs.next();
+    }
+}
+
+/// An argument that a committed vector `b` is the entrywise product of
+/// committed vectors `a_1, ..., a_m`, for `m >= 2`.
+#[derive(Clone, Debug, PartialEq)]
+struct HadamardArgument {
+    /// The commitments to the partial products `b_2, ..., b_{m-1}`.
+    c_b: Vec<ExtendedPoint>,
+    zero: ZeroArgument,
+}
+
+impl HadamardArgument {
+    #[allow(clippy::too_many_arguments)]
+    fn prove<R: RngCore + CryptoRng>(
+        ck: &CommitmentKey,
+        transcript: &mut Transcript,
+        a: &[Vec<Fr>],
+        r: &[Fr],
+        b: &[Fr],
+        s: &Fr,
+        rng: &mut R,
+    ) -> HadamardArgument {
+        let m = a.len();
+        let n = b.len();
+
+        // b_1 = a_1, b_i = b_{i-1} o a_i and b_m = b.
+        let mut partial = vec![a[0].clone()];
+        for a_i in &a[1..] {
+            let next = partial[partial.len() - 1]
+                .iter()
+                .zip(a_i.iter())
+                .map(|(p, a)| p * a)
+                .collect();
+            partial.push(next);
+        }
+        let mut s_partial = vec![r[0]];
+        s_partial.extend(random_vector(m - 2, rng));
+        s_partial.push(*s);
+
+        let c_b: Vec<ExtendedPoint> = (1..m - 1)
+            .map(|i| ck.commit(&partial[i], &s_partial[i]))
+            .collect();
+        append_points(transcript, b"hadamard c_b", &c_b);
+        let x = transcript.challenge_scalar(b"hadamard x");
+        let y = transcript.challenge_scalar(b"hadamard y");
+
+        // Prove sum_{i=1}^{m-1} a_{i+1} * d_i - 1 * d = 0 with d_i = x^i b_i
+        // and d = sum_{i=1}^{m-1} x^i b_{i+1}.
+        let x_powers = powers(&x, m);
+        let mut zero_a: Vec<Vec<Fr>> = a[1..].to_vec();
+        zero_a.push(vec![-Fr::one(); n]);
+        let mut zero_r: Vec<Fr> = r[1..].to_vec();
+        zero_r.push(Fr::zero());
+        let mut zero_b: Vec<Vec<Fr>> = (0..m - 1)
+            .map(|i| partial[i].iter().map(|b| b * x_powers[i + 1]).collect())
+            .collect();
+        zero_b.push(linear_combination(&partial[1..], &x_powers[1..]));
+        let mut zero_s: Vec<Fr> = (0..m - 1).map(|i| s_partial[i] * x_powers[i + 1]).collect();
+        zero_s.push(
+            s_partial[1..]
+                .iter()
+                .zip(x_powers[1..].iter())
+                .fold(Fr::zero(), |acc, (s, x)| acc + s * x),
+        );
+
+        let zero = ZeroArgument::prove(ck, transcript, &zero_a, &zero_r, &zero_b, &zero_s, &y, rng);
+
+        for v in partial
+            .iter_mut()
+            .chain(zero_a.iter_mut())
+            .chain(zero_b.iter_mut())
+        {
+            zeroize_all(v);
+        }
+        zeroize_all(&mut s_partial);
+        zeroize_all(&mut zero_r);
+        zeroize_all(&mut zero_s);
+
+        HadamardArgument { c_b, zero }
+    }
+
+    fn absorb(&self, transcript: &mut Transcript) -> (Fr, Fr, Fr) {
+        append_points(transcript, b"hadamard c_b", &self.c_b);
+        let x = transcript.challenge_scalar(b"hadamard x");
+        let y = transcript.challenge_scalar(b"hadamard y");
+
+        (x, y, self.zero.absorb(transcript))
+    }
+
+    /// Adds the equations of this argument for the commitments `c_A_i`
+    /// and `c_b` to `eqs`, given its challenges.
+    fn equations(
+        &self,
+        ck: &CommitmentKey,
+        c_a: &[ExtendedPoint],
+        c_b: &ExtendedPoint,
+        (x, y, zero_x): (Fr, Fr, Fr),
+        eqs: &mut Equations,
+    ) {
+        let m = c_a.len();
+        let n = self.zero.a.len();
+        let x_powers = powers(&x, m);
+
+        let mut partial = vec![c_a[0]];
+        partial.extend_from_slice(&self.c_b);
+        partial.push(*c_b);
+
+        let mut zero_a = c_a[1..].to_vec();
+        zero_a.push(
+            -ck.g[..n]
+                .iter()
+                .fold(ExtendedPoint::identity(), |acc, g| acc + g),
+        );
+        let mut zero_b: Vec<ExtendedPoint> =
+            (0..m - 1).map(|i| partial[i] * x_powers[i + 1]).collect();
+        zero_b.push(multiscalar_mul_vartime(&x_powers[1..], &partial[1..]));
+
+        self.zero.equations(&zero_a, &zero_b, &zero_x, &y, eqs);
+    }
+}
+
+/// An argument that the entries of a committed vector `a` have the
+/// product `b`.
+#[derive(Clone, Debug, PartialEq)]
+struct SingleValueProductArgument {
+    c_d: ExtendedPoint,
+    c_lower_delta: ExtendedPoint,
+    c_upper_delta: ExtendedPoint,
+    a: Vec<Fr>,
+    b: Vec<Fr>,
+    r: Fr,
+    s: Fr,
+}
+
+impl SingleValueProductArgument {
+    fn prove<R: RngCore + CryptoRng>(
+        ck: &CommitmentKey,
+        transcript: &mut Transcript,
+        a: &[Fr],
+        r: &Fr,
+        rng: &mut R,
+    ) -> SingleValueProductArgument {
+        let n = a.len();
+
+        let mut b = vec![a[0]];
+        for a_i in &a[1..] {
+            b.push(b[b.len() - 1] * a_i);
+        }
+        let mut d = random_vector(n, rng);
+        let mut r_d = Fr::random(&mut *rng);
+        let mut delta = random_vector(n, rng);
+        delta[0] = d[0];
+        delta[n - 1] = Fr::zero();
+        let mut s_1 = Fr::random(&mut *rng);
+        let mut s_x = Fr::random(&mut *rng);
+
+        let mut lower: Vec<Fr> = (0..n - 1).map(|i| -delta[i] * d[i + 1]).collect();
+        let mut upper: Vec<Fr> = (0..n - 1)
+            .map(|i| delta[i + 1] - a[i + 1] * delta[i] - b[i] * d[i + 1])
+            .collect();
+        let c_d = ck.commit(&d, &r_d);
+        let c_lower_delta = ck.commit(&lower, &s_1);
+        let c_upper_delta = ck.commit(&upper, &s_x);
+        append_points(
+            transcript,
+            b"product c_d",
+            &[c_d, c_lower_delta, c_upper_delta],
+        );
+        let x = transcript.challenge_scalar(b"product x");
+
+        let res = SingleValueProductArgument {
+            c_d,
+            c_lower_delta,
+            c_upper_delta,
+            a: a.iter().zip(d.iter()).map(|(a, d)| x * a + d).collect(),
+            b: b.iter().zip(delta.iter()).map(|(b, e)| x * b + e).collect(),
+            r: x * r + r_d,
+            s: x * s_x + s_1,
+        };
+
+        for v in [&mut b, &mut d, &mut delta, &mut lower, &mut upper].iter_mut() {
+            zeroize_all(v);
+        }
+        r_d.zeroize();
+        s_1.zeroize();
+        s_x.zeroize();
+
+        res
+    }
+
+    fn absorb(&self, transcript: &mut Transcript) -> Fr {
+        append_points(
+            transcript,
+            b"product c_d",
+            &[self.c_d, self.c_lower_delta, self.c_upper_delta],
+        );
+
+        transcript.challenge_scalar(b"product x")
+    }
+
+    fn append_responses(&self, transcript: &mut Transcript) {
+        append_scalars(transcript, b"product a", &self.a);
+        append_scalars(transcript, b"product b", &self.b);
+        append_scalars(transcript, b"product r", &[self.r, self.s]);
+    }
+
+    /// Adds the equations of this argument for the commitment `c_a` and
+    /// the product `b` to `eqs`, returning `false` if one of its scalar
+    /// equations does not hold.
+    fn equations(&self, c_a: &ExtendedPoint, b: &Fr, x: &Fr, eqs: &mut Equations) -> bool {
+        let n = self.a.len();
+        if self.b[0] != self.a[0] || self.b[n - 1] != x * b {
+            return false;
+        }
+
+        // x c_a + c_d = com(a; r)
+        eqs.add(*x, *c_a);
+        eqs.add(Fr::one(), self.c_d);
+        eqs.sub_commitment(&self.a, &self.r);
+        eqs.next();
+
+        // x c_Delta + c_delta = com(x b_{i+1} - b_i a_{i+1}; s)
+        let combined: Vec<Fr> = (0..n - 1)
+            .map(|i| x * self.b[i + 1] - self.b[i] * self.a[i + 1])
+            .collect();
+        eqs.add(*x, self.c_upper_delta);
+        eqs.add(Fr::one(), self.c_lower_delta);
+        eqs.sub_commitment(&combined, &self.s);
+        eqs.next();
+
+        true
+    }
+}
+
+/// An argument that the entries of committed vectors `a_1, ..., a_m`
+/// have the product `b`.
+#[derive(Clone, Debug, PartialEq)]
+struct ProductArgument {
+    /// The commitment to the entrywise product of the vectors and the
+    /// Hadamard argument for it, when `m >= 2`.
+    hadamard: Option<(ExtendedPoint, HadamardArgument)>,
+    single: SingleValueProductArgument,
+}
+
+impl ProductArgument {
+    fn prove<R: RngCore + CryptoRng>(
+        ck: &CommitmentKey,
+        transcript: &mut Transcript,
+        a: &[Vec<Fr>],
+        r: &[Fr],
+        rng: &mut R,
+    ) -> ProductArgument {
+        if a.len() == 1 {
+            return ProductArgument {
+                hadamard: None,
+                single: SingleValueProductArgument::prove(ck, transcript, &a[0], &r[0], rng),
+            };
+        }
+
+        let mut b = a[0].clone();
+        for a_i in &a[1..] {
+            for (b, a) in b.iter_mut().zip(a_i.iter()) {
+                *b *= a;
+            }
+        }
+        let mut s = Fr::random(&mut *rng);
+        let c_b = ck.commit(&b, &s);
+        append_points(transcript, b"product c_b", &[c_b]);
+
+        let hadamard = HadamardArgument::prove(ck, transcript, a, r, &b, &s, rng);
+        let single = SingleValueProductArgument::prove(ck, transcript, &b, &s, rng);
+        zeroize_all(&mut b);
+        s.zeroize();
+
+        ProductArgument {
+            hadamard: Some((c_b, hadamard)),
+            single,
+        }
+    }
+}
+
+/// An argument that `C = Enc(0; rho) + sum_i sum_j a_ij * C_ij` for a
+/// matrix of ciphertexts `C_ij` and committed vectors `a_1, ..., a_m`.
+#[derive(Clone, Debug, PartialEq)]
+struct MultiExponentiationArgument {
+    c_a0: ExtendedPoint,
+    /// The commitments `c_B_k` for `k` in `0..2m` except `m`, which is
+    /// the identity.
+    c_b: Vec<ExtendedPoint>,
+    /// The ciphertexts `E_k` for `k` in `0..2m` except `m`, which is `C`.
+    e: Vec<Ciphertext>,
+    a: Vec<Fr>,
+    r: Fr,
+    b: Fr,
+    s: Fr,
+    tau: Fr,
+}
+
+impl MultiExponentiationArgument {
+    #[allow(clippy::too_many_arguments)]
+    fn prove<R: RngCore + CryptoRng>(
+        ck: &CommitmentKey,
+        transcript: &mut Transcript,
+        pk: &PublicKey,
+        rows: &[&[Ciphertext]],
+        a: &[Vec<Fr>],
+        r: &[Fr],
+        rho: &Fr,
+        rng: &mut R,
+    ) -> MultiExponentiationArgument {
+        let m = a.len();
+        let n = a[0].len();
+
+        let mut a_full = vec![random_vector(n, rng)];
+        a_full.extend_from_slice(a);
+        let mut r_full = vec![Fr::random(&mut *rng)];
+        r_full.extend_from_slice(r);
+        let mut b = random_vector(2 * m, rng);
+        let mut s = random_vector(2 * m, rng);
+        let mut tau = random_vector(2 * m, rng);
+        b[m] = Fr::zero();
+        s[m] = Fr::zero();
+        tau[m] = *rho;
+
+        let c_a0 = ck.commit(&a_full[0], &r_full[0]);
+        let mut c_b = Vec::with_capacity(2 * m - 1);
+        let mut e = Vec::with_capacity(2 * m - 1);
+        for k in (0..2 * m).filter(|k| *k != m) {
+            c_b.push(ck.commit(&[b[k]], &s[k]));
+
+            // E_k = Enc(b_k * G; tau_k) + sum_{j = k - m + i} C_i^{a_j}
+            let mut scalars = Vec::new();
+            let mut ciphertexts = Vec::new();
+            for (i, row) in rows.iter().enumerate() {
+                let j = k + i + 1;
+                if j >= m && j - m <= m {
+                    scalars.extend_from_slice(&a_full[j - m]);
+                    ciphertexts.extend(row.iter());
+                }
+            }
+            let encrypted =
+                pk.encrypt_point_with(&(ExtendedPoint::from(GENERATOR) * b[k]), &tau[k]);
+            e.push(encrypted + ciphertext_msm(&scalars, &ciphertexts));
+            zeroize_all(&mut scalars);
+        }
+
+        append_points(transcript, b"multiexp c_a0", &[c_a0]);
+        append_points(transcript, b"multiexp c_b", &c_b);
+        for e_k in &e {
+            transcript.append_message(b"multiexp e", &e_k.into_bytes());
+        }
+        let x = transcript.challenge_scalar(b"multiexp x");
+
+        let x_powers = powers(&x, 2 * m);
+        let dot = |v: &[Fr]| {
+            v.iter()
+                .zip(x_powers.iter())
+                .fold(Fr::zero(), |acc, (v, x)| acc + v * x)
+        };
+        let res = MultiExponentiationArgument {
+            c_a0,
+            c_b,
+            e,
+            a: linear_combination(&a_full, &x_powers[..=m]),
+            r: dot(&r_full),
+            b: dot(&b),
+            s: dot(&s),
+            tau: dot(&tau),
+        };
+
+        zeroize_all(&mut a_full[0]);
+        zeroize_all(&mut r_full);
+        zeroize_all(&mut b);
+        zeroize_all(&mut s);
+        zeroize_all(&mut tau);
+
+        res
+    }
+
+    fn absorb(&self, transcript: &mut Transcript) -> Fr {
+        append_points(transcript, b"multiexp c_a0", &[self.c_a0]);
+        append_points(transcript, b"multiexp c_b", &self.c_b);
+        for e_k in &self.e {
+            transcript.append_message(b"multiexp e", &e_k.into_bytes());
+        }
+
+        transcript.challenge_scalar(b"multiexp x")
+    }
+
+    fn append_responses(&self, transcript: &mut Transcript) {
+        append_scalars(transcript, b"multiexp a", &self.a);
+        append_scalars(
+            transcript,
+            b"multiexp r",
+            &[self.r, self.b, self.s, self.tau],
+        );
+    }
+
+    /// Adds the equations of this argument to `eqs`, where the target
+    /// ciphertext `C` is `sum_l c_l * C_l` for the terms `(c_l, C_l)` of
+    /// `target`.
+    #[allow(clippy::too_many_arguments)]
+    fn equations(
+        &self,
+        pk: &PublicKey,
+        rows: &[&[Ciphertext]],
+        c_a: &[ExtendedPoint],
+        target: &[(Fr, &Ciphertext)],
+        x: &Fr,
+        eqs: &mut Equations,
+    ) {
+        let m = c_a.len();
+        let x_powers = powers(x, 2 * m);
+
+        // c_A_0 + sum_i x^i c_A_i = com(a; r)
+        eqs.add(Fr::one(), self.c_a0);
+        for (c, x) in c_a.iter().zip(x_powers[1..].iter()) {
+            eqs.add(*x, *c);
+        }
+        eqs.sub_commitment(&self.a, &self.r);
+        eqs.next();
+
+        // sum_k x^k c_B_k = com(b; s)
+        let ks: Vec<usize> = (0..2 * m).filter(|k| *k != m).collect();
+        for (k, c) in ks.iter().zip(self.c_b.iter()) {
+            eqs.add(x_powers[*k], *c);
+        }
+        eqs.sub_commitment(&[self.b], &self.s);
+        eqs.next();
+
+        // sum_k x^k E_k = Enc(b * G; tau) + sum_i x^(m - i) C_i^a, checked
+        // separately for both components.
+        let pk_point = ExtendedPoint::from(pk.0);
+        for component in 0..2 {
+            let pick = |c: &Ciphertext| {
+                let (c1, c2) = c.components();
+                if component == 0 {
+                    c1
+                } else {
+                    c2
+                }
+            };
+
+            for (k, e_k) in ks.iter().zip(self.e.iter()) {
+                eqs.add(x_powers[*k], pick(e_k));
+            }
+            for (c, target) in target {
+                eqs.add(x_powers[m] * c, pick(target));
+            }
+            if component == 0 {
+                eqs.add(-self.tau, ExtendedPoint::from(GENERATOR));
+            } else {
+                eqs.add(-self.b, ExtendedPoint::from(GENERATOR));
+                eqs.add(-self.tau, pk_point);
+            }
+            for (i, row) in rows.iter().enumerate() {
+                let x_i = x_powers[m - i - 1];
+                for (a, c) in self.a.iter().zip(row.iter()) {
+                    eqs.add(-(x_i * a), pick(c));
+                }
+            }
+            eqs.next();
+        }
+    }
+}
+
+/// A shuffle of ElGamal ciphertexts, holding the secret permutation and
+/// randomness needed to prove it.
+pub struct Shuffle {
+    pk: PublicKey,
+    inputs: Vec<Ciphertext>,
+    outputs: Vec<Ciphertext>,
+    permutation: Vec<usize>,
+    randomness: Vec<Fr>,
+}
+
+impl fmt::Debug for Shuffle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Shuffle")
+            .field("pk", &self.pk)
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .finish()
+    }
+}
+
+impl Drop for Shuffle {
+    fn drop(&mut self) {
+        self.permutation.zeroize();
+        zeroize_all(&mut self.randomness);
+    }
+}
+
+impl Shuffle {
+    /// Shuffles `inputs` under `pk` with a random permutation and fresh
+    /// randomness.
+    pub fn new<R: RngCore + CryptoRng>(
+        pk: &PublicKey,
+        inputs: &[Ciphertext],
+        rng: &mut R,
+    ) -> Shuffle {
+        let mut permutation: Vec<usize> = (0..inputs.len()).collect();
+        for i in (1..permutation.len()).rev() {
+            permutation.swap(i, uniform_index(rng, i + 1));
+        }
+        let randomness = random_vector(inputs.len(), rng);
+
+        Shuffle::from_parts(pk, inputs, permutation, randomness).unwrap()
+    }
+
+    /// Shuffles `inputs` under `pk` into the outputs
+    /// `C'_i = C_permutation[i] + Enc(0; randomness[i])`. This returns
+    /// `None` if `permutation` is not a permutation of the indices of
+    /// `inputs` or `randomness` has a different length.
+    pub fn from_parts(
+        pk: &PublicKey,
+        inputs: &[Ciphertext],
+        permutation: Vec<usize>,
+        randomness: Vec<Fr>,
+    ) -> Option<Shuffle> {
+        let mut seen = vec![false; inputs.len()];
+        for p in &permutation {
+            if *seen.get(*p)? {
+                return None;
+            }
+            seen[*p] = true;
+        }
+        if permutation.len() != inputs.len() || randomness.len() != inputs.len() {
+            return None;
+        }
+
+        let outputs = permutation
+            .iter()
+            .zip(randomness.iter())
+            .map(|(p, rho)| inputs[*p].rerandomize_with(pk, rho))
+            .collect();
+
+        Some(Shuffle {
+            pk: *pk,
+            inputs: inputs.to_vec(),
+            outputs,
+            permutation,
+            randomness,
+        })
+    }
+
+    /// Returns the shuffled ciphertexts.
+    pub fn outputs(&self) -> &[Ciphertext] {
+        &self.outputs
+    }
+
+    /// Proves that this shuffle is correct. This returns `None` if there
+    /// are fewer than two ciphertexts or the capacity of `ck` is less
+    /// than `n = N / m`, which is `N` itself when the number `N` of
+    /// ciphertexts is prime.
+    pub fn prove<R: RngCore + CryptoRng>(
+        &self,
+        ck: &CommitmentKey,
+        rng: &mut R,
+    ) -> Option<ShuffleProof> {
+        let len = self.inputs.len();
+        let (m, n) = dimensions(len);
+        if len < 2 || ck.capacity() < n {
+            return None;
+        }
+        let mut transcript = statement_transcript(&self.pk, &self.inputs, &self.outputs, m, n);
+        let columns = |v: &[Fr]| -> Vec<Vec<Fr>> { v.chunks(n).map(|c| c.to_vec()).collect() };
+
+        // Commit to a_i = pi(i).
+        let mut a: Vec<Fr> = self
+            .permutation
+            .iter()
+            .map(|p| Fr::from(*p as u64 + 1))
+            .collect();
+        let mut a_columns = columns(&a);
+        let mut r = random_vector(m, rng);
+        let c_a: Vec<ExtendedPoint> = a_columns
+            .iter()
+            .zip(r.iter())
+            .map(|(a, r)| ck.commit(a, r))
+            .collect();
+        append_points(&mut transcript, b"c_a", &c_a);
+        let x = transcript.challenge_scalar(b"x");
+
+        // Commit to b_i = x^pi(i).
+        let x_powers = powers(&x, len + 1);
+        let mut b: Vec<Fr> = self.permutation.iter().map(|p| x_powers[p + 1]).collect();
+        let mut b_columns = columns(&b);
+        let mut s = random_vector(m, rng);
+        let c_b: Vec<ExtendedPoint> = b_columns
+            .iter()
+            .zip(s.iter())
+            .map(|(b, s)| ck.commit(b, s))
+            .collect();
+        append_points(&mut transcript, b"c_b", &c_b);
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+
+        // The product of the entries of y * a + b - z.
+        let mut d: Vec<Vec<Fr>> = a_columns
+            .iter()
+            .zip(b_columns.iter())
+            .map(|(a, b)| a.iter().zip(b.iter()).map(|(a, b)| y * a + b - z).collect())
+            .collect();
+        let mut t: Vec<Fr> = r.iter().zip(s.iter()).map(|(r, s)| y * r + s).collect();
+        let product = ProductArgument::prove(ck, &mut transcript, &d, &t, rng);
+
+        // sum_i x^i C_i = Enc(0; -sum_i rho_i b_i) + sum_i b_i C'_i
+        let mut rho = -self
+            .randomness
+            .iter()
+            .zip(b.iter())
+            .fold(Fr::zero(), |acc, (rho, b)| acc + rho * b);
+        let rows: Vec<&[Ciphertext]> = self.outputs.chunks(n).collect();
+        let multiexp = MultiExponentiationArgument::prove(
+            ck,
+            &mut transcript,
+            &self.pk,
+            &rows,
+            &b_columns,
+            &s,
+            &rho,
+            rng,
+        );
+
+        for v in a_columns
+            .iter_mut()
+            .chain(b_columns.iter_mut())
+            .chain(d.iter_mut())
+        {
+            zeroize_all(v);
+        }
+        for v in [&mut a, &mut b, &mut r, &mut s, &mut t].iter_mut() {
+            zeroize_all(v);
+        }
+        rho.zeroize();
+
+        Some(ShuffleProof {
+            c_a,
+            c_b,
+            product,
+            multiexp,
+        })
+    }
+}
+
+/// A proof that a list of ciphertexts is a shuffle of another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShuffleProof {
+    c_a: Vec<ExtendedPoint>,
+    c_b: Vec<ExtendedPoint>,
+    product: ProductArgument,
+    multiexp: MultiExponentiationArgument,
+}
+
+impl ShuffleProof {
+    /// Checks that `outputs` is a shuffle of `inputs` under `pk`.
+    pub fn verify(
+        &self,
+        ck: &CommitmentKey,
+        pk: &PublicKey,
+        inputs: &[Ciphertext],
+        outputs: &[Ciphertext],
+    ) -> bool {
+        let len = inputs.len();
+        let (m, n) = dimensions(len);
+        if len < 2 || outputs.len() != len || ck.capacity() < n || !self.has_dimensions(m, n) {
+            return false;
+        }
+
+        // Recompute every challenge, then derive the weight of the
+        // equations from the whole proof.
+        let mut transcript = statement_transcript(pk, inputs, outputs, m, n);
+        append_points(&mut transcript, b"c_a", &self.c_a);
+        let x = transcript.challenge_scalar(b"x");
+        append_points(&mut transcript, b"c_b", &self.c_b);
+        let y = transcript.challenge_scalar(b"y");
+        let z = transcript.challenge_scalar(b"z");
+        let hadamard_challenges = self.product.hadamard.as_ref().map(|(c_b, hadamard)| {
+            append_points(&mut transcript, b"product c_b", &[*c_b]);
+            hadamard.absorb(&mut transcript)
+        });
+        let product_x = self.product.single.absorb(&mut transcript);
+        let multiexp_x = self.multiexp.absorb(&mut transcript);
+
+        if let Some((_, hadamard)) = &self.product.hadamard {
+            hadamard.zero.append_responses(&mut transcript);
+        }
+        self.product.single.append_responses(&mut transcript);
+        self.multiexp.append_responses(&mut transcript);
+        let weight = transcript.challenge_scalar(b"weight");
+        let mut eqs = Equations::new(weight, n);
+
+        // The product argument is for the columns of y * a + b - z, whose
+        // entries must have the product prod_i (y * i + x^i - z).
+        let x_powers = powers(&x, len + 1);
+        let target = (1..=len).fold(Fr::one(), |acc, i| {
+            acc * (y * Fr::from(i as u64) + x_powers[i] - z)
+        });
+        let minus_z = ck.g[..n]
+            .iter()
+            .fold(ExtendedPoint::identity(), |acc, g| acc + g)
+            * (-z);
+        let c_d: Vec<ExtendedPoint> = self
+            .c_a
+            .iter()
+            .zip(self.c_b.iter())
+            .map(|(a, b)| a * y + b + minus_z)
+            .collect();
+        let c_product = match (&self.product.hadamard, hadamard_challenges) {
+            (Some((c_b, hadamard)), Some(challenges)) => {
+                hadamard.equations(ck, &c_d, c_b, challenges, &mut eqs);
+                *c_b
+            }
+            _ => c_d[0],
+        };
+        if !self
+            .product
+            .single
+            .equations(&c_product, &target, &product_x, &mut eqs)
+        {
+            return false;
+        }
+
+        // The multi-exponentiation argument is for C = sum_i x^i C_i.
+        let rows: Vec<&[Ciphertext]> = outputs.chunks(n).collect();
+        let target: Vec<(Fr, &Ciphertext)> = x_powers[1..].iter().copied().zip(inputs).collect();
+        self.multiexp
+            .equations(pk, &rows, &self.c_b, &target, &multiexp_x, &mut eqs);
+
+        eqs.check(ck)
+    }
+
+    /// Returns whether every component of this proof has the size of a
+    /// proof for an `m x n` matrix.
+    fn has_dimensions(&self, m: usize, n: usize) -> bool {
+        let hadamard_ok = match &self.product.hadamard {
+            Some((_, hadamard)) => {
+                m >= 2
+                    && hadamard.c_b.len() == m - 2
+                    && hadamard.zero.c_d.len() == 2 * m
+                    && hadamard.zero.a.len() == n
+                    && hadamard.zero.b.len() == n
+            }
+            None => m == 1,
+        };
+
+        hadamard_ok
+            && self.c_a.len() == m
+            && self.c_b.len() == m
+            && self.product.single.a.len() == n
+            && self.product.single.b.len() == n
+            && self.multiexp.c_b.len() == 2 * m - 1
+            && self.multiexp.e.len() == 2 * m - 1
+            && self.multiexp.a.len() == n
+    }
+
+    /// Returns the size in bytes of a proof for `len` ciphertexts, or
+    /// `None` if `len` is less than two.
+    pub fn size(len: usize) -> Option<usize> {
+        if len < 2 {
+            return None;
+        }
+        let (m, n) = dimensions(len);
+        let (points, scalars) = if m >= 2 {
+            // c_A, c_B, c_b, Hadamard, zero argument
+            (2 * m + 1 + (m - 2) + 2 + 2 * m, 2 * n + 3)
+        } else {
+            (2 * m, 0)
+        };
+        // Single value product and multi-exponentiation arguments.
+        let points = points + 3 + 1 + 3 * (2 * m - 1);
+        let scalars = scalars + 2 * n + 2 + n + 4;
+
+        Some(32 * (points + scalars))
+    }
+
+    /// Attempts to interpret the byte representation of a proof for a
+    /// shuffle of `len` ciphertexts.
+    pub fn from_bytes(bytes: &[u8], len: usize) -> Option<ShuffleProof> {
+        if Some(bytes.len()) != ShuffleProof::size(len) {
+            return None;
+        }
+        let (m, n) = dimensions(len);
+        let mut reader = Reader(bytes);
+
+        let c_a = reader.points(m)?;
+        let c_b = reader.points(m)?;
+        let hadamard = if m >= 2 {
+            let c_product = reader.point()?;
+            let c_partial = reader.points(m - 2)?;
+            let c_a0 = reader.point()?;
+            let c_b_last = reader.point()?;
+            let c_d = reader.points(2 * m)?;
+            let zero = ZeroArgument {
+                c_a0,
+                c_b_last,
+                c_d,
+                a: reader.scalars(n)?,
+                b: reader.scalars(n)?,
+                r: reader.scalar()?,
+                s: reader.scalar()?,
+                t: reader.scalar()?,
+            };
+            Some((
+                c_product,
+                HadamardArgument {
+                    c_b: c_partial,
+                    zero,
+                },
+            ))
+        } else {
+            None
+        };
+        let single = SingleValueProductArgument {
+            c_d: reader.point()?,
+            c_lower_delta: reader.point()?,
+            c_upper_delta: reader.point()?,
+            a: reader.scalars(n)?,
+            b: reader.scalars(n)?,
+            r: reader.scalar()?,
+            s: reader.scalar()?,
+        };
+        let c_a0 = reader.point()?;
+        let multiexp_c_b = reader.points(2 * m - 1)?;
+        let e = (0..2 * m - 1)
+            .map(|_| {
+                let c1 = reader.point()?;
+                let c2 = reader.point()?;
+                Some(Ciphertext { c1, c2 })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let multiexp = MultiExponentiationArgument {
+            c_a0,
+            c_b: multiexp_c_b,
+            e,
+            a: reader.scalars(n)?,
+            r: reader.scalar()?,
+            b: reader.scalar()?,
+            s: reader.scalar()?,
+            tau: reader.scalar()?,
+        };
+
+        Some(ShuffleProof {
+            c_a,
+            c_b,
+            product: ProductArgument { hadamard, single },
+            multiexp,
+        })
+    }
+
+    /// Converts this proof into its byte representation, which lists the
+    /// points of each argument followed by its scalars.
+    pub fn into_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer(Vec::new());
+
+        writer.points(&self.c_a);
+        writer.points(&self.c_b);
+        if let Some((c_product, hadamard)) = &self.product.hadamard {
+            writer.points(&[*c_product]);
+            writer.points(&hadamard.c_b);
+            let zero = &hadamard.zero;
+            writer.points(&[zero.c_a0, zero.c_b_last]);
+            writer.points(&zero.c_d);
+            writer.scalars(&zero.a);
+            writer.scalars(&zero.b);
+            writer.scalars(&[zero.r, zero.s, zero.t]);
+        }
+        let single = &self.product.single;
+        writer.points(&[single.c_d, single.c_lower_delta, single.c_upper_delta]);
+        writer.scalars(&single.a);
+        writer.scalars(&single.b);
+        writer.scalars(&[single.r, single.s]);
+        let multiexp = &self.multiexp;
+        writer.points(&[multiexp.c_a0]);
+        writer.points(&multiexp.c_b);
+        for e in &multiexp.e {
+            let (c1, c2) = e.components();
+            writer.points(&[c1, c2]);
+        }
+        writer.scalars(&multiexp.a);
+        writer.scalars(&[multiexp.r, multiexp.b, multiexp.s, multiexp.tau]);
+
+        writer.0
+    }
+}
+
+#[cfg(test)]
+fn test_shuffle(len: usize) -> (PublicKey, Vec<Ciphertext>, Shuffle) {
+    let mut rng = crate::test_rng();
+    let sk = crate::elgamal::SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+    let inputs: Vec<Ciphertext> = (0..len)
+        .map(|i| pk.encrypt(&Fr::from(i as u64), &mut rng))
+        .collect();
+    let shuffle = Shuffle::new(&pk, &inputs, &mut rng);
+
+    (pk, inputs, shuffle)
+}
+
+#[test]
+fn test_dimensions() {
+    assert_eq!(dimensions(2), (1, 2));
+    assert_eq!(dimensions(7), (1, 7));
+    assert_eq!(dimensions(12), (3, 4));
+    assert_eq!(dimensions(16), (4, 4));
+    assert_eq!(dimensions(1000), (25, 40));
+}
+
+#[test]
+fn test_shuffle_proof() {
+    let mut rng = crate::test_rng();
+    let ck = CommitmentKey::new(8);
+
+    for len in [2, 3, 6, 12, 16].iter() {
+        let (pk, inputs, shuffle) = test_shuffle(*len);
+        let proof = shuffle.prove(&ck, &mut rng).unwrap();
+        assert!(proof.verify(&ck, &pk, &inputs, shuffle.outputs()));
+
+        // The outputs are bound to the proof.
+        let mut outputs = shuffle.outputs().to_vec();
+        outputs.swap(0, 1);
+        assert!(!proof.verify(&ck, &pk, &inputs, &outputs));
+        outputs.swap(0, 1);
+        outputs[0] = outputs[0].rerandomize(&pk, &mut rng);
+        assert!(!proof.verify(&ck, &pk, &inputs, &outputs));
+        assert!(!proof.verify(&ck, &pk, &inputs[1..], &outputs[1..]));
+    }
+}
+
+#[test]
+fn test_prime_length() {
+    let mut rng = crate::test_rng();
+    let (pk, inputs, shuffle) = test_shuffle(11);
+    assert_eq!(dimensions(11), (1, 11));
+
+    // A prime number of ciphertexts needs a capacity of its own size.
+    let ck = CommitmentKey::new(10);
+    assert!(shuffle.prove(&ck, &mut rng).is_none());
+
+    let ck = CommitmentKey::new(11);
+    let proof = shuffle.prove(&ck, &mut rng).unwrap();
+    assert!(proof.verify(&ck, &pk, &inputs, shuffle.outputs()));
+    assert!(!proof.verify(&CommitmentKey::new(10), &pk, &inputs, shuffle.outputs()));
+
+    let mut outputs = shuffle.outputs().to_vec();
+    outputs.swap(3, 7);
+    assert!(!proof.verify(&ck, &pk, &inputs, &outputs));
+}
+
+#[test]
+fn test_invalid_shuffle() {
+    let mut rng = crate::test_rng();
+    let ck = CommitmentKey::new(8);
+    let (pk, inputs, _) = test_shuffle(12);
+
+    assert!(Shuffle::from_parts(&pk, &inputs, vec![0; 12], vec![Fr::zero(); 12]).is_none());
+    assert!(Shuffle::from_parts(&pk, &inputs, (0..11).collect(), vec![Fr::zero(); 11]).is_none());
+
+    // A proof for a shuffle of different inputs does not verify.
+    let mut other = inputs.clone();
+    other[3] = pk.encrypt(&Fr::from(100), &mut rng);
+    let shuffle = Shuffle::new(&pk, &other, &mut rng);
+    let proof = shuffle.prove(&ck, &mut rng).unwrap();
+    assert!(!proof.verify(&ck, &pk, &inputs, shuffle.outputs()));
+
+    // Too few ciphertexts or generators.
+    let (_, _, shuffle) = test_shuffle(1);
+    assert!(shuffle.prove(&ck, &mut rng).is_none());
+    let (_, _, shuffle) = test_shuffle(12);
+    assert!(shuffle.prove(&CommitmentKey::new(3), &mut rng).is_none());
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let ck = CommitmentKey::new(8);
+
+    for len in [3, 12].iter() {
+        let (pk, inputs, shuffle) = test_shuffle(*len);
+        let proof = shuffle.prove(&ck, &mut rng).unwrap();
+        let bytes = proof.into_bytes();
+        assert_eq!(Some(bytes.len()), ShuffleProof::size(*len));
+
+        let decoded = ShuffleProof::from_bytes(&bytes, *len).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(decoded.verify(&ck, &pk, &inputs, shuffle.outputs()));
+        assert!(ShuffleProof::from_bytes(&bytes[1..], *len).is_none());
+        assert!(ShuffleProof::from_bytes(&bytes, *len + 1).is_none());
+
+        let mut tampered = bytes.clone();
+        let last = tampered.len() - 32;
+        tampered[last] ^= 1;
+        if let Some(tampered) = ShuffleProof::from_bytes(&tampered, *len) {
+            assert!(!tampered.verify(&ck, &pk, &inputs, shuffle.outputs()));
+        }
+    }
+}
@@ -0,0 +1,893 @@
+//! This module implements sigma protocols for linear relations over
+//! Doppio, made non-interactive with the Fiat–Shamir transform.
+//!
+//! A [`LinearRelation`] is a list of equations
+//!
+//! ```text
+//! P_j = sum_i x_i * G_ij
+//! ```
+//!
+//! between public points `P_j` and `G_ij` and secret scalars `x_i`, the
+//! witness. Schnorr proofs of knowledge of a discrete logarithm,
+//! Chaum–Pedersen proofs of equality of discrete logarithms and openings
+//! of Pedersen commitments are all of this form. AND composition is
+//! [`LinearRelation::and`], which proves both relations at once, and OR
+//! composition is [`prove_or`], which uses the technique of Cramer,
+//! Damgård and Schoenmakers to prove that one of several relations holds
+//! without revealing which.
+//!
+//! For a witness `x` and nonces `k`, a proof consists of the commitments
+//! `A_j = sum_i k_i * G_ij`, the challenge `c` and the responses
+//! `s_i = k_i + c * x_i`. The challenge is derived from the caller's
+//! [`Transcript`] after absorbing the full statement and the commitments,
+//! so the transcript must be in the same state for proving and
+//! verification.
+//! Proofs have two encodings:
+//!
+//! * the batchable encoding `A_0 || ... || s_0 || ...`, which carries the
+//!   commitments so that [`verify_batch`] can combine the verification
+//!   equations of many proofs, and
+//! * the compact encoding `c || s_0 || ...`, from which the commitments
+//!   are recomputed as `A_j = sum_i s_i * G_ij - c * P_j`.
+//!
+//! Verification combines the equations of a proof with powers of a
+//! hashed weight and checks them with a single multiscalar
+//! multiplication, multiplied by the cofactor.
+
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_scalar;
+use crate::helpers::{read_point, read_scalar};
+use crate::schnorr::nonce_generate;
+use crate::{multiscalar_mul_vartime, AffinePoint, ExtendedPoint, Fr, Transcript};
+
+/// The context string prefixed to the nonce and weight hashes of this
+/// module. Challenges are derived from the caller's transcript instead.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-SIGMA-SHA512-v1";
+
+/// A secret scalar of a [`LinearRelation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScalarVar(usize);
+
+/// A public point of a [`LinearRelation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ElementVar(usize);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Equation {
+    lhs: usize,
+    terms: Vec<(usize, usize)>,
+}
+
+/// A statement consisting of linear equations between public points,
+/// with secret scalars as coefficients.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinearRelation {
+    scalars: usize,
+    elements: Vec<AffinePoint>,
+    equations: Vec<Equation>,
+}
+
+/// A non-interactive proof for a [`LinearRelation`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Proof {
+    commitments: Vec<AffinePoint>,
+    challenge: Fr,
+    responses: Vec<Fr>,
+}
+
+/// A non-interactive proof that one of several [`LinearRelation`]s
+/// holds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrProof {
+    commitments: Vec<Vec<AffinePoint>>,
+    challenges: Vec<Fr>,
+    responses: Vec<Vec<Fr>>,
+}
+
+/// A multiscalar multiplication accumulating verification equations,
+/// each weighted by the next power of a hashed weight.
+struct Batch {
+    scalars: Vec<Fr>,
+    points: Vec<ExtendedPoint>,
+    weight: Fr,
+    w: Fr,
+}
+
+impl Batch {
+    fn new(weight: Fr) -> Batch {
+        Batch {
+            scalars: Vec::new(),
+            points: Vec::new(),
+            weight,
+            w: Fr::one(),
+        }
+    }
+
+    /// Appends `sum_i s_i * G_ij - c * P_j - A_j` for every equation of
+    /// `relation`. The lengths of `commitments` and `responses` must
+    /// match the relation.
+    fn push(
+        &mut self,
+        relation: &LinearRelation,
+        commitments: &[AffinePoint],
+        challenge: &Fr,
+        responses: &[Fr],
+    ) {
+        let offset = self.scalars.len();
+        self.scalars
+            .resize(offset + relation.elements.len(), Fr::zero());
+        self.points
+            .extend(relation.elements.iter().map(|p| ExtendedPoint::from(*p)));
+        for (eq, a) in relation.equations.iter().zip(commitments.iter()) {
+            for (x, g) in &eq.terms {
+                self.scalars[offset + *g] += self.w * responses[*x];
+            }
+            self.scalars[offset + eq.lhs] -= self.w * challenge;
+            self.scalars.push(-self.w);
+            self.points.push(ExtendedPoint::from(*a));
+            self.w *= self.weight;
+        }
+    }
+
+    /// Checks that the accumulated sum vanishes after clearing the
+    /// cofactor.
+    fn is_valid(&self) -> bool {
+        bool::from(
+            multiscalar_mul_vartime(&self.scalars, &self.points)
+                .mul_by_cofactor()
+                .is_identity(),
+        )
+    }
+}
+
+fn normalize(points: &[ExtendedPoint]) -> Vec<AffinePoint> {
+    let mut points = points.to_vec();
+    crate::batch_normalize(&mut points).collect()
+}
+
+fn scalars_into_bytes(scalars: &[Fr], bytes: &mut Vec<u8>) {
+    for s in scalars {
+        bytes.extend_from_slice(&s.into_bytes());
+    }
+}
+
+fn points_into_bytes(points: &[AffinePoint], bytes: &mut Vec<u8>) {
+    for p in points {
+        bytes.extend_from_slice(&p.into_bytes());
+    }
+}
+
+/// Decodes `n` scalars from the front of `bytes`, advancing it.
+fn scalars_from_bytes(bytes: &mut &[u8], n: usize) -> Option<Vec<Fr>> {
+    (0..n).map(|_| read_scalar(bytes)).collect()
+}
+
+/// Decodes `n` points from the front of `bytes`, advancing it.
+fn points_from_bytes(bytes: &mut &[u8], n: usize) -> Option<Vec<AffinePoint>> {
+    (0..n).map(|_| read_point(bytes)).collect()
+}
+
+impl LinearRelation {
+    /// Constructs an empty relation.
+    pub fn new() -> LinearRelation {
+        LinearRelation::default()
+    }
+
+    /// Returns the relation `P = x * G`, the statement of a Schnorr
+    /// proof of knowledge of `x`.
+    pub fn discrete_log(g: AffinePoint, p: AffinePoint) -> LinearRelation {
+        let mut relation = LinearRelation::new();
+        let x = relation.allocate_scalar();
+        let g = relation.allocate_element(g);
+        let p = relation.allocate_element(p);
+        relation.constrain(p, &[(x, g)]);
+
+        relation
+    }
+
+    /// Returns the relation `P = x * G` and `Q = x * H`, the statement of
+    /// a Chaum–Pedersen proof that `P` and `Q` have the same discrete
+    /// logarithm with respect to `G` and `H`.
+    pub fn dleq(g: AffinePoint, p: AffinePoint, h: AffinePoint, q: AffinePoint) -> LinearRelation {
+        let mut relation = LinearRelation::new();
+        let x = relation.allocate_scalar();
+        let g = relation.allocate_element(g);
+        let p = relation.allocate_element(p);
+        let h = relation.allocate_element(h);
+        let q = relation.allocate_element(q);
+        relation.constrain(p, &[(x, g)]);
+        relation.constrain(q, &[(x, h)]);
+
+        relation
+    }
+
+    /// Allocates a new secret scalar, which is the next element of the
+    /// witness.
+    pub fn allocate_scalar(&mut self) -> ScalarVar {
+        self.scalars += 1;
+
+        ScalarVar(self.scalars - 1)
+    }
+
+    /// Allocates a new public point.
+    pub fn allocate_element(&mut self, point: AffinePoint) -> ElementVar {
+        self.elements.push(point);
+
+        ElementVar(self.elements.len() - 1)
+    }
+
+    /// Adds the equation `lhs = sum x_i * G_i` for the pairs `(x_i, G_i)`
+    /// in `terms`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the variables was not allocated by this relation.
+    pub fn constrain(&mut self, lhs: ElementVar, terms: &[(ScalarVar, ElementVar)]) {
+        assert!(lhs.0 < self.elements.len());
+        for (x, g) in terms {
+            assert!(x.0 < self.scalars && g.0 < self.elements.len());
+        }
+
+        self.equations.push(Equation {
+            lhs: lhs.0,
+            terms: terms.iter().map(|(x, g)| (x.0, g.0)).collect(),
+        });
+    }
+
+    /// Returns the relation that holds when both this relation and
+    /// `other` hold. Their scalars are distinct, so the witness of the
+    /// result is the witness of this relation followed by the witness of
+    /// `other`. Equality of scalars across the two relations can be
+    /// proven by building a single relation instead.
+    pub fn and(&self, other: &LinearRelation) -> LinearRelation {
+        let mut res = self.clone();
+        let (scalars, elements) = (res.scalars, res.elements.len());
+        res.scalars += other.scalars;
+        res.elements.extend_from_slice(&other.elements);
+        res.equations.extend(other.equations.iter().map(|eq| {
+            Equation {
+                lhs: eq.lhs + elements,
+                terms: eq
+                    .terms
+                    .iter()
+                    .map(|(x, g)| (x + scalars, g + elements))
+                    .collect(),
+            }
+        }));
+
+        res
+    }
+
+    /// Returns the number of secret scalars in this relation.
+    pub fn scalars(&self) -> usize {
+        self.scalars
+    }
+
+    /// Returns the number of equations in this relation.
+    pub fn equations(&self) -> usize {
+        self.equations.len()
+    }
+
+    /// Evaluates the right-hand side of every equation at `scalars`.
+    fn image(&self, scalars: &[Fr]) -> Vec<ExtendedPoint> {
+        self.equations
+            .iter()
+            .map(|eq| {
+                let (s, g): (Vec<Fr>, Vec<ExtendedPoint>) = eq
+                    .terms
+                    .iter()
+                    .map(|(x, g)| (scalars[*x], ExtendedPoint::from(self.elements[*g])))
+                    .unzip();
+                multiscalar_mul_vartime(&s, &g)
+            })
+            .collect()
+    }
+
+    /// Checks that `witness` satisfies every equation of this relation.
+    pub fn is_satisfied(&self, witness: &[Fr]) -> bool {
+        witness.len() == self.scalars
+            && self
+                .image(witness)
+                .iter()
+                .zip(self.equations.iter())
+                .all(|(p, eq)| *p == ExtendedPoint::from(self.elements[eq.lhs]))
+    }
+
+    /// Recomputes the commitments `sum_i s_i * G_ij - c * P_j` from a
+    /// challenge and responses.
+    fn simulate(&self, challenge: &Fr, responses: &[Fr]) -> Vec<AffinePoint> {
+        let image = self.image(responses);
+        let neg_c = -challenge;
+        let commitments: Vec<ExtendedPoint> = image
+            .iter()
+            .zip(self.equations.iter())
+            .map(|(p, eq)| p + ExtendedPoint::from(self.elements[eq.lhs]) * neg_c)
+            .collect();
+
+        normalize(&commitments)
+    }
+
+    /// Absorbs this statement into `transcript`.
+    fn absorb(&self, transcript: &mut Transcript) {
+        transcript.append_u64(b"scalars", self.scalars as u64);
+        transcript.append_u64(b"elements", self.elements.len() as u64);
+        for p in &self.elements {
+            transcript.append_point(b"element", p);
+        }
+        for eq in &self.equations {
+            let mut bytes = Vec::with_capacity(8 * eq.terms.len() + 4);
+            bytes.extend_from_slice(&(eq.lhs as u32).to_le_bytes());
+            for (x, g) in &eq.terms {
+                bytes.extend_from_slice(&(*x as u32).to_le_bytes());
+                bytes.extend_from_slice(&(*g as u32).to_le_bytes());
+            }
+            transcript.append_message(b"equation", &bytes);
+        }
+    }
+
+    /// Computes the Fiat–Shamir challenge for `commitments`.
+    fn challenge(&self, transcript: &mut Transcript, commitments: &[AffinePoint]) -> Fr {
+        transcript.append_message(b"dom-sep", b"sigma v1");
+        self.absorb(transcript);
+        for a in commitments {
+            transcript.append_point(b"commitment", a);
+        }
+
+        transcript.challenge_scalar(b"challenge")
+    }
+
+    /// Checks the verification equations of this relation with a single
+    /// multiscalar multiplication.
+    fn check(&self, commitments: &[AffinePoint], challenge: &Fr, responses: &[Fr]) -> bool {
+        if commitments.len() != self.equations.len() || responses.len() != self.scalars {
+            return false;
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&challenge.into_bytes());
+        points_into_bytes(commitments, &mut bytes);
+        scalars_into_bytes(responses, &mut bytes);
+        let weight = hash_to_scalar(&[CONTEXT_STRING, b"weight", &bytes]);
+
+        // sum_j w^j (sum_i s_i * G_ij - c * P_j - A_j) = 0
+        let mut batch = Batch::new(weight);
+        batch.push(self, commitments, challenge, responses);
+
+        batch.is_valid()
+    }
+
+    /// Proves knowledge of `witness` for this relation, binding the proof
+    /// to `transcript`. The caller must ensure that the witness satisfies
+    /// the relation, or the proof will not verify.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `witness` is not the number of scalars of
+    /// this relation.
+    pub fn prove<R: RngCore + CryptoRng>(
+        &self,
+        transcript: &mut Transcript,
+        witness: &[Fr],
+        rng: &mut R,
+    ) -> Proof {
+        assert_eq!(witness.len(), self.scalars);
+
+        let mut nonces: Vec<Fr> = witness
+            .iter()
+            .map(|x| nonce_generate(CONTEXT_STRING, x, rng))
+            .collect();
+        let commitments = normalize(&self.image(&nonces));
+        let challenge = self.challenge(transcript, &commitments);
+        let responses = nonces
+            .iter()
+            .zip(witness.iter())
+            .map(|(k, x)| k + challenge * x)
+            .collect();
+        for k in nonces.iter_mut() {
+            k.zeroize();
+        }
+
+        Proof {
+            commitments,
+            challenge,
+            responses,
+        }
+    }
+
+    /// Checks that `proof` is a valid proof for this relation, bound to
+    /// `transcript`.
+    pub fn verify(&self, transcript: &mut Transcript, proof: &Proof) -> bool {
+        proof.commitments.len() == self.equations.len()
+            && self.challenge(transcript, &proof.commitments) == proof.challenge
+            && self.check(&proof.commitments, &proof.challenge, &proof.responses)
+    }
+}
+
+/// Checks that every `proofs[k]` is a valid proof for `relations[k]`,
+/// bound to `transcripts[k]`. The verification equations of all proofs
+/// are combined into a single multiscalar multiplication, which is
+/// cheaper than verifying each proof on its own. This is the intended
+/// use of proofs decoded with [`Proof::from_batchable_bytes`].
+pub fn verify_batch(
+    relations: &[LinearRelation],
+    transcripts: &mut [Transcript],
+    proofs: &[Proof],
+) -> bool {
+    if relations.len() != proofs.len() || transcripts.len() != proofs.len() {
+        return false;
+    }
+
+    let mut bytes = Vec::new();
+    for ((relation, transcript), proof) in relations
+        .iter()
+        .zip(transcripts.iter_mut())
+        .zip(proofs.iter())
+    {
+        if proof.commitments.len() != relation.equations.len()
+            || proof.responses.len() != relation.scalars
+            || relation.challenge(transcript, &proof.commitments) != proof.challenge
+        {
+            return false;
+        }
+        bytes.extend_from_slice(&proof.challenge.into_bytes());
+        points_into_bytes(&proof.commitments, &mut bytes);
+        scalars_into_bytes(&proof.responses, &mut bytes);
+    }
+    let weight = hash_to_scalar(&[CONTEXT_STRING, b"batch-weight", &bytes]);
+
+    let mut batch = Batch::new(weight);
+    for (relation, proof) in relations.iter().zip(proofs.iter()) {
+        batch.push(
+            relation,
+            &proof.commitments,
+            &proof.challenge,
+            &proof.responses,
+        );
+    }
+
+    batch.is_valid()
+}
+
+impl Proof {
+    /// Returns the commitments `A_j` of this proof.
+    pub fn commitments(&self) -> &[AffinePoint] {
+        &self.commitments
+    }
+
+    /// Returns the challenge `c` of this proof.
+    pub fn challenge(&self) -> Fr {
+        self.challenge
+    }
+
+    /// Returns the responses `s_i` of this proof.
+    pub fn responses(&self) -> &[Fr] {
+        &self.responses
+    }
+
+    /// Attempts to interpret the batchable encoding of a proof for
+    /// `relation`, bound to `transcript`, from which the challenge is
+    /// recomputed on a copy of `transcript`. The transcript itself is left
+    /// unchanged, so that the result can be checked against it with
+    /// [`LinearRelation::verify`] or, together with other proofs, with
+    /// [`verify_batch`].
+    pub fn from_batchable_bytes(
+        relation: &LinearRelation,
+        transcript: &Transcript,
+        mut bytes: &[u8],
+    ) -> Option<Proof> {
+        let commitments = points_from_bytes(&mut bytes, relation.equations.len())?;
+        let responses = scalars_from_bytes(&mut bytes, relation.scalars)?;
+        if !bytes.is_empty() {
+            return None;
+        }
+        let challenge = relation.challenge(&mut transcript.clone(), &commitments);
+
+        Some(Proof {
+            commitments,
+            challenge,
+            responses,
+        })
+    }
+
+    /// Converts this proof into its batchable encoding
+    /// `A_0 || ... || s_0 || ...`.
+    pub fn into_batchable_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        points_into_bytes(&self.commitments, &mut bytes);
+        scalars_into_bytes(&self.responses, &mut bytes);
+
+        bytes
+    }
+
+    /// Attempts to interpret the compact encoding of a proof for
+    /// `relation`, from which the commitments are recomputed.
+    pub fn from_compact_bytes(relation: &LinearRelation, mut bytes: &[u8]) -> Option<Proof> {
+        let challenge = scalars_from_bytes(&mut bytes, 1)?[0];
+        let responses = scalars_from_bytes(&mut bytes, relation.scalars)?;
+        if !bytes.is_empty() {
+            return None;
+        }
+        let commitments = relation.simulate(&challenge, &responses);
+
+        Some(Proof {
+            commitments,
+            challenge,
+            responses,
+        })
+    }
+
+    /// Converts this proof into its compact encoding `c || s_0 || ...`.
+    pub fn into_compact_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scalars_into_bytes(&[self.challenge], &mut bytes);
+        scalars_into_bytes(&self.responses, &mut bytes);
+
+        bytes
+    }
+}
+
+/// Computes the challenge of an OR proof, which the challenges of the
+/// individual relations must sum to.
+fn or_challenge(
+    relations: &[LinearRelation],
+    transcript: &mut Transcript,
+    commitments: &[Vec<AffinePoint>],
+) -> Fr {
+    transcript.append_message(b"dom-sep", b"sigma-or v1");
+    transcript.append_u64(b"relations", relations.len() as u64);
+    for (relation, commitments) in relations.iter().zip(commitments.iter()) {
+        relation.absorb(transcript);
+        for a in commitments {
+            transcript.append_point(b"commitment", a);
+        }
+    }
+
+    transcript.challenge_scalar(b"challenge")
+}
+
+/// Proves that one of `relations` holds, namely the one at `index` with
+/// the given `witness`, without revealing which. The proofs for the other
+/// relations are simulated with random challenges that, together with
+/// the real one, sum to the Fiat–Shamir challenge.
+///
+/// # Panics
+///
+/// Panics if `index` is out of bounds or if the length of `witness` is
+/// not the number of scalars of `relations[index]`.
+pub fn prove_or<R: RngCore + CryptoRng>(
+    relations: &[LinearRelation],
+    transcript: &mut Transcript,
+    index: usize,
+    witness: &[Fr],
+    rng: &mut R,
+) -> OrProof {
+    let real = &relations[index];
+    assert_eq!(witness.len(), real.scalars);
+
+    let mut challenges = Vec::with_capacity(relations.len());
+    let mut responses = Vec::with_capacity(relations.len());
+    let mut commitments = Vec::with_capacity(relations.len());
+    let mut nonces: Vec<Fr> = witness
+        .iter()
+        .map(|x| nonce_generate(CONTEXT_STRING, x, rng))
+        .collect();
+    for (j, relation) in relations.iter().enumerate() {
+        if j == index {
+            challenges.push(Fr::zero());
+            responses.push(Vec::new());
+            commitments.push(normalize(&relation.image(&nonces)));
+        } else {
+            let c = Fr::random(&mut *rng);
+            let s: Vec<Fr> = (0..relation.scalars)
+                .map(|_| Fr::random(&mut *rng))
+                .collect();
+            commitments.push(relation.simulate(&c, &s));
+            challenges.push(c);
+            responses.push(s);
+        }
+    }
+
+    let c = or_challenge(relations, transcript, &commitments);
+    challenges[index] = challenges.iter().fold(c, |acc, c| acc - c);
+    responses[index] = nonces
+        .iter()
+        .zip(witness.iter())
+        .map(|(k, x)| k + challenges[index] * x)
+        .collect();
+    for k in nonces.iter_mut() {
+        k.zeroize();
+    }
+
+    OrProof {
+        commitments,
+        challenges,
+        responses,
+    }
+}
+
+/// Checks that `proof` is a valid proof, bound to `transcript`, that one
+/// of `relations` holds.
+pub fn verify_or(
+    relations: &[LinearRelation],
+    transcript: &mut Transcript,
+    proof: &OrProof,
+) -> bool {
+    if proof.commitments.len() != relations.len()
+        || proof.challenges.len() != relations.len()
+        || proof.responses.len() != relations.len()
+        || proof
+            .commitments
+            .iter()
+            .zip(relations.iter())
+            .any(|(a, relation)| a.len() != relation.equations.len())
+    {
+        return false;
+    }
+
+    let c = or_challenge(relations, transcript, &proof.commitments);
+    let sum = proof.challenges.iter().fold(Fr::zero(), |acc, c| acc + c);
+
+    c == sum
+        && relations
+            .iter()
+            .zip(proof.commitments.iter())
+            .zip(proof.challenges.iter().zip(proof.responses.iter()))
+            .all(|((relation, a), (c, s))| relation.check(a, c, s))
+}
+
+impl OrProof {
+    /// Returns the challenges of the individual relations.
+    pub fn challenges(&self) -> &[Fr] {
+        &self.challenges
+    }
+
+    /// Attempts to interpret the batchable encoding of a proof for
+    /// `relations`, bound to `transcript`. The challenge of the last
+    /// relation is recomputed from the others on a copy of `transcript`,
+    /// which is left unchanged for [`verify_or`].
+    pub fn from_batchable_bytes(
+        relations: &[LinearRelation],
+        transcript: &Transcript,
+        mut bytes: &[u8],
+    ) -> Option<OrProof> {
+        if relations.is_empty() {
+            return None;
+        }
+
+        let mut commitments = Vec::with_capacity(relations.len());
+        for relation in relations {
+            commitments.push(points_from_bytes(&mut bytes, relation.equations.len())?);
+        }
+        let mut challenges = scalars_from_bytes(&mut bytes, relations.len() - 1)?;
+        let mut responses = Vec::with_capacity(relations.len());
+        for relation in relations {
+            responses.push(scalars_from_bytes(&mut bytes, relation.scalars)?);
+        }
+        if !bytes.is_empty() {
+            return None;
+        }
+
+        let c = or_challenge(relations, &mut transcript.clone(), &commitments);
+        challenges.push(challenges.iter().fold(c, |acc, c| acc - c));
+
+        Some(OrProof {
+            commitments,
+            challenges,
+            responses,
+        })
+    }
+
+    /// Converts this proof into its batchable encoding, the commitments
+    /// of every relation, followed by all but the last challenge and
+    /// the responses of every relation.
+    pub fn into_batchable_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for a in &self.commitments {
+            points_into_bytes(a, &mut bytes);
+        }
+        scalars_into_bytes(&self.challenges[..self.challenges.len() - 1], &mut bytes);
+        for s in &self.responses {
+            scalars_into_bytes(s, &mut bytes);
+        }
+
+        bytes
+    }
+
+    /// Attempts to interpret the compact encoding of a proof for
+    /// `relations`, from which the commitments are recomputed.
+    pub fn from_compact_bytes(relations: &[LinearRelation], mut bytes: &[u8]) -> Option<OrProof> {
+        if relations.is_empty() {
+            return None;
+        }
+
+        let challenges = scalars_from_bytes(&mut bytes, relations.len())?;
+        let mut responses = Vec::with_capacity(relations.len());
+        for relation in relations {
+            responses.push(scalars_from_bytes(&mut bytes, relation.scalars)?);
+        }
+        if !bytes.is_empty() {
+            return None;
+        }
+
+        let commitments = relations
+            .iter()
+            .zip(challenges.iter().zip(responses.iter()))
+            .map(|(relation, (c, s))| relation.simulate(c, s))
+            .collect();
+
+        Some(OrProof {
+            commitments,
+            challenges,
+            responses,
+        })
+    }
+
+    /// Converts this proof into its compact encoding, the challenges of
+    /// every relation followed by their responses.
+    pub fn into_compact_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        scalars_into_bytes(&self.challenges, &mut bytes);
+        for s in &self.responses {
+            scalars_into_bytes(s, &mut bytes);
+        }
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+fn random_point(rng: &mut rand_chacha::ChaChaRng) -> (Fr, AffinePoint) {
+    let x = Fr::random(&mut *rng);
+
+    (
+        x,
+        AffinePoint::from(ExtendedPoint::from(crate::GENERATOR) * x),
+    )
+}
+
+#[test]
+fn test_discrete_log() {
+    let mut rng = crate::test_rng();
+    let (x, p) = random_point(&mut rng);
+    let relation = LinearRelation::discrete_log(crate::GENERATOR, p);
+    assert!(relation.is_satisfied(&[x]));
+
+    let proof = relation.prove(&mut Transcript::new(b"test"), &[x], &mut rng);
+    assert!(relation.verify(&mut Transcript::new(b"test"), &proof));
+    assert!(!relation.verify(&mut Transcript::new(b"other"), &proof));
+
+    // Decoding leaves the transcript in the state expected by verify.
+    let mut transcript = Transcript::new(b"test");
+    let batchable =
+        Proof::from_batchable_bytes(&relation, &transcript, &proof.into_batchable_bytes());
+    assert_eq!(batchable, Some(proof.clone()));
+    assert!(relation.verify(&mut transcript, &batchable.unwrap()));
+    let compact = Proof::from_compact_bytes(&relation, &proof.into_compact_bytes());
+    assert_eq!(compact, Some(proof.clone()));
+    assert!(relation.verify(&mut Transcript::new(b"test"), &compact.unwrap()));
+    assert_eq!(proof.into_compact_bytes().len(), 64);
+
+    let (_, q) = random_point(&mut rng);
+    let other = LinearRelation::discrete_log(crate::GENERATOR, q);
+    assert!(!other.verify(&mut Transcript::new(b"test"), &proof));
+}
+
+#[test]
+fn test_dleq_and() {
+    let mut rng = crate::test_rng();
+    let (x, p) = random_point(&mut rng);
+    let (_, h) = random_point(&mut rng);
+    let q = AffinePoint::from(ExtendedPoint::from(h) * x);
+    let dleq = LinearRelation::dleq(crate::GENERATOR, p, h, q);
+    assert_eq!(dleq.equations(), 2);
+
+    let proof = dleq.prove(&mut Transcript::new(b"test"), &[x], &mut rng);
+    assert!(dleq.verify(&mut Transcript::new(b"test"), &proof));
+
+    // A proof for a different discrete logarithm in the second equation
+    // does not verify.
+    let bad = LinearRelation::dleq(crate::GENERATOR, p, h, p);
+    let proof = bad.prove(&mut Transcript::new(b"test"), &[x], &mut rng);
+    assert!(!bad.verify(&mut Transcript::new(b"test"), &proof));
+
+    let (y, r) = random_point(&mut rng);
+    let both = dleq.and(&LinearRelation::discrete_log(crate::GENERATOR, r));
+    assert_eq!(both.scalars(), 2);
+    assert!(both.is_satisfied(&[x, y]));
+    let proof = both.prove(&mut Transcript::new(b"test"), &[x, y], &mut rng);
+    assert!(both.verify(&mut Transcript::new(b"test"), &proof));
+    assert!(!both.verify(
+        &mut Transcript::new(b"test"),
+        &both.prove(&mut Transcript::new(b"test"), &[y, x], &mut rng)
+    ));
+}
+
+#[test]
+fn test_or() {
+    let mut rng = crate::test_rng();
+    let (x, p) = random_point(&mut rng);
+    let (_, q) = random_point(&mut rng);
+    let (_, r) = random_point(&mut rng);
+    let relations = [
+        LinearRelation::discrete_log(crate::GENERATOR, q),
+        LinearRelation::discrete_log(crate::GENERATOR, p),
+        LinearRelation::discrete_log(crate::GENERATOR, r),
+    ];
+
+    let proof = prove_or(&relations, &mut Transcript::new(b"test"), 1, &[x], &mut rng);
+    assert!(verify_or(&relations, &mut Transcript::new(b"test"), &proof));
+    assert!(!verify_or(
+        &relations,
+        &mut Transcript::new(b"other"),
+        &proof
+    ));
+    assert!(!verify_or(
+        &relations[..2],
+        &mut Transcript::new(b"test"),
+        &proof
+    ));
+
+    let mut transcript = Transcript::new(b"test");
+    let batchable =
+        OrProof::from_batchable_bytes(&relations, &transcript, &proof.into_batchable_bytes());
+    assert_eq!(batchable, Some(proof.clone()));
+    assert!(verify_or(&relations, &mut transcript, &batchable.unwrap()));
+    let compact = OrProof::from_compact_bytes(&relations, &proof.into_compact_bytes());
+    assert_eq!(compact, Some(proof.clone()));
+
+    // Claiming the wrong branch produces an invalid proof.
+    let proof = prove_or(&relations, &mut Transcript::new(b"test"), 0, &[x], &mut rng);
+    assert!(!verify_or(
+        &relations,
+        &mut Transcript::new(b"test"),
+        &proof
+    ));
+}
+
+#[test]
+fn test_verify_batch() {
+    let mut rng = crate::test_rng();
+    let (x, p) = random_point(&mut rng);
+    let (y, q) = random_point(&mut rng);
+    let (_, h) = random_point(&mut rng);
+    let relations = [
+        LinearRelation::discrete_log(crate::GENERATOR, p),
+        LinearRelation::dleq(
+            crate::GENERATOR,
+            q,
+            h,
+            AffinePoint::from(ExtendedPoint::from(h) * y),
+        ),
+    ];
+    let labels: [&[u8]; 2] = [b"first", b"second"];
+    let transcripts = || -> Vec<Transcript> { labels.iter().map(|l| Transcript::new(l)).collect() };
+
+    // Each proof is decoded against the transcript it is then verified
+    // with.
+    let mut verifier_transcripts = transcripts();
+    let mut proofs = Vec::new();
+    for ((relation, transcript), witness) in relations
+        .iter()
+        .zip(verifier_transcripts.iter())
+        .zip([x, y].iter())
+    {
+        let proof = relation.prove(&mut transcript.clone(), &[*witness], &mut rng);
+        proofs.push(
+            Proof::from_batchable_bytes(relation, transcript, &proof.into_batchable_bytes())
+                .unwrap(),
+        );
+    }
+    assert!(verify_batch(&relations, &mut verifier_transcripts, &proofs));
+
+    // Swapping the proofs, or tampering with one response, fails.
+    let swapped = [proofs[1].clone(), proofs[0].clone()];
+    assert!(!verify_batch(&relations, &mut transcripts(), &swapped));
+    let mut tampered = proofs.clone();
+    tampered[1].responses[0] += Fr::one();
+    assert!(!verify_batch(&relations, &mut transcripts(), &tampered));
+    assert!(!verify_batch(&relations[..1], &mut transcripts(), &proofs));
+}
@@ -0,0 +1,226 @@
+//! A minimal implementation of STROBE-128 over Keccak-f[1600], covering
+//! the operations needed for Merlin transcripts.
+
+/// The number of bytes of the state that are absorbed into between
+/// permutations, `1600 / 8 - 128 / 4 - 2`.
+const STROBE_R: u8 = 166;
+
+const FLAG_I: u8 = 1;
+const FLAG_A: u8 = 1 << 1;
+const FLAG_C: u8 = 1 << 2;
+const FLAG_T: u8 = 1 << 3;
+const FLAG_M: u8 = 1 << 4;
+const FLAG_K: u8 = 1 << 5;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+/// The rotation offsets of the rho step, in the order of the pi step.
+const RHO: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+/// The lane positions visited by the pi step.
+const PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// Applies Keccak-f[1600] to `state`.
+pub(crate) fn keccak_f1600(state: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS.iter() {
+        // theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+
+        // rho and pi
+        let mut last = state[1];
+        for (pi, rho) in PI.iter().zip(RHO.iter()) {
+            let tmp = state[*pi];
+            state[*pi] = last.rotate_left(*rho);
+            last = tmp;
+        }
+
+        // chi
+        for y in 0..5 {
+            let row = [
+                state[5 * y],
+                state[5 * y + 1],
+                state[5 * y + 2],
+                state[5 * y + 3],
+                state[5 * y + 4],
+            ];
+            for x in 0..5 {
+                state[5 * y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // iota
+        state[0] ^= rc;
+    }
+}
+
+/// The state of a STROBE-128 instance.
+#[derive(Clone)]
+pub(crate) struct Strobe128 {
+    state: [u8; 200],
+    pos: u8,
+    pos_begin: u8,
+    cur_flags: u8,
+}
+
+impl Strobe128 {
+    pub(crate) fn new(protocol_label: &[u8]) -> Strobe128 {
+        let mut state = [0u8; 200];
+        state[0..6].copy_from_slice(&[1, STROBE_R + 2, 1, 0, 1, 96]);
+        state[6..18].copy_from_slice(b"STROBEv1.0.2");
+
+        let mut strobe = Strobe128 {
+            state,
+            pos: 0,
+            pos_begin: 0,
+            cur_flags: 0,
+        };
+        strobe.permute();
+        strobe.meta_ad(protocol_label, false);
+
+        strobe
+    }
+
+    pub(crate) fn meta_ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_M | FLAG_A, more);
+        self.absorb(data);
+    }
+
+    pub(crate) fn ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_A, more);
+        self.absorb(data);
+    }
+
+    pub(crate) fn prf(&mut self, data: &mut [u8], more: bool) {
+        self.begin_op(FLAG_I | FLAG_A | FLAG_C, more);
+        self.squeeze(data);
+    }
+
+    fn permute(&mut self) {
+        let mut lanes = [0u64; 25];
+        for (lane, bytes) in lanes.iter_mut().zip(self.state.chunks(8)) {
+            let mut repr = [0u8; 8];
+            repr.copy_from_slice(bytes);
+            *lane = u64::from_le_bytes(repr);
+        }
+        keccak_f1600(&mut lanes);
+        for (lane, bytes) in lanes.iter().zip(self.state.chunks_mut(8)) {
+            bytes.copy_from_slice(&lane.to_le_bytes());
+        }
+    }
+
+    fn run_f(&mut self) {
+        self.state[self.pos as usize] ^= self.pos_begin;
+        self.state[self.pos as usize + 1] ^= 0x04;
+        self.state[STROBE_R as usize + 1] ^= 0x80;
+        self.permute();
+        self.pos = 0;
+        self.pos_begin = 0;
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state[self.pos as usize] ^= byte;
+            self.pos += 1;
+            if self.pos == STROBE_R {
+                self.run_f();
+            }
+        }
+    }
+
+    fn squeeze(&mut self, data: &mut [u8]) {
+        for byte in data {
+            *byte = self.state[self.pos as usize];
+            self.state[self.pos as usize] = 0;
+            self.pos += 1;
+            if self.pos == STROBE_R {
+                self.run_f();
+            }
+        }
+    }
+
+    fn begin_op(&mut self, flags: u8, more: bool) {
+        if more {
+            debug_assert_eq!(self.cur_flags, flags);
+            return;
+        }
+        debug_assert_eq!(flags & FLAG_T, 0);
+
+        let old_begin = self.pos_begin;
+        self.pos_begin = self.pos + 1;
+        self.cur_flags = flags;
+        self.absorb(&[old_begin, flags]);
+
+        let force_f = (flags & (FLAG_C | FLAG_K)) != 0;
+        if force_f && self.pos != 0 {
+            self.run_f();
+        }
+    }
+}
+
+impl Drop for Strobe128 {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+
+        self.state.zeroize();
+    }
+}
+
+#[test]
+fn test_keccak_f1600() {
+    // SHA3-256 of the empty string, computed with a single permutation.
+    let mut lanes = [0u64; 25];
+    lanes[0] ^= 0x06;
+    lanes[16] ^= 0x80 << 56;
+    keccak_f1600(&mut lanes);
+
+    let mut digest = [0u8; 32];
+    for (bytes, lane) in digest.chunks_mut(8).zip(lanes.iter()) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    assert_eq!(
+        digest,
+        [
+            0xa7, 0xff, 0xc6, 0xf8, 0xbf, 0x1e, 0xd7, 0x66, 0x51, 0xc1, 0x47, 0x56, 0xa0, 0x61,
+            0xd6, 0x62, 0xf5, 0x80, 0xff, 0x4d, 0xe4, 0x3b, 0x49, 0xfa, 0x82, 0xd8, 0x0a, 0x4b,
+            0x80, 0xf8, 0x43, 0x4a
+        ]
+    );
+}
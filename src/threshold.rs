@@ -0,0 +1,309 @@
+//! This module implements threshold decryption of ElGamal ciphertexts
+//! from the `elgamal` module, for keys shared with the `sharing` or `dkg`
+//! modules.
+//!
+//! The holder of the share `x_i` of the secret key produces the partial
+//! decryption `D_i = x_i * C1` together with a Chaum–Pedersen proof that
+//! `log_G(Y_i) = log_C1(D_i)`, where `Y_i = x_i * G` is its verification
+//! key. Given valid partial decryptions from any `t` parties, the
+//! plaintext point is recovered as `C2 - sum_i l_i * D_i` with the
+//! Lagrange coefficients `l_i` at zero, computed with a single
+//! multiscalar multiplication.
+//!
+//! The proof is `(c, s)` with `A = k * G`, `B = k * C1`, `s = k + c * x_i`
+//! and
+//! `c = Fr::from_bytes_wide(SHA-512("DOPPIO-TDEC-SHA512-v1" || "dleq" || Y_i || C1 || D_i || A || B))`.
+//! Partial decryptions are only produced for, and only accepted with,
+//! points in the prime order subgroup, so that a malicious ciphertext
+//! cannot be used to learn a share modulo the cofactor.
+
+use std::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::elgamal::Ciphertext;
+use crate::hash::hash_to_scalar;
+use crate::schnorr::nonce_generate;
+use crate::sharing::{lagrange_coefficients_at_zero, FeldmanCommitment, Share};
+use crate::{multiscalar_mul_vartime, AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The context string prefixed to every hash computed by this module.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-TDEC-SHA512-v1";
+
+/// Computes the challenge of a proof of correct partial decryption.
+fn dleq_challenge(points: [&ExtendedPoint; 5]) -> Fr {
+    let mut points = [*points[0], *points[1], *points[2], *points[3], *points[4]];
+    let mut encoded = [[0u8; 32]; 5];
+    for (e, p) in encoded.iter_mut().zip(crate::batch_normalize(&mut points)) {
+        *e = p.into_bytes();
+    }
+
+    hash_to_scalar(&[
+        CONTEXT_STRING,
+        b"dleq",
+        &encoded[0],
+        &encoded[1],
+        &encoded[2],
+        &encoded[3],
+        &encoded[4],
+    ])
+}
+
+/// A partial decryption `D_i = x_i * C1` by the party with index `i`,
+/// with a proof that it is correct.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecryptionShare {
+    index: u32,
+    d: AffinePoint,
+    c: Fr,
+    s: Fr,
+}
+
+impl DecryptionShare {
+    /// Returns the index of the party that produced this share.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the partial decryption `D_i`.
+    pub fn to_point(&self) -> AffinePoint {
+        self.d
+    }
+
+    /// Attempts to interpret the 100-byte representation of a decryption
+    /// share, which is the little-endian index followed by `D_i || c || s`.
+    /// This fails if the index is zero or any component is not
+    /// canonically encoded.
+    pub fn from_bytes(bytes: [u8; 100]) -> CtOption<DecryptionShare> {
+        let mut index = [0u8; 4];
+        let mut d = [0u8; 32];
+        let mut c = [0u8; 32];
+        let mut s = [0u8; 32];
+        index.copy_from_slice(&bytes[..4]);
+        d.copy_from_slice(&bytes[4..36]);
+        c.copy_from_slice(&bytes[36..68]);
+        s.copy_from_slice(&bytes[68..]);
+
+        let index = u32::from_le_bytes(index);
+        let d = AffinePoint::from_bytes(d);
+        let c = Fr::from_bytes(c);
+        let s = Fr::from_bytes(s);
+        let is_some =
+            d.is_some() & c.is_some() & s.is_some() & subtle::Choice::from((index != 0) as u8);
+
+        CtOption::new(
+            DecryptionShare {
+                index,
+                d: d.unwrap_or(AffinePoint::identity()),
+                c: c.unwrap_or(Fr::zero()),
+                s: s.unwrap_or(Fr::zero()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this decryption share into its 100-byte representation.
+    pub fn into_bytes(&self) -> [u8; 100] {
+        let mut res = [0u8; 100];
+        res[..4].copy_from_slice(&self.index.to_le_bytes());
+        res[4..36].copy_from_slice(&self.d.into_bytes());
+        res[36..68].copy_from_slice(&self.c.into_bytes());
+        res[68..].copy_from_slice(&self.s.into_bytes());
+
+        res
+    }
+}
+
+/// Computes the partial decryption of `ciphertext` with `share` of the
+/// secret key. This returns `None` if `C1` is not in the prime order
+/// subgroup.
+pub fn decrypt_share<R: RngCore + CryptoRng>(
+    share: &Share,
+    ciphertext: &Ciphertext,
+    rng: &mut R,
+) -> Option<DecryptionShare> {
+    let (c1, _) = ciphertext.components();
+    if !bool::from(c1.is_torsion_free()) {
+        return None;
+    }
+
+    let x = share.value();
+    let g = ExtendedPoint::from(GENERATOR);
+    let y = g * x;
+    let d = c1 * x;
+
+    let mut k = nonce_generate(CONTEXT_STRING, x, rng);
+    let c = dleq_challenge([&y, &c1, &d, &(g * k), &(c1 * k)]);
+    let s = k + c * x;
+    k.zeroize();
+
+    Some(DecryptionShare {
+        index: share.index(),
+        d: AffinePoint::from(d),
+        c,
+        s,
+    })
+}
+
+/// Checks that `share` is a correct partial decryption of `ciphertext`
+/// by the party with the verification key `verification_key`. This
+/// rejects shares if any of the points involved is outside the prime
+/// order subgroup.
+pub fn verify_share(
+    verification_key: &ExtendedPoint,
+    ciphertext: &Ciphertext,
+    share: &DecryptionShare,
+) -> bool {
+    let (c1, _) = ciphertext.components();
+    let d = ExtendedPoint::from(share.d);
+    let in_subgroup =
+        verification_key.is_torsion_free() & c1.is_torsion_free() & d.is_torsion_free();
+    if !bool::from(in_subgroup) {
+        return false;
+    }
+
+    let neg_c = -share.c;
+    let a = multiscalar_mul_vartime(
+        &[share.s, neg_c],
+        &[ExtendedPoint::from(GENERATOR), *verification_key],
+    );
+    let b = multiscalar_mul_vartime(&[share.s, neg_c], &[c1, d]);
+
+    dleq_challenge([verification_key, &c1, &d, &a, &b]) == share.c
+}
+
+/// Combines the partial decryptions in `shares` into the plaintext point
+/// `C2 - x * C1`, without checking them. This returns `None` if
+/// `shares` is empty or contains two shares from the same party.
+pub fn combine(ciphertext: &Ciphertext, shares: &[DecryptionShare]) -> Option<ExtendedPoint> {
+    if shares.is_empty() {
+        return None;
+    }
+
+    let indices: Vec<u32> = shares.iter().map(|s| s.index).collect();
+    let coefficients = lagrange_coefficients_at_zero(&indices)?;
+    let points: Vec<ExtendedPoint> = shares.iter().map(|s| ExtendedPoint::from(s.d)).collect();
+
+    let (_, c2) = ciphertext.components();
+    Some(c2 - multiscalar_mul_vartime(&coefficients, &points))
+}
+
+/// Checks every share in `shares` against the verification keys derived
+/// from `commitment`, a Feldman commitment to the shared secret key, and
+/// combines them into the plaintext point. This returns `None` if any
+/// share is invalid, if there are fewer shares than the threshold, or if
+/// two shares come from the same party.
+pub fn combine_verified(
+    ciphertext: &Ciphertext,
+    shares: &[DecryptionShare],
+    commitment: &FeldmanCommitment,
+) -> Option<ExtendedPoint> {
+    if shares.len() < commitment.threshold() {
+        return None;
+    }
+
+    for share in shares {
+        if !verify_share(&commitment.evaluate(share.index), ciphertext, share) {
+            return None;
+        }
+    }
+
+    combine(ciphertext, shares)
+}
+
+#[cfg(test)]
+fn setup(
+    rng: &mut rand_chacha::ChaChaRng,
+) -> (Vec<Share>, FeldmanCommitment, crate::elgamal::PublicKey) {
+    let (shares, commitment) = crate::sharing::split(&Fr::random(&mut *rng), 3, 5, rng);
+    let pk =
+        crate::elgamal::PublicKey::from_point(AffinePoint::from(commitment.public_key())).unwrap();
+
+    (shares, commitment, pk)
+}
+
+#[test]
+fn test_threshold_decrypt() {
+    let mut rng = crate::test_rng();
+    let (shares, commitment, pk) = setup(&mut rng);
+
+    let m = Fr::from(1234);
+    let ciphertext = pk.encrypt(&m, &mut rng);
+    let partials: Vec<DecryptionShare> = [4, 0, 2]
+        .iter()
+        .map(|i| decrypt_share(&shares[*i], &ciphertext, &mut rng).unwrap())
+        .collect();
+
+    for partial in partials.iter() {
+        let key = commitment.evaluate(partial.index());
+        assert!(verify_share(&key, &ciphertext, partial));
+    }
+
+    let expected = ExtendedPoint::from(GENERATOR) * m;
+    assert_eq!(combine(&ciphertext, &partials), Some(expected));
+    assert_eq!(
+        combine_verified(&ciphertext, &partials, &commitment),
+        Some(expected)
+    );
+    assert_eq!(
+        combine_verified(&ciphertext, &partials[..2], &commitment),
+        None
+    );
+}
+
+#[test]
+fn test_invalid_share_rejected() {
+    let mut rng = crate::test_rng();
+    let (shares, commitment, pk) = setup(&mut rng);
+    let ciphertext = pk.encrypt(&Fr::from(5), &mut rng);
+
+    let mut partial = decrypt_share(&shares[1], &ciphertext, &mut rng).unwrap();
+    let key = commitment.evaluate(partial.index());
+
+    // A proof for another party's verification key does not verify.
+    assert!(!verify_share(
+        &commitment.evaluate(1),
+        &ciphertext,
+        &partial
+    ));
+
+    // Neither does a partial decryption that has been tampered with.
+    partial.d = AffinePoint::from(ExtendedPoint::from(partial.d).double());
+    assert!(!verify_share(&key, &ciphertext, &partial));
+
+    // Nor one with a small order component.
+    let two_torsion = AffinePoint::from_raw_unchecked(crate::Fq::zero(), -crate::Fq::one());
+    let mut partial = decrypt_share(&shares[1], &ciphertext, &mut rng).unwrap();
+    partial.d = AffinePoint::from(ExtendedPoint::from(partial.d) + two_torsion);
+    assert!(!verify_share(&key, &ciphertext, &partial));
+
+    // Ciphertexts outside the prime order subgroup are not decrypted.
+    let (c1, c2) = ciphertext.components();
+    let mut bytes = ciphertext.into_bytes();
+    bytes[..32].copy_from_slice(&AffinePoint::from(c1 + two_torsion).into_bytes());
+    let bad = Ciphertext::from_bytes(bytes).unwrap();
+    assert_eq!(bad.components().1, c2);
+    assert!(decrypt_share(&shares[1], &bad, &mut rng).is_none());
+}
+
+#[test]
+fn test_serialization() {
+    let mut rng = crate::test_rng();
+    let partial = DecryptionShare {
+        index: 3,
+        d: AffinePoint::from(ExtendedPoint::from(GENERATOR) * Fr::random(&mut rng)),
+        c: Fr::random(&mut rng),
+        s: Fr::random(&mut rng),
+    };
+    assert_eq!(
+        DecryptionShare::from_bytes(partial.into_bytes()).unwrap(),
+        partial
+    );
+    assert!(
+        DecryptionShare::from_bytes([0u8; 100])
+            .is_none()
+            .unwrap_u8()
+            == 1
+    );
+}
@@ -0,0 +1,219 @@
+//! A transcript for deriving Fiat–Shamir challenges from the messages of
+//! a protocol.
+
+use core::convert::TryFrom;
+use core::fmt;
+
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+use crate::strobe::Strobe128;
+use crate::{AffinePoint, Fr};
+
+/// The context string absorbed by every transcript with the SHA-512
+/// backend.
+const CONTEXT_STRING: &[u8] = b"DOPPIO-TRANSCRIPT-SHA512-v1";
+
+/// The protocol label of Merlin transcripts.
+const MERLIN_PROTOCOL_LABEL: &[u8] = b"Merlin v1.0";
+
+#[derive(Clone)]
+enum Backend {
+    Sha512(Sha512),
+    Merlin(Strobe128),
+}
+
+/// A transcript of the public messages of a protocol, from which
+/// challenges are derived for the Fiat–Shamir transform.
+///
+/// Every message is absorbed together with a label, and every challenge
+/// depends on all messages and challenges before it. A transcript is
+/// created with a label identifying the protocol, so transcripts of
+/// different protocols never produce related challenges.
+///
+/// [`Transcript::new`] uses SHA-512, absorbing each message as
+/// `len(label) || label || len(msg) || msg` with 64-bit little-endian
+/// lengths. [`Transcript::new_merlin`] produces exactly the challenges of
+/// a Merlin transcript with the same label and messages, so that proofs
+/// can be checked against implementations built on Merlin. In both cases
+/// points are absorbed with [`AffinePoint::into_bytes`] and scalars with
+/// [`Fr::into_bytes`].
+#[derive(Clone)]
+pub struct Transcript {
+    backend: Backend,
+}
+
+impl fmt::Debug for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Transcript").finish()
+    }
+}
+
+impl Transcript {
+    /// Creates a transcript for the protocol identified by `label`, using
+    /// SHA-512.
+    pub fn new(label: &[u8]) -> Transcript {
+        let mut transcript = Transcript {
+            backend: Backend::Sha512(Sha512::new_with_prefix(CONTEXT_STRING)),
+        };
+        transcript.append_message(b"dom-sep", label);
+
+        transcript
+    }
+
+    /// Creates a transcript for the protocol identified by `label` that
+    /// is compatible with `merlin::Transcript::new(label)`.
+    pub fn new_merlin(label: &[u8]) -> Transcript {
+        let mut transcript = Transcript {
+            backend: Backend::Merlin(Strobe128::new(MERLIN_PROTOCOL_LABEL)),
+        };
+        transcript.append_message(b"dom-sep", label);
+
+        transcript
+    }
+
+    /// Absorbs `message` with the given label.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is a Merlin transcript and `message` is longer than
+    /// `u32::MAX` bytes.
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) {
+        match &mut self.backend {
+            Backend::Sha512(h) => {
+                h.update((label.len() as u64).to_le_bytes());
+                h.update(label);
+                h.update((message.len() as u64).to_le_bytes());
+                h.update(message);
+            }
+            Backend::Merlin(strobe) => {
+                let len = u32::try_from(message.len()).expect("message too long");
+                strobe.meta_ad(label, false);
+                strobe.meta_ad(&len.to_le_bytes(), true);
+                strobe.ad(message, false);
+            }
+        }
+    }
+
+    /// Absorbs the little-endian encoding of `x` with the given label.
+    pub fn append_u64(&mut self, label: &[u8], x: u64) {
+        self.append_message(label, &x.to_le_bytes());
+    }
+
+    /// Absorbs `point` with the given label.
+    pub fn append_point(&mut self, label: &[u8], point: &AffinePoint) {
+        self.append_message(label, &point.into_bytes());
+    }
+
+    /// Absorbs `point` with the given label, unless it is the identity,
+    /// in which case nothing is absorbed and `false` is returned. Proofs
+    /// use this for points supplied by the prover for which the identity
+    /// would make the proof trivially valid.
+    pub fn validate_and_append_point(&mut self, label: &[u8], point: &AffinePoint) -> bool {
+        if *point == AffinePoint::identity() {
+            return false;
+        }
+        self.append_point(label, point);
+
+        true
+    }
+
+    /// Absorbs `scalar` with the given label.
+    pub fn append_scalar(&mut self, label: &[u8], scalar: &Fr) {
+        self.append_message(label, &scalar.into_bytes());
+    }
+
+    /// Fills `dest` with challenge bytes derived from everything absorbed
+    /// so far and `label`, and absorbs them in turn.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is a Merlin transcript and `dest` is longer than
+    /// `u32::MAX` bytes.
+    pub fn challenge_bytes(&mut self, label: &[u8], dest: &mut [u8]) {
+        match &mut self.backend {
+            Backend::Sha512(h) => {
+                h.update((label.len() as u64).to_le_bytes());
+                h.update(label);
+                h.update((dest.len() as u64).to_le_bytes());
+
+                for (i, chunk) in dest.chunks_mut(64).enumerate() {
+                    let block = h
+                        .clone()
+                        .chain_update(b"challenge")
+                        .chain_update((i as u64).to_le_bytes())
+                        .finalize();
+                    chunk.copy_from_slice(&block[..chunk.len()]);
+                }
+                h.update(b"ratchet");
+                h.update(&*dest);
+            }
+            Backend::Merlin(strobe) => {
+                let len = u32::try_from(dest.len()).expect("challenge too long");
+                strobe.meta_ad(label, false);
+                strobe.meta_ad(&len.to_le_bytes(), true);
+                strobe.prf(dest, false);
+            }
+        }
+    }
+
+    /// Returns a challenge scalar derived from everything absorbed so far
+    /// and `label`, by reducing 64 challenge bytes with
+    /// [`Fr::from_bytes_wide`].
+    pub fn challenge_scalar(&mut self, label: &[u8]) -> Fr {
+        let mut wide = [0u8; 64];
+        self.challenge_bytes(label, &mut wide);
+        let res = Fr::from_bytes_wide(wide);
+        wide.zeroize();
+
+        res
+    }
+}
+
+#[test]
+fn test_merlin_compatibility() {
+    // The "equivalence_simple" test vector from merlin.
+    let mut transcript = Transcript::new_merlin(b"test protocol");
+    transcript.append_message(b"some label", b"some data");
+
+    let mut challenge = [0u8; 32];
+    transcript.challenge_bytes(b"challenge", &mut challenge);
+    assert_eq!(
+        challenge,
+        [
+            0xd5, 0xa2, 0x19, 0x72, 0xd0, 0xd5, 0xfe, 0x32, 0x0c, 0x0d, 0x26, 0x3f, 0xac, 0x7f,
+            0xff, 0xb8, 0x14, 0x5a, 0xa6, 0x40, 0xaf, 0x6e, 0x9b, 0xca, 0x17, 0x7c, 0x03, 0xc7,
+            0xef, 0xcf, 0x06, 0x15
+        ]
+    );
+}
+
+#[test]
+fn test_challenges() {
+    for new in [Transcript::new, Transcript::new_merlin].iter() {
+        let mut a = new(b"protocol");
+        let mut b = new(b"protocol");
+        a.append_point(b"point", &crate::GENERATOR);
+        b.append_point(b"point", &crate::GENERATOR);
+        a.append_scalar(b"scalar", &Fr::one());
+        b.append_scalar(b"scalar", &Fr::one());
+
+        let mut c = b.clone();
+        assert_eq!(a.challenge_scalar(b"c"), b.challenge_scalar(b"c"));
+        assert_ne!(a.challenge_scalar(b"c"), c.challenge_scalar(b"d"));
+
+        // Challenges depend on the previous ones.
+        let first = c.challenge_scalar(b"c");
+        assert_ne!(c.challenge_scalar(b"c"), first);
+
+        // Labels are absorbed unambiguously.
+        let mut d = new(b"protocol");
+        let mut e = new(b"protocol");
+        d.append_message(b"ab", b"c");
+        e.append_message(b"a", b"bc");
+        assert_ne!(d.challenge_scalar(b"c"), e.challenge_scalar(b"c"));
+
+        assert!(!d.validate_and_append_point(b"point", &AffinePoint::identity()));
+        assert!(d.validate_and_append_point(b"point", &crate::GENERATOR));
+    }
+}
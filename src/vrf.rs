@@ -0,0 +1,364 @@
+//! This module implements an elliptic curve verifiable random function
+//! (ECVRF) over the prime order subgroup of Doppio, in the style of
+//! RFC 9381.
+//!
+//! # Specification
+//!
+//! The ciphersuite `ECVRF-DOPPIO-SHA512-ELL2` follows Section 5 of
+//! RFC 9381 with the following parameters and encodings:
+//!
+//! * `suite_string` is the single octet `0xD0`.
+//! * The group is the prime order subgroup of Doppio with generator
+//!   [`GENERATOR`], order `q = r` and cofactor `4`.
+//! * `ptLen = 32`: `point_to_string` is [`AffinePoint::into_bytes`](crate::AffinePoint::into_bytes)
+//!   and `string_to_point` is [`AffinePoint::from_bytes`](crate::AffinePoint::from_bytes),
+//!   which rejects non-canonical encodings.
+//! * `qLen = 32`: `int_to_string` is [`Fr::into_bytes`](crate::Fr::into_bytes), which is
+//!   little-endian, and `s` is rejected if it is not canonical.
+//! * `cLen = 16`: the challenge is the first 16 bytes of the challenge hash,
+//!   interpreted as a little-endian integer.
+//! * `Hash` is SHA-512.
+//! * `ECVRF_encode_to_curve` hashes `PK_string || alpha_string` with
+//!   [`hash_to_curve`](crate::hash_to_curve) under the domain separation
+//!   tag `"ECVRF_" || "DOPPIO_XMD:SHA-512_ELL2_RO_" || suite_string`.
+//! * A secret key is a 32-byte string `SK_string`. The secret scalar is
+//!   `x = Fr::from_bytes_wide(Hash(SK_string))` and the nonce for a
+//!   proof is `k = Fr::from_bytes_wide(Hash(SK_string || h_string))`.
+//!
+//! Everything else is as in RFC 9381: a proof is
+//! `pi_string = point_to_string(Gamma) || int_to_string(c, 16) || int_to_string(s, 32)`
+//! of 80 bytes, the challenge is computed over
+//! `suite_string || 0x02 || Y || H || Gamma || U || V || 0x00`, and the
+//! VRF output is `beta_string = Hash(suite_string || 0x03 || point_to_string(4 * Gamma) || 0x00)`.
+//! Verification rejects public keys of small order.
+
+use core::fmt;
+
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::hash::hash_to_curve_parts;
+use crate::{AffinePoint, CtOption, ExtendedPoint, Fr, GENERATOR};
+
+/// The `suite_string` of this ciphersuite.
+pub const SUITE_STRING: u8 = 0xd0;
+
+/// The domain separation tag used by `ECVRF_encode_to_curve`.
+const ENCODE_TO_CURVE_DST: &[u8] = b"ECVRF_DOPPIO_XMD:SHA-512_ELL2_RO_\xd0";
+
+/// A VRF secret key.
+#[derive(Clone)]
+pub struct SecretKey {
+    seed: [u8; 32],
+    x: Fr,
+}
+
+/// A VRF public key, which is the point `x * G`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PublicKey(AffinePoint);
+
+/// A VRF proof `(Gamma, c, s)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Proof {
+    gamma: AffinePoint,
+    c: Fr,
+    s: Fr,
+}
+
+/// Computes `ECVRF_encode_to_curve` for the given public key encoding.
+fn encode_to_curve(pk: &[u8; 32], alpha: &[u8]) -> ExtendedPoint {
+    hash_to_curve_parts(&[pk, alpha], ENCODE_TO_CURVE_DST)
+}
+
+/// Computes `ECVRF_challenge_generation`, truncating the hash to 16 bytes.
+fn challenge(points: [&AffinePoint; 5]) -> Fr {
+    let mut h = Sha512::new();
+    h.update([SUITE_STRING, 0x02]);
+    for p in points.iter() {
+        h.update(p.into_bytes());
+    }
+    h.update([0x00]);
+    let c_string = h.finalize();
+
+    let mut c = [0u8; 32];
+    c[..16].copy_from_slice(&c_string[..16]);
+
+    // This cannot fail, as c < 2^128 < r.
+    Fr::from_bytes(c).unwrap()
+}
+
+impl SecretKey {
+    /// Samples a new secret key from the provided RNG.
+    pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> SecretKey {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        let sk = SecretKey::from_bytes(seed);
+        seed.zeroize();
+
+        sk
+    }
+
+    /// Constructs a secret key from its 32-byte representation
+    /// `SK_string`.
+    pub fn from_bytes(seed: [u8; 32]) -> SecretKey {
+        let mut hashed = [0u8; 64];
+        hashed.copy_from_slice(&Sha512::digest(seed));
+        let x = Fr::from_bytes_wide(hashed);
+        hashed.zeroize();
+
+        SecretKey { seed, x }
+    }
+
+    /// Returns the 32-byte representation `SK_string` of this secret key.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.seed
+    }
+
+    /// Computes the VRF proof for `alpha`. The VRF output is obtained
+    /// from the proof with [`Proof::to_hash`].
+    pub fn prove(&self, alpha: &[u8]) -> Proof {
+        let y = PublicKey::from(self);
+        let h = AffinePoint::from(encode_to_curve(&y.into_bytes(), alpha));
+        let h_string = h.into_bytes();
+        let gamma = AffinePoint::from(ExtendedPoint::from(h) * self.x);
+
+        let mut k_string = [0u8; 64];
+        k_string.copy_from_slice(
+            &Sha512::new()
+                .chain_update(self.seed)
+                .chain_update(h_string)
+                .finalize(),
+        );
+        let mut k = Fr::from_bytes_wide(k_string);
+        k_string.zeroize();
+
+        let mut points = [
+            ExtendedPoint::from(GENERATOR) * k,
+            ExtendedPoint::from(h) * k,
+        ];
+        let mut points = crate::batch_normalize(&mut points);
+        let (u, v) = (points.next().unwrap(), points.next().unwrap());
+
+        let c = challenge([&y.0, &h, &gamma, &u, &v]);
+        let s = k + c * self.x;
+        k.zeroize();
+
+        Proof { gamma, c, s }
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.seed.zeroize();
+        self.x.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SecretKey").finish()
+    }
+}
+
+impl<'a> From<&'a SecretKey> for PublicKey {
+    fn from(sk: &'a SecretKey) -> PublicKey {
+        PublicKey(AffinePoint::from(ExtendedPoint::from(GENERATOR) * sk.x))
+    }
+}
+
+impl PublicKey {
+    /// Attempts to interpret a byte representation of a public key,
+    /// failing if it is not the canonical encoding of a curve point.
+    pub fn from_bytes(bytes: [u8; 32]) -> CtOption<PublicKey> {
+        let point = AffinePoint::from_bytes(bytes);
+        let is_some = point.is_some();
+
+        CtOption::new(PublicKey(point.unwrap_or(AffinePoint::identity())), is_some)
+    }
+
+    /// Converts this public key into its byte representation.
+    pub fn into_bytes(&self) -> [u8; 32] {
+        self.0.into_bytes()
+    }
+
+    /// Verifies `proof` for `alpha` under this public key, returning the
+    /// VRF output if it is valid. Public keys of small order are rejected.
+    pub fn verify(&self, alpha: &[u8], proof: &Proof) -> Option<[u8; 64]> {
+        if self.0.is_small_order().unwrap_u8() == 1 {
+            return None;
+        }
+
+        let h = AffinePoint::from(encode_to_curve(&self.into_bytes(), alpha));
+        let y = ExtendedPoint::from(self.0);
+        let gamma = ExtendedPoint::from(proof.gamma);
+
+        let mut points = [
+            ExtendedPoint::from(GENERATOR) * proof.s - y * proof.c,
+            ExtendedPoint::from(h) * proof.s - gamma * proof.c,
+        ];
+        let mut points = crate::batch_normalize(&mut points);
+        let (u, v) = (points.next().unwrap(), points.next().unwrap());
+
+        if challenge([&self.0, &h, &proof.gamma, &u, &v]) == proof.c {
+            Some(proof.to_hash())
+        } else {
+            None
+        }
+    }
+}
+
+impl ConstantTimeEq for PublicKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl Proof {
+    /// Attempts to interpret the 80-byte representation of a proof,
+    /// failing if `Gamma` is not a canonical point encoding or `s`
+    /// is not a canonical scalar encoding.
+    pub fn from_bytes(bytes: [u8; 80]) -> CtOption<Proof> {
+        let mut gamma = [0u8; 32];
+        let mut c = [0u8; 32];
+        let mut s = [0u8; 32];
+        gamma.copy_from_slice(&bytes[..32]);
+        c[..16].copy_from_slice(&bytes[32..48]);
+        s.copy_from_slice(&bytes[48..]);
+
+        let gamma = AffinePoint::from_bytes(gamma);
+        let s = Fr::from_bytes(s);
+        let is_some = gamma.is_some() & s.is_some();
+
+        CtOption::new(
+            Proof {
+                gamma: gamma.unwrap_or(AffinePoint::identity()),
+                // This cannot fail, as c < 2^128 < r.
+                c: Fr::from_bytes(c).unwrap(),
+                s: s.unwrap_or(Fr::zero()),
+            },
+            is_some,
+        )
+    }
+
+    /// Converts this proof into its 80-byte representation.
+    pub fn into_bytes(&self) -> [u8; 80] {
+        let mut res = [0u8; 80];
+        res[..32].copy_from_slice(&self.gamma.into_bytes());
+        res[32..48].copy_from_slice(&self.c.into_bytes()[..16]);
+        res[48..].copy_from_slice(&self.s.into_bytes());
+
+        res
+    }
+
+    /// Computes the VRF output `beta_string` from this proof. This does
+    /// not verify the proof, so it should only be used by the prover or
+    /// after [`PublicKey::verify`] has succeeded.
+    pub fn to_hash(&self) -> [u8; 64] {
+        let gamma = AffinePoint::from(self.gamma.mul_by_cofactor());
+
+        let mut res = [0u8; 64];
+        res.copy_from_slice(
+            &Sha512::new()
+                .chain_update([SUITE_STRING, 0x03])
+                .chain_update(gamma.into_bytes())
+                .chain_update([0x00])
+                .finalize(),
+        );
+
+        res
+    }
+}
+
+#[test]
+fn test_prove_verify() {
+    let mut rng = crate::test_rng();
+    let sk = SecretKey::new(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let proof = sk.prove(b"leader election round 1");
+    assert_eq!(
+        pk.verify(b"leader election round 1", &proof),
+        Some(proof.to_hash())
+    );
+    assert_eq!(pk.verify(b"leader election round 2", &proof), None);
+
+    let other = PublicKey::from(&SecretKey::new(&mut rng));
+    assert_eq!(other.verify(b"leader election round 1", &proof), None);
+}
+
+#[test]
+fn test_small_order_public_key_rejected() {
+    let sk = SecretKey::from_bytes([1; 32]);
+    let proof = sk.prove(b"");
+    let identity = PublicKey(AffinePoint::identity());
+
+    assert_eq!(identity.verify(b"", &proof), None);
+}
+
+#[test]
+fn test_proof_serialization() {
+    let sk = SecretKey::from_bytes([2; 32]);
+    let proof = sk.prove(b"sample");
+
+    assert_eq!(Proof::from_bytes(proof.into_bytes()).unwrap(), proof);
+
+    let mut bytes = proof.into_bytes();
+    bytes[79] = 0xff;
+    assert!(Proof::from_bytes(bytes).is_none().unwrap_u8() == 1);
+}
+
+#[test]
+fn test_vectors() {
+    let sk = SecretKey::from_bytes([
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+        25, 26, 27, 28, 29, 30, 31,
+    ]);
+    let pk = [
+        9, 20, 6, 233, 15, 204, 162, 91, 105, 16, 71, 241, 210, 215, 80, 163, 207, 238, 213, 115,
+        211, 12, 184, 169, 159, 242, 36, 75, 56, 178, 192, 1,
+    ];
+    assert_eq!(PublicKey::from(&sk).into_bytes(), pk);
+
+    let vectors: [(&[u8], [u8; 80], [u8; 64]); 2] = [
+        (
+            b"",
+            [
+                35, 36, 220, 82, 130, 144, 55, 203, 247, 249, 173, 26, 72, 52, 252, 132, 33, 61,
+                240, 121, 49, 196, 214, 60, 36, 37, 88, 160, 239, 227, 39, 15, 163, 98, 69, 138,
+                21, 225, 27, 61, 13, 235, 119, 4, 68, 202, 24, 180, 54, 17, 155, 188, 225, 156,
+                237, 62, 59, 63, 186, 224, 141, 147, 22, 106, 36, 144, 168, 34, 156, 134, 200, 0,
+                131, 113, 196, 234, 143, 50, 81, 1,
+            ],
+            [
+                22, 145, 93, 70, 52, 237, 159, 229, 74, 34, 18, 19, 21, 244, 130, 245, 222, 119,
+                147, 69, 203, 78, 174, 113, 78, 11, 160, 7, 232, 185, 52, 4, 99, 153, 253, 111, 4,
+                192, 145, 10, 2, 20, 101, 7, 177, 45, 10, 51, 118, 56, 41, 197, 47, 136, 111, 117,
+                45, 49, 54, 237, 23, 62, 182, 76,
+            ],
+        ),
+        (
+            b"sample",
+            [
+                113, 180, 21, 139, 28, 29, 181, 190, 231, 90, 195, 139, 134, 242, 144, 7, 220, 81,
+                145, 156, 250, 243, 57, 233, 252, 123, 186, 1, 91, 165, 157, 8, 243, 190, 209, 212,
+                187, 165, 101, 234, 9, 228, 13, 196, 64, 249, 25, 82, 52, 186, 103, 49, 194, 254,
+                232, 52, 99, 233, 169, 147, 111, 26, 52, 227, 140, 91, 146, 13, 216, 205, 29, 218,
+                191, 255, 116, 32, 65, 82, 157, 1,
+            ],
+            [
+                104, 40, 26, 72, 58, 189, 203, 162, 205, 86, 224, 238, 108, 5, 188, 183, 216, 118,
+                229, 126, 94, 137, 100, 181, 55, 76, 154, 10, 235, 140, 185, 59, 176, 80, 134, 40,
+                97, 228, 20, 26, 90, 139, 39, 13, 72, 92, 53, 221, 47, 66, 215, 154, 226, 117, 43,
+                251, 231, 123, 88, 165, 71, 119, 167, 99,
+            ],
+        ),
+    ];
+
+    for (alpha, pi, beta) in vectors.iter() {
+        let proof = sk.prove(alpha);
+        assert_eq!(&proof.into_bytes()[..], &pi[..]);
+        assert_eq!(&proof.to_hash()[..], &beta[..]);
+    }
+}